@@ -41,6 +41,53 @@ impl Error {
         }
     }
 
+    /// Describe an error in the query file at the given path, showing the line
+    /// of the file that contains the error.
+    pub fn query(path: &str, source: &str, error: QueryError) -> Self {
+        let mut msg = format!(
+            "Query error at {}:{}:{}. ",
+            path,
+            error.row + 1,
+            error.column + 1
+        );
+        match error.kind {
+            QueryErrorKind::Capture => write!(&mut msg, "Invalid capture name {}", error.message),
+            QueryErrorKind::Field => write!(&mut msg, "Invalid field name {}", error.message),
+            QueryErrorKind::NodeType => write!(&mut msg, "Invalid node type {}", error.message),
+            QueryErrorKind::Syntax => write!(&mut msg, "Invalid syntax"),
+            QueryErrorKind::Structure => write!(&mut msg, "Impossible pattern"),
+            QueryErrorKind::Predicate => write!(&mut msg, "Invalid predicate: {}", error.message),
+        }
+        .unwrap();
+        let caret_len = match error.kind {
+            QueryErrorKind::Capture | QueryErrorKind::Field | QueryErrorKind::NodeType => {
+                error.message.chars().count().max(1)
+            }
+            _ => 1,
+        };
+
+        // Render the line containing the error, with the error's location underlined.
+        if let Some(line) = source.split('\n').nth(error.row) {
+            let line_number = (error.row + 1).to_string();
+            let gutter = " ".repeat(line_number.len());
+            let indent = line
+                .get(..error.column)
+                .map_or(error.column, |prefix| prefix.chars().count());
+            write!(
+                &mut msg,
+                "\n{} |\n{} | {}\n{} | {}{}",
+                gutter,
+                line_number,
+                line,
+                gutter,
+                " ".repeat(indent),
+                "^".repeat(caret_len)
+            )
+            .unwrap();
+        }
+        Self::new(msg)
+    }
+
     pub fn message(&self) -> String {
         let mut result = self.0.last().unwrap().clone();
         if self.0.len() > 1 {
@@ -53,22 +100,6 @@ impl Error {
     }
 }
 
-impl<'a> From<(&str, QueryError)> for Error {
-    fn from((path, error): (&str, QueryError)) -> Self {
-        let mut msg = format!("Query error at {}:{}. ", path, error.row + 1);
-        match error.kind {
-            QueryErrorKind::Capture => write!(&mut msg, "Invalid capture name {}", error.message),
-            QueryErrorKind::Field => write!(&mut msg, "Invalid field name {}", error.message),
-            QueryErrorKind::NodeType => write!(&mut msg, "Invalid node type {}", error.message),
-            QueryErrorKind::Syntax => write!(&mut msg, "Invalid syntax:\n{}", error.message),
-            QueryErrorKind::Structure => write!(&mut msg, "Impossible pattern:\n{}", error.message),
-            QueryErrorKind::Predicate => write!(&mut msg, "Invalid predicate: {}", error.message),
-        }
-        .unwrap();
-        Self::new(msg)
-    }
-}
-
 impl<'a> From<tree_sitter_highlight::Error> for Error {
     fn from(error: tree_sitter_highlight::Error) -> Self {
         Error::new(format!("{:?}", error))
@@ -0,0 +1,491 @@
+//! Checking that incremental parsing agrees with parsing from scratch.
+//!
+//! Each corpus example is parsed, then edited randomly and re-parsed
+//! incrementally. The resulting trees are compared to fresh parses of the same
+//! text, and the ranges reported by `Tree::changed_ranges` are checked against
+//! the actual differences between the trees. The edits are then undone, and the
+//! tree is re-parsed again.
+
+pub mod edits;
+pub mod random;
+pub mod scope_sequence;
+
+use self::edits::{get_random_edit, invert_edit};
+use self::random::Rand;
+use self::scope_sequence::ScopeSequence;
+use super::error::{Error, Result};
+use super::parse::{perform_edit, Edit};
+use super::test::{parse_tests, TestEntry};
+use ansi_term::Colour;
+use std::collections::HashMap;
+use std::path::Path;
+use tree_sitter::{Language, Node, Parser, Point, Tree};
+
+/// The number of random edits that are applied in each trial.
+pub const EDIT_COUNT: usize = 3;
+
+pub struct FuzzOptions<'a> {
+    /// The other languages that tests can use via the `:language` attribute,
+    /// keyed by name.
+    pub languages: &'a HashMap<String, Language>,
+    pub filter: Option<&'a str>,
+    /// The number of random sequences of edits to try on each example.
+    pub iterations: usize,
+    pub seed: usize,
+}
+
+struct Failure {
+    name: String,
+    trial: usize,
+    input: Vec<u8>,
+    edits: Vec<Edit>,
+    message: String,
+}
+
+pub fn fuzz_tests_at_path(language: Language, path: &Path, options: &FuzzOptions) -> Result<()> {
+    let test_entry = parse_tests(path)?;
+    let mut parser = Parser::new();
+    parser.set_language(language).map_err(|e| e.to_string())?;
+
+    println!("Random seed: {}\n", options.seed);
+    let mut failures = Vec::new();
+    fuzz_tests(&mut parser, test_entry, options, 0, &mut failures)?;
+
+    if failures.len() > 0 {
+        println!("");
+        if failures.len() == 1 {
+            println!("1 failure:")
+        } else {
+            println!("{} failures:", failures.len())
+        }
+
+        for (i, failure) in failures.iter().enumerate() {
+            println!("\n  {}. {} - trial {}:", i + 1, failure.name, failure.trial);
+            for line in failure.message.lines() {
+                println!("    {}", line);
+            }
+            println!("\n    Input:");
+            for line in String::from_utf8_lossy(&failure.input).lines() {
+                println!("      {}", line);
+            }
+            println!("\n    Edits:");
+            for (j, edit) in failure.edits.iter().enumerate() {
+                println!(
+                    "      {}. at byte {}, delete {} bytes and insert {:?}",
+                    j + 1,
+                    edit.position,
+                    edit.deleted_length,
+                    String::from_utf8_lossy(&edit.inserted_text)
+                );
+            }
+        }
+        println!(
+            "\nRun with `--seed {} --iterations {}` to reproduce these failures.",
+            options.seed, options.iterations
+        );
+        Error::err(String::new())
+    } else {
+        Ok(())
+    }
+}
+
+fn fuzz_tests(
+    parser: &mut Parser,
+    test_entry: TestEntry,
+    options: &FuzzOptions,
+    indent_level: usize,
+    failures: &mut Vec<Failure>,
+) -> Result<()> {
+    match test_entry {
+        TestEntry::Example {
+            name,
+            input,
+            attributes,
+            ..
+        } => {
+            if attributes.is_skipped() || !options.filter.iter().all(|f| name.contains(f)) {
+                return Ok(());
+            }
+
+            let default_language = parser.language();
+            let test_languages = if attributes.languages.is_empty() {
+                vec![None]
+            } else {
+                attributes.languages.iter().map(Some).collect()
+            };
+            for language_name in test_languages {
+                let mut name = name.clone();
+                if let Some(language_name) = language_name {
+                    let language = options.languages.get(language_name).ok_or_else(|| {
+                        Error::new(format!(
+                            "Test {:?} uses unknown language {:?}",
+                            name, language_name
+                        ))
+                    })?;
+                    parser.set_language(*language).map_err(|e| e.to_string())?;
+                    if attributes.languages.len() > 1 {
+                        name += &format!(" ({})", language_name);
+                    }
+                }
+
+                let tree = parser.parse(&input, None).unwrap();
+                let mut failure = None;
+                for trial in 1..=options.iterations {
+                    let mut rand = Rand::new(options.seed.wrapping_add(trial));
+                    let edits = get_random_edits(&mut rand, &input);
+                    if run_trial(parser, &tree, &input, &edits).is_err() {
+                        let edits = minimize_edits(&input, edits, |edits| {
+                            run_trial(parser, &tree, &input, edits).is_err()
+                        });
+                        let message = run_trial(parser, &tree, &input, &edits).unwrap_err();
+                        failure = Some(Failure {
+                            name: name.clone(),
+                            trial,
+                            input: input.clone(),
+                            edits,
+                            message,
+                        });
+                        break;
+                    }
+                }
+
+                print_indent(indent_level);
+                if let Some(failure) = failure {
+                    println!("✗ {}", Colour::Red.paint(&name));
+                    failures.push(failure);
+                } else {
+                    println!("✓ {}", Colour::Green.paint(&name));
+                }
+            }
+            if let Some(language) = default_language {
+                parser.set_language(language).map_err(|e| e.to_string())?;
+            }
+        }
+        TestEntry::Group { name, children, .. } => {
+            if indent_level > 0 {
+                print_indent(indent_level);
+                println!("{}:", name);
+            }
+            for child in children {
+                fuzz_tests(parser, child, options, indent_level + 1, failures)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+// Apply the given edits to a copy of the given tree, re-parse it incrementally,
+// and check the result. Then undo the edits, and check the result again.
+fn run_trial(
+    parser: &mut Parser,
+    tree: &Tree,
+    input: &Vec<u8>,
+    edits: &[Edit],
+) -> std::result::Result<(), String> {
+    let mut tree = tree.clone();
+    let mut input = input.clone();
+    let original_sexp = tree.root_node().to_sexp();
+
+    let mut undo_stack = Vec::new();
+    for edit in edits {
+        undo_stack.push(invert_edit(&input, edit));
+        perform_edit(&mut tree, &mut input, edit);
+    }
+
+    let tree2 = parser.parse(&input, Some(&tree)).unwrap();
+    check_consistent_sizes(&tree2, &input)?;
+    check_changed_ranges(&tree, &tree2, &input)?;
+    let fresh_tree = parser.parse(&input, None).unwrap();
+    check_same_trees(&tree2, &fresh_tree.root_node().to_sexp())
+        .map_err(|e| format!("After the edits, {}", e))?;
+
+    let mut tree2 = tree2;
+    while let Some(edit) = undo_stack.pop() {
+        perform_edit(&mut tree2, &mut input, &edit);
+    }
+
+    let tree3 = parser.parse(&input, Some(&tree2)).unwrap();
+    check_consistent_sizes(&tree3, &input)?;
+    check_changed_ranges(&tree2, &tree3, &input)?;
+    check_same_trees(&tree3, &original_sexp).map_err(|e| format!("After undoing the edits, {}", e))
+}
+
+// Find a smaller sequence of edits that still causes a trial to fail, by
+// repeatedly removing edits and shortening them.
+fn minimize_edits(
+    input: &Vec<u8>,
+    mut edits: Vec<Edit>,
+    mut is_failure: impl FnMut(&[Edit]) -> bool,
+) -> Vec<Edit> {
+    let mut fails = |edits: &[Edit]| edits_are_valid(input, edits) && is_failure(edits);
+
+    loop {
+        let mut changed = false;
+
+        let mut i = 0;
+        while i < edits.len() {
+            let mut candidate = edits.clone();
+            candidate.remove(i);
+            if fails(&candidate) {
+                edits = candidate;
+                changed = true;
+            } else {
+                i += 1;
+            }
+        }
+
+        for i in 0..edits.len() {
+            for smaller_edit in shrink_edit(&edits[i]) {
+                let mut candidate = edits.clone();
+                candidate[i] = smaller_edit;
+                if fails(&candidate) {
+                    edits = candidate;
+                    changed = true;
+                    break;
+                }
+            }
+        }
+
+        if !changed {
+            break;
+        }
+    }
+    edits
+}
+
+// Get the edits that are like the given edit, but insert or delete less text.
+fn shrink_edit(edit: &Edit) -> Vec<Edit> {
+    let mut result = Vec::new();
+    let text = &edit.inserted_text;
+    if !text.is_empty() {
+        let half = text.len() / 2;
+        let mut texts = vec![text[..half].to_vec(), text[..text.len() - 1].to_vec()];
+        if half > 0 {
+            texts.push(text[half..].to_vec());
+        }
+        for inserted_text in texts {
+            result.push(Edit {
+                inserted_text,
+                ..edit.clone()
+            });
+        }
+    }
+    if edit.deleted_length > 0 {
+        for deleted_length in &[edit.deleted_length / 2, edit.deleted_length - 1] {
+            result.push(Edit {
+                deleted_length: *deleted_length,
+                ..edit.clone()
+            });
+        }
+    }
+    result
+}
+
+// Check that each of the edits is within the bounds of the text that it applies to.
+fn edits_are_valid(input: &Vec<u8>, edits: &[Edit]) -> bool {
+    let mut len = input.len();
+    for edit in edits {
+        if edit.position + edit.deleted_length > len {
+            return false;
+        }
+        len = len - edit.deleted_length + edit.inserted_text.len();
+    }
+    true
+}
+
+fn get_random_edits(rand: &mut Rand, input: &Vec<u8>) -> Vec<Edit> {
+    let mut input = input.clone();
+    let mut result = Vec::new();
+    for _ in 0..EDIT_COUNT {
+        let edit = get_random_edit(rand, &input);
+        apply_edit(&mut input, &edit);
+        result.push(edit);
+    }
+    result
+}
+
+fn apply_edit(input: &mut Vec<u8>, edit: &Edit) {
+    input.splice(
+        edit.position..(edit.position + edit.deleted_length),
+        edit.inserted_text.iter().cloned(),
+    );
+}
+
+fn check_same_trees(tree: &Tree, expected_sexp: &str) -> std::result::Result<(), String> {
+    let actual_sexp = tree.root_node().to_sexp();
+    if actual_sexp == expected_sexp {
+        Ok(())
+    } else {
+        Err(format!(
+            concat!(
+                "the incremental parse did not match a parse from scratch.\n",
+                "Expected: {}\n",
+                "Actual:   {}",
+            ),
+            expected_sexp, actual_sexp
+        ))
+    }
+}
+
+fn print_indent(indent_level: usize) {
+    for _ in 0..indent_level {
+        print!("  ");
+    }
+}
+
+/// Check that the sizes and positions of the nodes in the given tree are
+/// consistent with each other and with the given text.
+pub fn check_consistent_sizes(tree: &Tree, input: &Vec<u8>) -> std::result::Result<(), String> {
+    fn check(node: Node, line_offsets: &Vec<usize>) -> std::result::Result<(), String> {
+        let start_byte = node.start_byte();
+        let end_byte = node.end_byte();
+        let start_point = node.start_position();
+        let end_point = node.end_position();
+        let error = |message: &str| {
+            Err(format!(
+                "Inconsistent sizes for `{}` node at {}..{} ({} - {}): {}",
+                node.kind(),
+                start_byte,
+                end_byte,
+                start_point,
+                end_point,
+                message
+            ))
+        };
+
+        if start_byte > end_byte || start_point > end_point {
+            return error("the node ends before it starts");
+        }
+        let offset = |point: Point| line_offsets.get(point.row).map(|o| o + point.column);
+        if offset(start_point) != Some(start_byte) || offset(end_point) != Some(end_byte) {
+            return error("the node's positions don't match its byte offsets");
+        }
+
+        let mut last_child_end_byte = start_byte;
+        let mut last_child_end_point = start_point;
+        let mut some_child_has_changes = false;
+        let mut actual_named_child_count = 0;
+        for i in 0..node.child_count() {
+            let child = node.child(i).unwrap();
+            if child.start_byte() < last_child_end_byte
+                || child.start_position() < last_child_end_point
+            {
+                return error("the node's children overlap");
+            }
+            check(child, line_offsets)?;
+            if child.has_changes() {
+                some_child_has_changes = true;
+            }
+            if child.is_named() {
+                actual_named_child_count += 1;
+            }
+            last_child_end_byte = child.end_byte();
+            last_child_end_point = child.end_position();
+        }
+
+        if actual_named_child_count != node.named_child_count() {
+            return error("the node's named child count is incorrect");
+        }
+        if node.child_count() > 0
+            && (end_byte < last_child_end_byte || end_point < last_child_end_point)
+        {
+            return error("the node ends before its last child");
+        }
+        if some_child_has_changes && !node.has_changes() {
+            return error("the node's child has changes, but the node does not");
+        }
+        Ok(())
+    }
+
+    let mut line_offsets = vec![0];
+    for (i, c) in input.iter().enumerate() {
+        if *c == '\n' as u8 {
+            line_offsets.push(i + 1);
+        }
+    }
+
+    check(tree.root_node(), &line_offsets)
+}
+
+/// Check that every difference between the given trees is contained in one
+/// of the ranges returned by `Tree::changed_ranges`.
+pub fn check_changed_ranges(
+    old_tree: &Tree,
+    new_tree: &Tree,
+    input: &Vec<u8>,
+) -> std::result::Result<(), String> {
+    let changed_ranges = old_tree.changed_ranges(new_tree).collect();
+    let old_scope_sequence = ScopeSequence::new(old_tree);
+    let new_scope_sequence = ScopeSequence::new(new_tree);
+    old_scope_sequence.check_changes(&new_scope_sequence, &input, &changed_ranges)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::helpers::fixtures::get_language;
+
+    #[test]
+    fn test_run_trial() {
+        let mut parser = Parser::new();
+        parser.set_language(get_language("javascript")).unwrap();
+        let input = b"function a(b) {\n  return b + 1;\n}\n\nconst c = a([d, 'e']);\n".to_vec();
+        let tree = parser.parse(&input, None).unwrap();
+        for seed in 0..20 {
+            let edits = get_random_edits(&mut Rand::new(seed), &input);
+            assert!(edits_are_valid(&input, &edits));
+            assert_eq!(run_trial(&mut parser, &tree, &input, &edits), Ok(()));
+        }
+    }
+
+    #[test]
+    fn test_minimize_edits() {
+        let input = b"abcdef".to_vec();
+        let edits = vec![
+            Edit {
+                position: 0,
+                deleted_length: 0,
+                inserted_text: b"xyz".to_vec(),
+            },
+            Edit {
+                position: 3,
+                deleted_length: 2,
+                inserted_text: Vec::new(),
+            },
+            Edit {
+                position: 1,
+                deleted_length: 3,
+                inserted_text: b"hello world".to_vec(),
+            },
+        ];
+
+        // Any sequence of edits that inserts a `w` is treated as a failure.
+        let edits = minimize_edits(&input, edits, |edits| {
+            let mut input = input.clone();
+            for edit in edits {
+                apply_edit(&mut input, edit);
+            }
+            input.contains(&b'w')
+        });
+        assert_eq!(edits.len(), 1);
+        assert_eq!(edits[0].position, 1);
+        assert_eq!(edits[0].deleted_length, 0);
+        assert_eq!(edits[0].inserted_text, b"w");
+    }
+
+    #[test]
+    fn test_edits_are_valid() {
+        let input = b"abc".to_vec();
+        let edit = |position, deleted_length, inserted_text: &str| Edit {
+            position,
+            deleted_length,
+            inserted_text: inserted_text.as_bytes().to_vec(),
+        };
+        assert!(edits_are_valid(
+            &input,
+            &[edit(1, 2, "de"), edit(3, 0, "f")]
+        ));
+        assert!(!edits_are_valid(&input, &[edit(1, 2, ""), edit(2, 0, "f")]));
+        assert!(!edits_are_valid(&input, &[edit(2, 2, "")]));
+    }
+}
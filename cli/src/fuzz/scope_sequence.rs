@@ -45,13 +45,18 @@ impl ScopeSequence {
         known_changed_ranges: &Vec<Range>,
     ) -> Result<(), String> {
         if self.0.len() != text.len() {
-            panic!(
+            return Err(format!(
                 "Inconsistent scope sequence: {:?}",
                 self.0.iter().zip(text.iter().map(|c| *c as char)).collect::<Vec<_>>()
-            );
+            ));
+        }
+        if other.0.len() != text.len() {
+            return Err(format!(
+                "Inconsistent scope sequence: {:?}",
+                other.0.iter().zip(text.iter().map(|c| *c as char)).collect::<Vec<_>>()
+            ));
         }
 
-        assert_eq!(self.0.len(), other.0.len());
         let mut position = Point { row: 0, column: 0 };
         for (i, stack) in self.0.iter().enumerate() {
             let other_stack = &other.0[i];
@@ -1,3 +1,4 @@
+use super::conflict_examples;
 use super::item::{ParseItem, ParseItemSet, ParseItemSetCore};
 use super::item_set_builder::ParseItemSetBuilder;
 use crate::generate::grammars::{
@@ -600,6 +601,29 @@ impl<'a> ParseTableBuilder<'a> {
             self.symbol_name(&conflicting_lookahead)
         )
         .unwrap();
+
+        // Describe a concrete input that leads to the conflict, replacing each
+        // non-terminal symbol with its shortest sequence of tokens.
+        if let Some(example_tokens) =
+            conflict_examples::example_tokens(self.syntax_grammar, preceding_symbols)
+        {
+            if example_tokens != *preceding_symbols {
+                write!(&mut msg, "Example tokens:\n\n").unwrap();
+                for symbol in &example_tokens {
+                    write!(&mut msg, "  {}", self.symbol_name(symbol)).unwrap();
+                }
+                write!(
+                    &mut msg,
+                    "  •  {}  …\n\n",
+                    self.symbol_name(&conflicting_lookahead)
+                )
+                .unwrap();
+            }
+            if let Some(source) = self.example_source(&example_tokens, conflicting_lookahead) {
+                write!(&mut msg, "Example source code:\n\n{}\n\n", source).unwrap();
+            }
+        }
+
         write!(&mut msg, "Possible interpretations:\n\n").unwrap();
 
         let mut interpretions = conflicting_items
@@ -652,7 +676,7 @@ impl<'a> ParseTableBuilder<'a> {
                     None
                 };
 
-                (line, prec_line)
+                (line, prec_line, *item)
             })
             .collect::<Vec<_>>();
 
@@ -662,7 +686,11 @@ impl<'a> ParseTableBuilder<'a> {
             .max()
             .unwrap();
         interpretions.sort_unstable();
-        for (i, (line, prec_suffix)) in interpretions.into_iter().enumerate() {
+        let interpretation_items = interpretions
+            .iter()
+            .map(|(_, _, item)| *item)
+            .collect::<Vec<_>>();
+        for (i, (line, prec_suffix, _)) in interpretions.into_iter().enumerate() {
             write!(&mut msg, "  {}:", i + 1).unwrap();
             msg += &line;
             if let Some(prec_suffix) = prec_suffix {
@@ -689,6 +717,74 @@ impl<'a> ParseTableBuilder<'a> {
         shift_items.sort_unstable();
         reduce_items.sort_unstable();
 
+        // Refer to the interpretations that a resolution would choose, by number.
+        let list_interpretations = |items: &[&ParseItem]| {
+            let mut numbers = items
+                .iter()
+                .filter_map(|item| interpretation_items.iter().position(|i| i == item))
+                .collect::<Vec<_>>();
+            numbers.sort_unstable();
+            let mut numbers = numbers
+                .into_iter()
+                .map(|i| (i + 1).to_string())
+                .collect::<Vec<_>>();
+            if numbers.len() == 1 {
+                format!("interpretation {}", numbers[0])
+            } else {
+                let last = numbers.pop().unwrap_or_default();
+                format!("interpretations {} and {}", numbers.join(", "), last)
+            }
+        };
+
+        let lookahead_name = self.symbol_name(&conflicting_lookahead);
+        let describe_shift = |msg: &mut String, items: &[&ParseItem], annotation: &str| {
+            let mut rule_names = Vec::new();
+            for item in items {
+                let name = self.symbol_name(&Symbol::non_terminal(item.variable_index as usize));
+                if !rule_names.contains(&name) {
+                    rule_names.push(name);
+                }
+            }
+            let last = rule_names.pop().unwrap_or_default();
+            let rule_names = if rule_names.is_empty() {
+                format!("`{}`", last)
+            } else {
+                format!("`{}` or `{}`", rule_names.join("`, `"), last)
+            };
+            write!(
+                msg,
+                "      Using `{}`, the {} would be parsed as part of {} ({}).\n",
+                annotation,
+                lookahead_name,
+                rule_names,
+                list_interpretations(items)
+            )
+            .unwrap();
+        };
+        let describe_reduce = |msg: &mut String, items: &[&ParseItem], annotation: &str| {
+            let mut last_rule_id = None;
+            for item in items {
+                if last_rule_id == Some(item.variable_index) {
+                    continue;
+                }
+                last_rule_id = Some(item.variable_index);
+                let rule_items = items
+                    .iter()
+                    .filter(|i| i.variable_index == item.variable_index)
+                    .cloned()
+                    .collect::<Vec<_>>();
+                write!(
+                    msg,
+                    "      Using `{}`, the `{}` would end before the {} ({}).\n",
+                    annotation,
+                    self.symbol_name(&Symbol::non_terminal(item.variable_index as usize)),
+                    lookahead_name,
+                    list_interpretations(&rule_items)
+                )
+                .unwrap();
+            }
+        };
+
         let list_rule_names = |mut msg: &mut String, items: &[&ParseItem]| {
             let mut last_rule_id = None;
             for item in items {
@@ -721,6 +817,7 @@ impl<'a> ParseTableBuilder<'a> {
                 .unwrap();
                 list_rule_names(&mut msg, &shift_items);
                 write!(&mut msg, " than in the other rules.\n").unwrap();
+                describe_shift(&mut msg, &shift_items, "prec");
             }
 
             for item in &reduce_items {
@@ -732,6 +829,7 @@ impl<'a> ParseTableBuilder<'a> {
                     self.symbol_name(&Symbol::non_terminal(item.variable_index as usize))
                 )
                 .unwrap();
+                describe_reduce(&mut msg, &[item], "prec");
             }
         }
 
@@ -745,6 +843,10 @@ impl<'a> ParseTableBuilder<'a> {
             .unwrap();
             list_rule_names(&mut msg, &reduce_items);
             write!(&mut msg, "\n").unwrap();
+            describe_reduce(&mut msg, &reduce_items, "prec.left");
+            if !shift_items.is_empty() {
+                describe_shift(&mut msg, &shift_items, "prec.right");
+            }
         }
 
         resolution_count += 1;
@@ -761,10 +863,43 @@ impl<'a> ParseTableBuilder<'a> {
             write!(&mut msg, "`{}`", self.symbol_name(symbol)).unwrap();
         }
         write!(&mut msg, "\n").unwrap();
+        write!(
+            &mut msg,
+            "      Using `conflicts`, the parser would try {} at runtime, {}.\n",
+            list_interpretations(&interpretation_items),
+            "and keep the one with the highest `prec.dynamic`"
+        )
+        .unwrap();
 
         Err(Error::new(msg))
     }
 
+    // Render the given tokens as source code, using the shortest string that each
+    // token can match, with a caret under the lookahead token. External tokens
+    // have no known text, so no source code can be shown for inputs that contain
+    // them.
+    fn example_source(&self, tokens: &[Symbol], lookahead: Symbol) -> Option<String> {
+        let mut line = " ".to_string();
+        for symbol in tokens {
+            let text = conflict_examples::example_token_text(self.lexical_grammar, *symbol)?;
+            line.push(' ');
+            line += &escape_example_text(&text);
+        }
+
+        let text = conflict_examples::example_token_text(self.lexical_grammar, lookahead)?;
+        let text = escape_example_text(&text);
+        line.push(' ');
+        let column = line.chars().count();
+        line += &text;
+        if !lookahead.is_eof() {
+            line += " …";
+        }
+        line.push('\n');
+        line.extend(std::iter::repeat(' ').take(column));
+        line.extend(std::iter::repeat('^').take(text.chars().count().max(1)));
+        Some(line)
+    }
+
     fn compare_precedence(
         grammar: &SyntaxGrammar,
         left: &Precedence,
@@ -926,6 +1061,18 @@ impl<'a> ParseTableBuilder<'a> {
     }
 }
 
+fn escape_example_text(text: &str) -> String {
+    text.chars()
+        .map(|c| {
+            if c.is_control() {
+                c.escape_default().to_string()
+            } else {
+                c.to_string()
+            }
+        })
+        .collect()
+}
+
 fn populate_following_tokens(
     result: &mut Vec<TokenSet>,
     grammar: &SyntaxGrammar,
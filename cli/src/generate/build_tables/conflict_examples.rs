@@ -0,0 +1,284 @@
+use crate::generate::grammars::{LexicalGrammar, SyntaxGrammar};
+use crate::generate::nfa::{CharacterSet, NfaState};
+use crate::generate::rules::{Symbol, SymbolType};
+use std::collections::VecDeque;
+
+// When a conflict can't be resolved, the error message includes an example of
+// the input that leads to it. The parse state is described by a sequence of
+// symbols, so each non-terminal symbol is replaced with its shortest expansion,
+// and each token is replaced with the shortest string that it matches.
+
+/// Replace each non-terminal symbol in the given sequence with the shortest
+/// sequence of tokens that it can match. If some non-terminal can't match any
+/// finite sequence of tokens, there is no example, so this returns `None`.
+pub(crate) fn example_tokens(
+    syntax_grammar: &SyntaxGrammar,
+    symbols: &[Symbol],
+) -> Option<Vec<Symbol>> {
+    // Find the length of each variable's shortest expansion, along with the
+    // production that achieves it, by repeatedly improving the estimates until
+    // they stop changing.
+    let mut shortest_productions = vec![None; syntax_grammar.variables.len()];
+    let mut lengths = vec![usize::MAX; syntax_grammar.variables.len()];
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for (i, variable) in syntax_grammar.variables.iter().enumerate() {
+            for (j, production) in variable.productions.iter().enumerate() {
+                let length = production.steps.iter().try_fold(0, |length, step| {
+                    if step.symbol.is_non_terminal() {
+                        match lengths[step.symbol.index] {
+                            usize::MAX => None,
+                            step_length => Some(length + step_length),
+                        }
+                    } else {
+                        Some(length + 1)
+                    }
+                });
+                if let Some(length) = length {
+                    if length < lengths[i] {
+                        lengths[i] = length;
+                        shortest_productions[i] = Some(j);
+                        changed = true;
+                    }
+                }
+            }
+        }
+    }
+
+    // Each production was only chosen because it is strictly shorter than the
+    // ones that were chosen before it, so these expansions can't be cyclic.
+    fn expand(
+        syntax_grammar: &SyntaxGrammar,
+        shortest_productions: &[Option<usize>],
+        symbol: Symbol,
+        result: &mut Vec<Symbol>,
+    ) -> Option<()> {
+        if symbol.is_non_terminal() {
+            let production_index = shortest_productions[symbol.index]?;
+            let production = &syntax_grammar.variables[symbol.index].productions[production_index];
+            for step in &production.steps {
+                expand(syntax_grammar, shortest_productions, step.symbol, result)?;
+            }
+        } else {
+            result.push(symbol);
+        }
+        Some(())
+    }
+
+    let mut result = Vec::new();
+    for symbol in symbols {
+        expand(syntax_grammar, &shortest_productions, *symbol, &mut result)?;
+    }
+    Some(result)
+}
+
+/// Find the shortest string that the given token can match. External tokens
+/// don't have a known text, so they return `None`.
+pub(crate) fn example_token_text(
+    lexical_grammar: &LexicalGrammar,
+    symbol: Symbol,
+) -> Option<String> {
+    match symbol.kind {
+        SymbolType::End | SymbolType::EndOfNonTerminalExtra => Some(String::new()),
+        SymbolType::Terminal => {
+            let start_state = lexical_grammar.variables[symbol.index].start_state;
+            shortest_match(lexical_grammar, start_state)
+        }
+        SymbolType::External | SymbolType::NonTerminal => None,
+    }
+}
+
+// Search the NFA for the shortest path from the given state to an `Accept`
+// state. Only `Advance` states consume characters, so this is a breadth-first
+// search in which `Split` states are followed before any other states.
+fn shortest_match(lexical_grammar: &LexicalGrammar, start_state: u32) -> Option<String> {
+    let states = &lexical_grammar.nfa.states;
+    let mut lengths = vec![usize::MAX; states.len()];
+    let mut predecessors = vec![None; states.len()];
+    let mut queue = VecDeque::new();
+    lengths[start_state as usize] = 0;
+    queue.push_back(start_state);
+    while let Some(state_id) = queue.pop_front() {
+        let length = lengths[state_id as usize];
+        let mut add_successor = |next_state_id: u32, c: Option<char>| {
+            let next_length = length + c.map_or(0, |_| 1);
+            if next_length < lengths[next_state_id as usize] {
+                lengths[next_state_id as usize] = next_length;
+                predecessors[next_state_id as usize] = Some((state_id, c));
+                if c.is_some() {
+                    queue.push_back(next_state_id);
+                } else {
+                    queue.push_front(next_state_id);
+                }
+            }
+        };
+        match &states[state_id as usize] {
+            NfaState::Accept { .. } => {
+                let mut result = Vec::new();
+                let mut state_id = state_id;
+                while let Some((predecessor, c)) = predecessors[state_id as usize] {
+                    result.extend(c);
+                    state_id = predecessor;
+                }
+                return Some(result.into_iter().rev().collect());
+            }
+            NfaState::Split(a, b) => {
+                add_successor(*a, None);
+                add_successor(*b, None);
+            }
+            NfaState::Advance {
+                chars,
+                state_id: next_state_id,
+                is_sep,
+                ..
+            } => {
+                if let (false, Some(c)) = (*is_sep, example_char(chars)) {
+                    add_successor(*next_state_id, Some(c));
+                }
+            }
+        }
+    }
+    None
+}
+
+// Choose a readable character from the given set, if possible.
+fn example_char(chars: &CharacterSet) -> Option<char> {
+    chars
+        .chars()
+        .take(256)
+        .find(|c| c.is_ascii_lowercase())
+        .or_else(|| chars.chars().take(256).find(|c| c.is_ascii_alphanumeric()))
+        .or_else(|| chars.chars().take(256).find(|c| c.is_ascii_graphic()))
+        .or_else(|| chars.chars().next())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generate::grammars::{
+        Production, ProductionStep, SyntaxVariable, Variable, VariableType,
+    };
+    use crate::generate::prepare_grammar::{expand_tokens, ExtractedLexicalGrammar};
+    use crate::generate::rules::Rule;
+
+    #[test]
+    fn test_example_tokens() {
+        let production = |symbols: &[Symbol]| Production {
+            dynamic_precedence: 0,
+            steps: symbols.iter().cloned().map(ProductionStep::new).collect(),
+        };
+
+        let grammar = SyntaxGrammar {
+            variables: vec![
+                // expression: sum | call | terminal-0
+                SyntaxVariable {
+                    name: "expression".to_string(),
+                    kind: VariableType::Named,
+                    productions: vec![
+                        production(&[Symbol::non_terminal(1)]),
+                        production(&[Symbol::non_terminal(2)]),
+                        production(&[Symbol::terminal(0)]),
+                    ],
+                },
+                // sum: expression terminal-1 expression
+                SyntaxVariable {
+                    name: "sum".to_string(),
+                    kind: VariableType::Named,
+                    productions: vec![production(&[
+                        Symbol::non_terminal(0),
+                        Symbol::terminal(1),
+                        Symbol::non_terminal(0),
+                    ])],
+                },
+                // call: expression terminal-2 terminal-3
+                SyntaxVariable {
+                    name: "call".to_string(),
+                    kind: VariableType::Named,
+                    productions: vec![production(&[
+                        Symbol::non_terminal(0),
+                        Symbol::terminal(2),
+                        Symbol::terminal(3),
+                    ])],
+                },
+                // loop: loop terminal-1
+                SyntaxVariable {
+                    name: "loop".to_string(),
+                    kind: VariableType::Named,
+                    productions: vec![production(&[Symbol::non_terminal(3), Symbol::terminal(1)])],
+                },
+            ],
+            ..Default::default()
+        };
+
+        assert_eq!(
+            example_tokens(
+                &grammar,
+                &[
+                    Symbol::non_terminal(1),
+                    Symbol::terminal(1),
+                    Symbol::non_terminal(2)
+                ]
+            ),
+            Some(vec![
+                Symbol::terminal(0),
+                Symbol::terminal(1),
+                Symbol::terminal(0),
+                Symbol::terminal(1),
+                Symbol::terminal(0),
+                Symbol::terminal(2),
+                Symbol::terminal(3),
+            ])
+        );
+
+        // A symbol that can't match any finite sequence of tokens has no example.
+        assert_eq!(
+            example_tokens(&grammar, &[Symbol::terminal(0), Symbol::non_terminal(3)]),
+            None
+        );
+    }
+
+    #[test]
+    fn test_example_token_text() {
+        let grammar = expand_tokens(ExtractedLexicalGrammar {
+            separators: vec![Rule::pattern("\\s")],
+            variables: vec![
+                Variable {
+                    name: "keyword".to_string(),
+                    kind: VariableType::Anonymous,
+                    rule: Rule::string("function"),
+                },
+                Variable {
+                    name: "identifier".to_string(),
+                    kind: VariableType::Named,
+                    rule: Rule::pattern("[_A-Za-z][_A-Za-z0-9]*"),
+                },
+                Variable {
+                    name: "number".to_string(),
+                    kind: VariableType::Named,
+                    rule: Rule::pattern("\\d+(\\.\\d+)?|0x[0-9a-f]+"),
+                },
+                Variable {
+                    name: "string".to_string(),
+                    kind: VariableType::Named,
+                    rule: Rule::pattern("\"([^\"\\\\]|\\\\.)*\""),
+                },
+                Variable {
+                    name: "newline".to_string(),
+                    kind: VariableType::Named,
+                    rule: Rule::pattern("\\n"),
+                },
+            ],
+        })
+        .unwrap();
+
+        let text = |symbol| example_token_text(&grammar, symbol);
+        assert_eq!(text(Symbol::terminal(0)), Some("function".to_string()));
+        assert_eq!(text(Symbol::terminal(1)), Some("a".to_string()));
+        assert_eq!(text(Symbol::terminal(2)), Some("0".to_string()));
+        assert_eq!(text(Symbol::terminal(3)), Some("\"\"".to_string()));
+        assert_eq!(text(Symbol::terminal(4)), Some("\n".to_string()));
+        assert_eq!(text(Symbol::end()), Some(String::new()));
+        assert_eq!(text(Symbol::external(0)), None);
+    }
+}
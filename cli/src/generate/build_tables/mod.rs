@@ -1,6 +1,7 @@
 pub(crate) mod build_lex_table;
 pub(crate) mod build_parse_table;
 mod coincident_tokens;
+mod conflict_examples;
 mod item;
 mod item_set_builder;
 mod minimize_parse_table;
@@ -9,6 +9,7 @@ pub mod parse_grammar;
 mod prepare_grammar;
 mod render;
 mod rules;
+mod rust_ast;
 mod tables;
 
 use self::build_tables::build_tables;
@@ -35,13 +36,15 @@ lazy_static! {
 struct GeneratedParser {
     c_code: String,
     node_types_json: String,
+    node_types: Vec<node_types::NodeInfoJSON>,
 }
 
 pub fn generate_parser_in_directory(
     repo_path: &PathBuf,
     grammar_path: Option<&str>,
-    next_abi: bool,
+    abi_version: usize,
     generate_bindings: bool,
+    generate_rust_ast: bool,
     report_symbol_name: Option<&str>,
 ) -> Result<()> {
     let src_path = repo_path.join("src");
@@ -74,27 +77,35 @@ pub fn generate_parser_in_directory(
     let GeneratedParser {
         c_code,
         node_types_json,
+        node_types,
     } = generate_parser_for_grammar_with_opts(
         &language_name,
         syntax_grammar,
         lexical_grammar,
         inlines,
         simple_aliases,
-        next_abi,
+        abi_version,
         report_symbol_name,
     )?;
 
     write_file(&src_path.join("parser.c"), c_code)?;
     write_file(&src_path.join("node-types.json"), node_types_json)?;
 
-    if next_abi {
-        write_file(&header_path.join("parser.h"), tree_sitter::PARSER_HEADER)?;
-    }
+    write_file(&header_path.join("parser.h"), tree_sitter::PARSER_HEADER)?;
 
     if generate_bindings {
         binding_files::generate_binding_files(&repo_path, &language_name)?;
     }
 
+    if generate_rust_ast {
+        let rust_binding_dir = repo_path.join("bindings").join("rust");
+        fs::create_dir_all(&rust_binding_dir)?;
+        write_file(
+            &rust_binding_dir.join("ast.rs"),
+            rust_ast::generate_rust_ast(&node_types),
+        )?;
+    }
+
     Ok(())
 }
 
@@ -109,7 +120,7 @@ pub fn generate_parser_for_grammar(grammar_json: &str) -> Result<(String, String
         lexical_grammar,
         inlines,
         simple_aliases,
-        true,
+        tree_sitter::LANGUAGE_VERSION,
         None,
     )?;
     Ok((input_grammar.name, parser.c_code))
@@ -121,7 +132,7 @@ fn generate_parser_for_grammar_with_opts(
     lexical_grammar: LexicalGrammar,
     inlines: InlinedProductionMap,
     simple_aliases: AliasMap,
-    next_abi: bool,
+    abi_version: usize,
     report_symbol_name: Option<&str>,
 ) -> Result<GeneratedParser> {
     let variable_info =
@@ -132,6 +143,8 @@ fn generate_parser_for_grammar_with_opts(
         &simple_aliases,
         &variable_info,
     );
+    let supertype_symbol_map =
+        node_types::get_supertype_symbol_map(&syntax_grammar, &variable_info);
     let (parse_table, main_lex_table, keyword_lex_table, keyword_capture_token) = build_tables(
         &syntax_grammar,
         &lexical_grammar,
@@ -149,11 +162,13 @@ fn generate_parser_for_grammar_with_opts(
         syntax_grammar,
         lexical_grammar,
         simple_aliases,
-        next_abi,
+        supertype_symbol_map,
+        abi_version,
     );
     Ok(GeneratedParser {
         c_code,
         node_types_json: serde_json::to_string_pretty(&node_types_json).unwrap(),
+        node_types: node_types_json,
     })
 }
 
@@ -1,7 +1,7 @@
 use super::grammars::{LexicalGrammar, SyntaxGrammar, VariableType};
 use super::rules::{Alias, AliasMap, Symbol, SymbolType};
 use crate::error::{Error, Result};
-use serde_derive::Serialize;
+use serde_derive::{Deserialize, Serialize};
 use std::cmp::Ordering;
 use std::collections::{BTreeMap, HashMap, HashSet};
 
@@ -25,31 +25,31 @@ pub(crate) struct VariableInfo {
     pub has_multi_step_production: bool,
 }
 
-#[derive(Debug, Serialize, PartialEq, Eq, Default, PartialOrd, Ord)]
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Default, PartialOrd, Ord)]
 pub(crate) struct NodeInfoJSON {
     #[serde(rename = "type")]
-    kind: String,
-    named: bool,
+    pub kind: String,
+    pub named: bool,
     #[serde(skip_serializing_if = "Option::is_none")]
-    fields: Option<BTreeMap<String, FieldInfoJSON>>,
+    pub fields: Option<BTreeMap<String, FieldInfoJSON>>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    children: Option<FieldInfoJSON>,
+    pub children: Option<FieldInfoJSON>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    subtypes: Option<Vec<NodeTypeJSON>>,
+    pub subtypes: Option<Vec<NodeTypeJSON>>,
 }
 
-#[derive(Clone, Debug, Serialize, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub(crate) struct NodeTypeJSON {
     #[serde(rename = "type")]
-    kind: String,
-    named: bool,
+    pub kind: String,
+    pub named: bool,
 }
 
-#[derive(Debug, Serialize, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
 pub(crate) struct FieldInfoJSON {
-    multiple: bool,
-    required: bool,
-    types: Vec<NodeTypeJSON>,
+    pub multiple: bool,
+    pub required: bool,
+    pub types: Vec<NodeTypeJSON>,
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
@@ -356,6 +356,24 @@ pub(crate) fn get_variable_info(
     Ok(result)
 }
 
+/// Get the list of subtypes for each of the grammar's supertype symbols.
+///
+/// The subtypes are the visible child types of each supertype, as they appear
+/// in the `subtypes` list of the `node-types.json` file.
+pub(crate) fn get_supertype_symbol_map(
+    syntax_grammar: &SyntaxGrammar,
+    variable_info: &Vec<VariableInfo>,
+) -> BTreeMap<Symbol, Vec<ChildType>> {
+    let mut result = BTreeMap::new();
+    for supertype_symbol in &syntax_grammar.supertype_symbols {
+        let mut subtypes = variable_info[supertype_symbol.index].children.types.clone();
+        subtypes.sort_unstable();
+        subtypes.dedup();
+        result.insert(*supertype_symbol, subtypes);
+    }
+    result
+}
+
 pub(crate) fn generate_node_types_json(
     syntax_grammar: &SyntaxGrammar,
     lexical_grammar: &LexicalGrammar,
@@ -1,5 +1,6 @@
 use super::char_tree::{CharacterTree, Comparator};
 use super::grammars::{ExternalToken, LexicalGrammar, SyntaxGrammar, VariableType};
+use super::node_types::ChildType;
 use super::rules::{Alias, AliasMap, Symbol, SymbolType};
 use super::tables::{
     AdvanceAction, FieldLocation, GotoAction, LexState, LexTable, ParseAction, ParseTable,
@@ -7,12 +8,13 @@ use super::tables::{
 };
 use core::ops::Range;
 use std::cmp;
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fmt::Write;
 use std::mem::swap;
 
 const LARGE_CHARACTER_RANGE_COUNT: usize = 8;
 const SMALL_STATE_THRESHOLD: usize = 64;
+const ABI_VERSION_WITH_SUPERTYPES: usize = 14;
 
 macro_rules! add {
     ($this: tt, $($arg: tt)*) => {{
@@ -67,9 +69,9 @@ struct Generator {
     unique_aliases: Vec<Alias>,
     symbol_map: HashMap<Symbol, Symbol>,
     field_names: Vec<String>,
+    supertype_symbol_map: BTreeMap<Symbol, Vec<ChildType>>,
 
-    #[allow(unused)]
-    next_abi: bool,
+    abi_version: usize,
 }
 
 struct TransitionSummary {
@@ -107,6 +109,10 @@ impl Generator {
 
         self.add_non_terminal_alias_map();
 
+        if self.has_supertype_map() {
+            self.add_supertype_map();
+        }
+
         let mut main_lex_table = LexTable::default();
         swap(&mut main_lex_table, &mut self.main_lex_table);
         self.add_lex_function("ts_lex", main_lex_table, true);
@@ -210,7 +216,8 @@ impl Generator {
                     if let Some(existing_symbol) = existing_symbol {
                         alias_id = self.symbol_ids[&self.symbol_map[&existing_symbol]].clone();
                     }
-                    // Other aliases don't match any existing symbol, and need their own identifiers.
+                    // Other aliases don't match any existing symbol, and need their own
+                    // identifiers.
                     else {
                         if let Err(i) = self.unique_aliases.binary_search(alias) {
                             self.unique_aliases.insert(i, alias.clone());
@@ -290,15 +297,7 @@ impl Generator {
             })
             .count();
 
-        add_line!(
-            self,
-            "#define LANGUAGE_VERSION {}",
-            if self.next_abi {
-                tree_sitter::LANGUAGE_VERSION
-            } else {
-                tree_sitter::LANGUAGE_VERSION - 1
-            }
-        );
+        add_line!(self, "#define LANGUAGE_VERSION {}", self.abi_version);
 
         add_line!(
             self,
@@ -330,6 +329,13 @@ impl Generator {
             "#define PRODUCTION_ID_COUNT {}",
             self.parse_table.production_infos.len()
         );
+        if self.abi_version >= ABI_VERSION_WITH_SUPERTYPES {
+            add_line!(
+                self,
+                "#define SUPERTYPE_COUNT {}",
+                self.supertype_ids().len()
+            );
+        }
         add_line!(self, "");
     }
 
@@ -543,7 +549,10 @@ impl Generator {
         let mut alias_ids_by_symbol = alias_ids_by_symbol.iter().collect::<Vec<_>>();
         alias_ids_by_symbol.sort_unstable_by_key(|e| e.0);
 
-        add_line!(self, "static const uint16_t ts_non_terminal_alias_map[] = {{");
+        add_line!(
+            self,
+            "static const uint16_t ts_non_terminal_alias_map[] = {{"
+        );
         indent!(self);
         for (symbol, alias_ids) in alias_ids_by_symbol {
             let symbol_id = &self.symbol_ids[symbol];
@@ -562,6 +571,68 @@ impl Generator {
         add_line!(self, "");
     }
 
+    fn add_supertype_map(&mut self) {
+        let subtype_ids_by_supertype = self.supertype_ids();
+
+        add_line!(
+            self,
+            "static const TSSymbol ts_supertype_symbols[SUPERTYPE_COUNT] = {{"
+        );
+        indent!(self);
+        for (supertype_id, _) in &subtype_ids_by_supertype {
+            add_line!(self, "{},", supertype_id);
+        }
+        dedent!(self);
+        add_line!(self, "}};");
+        add_line!(self, "");
+
+        add_line!(
+            self,
+            "static const TSMapSlice ts_supertype_map_slices[] = {{"
+        );
+        indent!(self);
+        let mut index = 0;
+        for (supertype_id, subtype_ids) in &subtype_ids_by_supertype {
+            add_line!(
+                self,
+                "[{}] = {{.index = {}, .length = {}}},",
+                supertype_id,
+                index,
+                subtype_ids.len()
+            );
+            index += subtype_ids.len();
+        }
+        dedent!(self);
+        add_line!(self, "}};");
+        add_line!(self, "");
+
+        if !self.has_supertype_map_entries() {
+            return;
+        }
+
+        add_line!(
+            self,
+            "static const TSSymbol ts_supertype_map_entries[] = {{"
+        );
+        indent!(self);
+        let mut index = 0;
+        for (_, subtype_ids) in subtype_ids_by_supertype {
+            if subtype_ids.is_empty() {
+                continue;
+            }
+            add_line!(self, "[{}] =", index);
+            indent!(self);
+            for subtype_id in &subtype_ids {
+                add_line!(self, "{},", subtype_id);
+            }
+            dedent!(self);
+            index += subtype_ids.len();
+        }
+        dedent!(self);
+        add_line!(self, "}};");
+        add_line!(self, "");
+    }
+
     fn add_field_sequences(&mut self) {
         let mut flat_field_maps = vec![];
         let mut next_flat_field_map_index = 0;
@@ -962,7 +1033,10 @@ impl Generator {
     }
 
     fn add_lex_modes_list(&mut self) {
-        add_line!(self, "static const TSLexMode ts_lex_modes[STATE_COUNT] = {{");
+        add_line!(
+            self,
+            "static const TSLexMode ts_lex_modes[STATE_COUNT] = {{"
+        );
         indent!(self);
         for (i, state) in self.parse_table.states.iter().enumerate() {
             if state.is_end_of_non_terminal_extra() {
@@ -1200,7 +1274,10 @@ impl Generator {
             add_line!(self, "}};");
             add_line!(self, "");
 
-            add_line!(self, "static const uint32_t ts_small_parse_table_map[] = {{");
+            add_line!(
+                self,
+                "static const uint32_t ts_small_parse_table_map[] = {{"
+            );
             indent!(self);
             for i in self.large_state_count..self.parse_table.states.len() {
                 add_line!(
@@ -1219,7 +1296,10 @@ impl Generator {
     }
 
     fn add_parse_action_list(&mut self, parse_table_entries: Vec<(usize, ParseTableEntry)>) {
-        add_line!(self, "static const TSParseActionEntry ts_parse_actions[] = {{");
+        add_line!(
+            self,
+            "static const TSParseActionEntry ts_parse_actions[] = {{"
+        );
         indent!(self);
         for (i, entry) in parse_table_entries {
             add!(
@@ -1322,9 +1402,7 @@ impl Generator {
         add_line!(self, ".external_token_count = EXTERNAL_TOKEN_COUNT,");
         add_line!(self, ".state_count = STATE_COUNT,");
         add_line!(self, ".large_state_count = LARGE_STATE_COUNT,");
-        if self.next_abi {
-            add_line!(self, ".production_id_count = PRODUCTION_ID_COUNT,");
-        }
+        add_line!(self, ".production_id_count = PRODUCTION_ID_COUNT,");
         add_line!(self, ".field_count = FIELD_COUNT,");
         add_line!(
             self,
@@ -1334,14 +1412,8 @@ impl Generator {
         // Parse table
         add_line!(self, ".parse_table = &ts_parse_table[0][0],");
         if self.large_state_count < self.parse_table.states.len() {
-            add_line!(
-                self,
-                ".small_parse_table = ts_small_parse_table,"
-            );
-            add_line!(
-                self,
-                ".small_parse_table_map = ts_small_parse_table_map,"
-            );
+            add_line!(self, ".small_parse_table = ts_small_parse_table,");
+            add_line!(self, ".small_parse_table_map = ts_small_parse_table_map,");
         }
         add_line!(self, ".parse_actions = ts_parse_actions,");
 
@@ -1349,23 +1421,14 @@ impl Generator {
         add_line!(self, ".symbol_names = ts_symbol_names,");
         if !self.field_names.is_empty() {
             add_line!(self, ".field_names = ts_field_names,");
-            add_line!(
-                self,
-                ".field_map_slices = ts_field_map_slices,"
-            );
-            add_line!(
-                self,
-                ".field_map_entries = ts_field_map_entries,"
-            );
+            add_line!(self, ".field_map_slices = ts_field_map_slices,");
+            add_line!(self, ".field_map_entries = ts_field_map_entries,");
         }
         add_line!(self, ".symbol_metadata = ts_symbol_metadata,");
         add_line!(self, ".public_symbol_map = ts_symbol_map,");
         add_line!(self, ".alias_map = ts_non_terminal_alias_map,");
         if !self.parse_table.production_infos.is_empty() {
-            add_line!(
-                self,
-                ".alias_sequences = &ts_alias_sequences[0][0],"
-            );
+            add_line!(self, ".alias_sequences = &ts_alias_sequences[0][0],");
         }
 
         // Lexing
@@ -1394,6 +1457,17 @@ impl Generator {
             add_line!(self, "}},");
         }
 
+        if self.abi_version >= ABI_VERSION_WITH_SUPERTYPES {
+            add_line!(self, ".supertype_count = SUPERTYPE_COUNT,");
+            if self.has_supertype_map() {
+                add_line!(self, ".supertype_symbols = ts_supertype_symbols,");
+                add_line!(self, ".supertype_map_slices = ts_supertype_map_slices,");
+                if self.has_supertype_map_entries() {
+                    add_line!(self, ".supertype_map_entries = ts_supertype_map_entries,");
+                }
+            }
+        }
+
         dedent!(self);
         add_line!(self, "}};");
         add_line!(self, "return &language;");
@@ -1471,6 +1545,51 @@ impl Generator {
         self.symbol_ids.insert(symbol, id);
     }
 
+    // Supertypes without a public symbol id are omitted from the generated
+    // tables, so this is the source of truth for `SUPERTYPE_COUNT`.
+    fn supertype_ids(&self) -> Vec<(String, Vec<String>)> {
+        self.supertype_symbol_map
+            .iter()
+            .filter_map(|(supertype, subtypes)| {
+                let supertype_id = self.public_symbol_id(*supertype)?.clone();
+                let subtype_ids = subtypes
+                    .iter()
+                    .filter_map(|subtype| self.child_type_id(subtype))
+                    .collect();
+                Some((supertype_id, subtype_ids))
+            })
+            .collect()
+    }
+
+    fn has_supertype_map(&self) -> bool {
+        self.abi_version >= ABI_VERSION_WITH_SUPERTYPES && !self.supertype_ids().is_empty()
+    }
+
+    fn has_supertype_map_entries(&self) -> bool {
+        self.supertype_ids()
+            .iter()
+            .any(|(_, subtype_ids)| !subtype_ids.is_empty())
+    }
+
+    fn public_symbol_id(&self, symbol: Symbol) -> Option<&String> {
+        self.symbol_map
+            .get(&symbol)
+            .and_then(|symbol| self.symbol_ids.get(symbol))
+    }
+
+    fn child_type_id(&self, child_type: &ChildType) -> Option<String> {
+        match child_type {
+            ChildType::Normal(symbol) => self.public_symbol_id(*symbol).cloned(),
+            ChildType::Aliased(alias) => self.alias_ids.get(alias).cloned().or_else(|| {
+                self.parse_table
+                    .symbols
+                    .iter()
+                    .find(|symbol| self.default_aliases.get(symbol) == Some(alias))
+                    .and_then(|symbol| self.public_symbol_id(*symbol).cloned())
+            }),
+        }
+    }
+
     fn field_id(&self, field_name: &String) -> String {
         format!("field_{}", field_name)
     }
@@ -1600,8 +1719,11 @@ impl Generator {
 /// * `default_aliases` - A map describing the global rename rules that should apply.
 ///    the keys are symbols that are *always* aliased in the same way, and the values
 ///    are the aliases that are applied to those symbols.
-/// * `next_abi` - A boolean indicating whether to opt into the new, unstable parse
-///    table format. This is mainly used for testing, when developing Tree-sitter itself.
+/// * `supertype_symbol_map` - A map from each of the grammar's supertype symbols to
+///    the list of that supertype's visible subtypes.
+/// * `abi_version` - The version of the parse table format to generate. This is
+///    normally the version before the library's current `LANGUAGE_VERSION`, but the
+///    current version can be opted into with `tree-sitter generate --next-abi`.
 pub(crate) fn render_c_code(
     name: &str,
     parse_table: ParseTable,
@@ -1611,7 +1733,8 @@ pub(crate) fn render_c_code(
     syntax_grammar: SyntaxGrammar,
     lexical_grammar: LexicalGrammar,
     default_aliases: AliasMap,
-    next_abi: bool,
+    supertype_symbol_map: BTreeMap<Symbol, Vec<ChildType>>,
+    abi_version: usize,
 ) -> String {
     Generator {
         buffer: String::new(),
@@ -1631,7 +1754,8 @@ pub(crate) fn render_c_code(
         symbol_map: HashMap::new(),
         unique_aliases: Vec::new(),
         field_names: Vec::new(),
-        next_abi,
+        supertype_symbol_map,
+        abi_version,
     }
     .generate()
 }
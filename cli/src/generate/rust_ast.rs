@@ -0,0 +1,645 @@
+use super::node_types::{FieldInfoJSON, NodeInfoJSON, NodeTypeJSON};
+use std::collections::{HashMap, HashSet};
+
+// Names that the generated code uses for its own items.
+const RESERVED_TYPE_NAMES: &'static [&'static str] = &["AstNode", "Children", "Self"];
+
+// Names of the methods that every generated type has, either inherently or via `AstNode`.
+const RESERVED_METHOD_NAMES: &'static [&'static str] = &["cast", "child", "children", "node"];
+
+const KEYWORDS: &'static [&'static str] = &[
+    "abstract", "as", "async", "await", "become", "box", "break", "const", "continue", "do", "dyn",
+    "else", "enum", "extern", "false", "final", "fn", "for", "if", "impl", "in", "let", "loop",
+    "macro", "match", "mod", "move", "mut", "override", "priv", "pub", "ref", "return", "static",
+    "struct", "trait", "true", "try", "type", "typeof", "unsafe", "unsized", "use", "virtual",
+    "where", "while", "yield",
+];
+
+// Keywords that can't be used as raw identifiers.
+const NON_RAW_KEYWORDS: &'static [&'static str] = &["crate", "self", "super", "Self"];
+
+const PRELUDE: &'static str = "
+/// A typed wrapper around a syntax node.
+pub trait AstNode<'tree>: Sized {
+    /// Wrap the given node, if it is of the right kind.
+    fn cast(node: tree_sitter::Node<'tree>) -> ::std::option::Option<Self>;
+
+    /// Get the underlying syntax node.
+    fn node(&self) -> tree_sitter::Node<'tree>;
+}
+
+impl<'tree> AstNode<'tree> for tree_sitter::Node<'tree> {
+    fn cast(node: tree_sitter::Node<'tree>) -> ::std::option::Option<Self> {
+        ::std::option::Option::Some(node)
+    }
+
+    fn node(&self) -> tree_sitter::Node<'tree> {
+        *self
+    }
+}
+
+/// An iterator over the children of a node in a given field, or over the
+/// children that are not in any field, that can be wrapped as a `T`.
+pub struct Children<'tree, T> {
+    cursor: tree_sitter::TreeCursor<'tree>,
+    field_name: ::std::option::Option<&'static str>,
+    done: bool,
+    _type: ::std::marker::PhantomData<T>,
+}
+
+impl<'tree, T> Children<'tree, T> {
+    fn new(
+        node: tree_sitter::Node<'tree>,
+        field_name: ::std::option::Option<&'static str>,
+    ) -> Self {
+        let mut cursor = node.walk();
+        let done = !cursor.goto_first_child();
+        Children {
+            cursor,
+            field_name,
+            done,
+            _type: ::std::marker::PhantomData,
+        }
+    }
+}
+
+impl<'tree, T: AstNode<'tree>> ::std::iter::Iterator for Children<'tree, T> {
+    type Item = T;
+
+    fn next(&mut self) -> ::std::option::Option<T> {
+        while !self.done {
+            let node = self.cursor.node();
+            let field_name = self.cursor.field_name();
+            self.done = !self.cursor.goto_next_sibling();
+            if field_name == self.field_name {
+                if let ::std::option::Option::Some(child) = T::cast(node) {
+                    return ::std::option::Option::Some(child);
+                }
+            }
+        }
+        ::std::option::Option::None
+    }
+}
+";
+
+macro_rules! add_line {
+    ($this: tt, $($arg: tt)*) => {{
+        let line = format!($($arg)*);
+        if !line.is_empty() {
+            for _ in 0..$this.indent_level {
+                $this.buffer += "    ";
+            }
+        }
+        $this.buffer += &line;
+        $this.buffer += "\n";
+    }}
+}
+
+macro_rules! indent {
+    ($this: tt) => {
+        $this.indent_level += 1;
+    };
+}
+
+macro_rules! dedent {
+    ($this: tt) => {
+        assert_ne!($this.indent_level, 0);
+        $this.indent_level -= 1;
+    };
+}
+
+struct Generator<'a> {
+    buffer: String,
+    indent_level: usize,
+    node_types: &'a [NodeInfoJSON],
+    type_names: HashMap<&'a str, String>,
+    used_type_names: HashSet<String>,
+}
+
+// The type that is used for a set of possible child nodes.
+enum ChildTypeName<'a> {
+    // A single named node kind.
+    Single(&'a str),
+    // Only anonymous nodes, which are represented as plain syntax nodes.
+    Anonymous,
+    // A generated enum with one variant for each named node kind.
+    Enum(String),
+}
+
+/// Generate Rust source code containing a typed wrapper for each named node
+/// kind in the given node types.
+///
+/// Each wrapper is a struct containing a `tree_sitter::Node`, with one accessor
+/// method for each field, and one for the children that are not in a field.
+/// Supertypes, along with fields and children that can contain several kinds
+/// of nodes, are represented as enums.
+pub(crate) fn generate_rust_ast(node_types: &[NodeInfoJSON]) -> String {
+    let mut generator = Generator {
+        buffer: String::new(),
+        indent_level: 0,
+        node_types,
+        type_names: HashMap::new(),
+        used_type_names: RESERVED_TYPE_NAMES.iter().map(|s| s.to_string()).collect(),
+    };
+    generator.generate();
+    generator.buffer
+}
+
+impl<'a> Generator<'a> {
+    fn generate(&mut self) {
+        for node_type in self.named_node_types() {
+            let name = self.unique_type_name(camel_case(&node_type.kind));
+            self.type_names.insert(&node_type.kind, name);
+        }
+
+        add_line!(
+            self,
+            "// This file was generated by `tree-sitter generate --rust-ast`. Do not edit it by hand."
+        );
+        add_line!(self, "");
+        add_line!(
+            self,
+            "//! Typed wrappers for the syntax nodes of this grammar, derived from `node-types.json`."
+        );
+        self.buffer += PRELUDE;
+
+        for node_type in self.named_node_types() {
+            add_line!(self, "");
+            match &node_type.subtypes {
+                Some(subtypes) => self.add_supertype(node_type, subtypes),
+                None => self.add_node_struct(node_type),
+            }
+        }
+    }
+
+    fn named_node_types(&self) -> impl Iterator<Item = &'a NodeInfoJSON> {
+        self.node_types.iter().filter(|node_type| node_type.named)
+    }
+
+    fn add_supertype(&mut self, node_type: &NodeInfoJSON, subtypes: &[NodeTypeJSON]) {
+        let name = self.type_names[node_type.kind.as_str()].clone();
+        add_line!(self, "/// A `{}` node.", node_type.kind);
+        self.add_enum(&name, subtypes);
+    }
+
+    fn add_node_struct(&mut self, node_type: &'a NodeInfoJSON) {
+        let name = self.type_names[node_type.kind.as_str()].clone();
+        add_line!(self, "/// A `{}` node.", node_type.kind);
+        add_line!(self, "#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]");
+        add_line!(self, "#[repr(transparent)]");
+        add_line!(
+            self,
+            "pub struct {}<'tree>(tree_sitter::Node<'tree>);",
+            name
+        );
+        add_line!(self, "");
+
+        // Choose the types of the children before generating any code, because
+        // the types may require additional enums.
+        let mut accessors = Vec::new();
+        for (field_name, field) in node_type.fields.iter().flatten() {
+            let type_name = self.child_type_name(&name, &camel_case(field_name), &field.types);
+            let mut method_name = snake_case(field_name);
+            if RESERVED_METHOD_NAMES.contains(&method_name.as_str()) {
+                method_name += "_field";
+            }
+            accessors.push((Some(field_name), method_name, field, type_name));
+        }
+        if let Some(children) = &node_type.children {
+            let type_name = self.child_type_name(&name, "Child", &children.types);
+            let method_name = if children.multiple {
+                "children"
+            } else {
+                "child"
+            };
+            accessors.push((None, method_name.to_string(), children, type_name));
+        }
+
+        add_line!(self, "impl<'tree> {}<'tree> {{", name);
+        indent!(self);
+        add_line!(self, "pub const KIND: &'static str = {:?};", node_type.kind);
+        for (field_name, method_name, field, type_name) in &accessors {
+            self.add_accessor(*field_name, method_name, field, type_name);
+        }
+        dedent!(self);
+        add_line!(self, "}}");
+        add_line!(self, "");
+
+        add_line!(self, "impl<'tree> AstNode<'tree> for {}<'tree> {{", name);
+        indent!(self);
+        add_line!(
+            self,
+            "fn cast(node: tree_sitter::Node<'tree>) -> ::std::option::Option<Self> {{"
+        );
+        indent!(self);
+        add_line!(self, "if node.is_named() && node.kind() == Self::KIND {{");
+        indent!(self);
+        add_line!(self, "::std::option::Option::Some({}(node))", name);
+        dedent!(self);
+        add_line!(self, "}} else {{");
+        indent!(self);
+        add_line!(self, "::std::option::Option::None");
+        dedent!(self);
+        add_line!(self, "}}");
+        dedent!(self);
+        add_line!(self, "}}");
+        add_line!(self, "");
+        add_line!(self, "fn node(&self) -> tree_sitter::Node<'tree> {{");
+        indent!(self);
+        add_line!(self, "self.0");
+        dedent!(self);
+        add_line!(self, "}}");
+        dedent!(self);
+        add_line!(self, "}}");
+
+        for (_, _, field, type_name) in accessors {
+            if let ChildTypeName::Enum(enum_name) = type_name {
+                add_line!(self, "");
+                add_line!(self, "/// A child of a `{}` node.", node_type.kind);
+                self.add_enum(&enum_name, &field.types);
+            }
+        }
+    }
+
+    fn add_accessor(
+        &mut self,
+        field_name: Option<&String>,
+        method_name: &str,
+        field: &FieldInfoJSON,
+        type_name: &ChildTypeName,
+    ) {
+        let type_name = match type_name {
+            ChildTypeName::Single(kind) => format!("{}<'tree>", self.type_names[kind]),
+            ChildTypeName::Anonymous => "tree_sitter::Node<'tree>".to_string(),
+            ChildTypeName::Enum(name) => format!("{}<'tree>", name),
+        };
+        let field_name_arg = match field_name {
+            Some(field_name) => format!("::std::option::Option::Some({:?})", field_name),
+            None => "::std::option::Option::None".to_string(),
+        };
+
+        add_line!(self, "");
+        match field_name {
+            Some(field_name) => add_line!(self, "/// The `{}` field.", field_name),
+            None => add_line!(self, "/// The named children that are not in any field."),
+        }
+        if field.required && !field.multiple {
+            add_line!(self, "///");
+            add_line!(
+                self,
+                "/// This is always present, unless the tree contains errors."
+            );
+        }
+        if field.multiple {
+            add_line!(
+                self,
+                "pub fn {}(&self) -> Children<'tree, {}> {{",
+                method_name,
+                type_name
+            );
+            indent!(self);
+            add_line!(self, "Children::new(self.0, {})", field_name_arg);
+        } else {
+            add_line!(
+                self,
+                "pub fn {}(&self) -> ::std::option::Option<{}> {{",
+                method_name,
+                type_name
+            );
+            indent!(self);
+            match field_name {
+                Some(field_name) => add_line!(
+                    self,
+                    "self.0.child_by_field_name({:?}).and_then(AstNode::cast)",
+                    field_name
+                ),
+                None => add_line!(self, "Children::new(self.0, {}).next()", field_name_arg),
+            }
+        }
+        dedent!(self);
+        add_line!(self, "}}");
+    }
+
+    fn add_enum(&mut self, name: &str, types: &[NodeTypeJSON]) {
+        let variants = types
+            .iter()
+            .filter(|t| t.named)
+            .filter_map(|t| Some((t, self.type_names.get(t.kind.as_str())?.clone())))
+            .collect::<Vec<_>>();
+        let has_anonymous_variant = types.iter().any(|t| !t.named);
+
+        add_line!(self, "#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]");
+        add_line!(self, "pub enum {}<'tree> {{", name);
+        indent!(self);
+        for (_, variant_name) in &variants {
+            add_line!(self, "{}({}<'tree>),", variant_name, variant_name);
+        }
+        if has_anonymous_variant {
+            add_line!(self, "/// An anonymous node.");
+            add_line!(self, "Token(tree_sitter::Node<'tree>),");
+        }
+        dedent!(self);
+        add_line!(self, "}}");
+        add_line!(self, "");
+
+        add_line!(self, "impl<'tree> AstNode<'tree> for {}<'tree> {{", name);
+        indent!(self);
+        add_line!(
+            self,
+            "fn cast(node: tree_sitter::Node<'tree>) -> ::std::option::Option<Self> {{"
+        );
+        indent!(self);
+
+        // Nodes are matched by their kind, except for supertypes, which never
+        // appear in syntax trees themselves.
+        let (supertype_variants, kind_variants): (Vec<_>, Vec<_>) = variants
+            .iter()
+            .partition(|(node_type, _)| self.is_supertype(&node_type.kind));
+        let has_match = !kind_variants.is_empty();
+        if has_match {
+            add_line!(self, "match node.kind() {{");
+            indent!(self);
+            for (node_type, variant_name) in kind_variants {
+                add_line!(
+                    self,
+                    "{:?} if node.is_named() => ::std::option::Option::Some({}::{}({}(node))),",
+                    node_type.kind,
+                    name,
+                    variant_name,
+                    variant_name
+                );
+            }
+            add_line!(self, "_ => {{");
+            indent!(self);
+        }
+        for (_, variant_name) in &supertype_variants {
+            add_line!(
+                self,
+                "if let ::std::option::Option::Some(node) = {}::cast(node) {{",
+                variant_name
+            );
+            indent!(self);
+            add_line!(
+                self,
+                "return ::std::option::Option::Some({}::{}(node));",
+                name,
+                variant_name
+            );
+            dedent!(self);
+            add_line!(self, "}}");
+        }
+        if has_anonymous_variant {
+            add_line!(self, "if !node.is_named() {{");
+            indent!(self);
+            add_line!(
+                self,
+                "return ::std::option::Option::Some({}::Token(node));",
+                name
+            );
+            dedent!(self);
+            add_line!(self, "}}");
+        }
+        add_line!(self, "::std::option::Option::None");
+        if has_match {
+            dedent!(self);
+            add_line!(self, "}}");
+            dedent!(self);
+            add_line!(self, "}}");
+        }
+        dedent!(self);
+        add_line!(self, "}}");
+        add_line!(self, "");
+
+        add_line!(self, "fn node(&self) -> tree_sitter::Node<'tree> {{");
+        indent!(self);
+        add_line!(self, "match self {{");
+        indent!(self);
+        for (_, variant_name) in &variants {
+            add_line!(self, "{}::{}(node) => node.node(),", name, variant_name);
+        }
+        if has_anonymous_variant {
+            add_line!(self, "{}::Token(node) => *node,", name);
+        }
+        dedent!(self);
+        add_line!(self, "}}");
+        dedent!(self);
+        add_line!(self, "}}");
+        dedent!(self);
+        add_line!(self, "}}");
+    }
+
+    fn child_type_name(
+        &mut self,
+        parent_name: &str,
+        suffix: &str,
+        types: &'a [NodeTypeJSON],
+    ) -> ChildTypeName<'a> {
+        let named_types = types
+            .iter()
+            .filter(|t| t.named && self.type_names.contains_key(t.kind.as_str()))
+            .collect::<Vec<_>>();
+        if named_types.is_empty() {
+            ChildTypeName::Anonymous
+        } else if named_types.len() == 1 && named_types.len() == types.len() {
+            ChildTypeName::Single(&named_types[0].kind)
+        } else {
+            ChildTypeName::Enum(self.unique_type_name(format!("{}{}", parent_name, suffix)))
+        }
+    }
+
+    fn is_supertype(&self, kind: &str) -> bool {
+        self.node_types
+            .iter()
+            .any(|t| t.named && t.kind == kind && t.subtypes.is_some())
+    }
+
+    fn unique_type_name(&mut self, mut name: String) -> String {
+        while self.used_type_names.contains(&name) {
+            name.push('_');
+        }
+        self.used_type_names.insert(name.clone());
+        name
+    }
+}
+
+fn camel_case(name: &str) -> String {
+    let mut result = String::new();
+    let mut capitalize = true;
+    for c in name.chars() {
+        if c.is_ascii_alphanumeric() {
+            if capitalize {
+                result.push(c.to_ascii_uppercase());
+                capitalize = false;
+            } else {
+                result.push(c);
+            }
+        } else {
+            capitalize = true;
+        }
+    }
+    if result.chars().next().map_or(true, |c| c.is_ascii_digit()) {
+        result.insert(0, 'N');
+    }
+    result
+}
+
+fn snake_case(name: &str) -> String {
+    let mut result = name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_lowercase()
+            } else {
+                '_'
+            }
+        })
+        .collect::<String>();
+    if result.chars().next().map_or(true, |c| c.is_ascii_digit()) {
+        result.insert(0, '_');
+    }
+    if NON_RAW_KEYWORDS.contains(&result.as_str()) {
+        result.push('_');
+    } else if KEYWORDS.contains(&result.as_str()) {
+        result.insert_str(0, "r#");
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rust_ast_structs_and_fields() {
+        let code = generate_rust_ast(&parse_node_types(
+            r#"[
+                {
+                    "type": "call",
+                    "named": true,
+                    "fields": {
+                        "function": {
+                            "multiple": false,
+                            "required": true,
+                            "types": [{"type": "identifier", "named": true}]
+                        },
+                        "type": {
+                            "multiple": false,
+                            "required": false,
+                            "types": [{"type": "identifier", "named": true}]
+                        },
+                        "arguments": {
+                            "multiple": true,
+                            "required": false,
+                            "types": [
+                                {"type": "identifier", "named": true},
+                                {"type": "number", "named": true},
+                                {"type": ",", "named": false}
+                            ]
+                        },
+                        "operator": {
+                            "multiple": false,
+                            "required": true,
+                            "types": [{"type": "!", "named": false}]
+                        }
+                    }
+                },
+                {"type": "identifier", "named": true},
+                {"type": "number", "named": true},
+                {"type": ",", "named": false},
+                {"type": "!", "named": false}
+            ]"#,
+        ));
+
+        assert!(code.contains("pub struct Call<'tree>(tree_sitter::Node<'tree>);"));
+        assert!(code.contains("pub struct Identifier<'tree>(tree_sitter::Node<'tree>);"));
+        assert!(code.contains("pub const KIND: &'static str = \"call\";"));
+        assert!(!code.contains("pub struct Comma"));
+
+        assert!(code.contains(
+            "pub fn function(&self) -> ::std::option::Option<Identifier<'tree>> {\n\
+             \x20       self.0.child_by_field_name(\"function\").and_then(AstNode::cast)\n"
+        ));
+        assert!(code.contains("pub fn r#type(&self) -> ::std::option::Option<Identifier<'tree>>"));
+        assert!(code.contains(
+            "pub fn arguments(&self) -> Children<'tree, CallArguments<'tree>> {\n\
+             \x20       Children::new(self.0, ::std::option::Option::Some(\"arguments\"))\n"
+        ));
+        assert!(code
+            .contains("pub fn operator(&self) -> ::std::option::Option<tree_sitter::Node<'tree>>"));
+        assert!(code.contains(
+            "pub enum CallArguments<'tree> {\n\
+             \x20   Identifier(Identifier<'tree>),\n\
+             \x20   Number(Number<'tree>),\n\
+             \x20   /// An anonymous node.\n\
+             \x20   Token(tree_sitter::Node<'tree>),\n\
+             }"
+        ));
+    }
+
+    #[test]
+    fn test_rust_ast_supertypes_and_children() {
+        let code = generate_rust_ast(&parse_node_types(
+            r#"[
+                {
+                    "type": "expression",
+                    "named": true,
+                    "subtypes": [
+                        {"type": "primary_expression", "named": true},
+                        {"type": "binary_expression", "named": true}
+                    ]
+                },
+                {
+                    "type": "primary_expression",
+                    "named": true,
+                    "subtypes": [{"type": "identifier", "named": true}]
+                },
+                {
+                    "type": "binary_expression",
+                    "named": true,
+                    "children": {
+                        "multiple": true,
+                        "required": true,
+                        "types": [{"type": "expression", "named": true}]
+                    }
+                },
+                {
+                    "type": "parenthesized",
+                    "named": true,
+                    "children": {
+                        "multiple": false,
+                        "required": true,
+                        "types": [{"type": "expression", "named": true}]
+                    }
+                },
+                {"type": "identifier", "named": true},
+                {"type": "self", "named": true}
+            ]"#,
+        ));
+
+        assert!(code.contains(
+            "pub enum Expression<'tree> {\n\
+             \x20   PrimaryExpression(PrimaryExpression<'tree>),\n\
+             \x20   BinaryExpression(BinaryExpression<'tree>),\n\
+             }"
+        ));
+        assert!(code.contains(
+            "        match node.kind() {\n\
+             \x20           \"binary_expression\" if node.is_named() => \
+             ::std::option::Option::Some(Expression::BinaryExpression(BinaryExpression(node))),\n\
+             \x20           _ => {\n\
+             \x20               if let ::std::option::Option::Some(node) = PrimaryExpression::cast(node) {\n"
+        ));
+        assert!(code.contains("pub fn children(&self) -> Children<'tree, Expression<'tree>> {"));
+        assert!(code.contains(
+            "pub fn child(&self) -> ::std::option::Option<Expression<'tree>> {\n\
+             \x20       Children::new(self.0, ::std::option::Option::None).next()\n"
+        ));
+
+        // Names that would clash with keywords or with the generated items are changed.
+        assert!(code.contains("pub struct Self_<'tree>"));
+    }
+
+    fn parse_node_types(json: &str) -> Vec<NodeInfoJSON> {
+        serde_json::from_str(json).unwrap()
+    }
+}
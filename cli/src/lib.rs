@@ -1,5 +1,6 @@
 pub mod config;
 pub mod error;
+pub mod fuzz;
 pub mod generate;
 pub mod highlight;
 pub mod loader;
@@ -10,6 +11,7 @@ pub mod query_testing;
 pub mod tags;
 pub mod test;
 pub mod test_highlight;
+pub mod test_report;
 pub mod util;
 pub mod wasm;
 pub mod web_ui;
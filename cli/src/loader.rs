@@ -12,8 +12,8 @@ use std::sync::Mutex;
 use std::time::SystemTime;
 use std::{fs, mem};
 use tree_sitter::{Language, QueryError};
-use tree_sitter_highlight::HighlightConfiguration;
-use tree_sitter_tags::{Error as TagsError, TagsConfiguration};
+use tree_sitter_highlight::{HighlightConfiguration, HighlightQueryError, QuerySection};
+use tree_sitter_tags::{Error as TagsError, QuerySection as TagsQuerySection, TagsConfiguration};
 
 #[cfg(unix)]
 const DYLIB_EXTENSION: &'static str = "so";
@@ -39,6 +39,19 @@ pub struct LanguageConfiguration<'a> {
     tags_config: OnceCell<Option<TagsConfiguration>>,
     highlight_names: &'a Mutex<Vec<String>>,
     use_all_highlight_names: bool,
+    query_paths: &'a Mutex<Vec<QueryPaths>>,
+}
+
+/// The locations of a language's query files. These are used to resolve the
+/// `; inherits:` modelines in the query files of other languages.
+struct QueryPaths {
+    name: String,
+    scope: Option<String>,
+    root_path: PathBuf,
+    highlights_filenames: Option<Vec<String>>,
+    injections_filenames: Option<Vec<String>>,
+    locals_filenames: Option<Vec<String>>,
+    tags_filenames: Option<Vec<String>>,
 }
 
 pub struct Loader {
@@ -48,6 +61,7 @@ pub struct Loader {
     language_configuration_ids_by_file_type: HashMap<String, Vec<usize>>,
     highlight_names: Box<Mutex<Vec<String>>>,
     use_all_highlight_names: bool,
+    query_paths: Box<Mutex<Vec<QueryPaths>>>,
 }
 
 unsafe impl Send for Loader {}
@@ -62,6 +76,7 @@ impl Loader {
             language_configuration_ids_by_file_type: HashMap::new(),
             highlight_names: Box::new(Mutex::new(Vec::new())),
             use_all_highlight_names: true,
+            query_paths: Box::new(Mutex::new(Vec::new())),
         }
     }
 
@@ -112,6 +127,20 @@ impl Loader {
         }
     }
 
+    /// Get the languages of the grammars in the given directory, along with
+    /// their names. A grammar's name is the name of its directory, without the
+    /// `tree-sitter-` prefix.
+    pub fn named_languages_at_path(&mut self, path: &Path) -> Result<Vec<(String, Language)>> {
+        let languages = self.languages_at_path(path)?;
+        Ok(languages
+            .into_iter()
+            .map(|language| {
+                let path = self.language_path(language).unwrap_or(path);
+                (grammar_name(path).to_string(), language)
+            })
+            .collect())
+    }
+
     pub fn get_all_language_configurations(&self) -> Vec<(&LanguageConfiguration, &Path)> {
         self.language_configurations
             .iter()
@@ -230,6 +259,14 @@ impl Loader {
         }
     }
 
+    /// Get the directory of a grammar whose language has already been loaded.
+    pub fn language_path(&self, language: Language) -> Option<&Path> {
+        self.languages_by_id
+            .iter()
+            .find(|(_, l)| l.get() == Some(&language))
+            .map(|(path, _)| path.as_ref())
+    }
+
     fn language_for_id(&self, id: usize) -> Result<Language> {
         let (path, language) = &self.languages_by_id[id];
         language
@@ -493,8 +530,10 @@ impl Loader {
                         tags_config: OnceCell::new(),
                         highlight_names: &*self.highlight_names,
                         use_all_highlight_names: self.use_all_highlight_names,
+                        query_paths: &*self.query_paths,
                     };
 
+                    self.add_query_paths(&configuration);
                     for file_type in &configuration.file_types {
                         self.language_configuration_ids_by_file_type
                             .entry(file_type.to_string())
@@ -527,7 +566,9 @@ impl Loader {
                 tags_config: OnceCell::new(),
                 highlight_names: &*self.highlight_names,
                 use_all_highlight_names: self.use_all_highlight_names,
+                query_paths: &*self.query_paths,
             };
+            self.add_query_paths(&configuration);
             self.language_configurations
                 .push(unsafe { mem::transmute(configuration) });
             self.languages_by_id
@@ -537,11 +578,31 @@ impl Loader {
         Ok(&self.language_configurations[initial_language_configuration_count..])
     }
 
+    fn add_query_paths(&self, configuration: &LanguageConfiguration) {
+        // Other languages' queries refer to this language by the name of its
+        // directory, without the `tree-sitter-` prefix, or by its scope.
+        let name = grammar_name(&configuration.root_path);
+        self.query_paths.lock().unwrap().push(QueryPaths {
+            name: name.to_string(),
+            scope: configuration.scope.clone(),
+            root_path: configuration.root_path.clone(),
+            highlights_filenames: configuration.highlights_filenames.clone(),
+            injections_filenames: configuration.injections_filenames.clone(),
+            locals_filenames: configuration.locals_filenames.clone(),
+            tags_filenames: configuration.tags_filenames.clone(),
+        });
+    }
+
     fn regex(pattern: Option<String>) -> Option<Regex> {
         pattern.and_then(|r| RegexBuilder::new(&r).multi_line(true).build().ok())
     }
 }
 
+fn grammar_name(path: &Path) -> &str {
+    let name = path.file_name().and_then(|name| name.to_str()).unwrap_or("");
+    name.strip_prefix("tree-sitter-").unwrap_or(name)
+}
+
 impl<'a> LanguageConfiguration<'a> {
     pub fn highlight_config(&self, language: Language) -> Result<Option<&HighlightConfiguration>> {
         return self
@@ -563,29 +624,13 @@ impl<'a> LanguageConfiguration<'a> {
                         &injections_query,
                         &locals_query,
                     )
-                    .map_err(|error| {
-                        if error.offset < injections_query.len() {
-                            Self::include_path_in_query_error(
-                                error,
-                                &injection_ranges,
-                                &injections_query,
-                                0,
-                            )
-                        } else if error.offset < injections_query.len() + locals_query.len() {
-                            Self::include_path_in_query_error(
-                                error,
-                                &locals_ranges,
-                                &locals_query,
-                                injections_query.len(),
-                            )
-                        } else {
-                            Self::include_path_in_query_error(
-                                error,
-                                &highlight_ranges,
-                                &highlights_query,
-                                injections_query.len() + locals_query.len(),
-                            )
-                        }
+                    .map_err(|HighlightQueryError { section, error }| {
+                        let (source, ranges) = match section {
+                            QuerySection::Injections => (&injections_query, &injection_ranges),
+                            QuerySection::Locals => (&locals_query, &locals_ranges),
+                            QuerySection::Highlights => (&highlights_query, &highlight_ranges),
+                        };
+                        Self::query_error(error, source, ranges)
                     })?;
                     let mut all_highlight_names = self.highlight_names.lock().unwrap();
                     if self.use_all_highlight_names {
@@ -615,23 +660,12 @@ impl<'a> LanguageConfiguration<'a> {
                     TagsConfiguration::new(language, &tags_query, &locals_query)
                         .map(Some)
                         .map_err(|error| {
-                            if let TagsError::Query(error) = error {
-                                if error.offset < locals_query.len() {
-                                    Self::include_path_in_query_error(
-                                        error,
-                                        &locals_ranges,
-                                        &locals_query,
-                                        0,
-                                    )
-                                } else {
-                                    Self::include_path_in_query_error(
-                                        error,
-                                        &tags_ranges,
-                                        &tags_query,
-                                        locals_query.len(),
-                                    )
-                                }
-                                .into()
+                            if let TagsError::Query(section, error) = error {
+                                let (source, ranges) = match section {
+                                    TagsQuerySection::Locals => (&locals_query, &locals_ranges),
+                                    TagsQuerySection::Tags => (&tags_query, &tags_ranges),
+                                };
+                                Self::query_error(error, source, ranges)
                             } else {
                                 error.into()
                             }
@@ -641,23 +675,20 @@ impl<'a> LanguageConfiguration<'a> {
             .map(Option::as_ref)
     }
 
-    fn include_path_in_query_error<'b>(
-        mut error: QueryError,
-        ranges: &'b Vec<(String, Range<usize>)>,
+    // Given an error in a query that was formed by concatenating the given files,
+    // find the file that contains the error, and report its location within that file.
+    fn query_error(
+        error: QueryError,
         source: &str,
-        start_offset: usize,
-    ) -> (&'b str, QueryError) {
-        let offset_within_section = error.offset - start_offset;
-        let (path, range) = ranges
-            .iter()
-            .find(|(_, range)| range.contains(&offset_within_section))
-            .unwrap();
-        error.offset = offset_within_section - range.start;
-        error.row = source[range.start..offset_within_section]
-            .chars()
-            .filter(|c| *c == '\n')
-            .count();
-        (path.as_ref(), error)
+        ranges: &[(String, Range<usize>)],
+    ) -> Error {
+        if ranges.is_empty() {
+            return Error::query("query", "", error);
+        }
+        let starts = ranges.iter().map(|(_, r)| r.start).collect::<Vec<_>>();
+        let (index, error) = error.relative_to_section(source, &starts);
+        let (path, range) = &ranges[index];
+        Error::query(path, &source[range.clone()], error)
     }
 
     fn read_queries(
@@ -665,30 +696,134 @@ impl<'a> LanguageConfiguration<'a> {
         paths: &Option<Vec<String>>,
         default_path: &str,
     ) -> Result<(String, Vec<(String, Range<usize>)>)> {
-        let mut query = String::new();
-        let mut path_ranges = Vec::new();
-        if let Some(paths) = paths.as_ref() {
-            for path in paths {
-                let abs_path = self.root_path.join(path);
-                let prev_query_len = query.len();
-                query += &fs::read_to_string(&abs_path).map_err(Error::wrap(|| {
-                    format!("Failed to read query file {:?}", path)
-                }))?;
-                path_ranges.push((path.clone(), prev_query_len..query.len()));
+        let query_paths = self.query_paths.lock().unwrap();
+        let mut reader = QueryReader {
+            query_paths: &query_paths,
+            default_path,
+            query: String::new(),
+            path_ranges: Vec::new(),
+            included_paths: Vec::new(),
+            ancestor_paths: Vec::new(),
+        };
+        for (path, abs_path) in query_file_paths(&self.root_path, paths, default_path) {
+            reader.read_file(path, abs_path)?;
+        }
+        Ok((reader.query, reader.path_ranges))
+    }
+}
+
+/// Concatenates query files, preceding each file with the files of the same kind
+/// from the languages listed in its `; inherits:` modeline.
+struct QueryReader<'a> {
+    query_paths: &'a [QueryPaths],
+    default_path: &'a str,
+    query: String,
+    path_ranges: Vec<(String, Range<usize>)>,
+    included_paths: Vec<PathBuf>,
+    ancestor_paths: Vec<PathBuf>,
+}
+
+impl<'a> QueryReader<'a> {
+    fn read_file(&mut self, path: String, abs_path: PathBuf) -> Result<()> {
+        if self.ancestor_paths.contains(&abs_path) {
+            return Err(Error::new(format!(
+                "Query file {:?} inherits from itself",
+                path
+            )));
+        }
+        if self.included_paths.contains(&abs_path) {
+            return Ok(());
+        }
+
+        let mut contents = fs::read_to_string(&abs_path).map_err(Error::wrap(|| {
+            format!("Failed to read query file {:?}", path)
+        }))?;
+        if !contents.is_empty() && !contents.ends_with('\n') {
+            contents.push('\n');
+        }
+
+        self.ancestor_paths.push(abs_path.clone());
+        for language_name in inherited_language_names(&contents) {
+            let query_paths = self
+                .query_paths
+                .iter()
+                .find(|p| p.name == language_name || p.scope.as_deref() == Some(language_name))
+                .ok_or_else(|| {
+                    Error::new(format!(
+                        "Query file {:?} inherits from unknown language {:?}",
+                        path, language_name
+                    ))
+                })?;
+            let filenames = match self.default_path {
+                "highlights.scm" => &query_paths.highlights_filenames,
+                "injections.scm" => &query_paths.injections_filenames,
+                "locals.scm" => &query_paths.locals_filenames,
+                _ => &query_paths.tags_filenames,
+            };
+            for (_, abs_path) in
+                query_file_paths(&query_paths.root_path, filenames, self.default_path)
+            {
+                let path = abs_path.to_string_lossy().to_string();
+                self.read_file(path, abs_path)?;
             }
+        }
+        self.ancestor_paths.pop();
+
+        let prev_query_len = self.query.len();
+        self.query += &contents;
+        self.path_ranges
+            .push((path, prev_query_len..self.query.len()));
+        self.included_paths.push(abs_path);
+        Ok(())
+    }
+}
+
+/// Get the query files of one kind for the language at the given path, along with
+/// their paths relative to the language's root directory.
+fn query_file_paths(
+    root_path: &Path,
+    paths: &Option<Vec<String>>,
+    default_path: &str,
+) -> Vec<(String, PathBuf)> {
+    if let Some(paths) = paths.as_ref() {
+        paths
+            .iter()
+            .map(|path| (path.clone(), root_path.join(path)))
+            .collect()
+    } else {
+        let path = root_path.join("queries").join(default_path);
+        if path.exists() {
+            vec![(default_path.to_string(), path)]
         } else {
-            let queries_path = self.root_path.join("queries");
-            let path = queries_path.join(default_path);
-            if path.exists() {
-                query = fs::read_to_string(&path).map_err(Error::wrap(|| {
-                    format!("Failed to read query file {:?}", path)
-                }))?;
-                path_ranges.push((default_path.to_string(), 0..query.len()));
-            }
+            Vec::new()
         }
+    }
+}
 
-        Ok((query, path_ranges))
+/// Parse the `; inherits: lang1, lang2` modeline in the comments at the start of
+/// a query file.
+fn inherited_language_names(contents: &str) -> Vec<&str> {
+    for line in contents.lines() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        if !line.starts_with(';') {
+            break;
+        }
+        if let Some(names) = line
+            .trim_start_matches(';')
+            .trim()
+            .strip_prefix("inherits:")
+        {
+            return names
+                .split(',')
+                .map(str::trim)
+                .filter(|name| !name.is_empty())
+                .collect();
+        }
     }
+    Vec::new()
 }
 
 fn needs_recompile(
@@ -1,14 +1,18 @@
 use clap::{App, AppSettings, Arg, SubCommand};
 use error::Error;
 use glob::glob;
+use std::collections::HashMap;
+use std::io;
 use std::path::Path;
 use std::process::exit;
+use std::time::{SystemTime, UNIX_EPOCH};
 use std::{env, fs, u64};
 use tree_sitter::Language;
 use tree_sitter_cli::{
-    config, error, generate, highlight, loader, logger, parse, query, tags, test, test_highlight,
-    util, wasm, web_ui,
+    config, error, fuzz, generate, highlight, loader, logger, parse, query, tags, test,
+    test_highlight, test_report, util, wasm, web_ui,
 };
+use test_report::TestFormat;
 
 const BUILD_VERSION: &'static str = env!("CARGO_PKG_VERSION");
 const BUILD_SHA: Option<&'static str> = option_env!("BUILD_SHA");
@@ -41,8 +45,13 @@ fn run() -> error::Result<()> {
                 .about("Generate a parser")
                 .arg(Arg::with_name("grammar-path").index(1))
                 .arg(Arg::with_name("log").long("log"))
-                .arg(Arg::with_name("prev-abi").long("prev-abi"))
+                .arg(Arg::with_name("next-abi").long("next-abi"))
                 .arg(Arg::with_name("no-bindings").long("no-bindings"))
+                .arg(
+                    Arg::with_name("rust-ast")
+                        .help("Generate typed Rust wrappers for the syntax nodes in bindings/rust/ast.rs")
+                        .long("rust-ast"),
+                )
                 .arg(
                     Arg::with_name("report-states-for-rule")
                         .long("report-states-for-rule")
@@ -65,6 +74,7 @@ fn run() -> error::Result<()> {
                 .arg(Arg::with_name("debug").long("debug").short("d"))
                 .arg(Arg::with_name("debug-graph").long("debug-graph").short("D"))
                 .arg(Arg::with_name("debug-xml").long("xml").short("x"))
+                .arg(Arg::with_name("diagnostics").long("diagnostics"))
                 .arg(Arg::with_name("quiet").long("quiet").short("q"))
                 .arg(Arg::with_name("stat").long("stat").short("s"))
                 .arg(Arg::with_name("time").long("time").short("t"))
@@ -97,7 +107,18 @@ fn run() -> error::Result<()> {
                 )
                 .arg(Arg::with_name("scope").long("scope").takes_value(true))
                 .arg(Arg::with_name("captures").long("captures").short("c"))
-                .arg(Arg::with_name("test").long("test")),
+                .arg(Arg::with_name("test").long("test"))
+                .arg(Arg::with_name("stats").long("stats"))
+                .arg(
+                    Arg::with_name("lint")
+                        .help("Report the patterns that can never match, instead of running the query")
+                        .long("lint"),
+                )
+                .arg(
+                    Arg::with_name("check")
+                        .help("Check the query against the grammar's node types, instead of running it")
+                        .long("check"),
+                ),
         )
         .subcommand(
             SubCommand::with_name("tags")
@@ -124,7 +145,41 @@ fn run() -> error::Result<()> {
                 )
                 .arg(Arg::with_name("update").long("update").short("u").help("Update all syntax trees in corpus files with current parser output"))
                 .arg(Arg::with_name("debug").long("debug").short("d"))
-                .arg(Arg::with_name("debug-graph").long("debug-graph").short("D")),
+                .arg(Arg::with_name("debug-graph").long("debug-graph").short("D"))
+                .arg(
+                    Arg::with_name("format")
+                        .long("format")
+                        .takes_value(true)
+                        .possible_values(&["json", "junit"])
+                        .help("Print the test results in a machine-readable format"),
+                )
+                .arg(
+                    Arg::with_name("fuzz")
+                        .long("fuzz")
+                        .conflicts_with_all(&["update", "format"])
+                        .help("Check that random edits to the corpus tests are parsed incrementally to the same trees as parsing from scratch"),
+                )
+                .arg(
+                    Arg::with_name("iterations")
+                        .long("iterations")
+                        .takes_value(true)
+                        .requires("fuzz")
+                        .help("The number of random sequences of edits to try on each test"),
+                )
+                .arg(
+                    Arg::with_name("seed")
+                        .long("seed")
+                        .takes_value(true)
+                        .requires("fuzz")
+                        .help("The seed for the random edits"),
+                )
+                .arg(
+                    Arg::with_name("time")
+                        .long("time")
+                        .short("t")
+                        .conflicts_with_all(&["format", "fuzz"])
+                        .help("Print the slowest tests and the parsing throughput"),
+                ),
         )
         .subcommand(
             SubCommand::with_name("highlight")
@@ -187,24 +242,43 @@ fn run() -> error::Result<()> {
         if matches.is_present("log") {
             logger::init();
         }
-        let new_abi = !matches.is_present("prev-abi");
+        let abi_version = if matches.is_present("next-abi") {
+            tree_sitter::LANGUAGE_VERSION
+        } else {
+            tree_sitter::LANGUAGE_VERSION - 1
+        };
         let generate_bindings = !matches.is_present("no-bindings");
+        let generate_rust_ast = matches.is_present("rust-ast");
         generate::generate_parser_in_directory(
             &current_dir,
             grammar_path,
-            new_abi,
+            abi_version,
             generate_bindings,
+            generate_rust_ast,
             report_symbol_name,
         )?;
     } else if let Some(matches) = matches.subcommand_matches("test") {
-        let debug = matches.is_present("debug");
-        let debug_graph = matches.is_present("debug-graph");
-        let update = matches.is_present("update");
-        let filter = matches.value_of("filter");
-        let languages = loader.languages_at_path(&current_dir)?;
-        let language = languages
+        let format = match matches.value_of("format") {
+            Some("json") => Some(TestFormat::Json),
+            Some("junit") => Some(TestFormat::Junit),
+            _ => None,
+        };
+        let named_languages = loader.named_languages_at_path(&current_dir)?;
+        let language = named_languages
             .first()
+            .map(|(_, language)| *language)
             .ok_or_else(|| "No language found".to_string())?;
+        let languages = named_languages.into_iter().collect::<HashMap<_, _>>();
+        let options = test::TestOptions {
+            languages: &languages,
+            debug: matches.is_present("debug"),
+            debug_graph: matches.is_present("debug-graph"),
+            filter: matches.value_of("filter"),
+            update: matches.is_present("update"),
+            time: matches.is_present("time"),
+            format,
+        };
+        let mut results = Vec::new();
         let test_dir = current_dir.join("test");
 
         // Run the corpus tests. Look for them at two paths: `test/corpus` and `corpus`.
@@ -212,29 +286,58 @@ fn run() -> error::Result<()> {
         if !test_corpus_dir.is_dir() {
             test_corpus_dir = current_dir.join("corpus");
         }
+
+        // Fuzz the corpus tests instead of running the tests normally.
+        if matches.is_present("fuzz") {
+            if !test_corpus_dir.is_dir() {
+                return Error::err("No corpus tests found".to_string());
+            }
+            let iterations = match matches.value_of("iterations") {
+                Some(iterations) => usize::from_str_radix(iterations, 10)
+                    .map_err(|_| format!("Invalid iteration count '{}'", iterations))?,
+                None => 10,
+            };
+            let seed = match matches.value_of("seed") {
+                Some(seed) => usize::from_str_radix(seed, 10)
+                    .map_err(|_| format!("Invalid seed '{}'", seed))?,
+                None => SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .unwrap()
+                    .as_secs() as usize,
+            };
+            let options = fuzz::FuzzOptions {
+                languages: &languages,
+                filter: matches.value_of("filter"),
+                iterations,
+                seed,
+            };
+            return fuzz::fuzz_tests_at_path(language, &test_corpus_dir, &options);
+        }
+
         if test_corpus_dir.is_dir() {
-            test::run_tests_at_path(
-                *language,
-                &test_corpus_dir,
-                debug,
-                debug_graph,
-                filter,
-                update,
-            )?;
+            test::run_tests_at_path(language, &test_corpus_dir, &options, &mut results)?;
         }
 
         // Check that all of the queries are valid.
-        test::check_queries_at_path(*language, &current_dir.join("queries"))?;
+        test::check_queries_at_path(language, &current_dir.join("queries"))?;
 
         // Run the syntax highlighting tests.
         let test_highlight_dir = test_dir.join("highlight");
         if test_highlight_dir.is_dir() {
-            test_highlight::test_highlights(&loader, &test_highlight_dir)?;
+            test_highlight::test_highlights(&loader, &test_highlight_dir, format, &mut results)?;
+        }
+
+        if let Some(format) = format {
+            test_report::write_report(&mut io::stdout(), &results, format)?;
+            if results.iter().any(|r| r.is_failure()) {
+                return Error::err(String::new());
+            }
         }
     } else if let Some(matches) = matches.subcommand_matches("parse") {
         let debug = matches.is_present("debug");
         let debug_graph = matches.is_present("debug-graph");
         let debug_xml = matches.is_present("debug-xml");
+        let print_diagnostics = matches.is_present("diagnostics");
         let quiet = matches.is_present("quiet");
         let time = matches.is_present("time");
         let edits = matches
@@ -271,6 +374,7 @@ fn run() -> error::Result<()> {
                 debug,
                 debug_graph,
                 debug_xml,
+                print_diagnostics,
                 Some(cancellation_flag.clone()),
             )?;
 
@@ -293,15 +397,35 @@ fn run() -> error::Result<()> {
         }
     } else if let Some(matches) = matches.subcommand_matches("query") {
         let ordered_captures = matches.values_of("captures").is_some();
-        let paths = collect_paths(matches.value_of("paths-file"), matches.values_of("paths"))?;
+        let lint = matches.is_present("lint");
+        let check = matches.is_present("check");
+
+        // When linting or checking, the paths are only used to select the language.
+        let paths = if (lint || check) && !matches.is_present("paths") && !matches.is_present("paths-file") {
+            Vec::new()
+        } else {
+            collect_paths(matches.value_of("paths-file"), matches.values_of("paths"))?
+        };
         loader.find_all_languages(&config.parser_directories)?;
         let language = select_language(
             &mut loader,
-            Path::new(&paths[0]),
+            Path::new(paths.first().map_or("", String::as_str)),
             &current_dir,
             matches.value_of("scope"),
         )?;
         let query_path = Path::new(matches.value_of("query-path").unwrap());
+        if lint {
+            return query::lint_query_at_path(language, query_path);
+        }
+        if check {
+            let node_types_path = loader
+                .language_path(language)
+                .map(|path| path.join("src").join("node-types.json"))
+                .ok_or_else(|| {
+                    Error::new("Failed to find the grammar directory for the query".to_string())
+                })?;
+            return query::check_query_at_path(language, query_path, &node_types_path);
+        }
         let range = matches.value_of("byte-range").map(|br| {
             let r: Vec<&str> = br.split(":").collect();
             (r[0].parse().unwrap(), r[1].parse().unwrap())
@@ -314,6 +438,7 @@ fn run() -> error::Result<()> {
             ordered_captures,
             range,
             should_test,
+            matches.is_present("stats"),
         )?;
     } else if let Some(matches) = matches.subcommand_matches("tags") {
         loader.find_all_languages(&config.parser_directories)?;
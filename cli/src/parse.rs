@@ -5,9 +5,9 @@ use std::path::Path;
 use std::sync::{atomic::AtomicUsize, Arc};
 use std::time::Instant;
 use std::{fmt, fs, usize};
-use tree_sitter::{InputEdit, Language, LogType, Parser, Point, Tree};
+use tree_sitter::{diagnostics, InputEdit, Language, LogType, Parser, Point, Tree};
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct Edit {
     pub position: usize,
     pub deleted_length: usize,
@@ -41,6 +41,7 @@ pub fn parse_file_at_path(
     debug: bool,
     debug_graph: bool,
     debug_xml: bool,
+    print_diagnostics: bool,
     cancellation_flag: Option<Arc<AtomicUsize>>,
 ) -> Result<bool> {
     let mut _log_session = None;
@@ -257,6 +258,10 @@ pub fn parse_file_at_path(
             write!(&mut stdout, "\n")?;
         }
 
+        if print_diagnostics {
+            write_diagnostics(&mut stdout, path, &source_code, &tree)?;
+        }
+
         return Ok(first_error.is_some());
     } else if print_time {
         let duration = time.elapsed();
@@ -273,6 +278,64 @@ pub fn parse_file_at_path(
     Ok(false)
 }
 
+fn write_diagnostics(
+    stdout: &mut impl Write,
+    path: &Path,
+    source_code: &[u8],
+    tree: &Tree,
+) -> Result<()> {
+    for diagnostic in diagnostics::diagnostics(tree) {
+        let start = diagnostic.range.start_point;
+        let end = diagnostic.range.end_point;
+        writeln!(
+            stdout,
+            "{}:{}:{}: error: {}",
+            path.display(),
+            start.row + 1,
+            start.column + 1,
+            diagnostic.message(source_code)
+        )?;
+
+        // Show the first line of the error, with the erroneous part underlined.
+        let line_start = diagnostic.range.start_byte - start.column;
+        let line = source_code[line_start..]
+            .split(|b| *b == b'\n')
+            .next()
+            .unwrap_or(&[]);
+
+        // The columns are byte offsets, so convert them to character counts in order
+        // to line the underline up with the printed line.
+        let char_column = |column: usize| {
+            String::from_utf8_lossy(&line[..column.min(line.len())])
+                .chars()
+                .count()
+        };
+        let underline_start = char_column(start.column);
+        let underline_end = if end.row == start.row {
+            char_column(end.column)
+        } else {
+            char_column(line.len())
+        };
+        let line_number = (start.row + 1).to_string();
+        writeln!(
+            stdout,
+            "{} | {}",
+            line_number,
+            String::from_utf8_lossy(line).trim_end()
+        )?;
+        writeln!(
+            stdout,
+            "{:indent$} | {:column$}{}",
+            "",
+            "",
+            "^".repeat(underline_end.saturating_sub(underline_start).max(1)),
+            indent = line_number.len(),
+            column = underline_start,
+        )?;
+    }
+    Ok(())
+}
+
 pub fn perform_edit(tree: &mut Tree, input: &mut Vec<u8>, edit: &Edit) -> InputEdit {
     let start_byte = edit.position;
     let old_end_byte = edit.position + edit.deleted_length;
@@ -360,3 +423,30 @@ fn position_for_offset(input: &Vec<u8>, offset: usize) -> Point {
     }
     result
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::helpers::fixtures::get_language;
+
+    #[test]
+    fn test_write_diagnostics_with_multibyte_characters() {
+        let source_code = "let s = 'ééé'; @@@ ;";
+        let mut parser = Parser::new();
+        parser.set_language(get_language("javascript")).unwrap();
+        let tree = parser.parse(source_code, None).unwrap();
+
+        let mut output = Vec::new();
+        write_diagnostics(
+            &mut output,
+            Path::new("test.js"),
+            source_code.as_bytes(),
+            &tree,
+        )
+        .unwrap();
+        let output = String::from_utf8(output).unwrap();
+        let lines = output.lines().collect::<Vec<_>>();
+        assert_eq!(lines[1], "1 | let s = 'ééé'; @@@ ;");
+        assert_eq!(lines[2], format!("  | {}^^^", " ".repeat(15)));
+    }
+}
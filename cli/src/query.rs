@@ -3,6 +3,7 @@ use crate::query_testing;
 use std::fs;
 use std::io::{self, Write};
 use std::path::Path;
+use tree_sitter::query_validation::{validate_query, NodeTypes};
 use tree_sitter::{Language, Node, Parser, Query, QueryCursor};
 
 pub fn query_files_at_paths(
@@ -12,6 +13,7 @@ pub fn query_files_at_paths(
     ordered_captures: bool,
     range: Option<(usize, usize)>,
     should_test: bool,
+    print_stats: bool,
 ) -> Result<()> {
     let stdout = io::stdout();
     let mut stdout = stdout.lock();
@@ -20,7 +22,7 @@ pub fn query_files_at_paths(
         format!("Error reading query file {:?}", query_path)
     }))?;
     let query = Query::new(language, &query_source)
-        .map_err(|e| Error::new(format!("Query compilation failed: {:?}", e)))?;
+        .map_err(|e| Error::query(&query_path.to_string_lossy(), &query_source, e))?;
 
     let mut query_cursor = QueryCursor::new();
     if let Some((beg, end)) = range {
@@ -91,6 +93,9 @@ pub fn query_files_at_paths(
                 }
             }
         }
+        if print_stats {
+            write_stats(&mut stdout, &query, &query_source, &query_cursor)?;
+        }
         if should_test {
             query_testing::assert_expected_captures(results, path, &mut parser, language)?
         }
@@ -98,3 +103,130 @@ pub fn query_files_at_paths(
 
     Ok(())
 }
+
+// Report the patterns that can never match, given the structure of the
+// language's grammar. Each pattern is located by compiling it on its own.
+pub fn lint_query_at_path(language: Language, query_path: &Path) -> Result<()> {
+    let stdout = io::stdout();
+    let mut stdout = stdout.lock();
+
+    let query_source = fs::read_to_string(query_path).map_err(Error::wrap(|| {
+        format!("Error reading query file {:?}", query_path)
+    }))?;
+    let query = Query::new_lenient(language, &query_source)
+        .map_err(|e| Error::query(&query_path.to_string_lossy(), &query_source, e))?;
+
+    let mut impossible_count = 0;
+    for i in 0..query.pattern_count() {
+        if !query.is_pattern_impossible(i) {
+            continue;
+        }
+        impossible_count += 1;
+
+        let start_byte = query.start_byte_for_pattern(i);
+        let end_byte = if i + 1 < query.pattern_count() {
+            query.start_byte_for_pattern(i + 1)
+        } else {
+            query_source.len()
+        };
+        let offset = Query::new(language, &query_source[start_byte..end_byte])
+            .err()
+            .map_or(start_byte, |error| start_byte + error.offset);
+        let line_start = query_source[..offset].rfind('\n').map_or(0, |i| i + 1);
+        writeln!(
+            &mut stdout,
+            "{}:{}:{}: error: pattern {} can never match",
+            query_path.display(),
+            query_source[..offset].matches('\n').count() + 1,
+            offset - line_start + 1,
+            i,
+        )?;
+    }
+
+    if impossible_count > 0 {
+        return Error::err(format!(
+            "{} of {} patterns can never match",
+            impossible_count,
+            query.pattern_count()
+        ));
+    }
+    Ok(())
+}
+
+// Report the patterns that can never match, given the parent, child and field
+// relationships described in the grammar's node types.
+pub fn check_query_at_path(
+    language: Language,
+    query_path: &Path,
+    node_types_path: &Path,
+) -> Result<()> {
+    let stdout = io::stdout();
+    let mut stdout = stdout.lock();
+
+    let query_source = fs::read_to_string(query_path).map_err(Error::wrap(|| {
+        format!("Error reading query file {:?}", query_path)
+    }))?;
+    let query = Query::new_lenient(language, &query_source)
+        .map_err(|e| Error::query(&query_path.to_string_lossy(), &query_source, e))?;
+    let node_types_json = fs::read_to_string(node_types_path).map_err(Error::wrap(|| {
+        format!("Error reading node types file {:?}", node_types_path)
+    }))?;
+    let node_types = NodeTypes::from_json(&node_types_json).map_err(Error::wrap(|| {
+        format!("Error parsing node types file {:?}", node_types_path)
+    }))?;
+
+    let impossible_patterns = validate_query(&node_types, &query_source)
+        .map_err(|e| Error::query(&query_path.to_string_lossy(), &query_source, e))?;
+    let mut impossible_pattern_indices = Vec::new();
+    for impossible_pattern in &impossible_patterns {
+        writeln!(
+            &mut stdout,
+            "{}:{}:{}: error: {}",
+            query_path.display(),
+            impossible_pattern.row + 1,
+            impossible_pattern.column + 1,
+            impossible_pattern.message,
+        )?;
+        if !impossible_pattern_indices.contains(&impossible_pattern.pattern_index) {
+            impossible_pattern_indices.push(impossible_pattern.pattern_index);
+        }
+    }
+
+    if !impossible_pattern_indices.is_empty() {
+        return Error::err(format!(
+            "{} of {} patterns can never match",
+            impossible_pattern_indices.len(),
+            query.pattern_count()
+        ));
+    }
+    Ok(())
+}
+
+// Print the patterns that did any work, starting with the most expensive ones.
+fn write_stats(
+    stdout: &mut impl Write,
+    query: &Query,
+    query_source: &str,
+    query_cursor: &QueryCursor,
+) -> Result<()> {
+    let stats = query_cursor.stats();
+    writeln!(stdout, "  nodes visited: {}", stats.nodes_visited)?;
+    let mut patterns = stats
+        .patterns
+        .iter()
+        .enumerate()
+        .filter(|(_, stats)| stats.states_created > 0)
+        .collect::<Vec<_>>();
+    patterns.sort_by_key(|(i, stats)| (std::cmp::Reverse(stats.states_created), *i));
+    for (i, stats) in patterns {
+        let row = query_source[..query.start_byte_for_pattern(i)]
+            .matches('\n')
+            .count();
+        writeln!(
+            stdout,
+            "  pattern: {}, row: {}, states created: {}, matches: {}, dropped: {}",
+            i, row, stats.states_created, stats.matches_produced, stats.matches_dropped,
+        )?;
+    }
+    Ok(())
+}
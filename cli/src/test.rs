@@ -1,4 +1,5 @@
 use super::error::{Error, Result};
+use super::test_report::{TestFormat, TestKind, TestResult, TestStatus};
 use super::util;
 use ansi_term::Colour;
 use difference::{Changeset, Difference};
@@ -6,15 +7,23 @@ use lazy_static::lazy_static;
 use regex::bytes::{Regex as ByteRegex, RegexBuilder as ByteRegexBuilder};
 use regex::Regex;
 use std::char;
+use std::collections::{HashMap, VecDeque};
+use std::env;
 use std::ffi::OsStr;
 use std::fmt::Write as FmtWrite;
 use std::fs;
 use std::io::{self, Write};
 use std::path::{Path, PathBuf};
 use std::str;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
 use tree_sitter::{Language, LogType, Parser, Query};
 use walkdir::WalkDir;
 
+// The number of tests that are listed by `--time`.
+const SLOW_TEST_COUNT: usize = 10;
+
 lazy_static! {
     static ref HEADER_REGEX: ByteRegex = ByteRegexBuilder::new(r"^===+\r?\n([^=]*)\r?\n===+\r?\n")
         .multi_line(true)
@@ -41,9 +50,40 @@ pub enum TestEntry {
         input: Vec<u8>,
         output: String,
         has_fields: bool,
+        attributes: TestAttributes,
+        /// The one-based line number of the test's header.
+        line: usize,
     },
 }
 
+/// The options that can be given to a test, on separate lines in its header,
+/// after the test's name.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct TestAttributes {
+    /// `:skip` - Don't run the test.
+    pub skip: bool,
+    /// `:error` - Only check that the tree contains an error, instead of
+    /// comparing it to the expected output.
+    pub error: bool,
+    /// `:fail-fast` - Stop running tests if this test fails.
+    pub fail_fast: bool,
+    /// `:platform(name)` - Only run the test on the given operating systems,
+    /// using the names from `std::env::consts::OS`.
+    pub platforms: Vec<String>,
+    /// `:language(name)` - Run the test with the given languages, instead of
+    /// the default one.
+    pub languages: Vec<String>,
+}
+
+impl TestAttributes {
+    /// Whether the test should be skipped on the current platform.
+    pub fn is_skipped(&self) -> bool {
+        self.skip
+            || (!self.platforms.is_empty()
+                && !self.platforms.iter().any(|p| p == env::consts::OS))
+    }
+}
+
 impl Default for TestEntry {
     fn default() -> Self {
         TestEntry::Group {
@@ -54,22 +94,65 @@ impl Default for TestEntry {
     }
 }
 
+/// The options for running the corpus tests at a given path.
+pub struct TestOptions<'a> {
+    /// The other languages that tests can use via the `:language` attribute,
+    /// keyed by name.
+    pub languages: &'a HashMap<String, Language>,
+    pub debug: bool,
+    pub debug_graph: bool,
+    pub filter: Option<&'a str>,
+    pub update: bool,
+    /// Print the slowest tests, and the parsing throughput, after the tests
+    /// have run. The tests are parsed one at a time, so that the parses don't
+    /// compete with each other for the CPU.
+    pub time: bool,
+    /// If present, collect the results for a machine-readable report instead
+    /// of printing them.
+    pub format: Option<TestFormat>,
+}
+
+// An example that needs to be parsed, with one of the languages that it is
+// tested with.
+struct ParseJob {
+    input: Vec<u8>,
+    language: Language,
+    has_fields: bool,
+    /// Whether this is the last example of a `:fail-fast` test, after which no
+    /// more examples should be parsed until the test's result is known.
+    ends_batch: bool,
+}
+
+// The outputs of a sequence of parse jobs. The jobs are parsed lazily, in
+// batches that end at each `:fail-fast` test, so that no more examples are
+// parsed once a `:fail-fast` test has failed.
+struct ParseOutputs<'a, 'b> {
+    parser: &'b mut Parser,
+    jobs: &'a [ParseJob],
+    parallel: bool,
+    outputs: VecDeque<Result<ParseOutput>>,
+}
+
+struct ParseOutput {
+    sexp: String,
+    has_error: bool,
+    duration: Duration,
+}
+
 pub fn run_tests_at_path(
     language: Language,
     path: &Path,
-    debug: bool,
-    debug_graph: bool,
-    filter: Option<&str>,
-    update: bool,
+    options: &TestOptions,
+    results: &mut Vec<TestResult>,
 ) -> Result<()> {
     let test_entry = parse_tests(path)?;
     let mut _log_session = None;
     let mut parser = Parser::new();
     parser.set_language(language).map_err(|e| e.to_string())?;
 
-    if debug_graph {
+    if options.debug_graph {
         _log_session = Some(util::log_graphs(&mut parser, "log.html")?);
-    } else if debug {
+    } else if options.debug {
         parser.set_logger(Some(Box::new(|log_type, message| {
             if log_type == LogType::Lex {
                 io::stderr().write(b"  ").unwrap();
@@ -78,30 +161,41 @@ pub fn run_tests_at_path(
         })));
     }
 
-    let mut failures = Vec::new();
+    let result_count = results.len();
     let mut corrected_entries = Vec::new();
     run_tests(
         &mut parser,
         test_entry,
-        filter,
-        0,
-        &mut failures,
-        update,
+        options,
+        results,
         &mut corrected_entries,
     )?;
 
+    // Machine-readable reports are written once all of the tests have run.
+    if options.format.is_some() {
+        return Ok(());
+    }
+
+    if options.time {
+        print_time_report(&results[result_count..]);
+    }
+
+    let failures = results[result_count..]
+        .iter()
+        .filter(|r| r.status == TestStatus::Failed || r.status == TestStatus::Updated)
+        .collect::<Vec<_>>();
     if failures.len() > 0 {
         println!("");
 
-        if update {
+        if options.update {
             if failures.len() == 1 {
                 println!("1 update:\n")
             } else {
                 println!("{} updates:\n", failures.len())
             }
 
-            for (i, (name, ..)) in failures.iter().enumerate() {
-                println!("  {}. {}", i + 1, name);
+            for (i, failure) in failures.iter().enumerate() {
+                println!("  {}. {}", i + 1, failure.name);
             }
             Ok(())
         } else {
@@ -112,9 +206,12 @@ pub fn run_tests_at_path(
             }
 
             print_diff_key();
-            for (i, (name, actual, expected)) in failures.iter().enumerate() {
-                println!("\n  {}. {}:", i + 1, name);
-                print_diff(actual, expected);
+            for (i, failure) in failures.iter().enumerate() {
+                println!("\n  {}. {}:", i + 1, failure.name);
+                print_diff(
+                    failure.actual.as_ref().unwrap(),
+                    failure.expected.as_ref().unwrap(),
+                );
             }
             Error::err(String::new())
         }
@@ -138,7 +235,7 @@ pub fn check_queries_at_path(language: Language, path: &Path) -> Result<()> {
             let content = fs::read_to_string(entry.path()).map_err(Error::wrap(|| {
                 format!("Error reading query file {:?}", entry.file_name())
             }))?;
-            Query::new(language, &content).map_err(|e| (filepath, e))?;
+            Query::new(language, &content).map_err(|e| Error::query(filepath, &content, e))?;
         }
     }
     Ok(())
@@ -171,57 +268,303 @@ pub fn print_diff(actual: &String, expected: &String) {
     println!("");
 }
 
+// Run the given tests, returning false if a test with the `:fail-fast`
+// attribute failed, and no more tests should be run.
+//
+// The examples are parsed ahead of reporting, in parallel unless the parser is
+// logging or the parse times are being measured. The results are then reported
+// in order, as if the tests were run one at a time.
 fn run_tests(
     parser: &mut Parser,
     test_entry: TestEntry,
-    filter: Option<&str>,
-    mut indent_level: i32,
-    failures: &mut Vec<(String, String, String)>,
-    update: bool,
+    options: &TestOptions,
+    results: &mut Vec<TestResult>,
     corrected_entries: &mut Vec<(String, String, String)>,
+) -> Result<bool> {
+    let mut jobs = Vec::new();
+    collect_parse_jobs(&test_entry, parser.language().unwrap(), options, &mut jobs)?;
+    let mut outputs = ParseOutputs {
+        parser,
+        jobs: &jobs,
+        parallel: !options.debug && !options.debug_graph && !options.time,
+        outputs: VecDeque::new(),
+    };
+    report_tests(
+        test_entry,
+        options,
+        0,
+        &mut outputs,
+        results,
+        corrected_entries,
+    )
+}
+
+fn collect_parse_jobs(
+    test_entry: &TestEntry,
+    default_language: Language,
+    options: &TestOptions,
+    jobs: &mut Vec<ParseJob>,
 ) -> Result<()> {
     match test_entry {
         TestEntry::Example {
             name,
             input,
-            output,
             has_fields,
+            attributes,
+            ..
         } => {
-            if let Some(filter) = filter {
-                if !name.contains(filter) {
-                    if update {
-                        let input = String::from_utf8(input).unwrap();
-                        let output = format_sexp(&output);
-                        corrected_entries.push((name, input, output));
-                    }
-                    return Ok(());
+            if attributes.is_skipped() || !options.filter.iter().all(|f| name.contains(f)) {
+                return Ok(());
+            }
+            if attributes.languages.is_empty() {
+                jobs.push(ParseJob {
+                    input: input.clone(),
+                    language: default_language,
+                    has_fields: *has_fields,
+                    ends_batch: false,
+                });
+            }
+            for language_name in &attributes.languages {
+                let language = options.languages.get(language_name).ok_or_else(|| {
+                    Error::new(format!(
+                        "Test {:?} uses unknown language {:?}",
+                        name, language_name
+                    ))
+                })?;
+                jobs.push(ParseJob {
+                    input: input.clone(),
+                    language: *language,
+                    has_fields: *has_fields,
+                    ends_batch: false,
+                });
+            }
+            if attributes.fail_fast && !options.update {
+                if let Some(job) = jobs.last_mut() {
+                    job.ends_batch = true;
                 }
             }
-            let tree = parser.parse(&input, None).unwrap();
-            let mut actual = tree.root_node().to_sexp();
-            if !has_fields {
-                actual = strip_sexp_fields(actual);
+        }
+        TestEntry::Group { children, .. } => {
+            for child in children {
+                collect_parse_jobs(child, default_language, options, jobs)?;
             }
-            for _ in 0..indent_level {
-                print!("  ");
+        }
+    }
+    Ok(())
+}
+
+impl<'a, 'b> Iterator for ParseOutputs<'a, 'b> {
+    type Item = Result<ParseOutput>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.outputs.is_empty() && !self.jobs.is_empty() {
+            let batch_len = if self.parallel {
+                self.jobs
+                    .iter()
+                    .position(|job| job.ends_batch)
+                    .map_or(self.jobs.len(), |i| i + 1)
+            } else {
+                1
+            };
+            let (batch, rest) = self.jobs.split_at(batch_len);
+            self.jobs = rest;
+            if self.parallel {
+                match parse_in_parallel(batch) {
+                    Ok(outputs) => self.outputs.extend(outputs),
+                    Err(error) => {
+                        self.jobs = &[];
+                        return Some(Err(error));
+                    }
+                }
+            } else {
+                let parser = &mut *self.parser;
+                self.outputs
+                    .extend(batch.iter().map(|job| parse_example(parser, job)));
             }
-            if actual == output {
-                println!("✓ {}", Colour::Green.paint(&name));
+        }
+        self.outputs.pop_front()
+    }
+}
+
+// Parse the given examples using one parser per thread, returning the outputs
+// in the same order as the examples.
+fn parse_in_parallel(jobs: &[ParseJob]) -> Result<Vec<Result<ParseOutput>>> {
+    let thread_count = thread::available_parallelism()
+        .map_or(1, |count| count.get())
+        .min(jobs.len());
+    let next_index = AtomicUsize::new(0);
+    let worker_results = thread::scope(|scope| {
+        let workers = (0..thread_count)
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut parser = Parser::new();
+                    let mut outputs = Vec::new();
+                    loop {
+                        let index = next_index.fetch_add(1, Ordering::Relaxed);
+                        match jobs.get(index) {
+                            Some(job) => outputs.push((index, parse_example(&mut parser, job))),
+                            None => break outputs,
+                        }
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+
+        // Join every worker, so that a panic in one of them is reported as an
+        // error instead of propagating out of the scope.
+        workers
+            .into_iter()
+            .map(|worker| worker.join())
+            .collect::<Vec<_>>()
+    });
+
+    let mut outputs = Vec::with_capacity(jobs.len());
+    for worker_result in worker_results {
+        match worker_result {
+            Ok(worker_outputs) => outputs.extend(worker_outputs),
+            Err(payload) => {
+                let message = payload
+                    .downcast_ref::<&str>()
+                    .map(|message| message.to_string())
+                    .or_else(|| payload.downcast_ref::<String>().cloned())
+                    .unwrap_or_default();
+                return Err(Error::new(format!(
+                    "A thread panicked while parsing the tests: {}",
+                    message
+                )));
+            }
+        }
+    }
+    outputs.sort_unstable_by_key(|(index, _)| *index);
+    Ok(outputs.into_iter().map(|(_, output)| output).collect())
+}
+
+fn parse_example(parser: &mut Parser, job: &ParseJob) -> Result<ParseOutput> {
+    if parser.language() != Some(job.language) {
+        parser
+            .set_language(job.language)
+            .map_err(|e| e.to_string())?;
+    }
+    let start_time = Instant::now();
+    let tree = parser.parse(&job.input, None).unwrap();
+    let duration = start_time.elapsed();
+    let mut sexp = tree.root_node().to_sexp();
+    if !job.has_fields {
+        sexp = strip_sexp_fields(sexp);
+    }
+    Ok(ParseOutput {
+        sexp,
+        has_error: tree.root_node().has_error(),
+        duration,
+    })
+}
+
+fn report_tests(
+    test_entry: TestEntry,
+    options: &TestOptions,
+    indent_level: i32,
+    outputs: &mut impl Iterator<Item = Result<ParseOutput>>,
+    results: &mut Vec<TestResult>,
+    corrected_entries: &mut Vec<(String, String, String)>,
+) -> Result<bool> {
+    let update = options.update;
+    match test_entry {
+        TestEntry::Example {
+            name,
+            input,
+            output,
+            has_fields: _,
+            attributes,
+            line,
+        } => {
+            let header = format_header(&name, &attributes);
+            let is_skipped = attributes.is_skipped();
+            let is_filtered = options
+                .filter
+                .map_or(false, |filter| !name.contains(filter));
+            if is_skipped || is_filtered {
+                if is_skipped && !is_filtered {
+                    let mut result = TestResult::new(TestKind::Corpus, name, TestStatus::Skipped);
+                    result.line = Some(line);
+                    print_result(options, indent_level, &result);
+                    results.push(result);
+                }
                 if update {
                     let input = String::from_utf8(input).unwrap();
                     let output = format_sexp(&output);
-                    corrected_entries.push((name, input, output));
+                    corrected_entries.push((header, input, output));
                 }
+                return Ok(true);
+            }
+
+            let test_languages = if attributes.languages.is_empty() {
+                vec![None]
             } else {
-                if update {
-                    let input = String::from_utf8(input).unwrap();
-                    let output = format_sexp(&actual);
-                    corrected_entries.push((name.clone(), input, output));
-                    println!("✓ {}", Colour::Blue.paint(&name));
+                attributes.languages.iter().map(Some).collect()
+            };
+            let mut passed = true;
+            for (i, language_name) in test_languages.into_iter().enumerate() {
+                let mut name = name.clone();
+                if let Some(language_name) = language_name {
+                    if attributes.languages.len() > 1 {
+                        name += &format!(" ({})", language_name);
+                    }
+                }
+
+                let ParseOutput {
+                    sexp: actual,
+                    has_error,
+                    duration,
+                } = outputs.next().unwrap()?;
+                let mut result = TestResult::new(TestKind::Corpus, name, TestStatus::Passed);
+                result.line = Some(line);
+                result.bytes = Some(input.len());
+                result.duration = duration;
+
+                // Tests with the `:error` attribute can't be corrected automatically,
+                // so they are always written back as they were.
+                if attributes.error {
+                    if !has_error {
+                        result.status = TestStatus::Failed;
+                        result.message = Some("expected the tree to contain an error".to_string());
+                        result.expected = Some("(ERROR)".to_string());
+                        result.actual = Some(actual);
+                        passed = false;
+                    }
+                    if update && i == 0 {
+                        let input = String::from_utf8(input.clone()).unwrap();
+                        corrected_entries.push((header.clone(), input, format_sexp(&output)));
+                    }
+                } else if actual == output {
+                    if update && i == 0 {
+                        let input = String::from_utf8(input.clone()).unwrap();
+                        corrected_entries.push((header.clone(), input, format_sexp(&output)));
+                    }
                 } else {
-                    println!("✗ {}", Colour::Red.paint(&name));
+                    if update && i == 0 {
+                        let input = String::from_utf8(input.clone()).unwrap();
+                        let corrected_output = format_sexp(&actual);
+                        corrected_entries.push((header.clone(), input, corrected_output));
+                        result.status = TestStatus::Updated;
+                    } else if update {
+                        // The entry has a single expected output, which is corrected
+                        // using the first language's tree.
+                        result.status = TestStatus::Skipped;
+                        result.message =
+                            Some("only the first language's output can be updated".to_string());
+                    } else {
+                        result.status = TestStatus::Failed;
+                        result.message = Some("the syntax tree did not match".to_string());
+                    }
+                    result.expected = Some(output.clone());
+                    result.actual = Some(actual);
+                    passed = false;
                 }
-                failures.push((name, actual, output));
+                print_result(options, indent_level, &result);
+                results.push(result);
+            }
+            if !passed && attributes.fail_fast && !update {
+                return Ok(false);
             }
         }
         TestEntry::Group {
@@ -229,41 +572,148 @@ fn run_tests(
             children,
             file_path,
         } => {
-            if indent_level > 0 {
-                for _ in 0..indent_level {
-                    print!("  ");
-                }
+            if indent_level > 0 && options.format.is_none() {
+                print_indent(indent_level);
                 println!("{}:", name);
             }
 
-            let failure_count = failures.len();
-
-            indent_level += 1;
+            let result_count = results.len();
+            let mut should_continue = true;
             for child in children {
-                run_tests(
-                    parser,
+                should_continue = report_tests(
                     child,
-                    filter,
-                    indent_level,
-                    failures,
-                    update,
+                    options,
+                    indent_level + 1,
+                    outputs,
+                    results,
                     corrected_entries,
                 )?;
+                if !should_continue {
+                    break;
+                }
+            }
+
+            // The top-level group is the test directory itself, so it isn't
+            // included in the tests' group names.
+            for result in &mut results[result_count..] {
+                if indent_level > 0 {
+                    result.groups.insert(0, name.clone());
+                }
+                if result.file.is_none() {
+                    result.file = file_path.clone();
+                }
             }
 
             if let Some(file_path) = file_path {
-                if update && failures.len() - failure_count > 0 {
+                let has_failures = results[result_count..]
+                    .iter()
+                    .any(|r| r.status == TestStatus::Failed || r.status == TestStatus::Updated);
+                if update && has_failures {
                     write_tests(&file_path, corrected_entries)?;
                 }
                 corrected_entries.clear();
             }
+            return Ok(should_continue);
         }
     }
-    Ok(())
+    Ok(true)
+}
+
+// Print the total parsing throughput of the given tests, and the tests that
+// took the longest to parse.
+fn print_time_report(results: &[TestResult]) {
+    let mut parsed_results = results
+        .iter()
+        .filter(|r| r.bytes.is_some())
+        .collect::<Vec<_>>();
+    if parsed_results.is_empty() {
+        return;
+    }
+
+    let total_bytes = parsed_results.iter().filter_map(|r| r.bytes).sum::<usize>();
+    let total_duration = parsed_results.iter().map(|r| r.duration).sum::<Duration>();
+    println!(
+        "\nParsed {} bytes in {:.3} ms ({} bytes/ms)",
+        total_bytes,
+        total_duration.as_secs_f64() * 1000.0,
+        format_throughput(total_bytes, total_duration),
+    );
+
+    parsed_results.sort_by(|a, b| b.duration.cmp(&a.duration));
+    println!("\nSlowest tests:");
+    for (i, result) in parsed_results.iter().take(SLOW_TEST_COUNT).enumerate() {
+        let bytes = result.bytes.unwrap();
+        let mut name = result.groups.join(" - ");
+        if !name.is_empty() {
+            name += " - ";
+        }
+        name += &result.name;
+        println!(
+            "  {:>2}. {:>9.3} ms {:>8} bytes {:>8} bytes/ms  {}",
+            i + 1,
+            result.duration.as_secs_f64() * 1000.0,
+            bytes,
+            format_throughput(bytes, result.duration),
+            name,
+        );
+    }
+}
+
+fn format_throughput(bytes: usize, duration: Duration) -> String {
+    let ms = duration.as_secs_f64() * 1000.0;
+    if ms > 0.0 {
+        format!("{:.0}", bytes as f64 / ms)
+    } else {
+        "-".to_string()
+    }
+}
+
+fn print_result(options: &TestOptions, indent_level: i32, result: &TestResult) {
+    if options.format.is_some() {
+        return;
+    }
+    print_indent(indent_level);
+    match result.status {
+        TestStatus::Passed => println!("✓ {}", Colour::Green.paint(&result.name)),
+        TestStatus::Failed => println!("✗ {}", Colour::Red.paint(&result.name)),
+        TestStatus::Skipped => println!("⌀ {}", Colour::Yellow.paint(&result.name)),
+        TestStatus::Updated => println!("✓ {}", Colour::Blue.paint(&result.name)),
+    }
+}
+
+fn print_indent(indent_level: i32) {
+    for _ in 0..indent_level {
+        print!("  ");
+    }
+}
+
+// Write a test's name, followed by its attributes, in the form that they
+// appear in the test's header.
+fn format_header(name: &str, attributes: &TestAttributes) -> String {
+    let mut result = name.to_string();
+    if attributes.skip {
+        result += "\n:skip";
+    }
+    if attributes.error {
+        result += "\n:error";
+    }
+    if attributes.fail_fast {
+        result += "\n:fail-fast";
+    }
+    for platform in &attributes.platforms {
+        write!(result, "\n:platform({})", platform).unwrap();
+    }
+    for language in &attributes.languages {
+        write!(result, "\n:language({})", language).unwrap();
+    }
+    result
 }
 
 fn format_sexp(sexp: &String) -> String {
     let mut formatted = String::new();
+    if sexp.is_empty() {
+        return formatted;
+    }
 
     let mut indent_level = 0;
     let mut has_field = false;
@@ -369,11 +819,44 @@ pub fn strip_sexp_fields(sexp: String) -> String {
     SEXP_FIELD_REGEX.replace_all(&sexp, " (").to_string()
 }
 
+// Split a test's header into the test's name and its attributes. Lines that
+// are not valid attributes are treated as part of the name.
+fn parse_header(header: &str) -> (String, TestAttributes) {
+    let mut name_lines = Vec::new();
+    let mut attributes = TestAttributes::default();
+    for line in header.trim_matches(|c| char::is_whitespace(c) || c == '=').lines() {
+        let line = line.trim_end();
+        let argument = |prefix: &str| {
+            line.strip_prefix(prefix)
+                .and_then(|rest| rest.strip_suffix(')'))
+                .map(|argument| argument.trim().to_string())
+        };
+        if line == ":skip" {
+            attributes.skip = true;
+        } else if line == ":error" {
+            attributes.error = true;
+        } else if line == ":fail-fast" {
+            attributes.fail_fast = true;
+        } else if let Some(platform) = argument(":platform(") {
+            attributes.platforms.push(platform);
+        } else if let Some(language) = argument(":language(") {
+            attributes.languages.push(language);
+        } else {
+            name_lines.push(line);
+        }
+    }
+    (name_lines.join("\n").trim().to_string(), attributes)
+}
+
 fn parse_test_content(name: String, content: String, file_path: Option<PathBuf>) -> TestEntry {
     let mut children = Vec::new();
     let bytes = content.as_bytes();
     let mut prev_name = String::new();
+    let mut prev_attributes = TestAttributes::default();
     let mut prev_header_end = 0;
+    let mut prev_line = 0;
+    let mut line = 1;
+    let mut line_offset = 0;
 
     // Identify all of the test descriptions using the `======` headers.
     for (header_start, header_end) in HEADER_REGEX
@@ -413,13 +896,23 @@ fn parse_test_content(name: String, content: String, file_path: Option<PathBuf>)
                         input,
                         output,
                         has_fields,
+                        attributes: prev_attributes,
+                        line: prev_line,
                     });
                 }
             }
         }
-        prev_name = String::from_utf8_lossy(&bytes[header_start..header_end])
-            .trim_matches(|c| char::is_whitespace(c) || c == '=')
-            .to_string();
+        let (name, attributes) = parse_header(&String::from_utf8_lossy(
+            &bytes[header_start..header_end],
+        ));
+        line += bytes[line_offset..header_start]
+            .iter()
+            .filter(|b| **b == b'\n')
+            .count();
+        line_offset = header_start;
+        prev_name = name;
+        prev_attributes = attributes;
+        prev_line = line;
         prev_header_end = header_end;
     }
     TestEntry::Group {
@@ -432,6 +925,7 @@ fn parse_test_content(name: String, content: String, file_path: Option<PathBuf>)
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::tests::helpers::fixtures::get_language;
 
     #[test]
     fn test_parse_test_content() {
@@ -471,12 +965,16 @@ d
                         input: "\na b c\n".as_bytes().to_vec(),
                         output: "(a (b c))".to_string(),
                         has_fields: false,
+                        attributes: TestAttributes::default(),
+                        line: 1,
                     },
                     TestEntry::Example {
                         name: "The second test".to_string(),
                         input: "d".as_bytes().to_vec(),
                         output: "(d)".to_string(),
                         has_fields: false,
+                        attributes: TestAttributes::default(),
+                        line: 12,
                     },
                 ],
                 file_path: None,
@@ -525,12 +1023,82 @@ abc
                         input: "abc\n---\ndefg\n----\nhijkl".as_bytes().to_vec(),
                         output: "(a (b))".to_string(),
                         has_fields: false,
+                        attributes: TestAttributes::default(),
+                        line: 1,
                     },
                     TestEntry::Example {
                         name: "Code ending with dashes".to_string(),
                         input: "abc\n-----------".as_bytes().to_vec(),
                         output: "(c (d))".to_string(),
                         has_fields: false,
+                        attributes: TestAttributes::default(),
+                        line: 13,
+                    },
+                ],
+                file_path: None,
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_test_content_with_attributes() {
+        let entry = parse_test_content(
+            "the-filename".to_string(),
+            r#"
+==================
+Error test
+:error
+:fail-fast
+:language(tsx)
+:unknown
+==================
+b
+---
+
+==================
+Skipped test
+:skip
+:platform(linux)
+:platform(macos)
+==================
+a
+---
+(a)
+        "#
+            .trim()
+            .to_string(),
+            None,
+        );
+
+        assert_eq!(
+            entry,
+            TestEntry::Group {
+                name: "the-filename".to_string(),
+                children: vec![
+                    TestEntry::Example {
+                        name: "Error test\n:unknown".to_string(),
+                        input: "b".as_bytes().to_vec(),
+                        output: "".to_string(),
+                        has_fields: false,
+                        attributes: TestAttributes {
+                            error: true,
+                            fail_fast: true,
+                            languages: vec!["tsx".to_string()],
+                            ..Default::default()
+                        },
+                        line: 1,
+                    },
+                    TestEntry::Example {
+                        name: "Skipped test".to_string(),
+                        input: "a".as_bytes().to_vec(),
+                        output: "(a)".to_string(),
+                        has_fields: false,
+                        attributes: TestAttributes {
+                            skip: true,
+                            platforms: vec!["linux".to_string(), "macos".to_string()],
+                            ..Default::default()
+                        },
+                        line: 11,
                     },
                 ],
                 file_path: None,
@@ -538,6 +1106,294 @@ abc
         );
     }
 
+    #[test]
+    fn test_run_tests_with_attributes() {
+        let language = get_language("javascript");
+        let languages = vec![("javascript".to_string(), language)]
+            .into_iter()
+            .collect();
+        let entry = parse_test_content(
+            "the-filename".to_string(),
+            r#"
+==================
+Error
+:error
+==================
+a b c;
+---
+
+==================
+Skipped
+:skip
+==================
+a;
+---
+(wrong)
+
+==================
+Other platform
+:platform(none)
+==================
+a;
+---
+(wrong)
+
+==================
+Fail fast
+:fail-fast
+:language(javascript)
+==================
+a;
+---
+(wrong)
+
+==================
+Not run
+==================
+a;
+---
+(wrong)
+        "#
+            .trim()
+            .to_string(),
+            None,
+        );
+
+        let mut parser = Parser::new();
+        parser.set_language(language).unwrap();
+        let options = TestOptions {
+            languages: &languages,
+            debug: false,
+            debug_graph: false,
+            filter: None,
+            update: false,
+            time: false,
+            format: None,
+        };
+        let mut results = Vec::new();
+        let should_continue =
+            run_tests(&mut parser, entry, &options, &mut results, &mut Vec::new()).unwrap();
+        assert!(!should_continue);
+        assert_eq!(
+            results
+                .iter()
+                .map(|r| (r.name.as_str(), r.status, r.line))
+                .collect::<Vec<_>>(),
+            &[
+                ("Error", TestStatus::Passed, Some(1)),
+                ("Skipped", TestStatus::Skipped, Some(8)),
+                ("Other platform", TestStatus::Skipped, Some(16)),
+                ("Fail fast", TestStatus::Failed, Some(24)),
+            ]
+        );
+        assert_eq!(
+            results[3].actual.as_deref(),
+            Some("(program (expression_statement (identifier)))")
+        );
+        assert_eq!(results[3].expected.as_deref(), Some("(wrong)"));
+        assert!(results
+            .iter()
+            .all(|r| r.groups.is_empty() && r.kind == TestKind::Corpus));
+
+        // Tests that use unknown languages are reported as errors.
+        let entry = parse_test_content(
+            "the-filename".to_string(),
+            "===\nTest\n:language(cobol)\n===\na;\n---\n(program)".to_string(),
+            None,
+        );
+        assert!(run_tests(
+            &mut parser,
+            entry,
+            &options,
+            &mut Vec::new(),
+            &mut Vec::new(),
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_run_tests_with_update_and_several_languages() {
+        let javascript = get_language("javascript");
+        let languages = vec![
+            ("javascript".to_string(), javascript),
+            ("json".to_string(), get_language("json")),
+        ]
+        .into_iter()
+        .collect();
+        let entry = parse_test_content(
+            "the-filename".to_string(),
+            "===\nTest\n:language(javascript)\n:language(json)\n===\na;\n---\n(wrong)".to_string(),
+            None,
+        );
+
+        let mut parser = Parser::new();
+        parser.set_language(javascript).unwrap();
+        let options = TestOptions {
+            languages: &languages,
+            debug: false,
+            debug_graph: false,
+            filter: None,
+            update: true,
+            time: false,
+            format: None,
+        };
+        let mut results = Vec::new();
+        let mut corrected_entries = Vec::new();
+        run_tests(
+            &mut parser,
+            entry,
+            &options,
+            &mut results,
+            &mut corrected_entries,
+        )
+        .unwrap();
+        assert_eq!(
+            results
+                .iter()
+                .map(|r| (r.name.as_str(), r.status))
+                .collect::<Vec<_>>(),
+            &[
+                ("Test (javascript)", TestStatus::Updated),
+                ("Test (json)", TestStatus::Skipped),
+            ]
+        );
+        assert_eq!(
+            corrected_entries,
+            &[(
+                "Test\n:language(javascript)\n:language(json)".to_string(),
+                "a;".to_string(),
+                "(program\n  (expression_statement\n    (identifier)))".to_string(),
+            )]
+        );
+    }
+
+    #[test]
+    fn test_run_tests_with_groups() {
+        let language = get_language("javascript");
+        let languages = HashMap::new();
+        let file_path = PathBuf::from("corpus/statements.txt");
+        let file_entry = parse_test_content(
+            "statements".to_string(),
+            "===\nFirst\n===\na;\n---\n(program (expression_statement (identifier)))\n\n===\nSecond\n===\nb;\n---\n(program)".to_string(),
+            Some(file_path.clone()),
+        );
+        let entry = TestEntry::Group {
+            name: "corpus".to_string(),
+            children: vec![TestEntry::Group {
+                name: "javascript".to_string(),
+                children: vec![file_entry],
+                file_path: None,
+            }],
+            file_path: None,
+        };
+
+        let mut parser = Parser::new();
+        parser.set_language(language).unwrap();
+        let options = TestOptions {
+            languages: &languages,
+            debug: false,
+            debug_graph: false,
+            filter: None,
+            update: false,
+            time: false,
+            format: Some(TestFormat::Json),
+        };
+        let mut results = Vec::new();
+        run_tests(&mut parser, entry, &options, &mut results, &mut Vec::new()).unwrap();
+        assert_eq!(
+            results
+                .iter()
+                .map(|r| (r.name.as_str(), r.status, r.line))
+                .collect::<Vec<_>>(),
+            &[
+                ("First", TestStatus::Passed, Some(1)),
+                ("Second", TestStatus::Failed, Some(8)),
+            ]
+        );
+        for result in &results {
+            assert_eq!(result.groups, &["javascript", "statements"]);
+            assert_eq!(result.file.as_ref(), Some(&file_path));
+        }
+    }
+
+    #[test]
+    fn test_parse_in_parallel() {
+        let language = get_language("javascript");
+        let inputs = (0..50)
+            .map(|i| "a + b;\n".repeat(i).into_bytes())
+            .collect::<Vec<_>>();
+        let jobs = inputs
+            .into_iter()
+            .map(|input| ParseJob {
+                input,
+                language,
+                has_fields: false,
+                ends_batch: false,
+            })
+            .collect::<Vec<_>>();
+
+        // The outputs are in the same order as the inputs.
+        let mut parser = Parser::new();
+        let outputs = parse_in_parallel(&jobs).unwrap();
+        assert_eq!(outputs.len(), jobs.len());
+        for (job, output) in jobs.iter().zip(outputs) {
+            let expected_output = parse_example(&mut parser, job).unwrap();
+            let output = output.unwrap();
+            assert_eq!(output.sexp, expected_output.sexp);
+            assert_eq!(output.has_error, expected_output.has_error);
+        }
+        assert!(parse_in_parallel(&[]).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_parse_outputs_stop_at_fail_fast_tests() {
+        let language = get_language("javascript");
+        let jobs = (0..6)
+            .map(|i| ParseJob {
+                input: b"a;".to_vec(),
+                language,
+                has_fields: false,
+                ends_batch: i == 2,
+            })
+            .collect::<Vec<_>>();
+
+        // The examples up to the end of the `:fail-fast` test are parsed
+        // together, and the rest aren't parsed until they are needed.
+        let mut parser = Parser::new();
+        let mut outputs = ParseOutputs {
+            parser: &mut parser,
+            jobs: &jobs,
+            parallel: true,
+            outputs: VecDeque::new(),
+        };
+        assert!(outputs.next().unwrap().is_ok());
+        assert_eq!(outputs.outputs.len(), 2);
+        assert_eq!(outputs.jobs.len(), 3);
+        assert_eq!(outputs.by_ref().take(2).count(), 2);
+        assert_eq!(outputs.jobs.len(), 3);
+        assert_eq!(outputs.count(), 3);
+    }
+
+    #[test]
+    fn test_format_header() {
+        let attributes = TestAttributes {
+            skip: true,
+            error: true,
+            fail_fast: true,
+            platforms: vec!["linux".to_string()],
+            languages: vec!["a".to_string(), "b".to_string()],
+        };
+        let header = format_header("the name", &attributes);
+        assert_eq!(
+            header,
+            "the name\n:skip\n:error\n:fail-fast\n:platform(linux)\n:language(a)\n:language(b)"
+        );
+        assert_eq!(
+            parse_header(&header),
+            ("the name".to_string(), attributes)
+        );
+    }
+
     #[test]
     fn test_format_sexp() {
         assert_eq!(
@@ -556,6 +1412,12 @@ abc
         );
     }
 
+    #[test]
+    fn test_format_empty_sexp() {
+        // Tests with the `:error` attribute may omit the expected output.
+        assert_eq!(format_sexp(&String::new()), "");
+    }
+
     #[test]
     fn test_write_tests_to_buffer() {
         let mut buffer = Vec::new();
@@ -646,18 +1508,24 @@ code
                         input: "code".as_bytes().to_vec(),
                         output: "(a (b))".to_string(),
                         has_fields: false,
+                        attributes: TestAttributes::default(),
+                        line: 1,
                     },
                     TestEntry::Example {
                         name: "sexp with comment between".to_string(),
                         input: "code".as_bytes().to_vec(),
                         output: "(a (b))".to_string(),
                         has_fields: false,
+                        attributes: TestAttributes::default(),
+                        line: 10,
                     },
                     TestEntry::Example {
                         name: "sexp with ';'".to_string(),
                         input: "code".as_bytes().to_vec(),
                         output: "(MISSING \";\")".to_string(),
                         has_fields: false,
+                        attributes: TestAttributes::default(),
+                        line: 23,
                     }
                 ],
                 file_path: None,
@@ -1,9 +1,11 @@
 use super::error::Result;
 use crate::loader::Loader;
 use crate::query_testing::{parse_position_comments, Assertion};
+use crate::test_report::{TestFormat, TestKind, TestResult, TestStatus};
 use ansi_term::Colour;
 use std::fs;
 use std::path::Path;
+use std::time::Instant;
 use tree_sitter::Point;
 use tree_sitter_highlight::{Highlight, HighlightConfiguration, HighlightEvent, Highlighter};
 
@@ -36,11 +38,18 @@ impl Failure {
     }
 }
 
-pub fn test_highlights(loader: &Loader, directory: &Path) -> Result<()> {
+pub fn test_highlights(
+    loader: &Loader,
+    directory: &Path,
+    format: Option<TestFormat>,
+    results: &mut Vec<TestResult>,
+) -> Result<()> {
     let mut failed = false;
     let mut highlighter = Highlighter::new();
 
-    println!("syntax highlighting:");
+    if format.is_none() {
+        println!("syntax highlighting:");
+    }
     for highlight_test_file in fs::read_dir(directory)? {
         let highlight_test_file = highlight_test_file?;
         let test_file_path = highlight_test_file.path();
@@ -51,31 +60,49 @@ pub fn test_highlights(loader: &Loader, directory: &Path) -> Result<()> {
         let highlight_config = language_config
             .highlight_config(language)?
             .ok_or_else(|| format!("No highlighting config found for {:?}", test_file_path))?;
-        match test_highlight(
+        let start_time = Instant::now();
+        let test_result = test_highlight(
             &loader,
             &mut highlighter,
             highlight_config,
             fs::read(&test_file_path)?.as_slice(),
-        ) {
+        );
+        let mut result = TestResult::new(
+            TestKind::Highlight,
+            test_file_name.to_string_lossy().to_string(),
+            TestStatus::Passed,
+        );
+        result.file = Some(test_file_path.clone());
+        result.duration = start_time.elapsed();
+        match test_result {
             Ok(assertion_count) => {
-                println!(
-                    "  ✓ {} ({} assertions)",
-                    Colour::Green.paint(test_file_name.to_string_lossy().as_ref()),
-                    assertion_count
-                );
+                result.message = Some(format!("{} assertions", assertion_count));
+                if format.is_none() {
+                    println!(
+                        "  ✓ {} ({} assertions)",
+                        Colour::Green.paint(test_file_name.to_string_lossy().as_ref()),
+                        assertion_count
+                    );
+                }
             }
             Err(e) => {
-                println!(
-                    "  ✗ {}",
-                    Colour::Red.paint(test_file_name.to_string_lossy().as_ref())
-                );
-                println!("    {}", e.message());
+                result.status = TestStatus::Failed;
+                result.message = Some(e.message());
+                if format.is_none() {
+                    println!(
+                        "  ✗ {}",
+                        Colour::Red.paint(test_file_name.to_string_lossy().as_ref())
+                    );
+                    println!("    {}", e.message());
+                }
                 failed = true;
             }
         }
+        results.push(result);
     }
 
-    if failed {
+    // Machine-readable reports are written once all of the tests have run.
+    if failed && format.is_none() {
         Err(String::new().into())
     } else {
         Ok(())
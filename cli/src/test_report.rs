@@ -0,0 +1,358 @@
+use super::error::Result;
+use serde::Serializer;
+use serde_derive::Serialize;
+use std::io::Write;
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// The machine-readable formats in which `tree-sitter test` can report its results.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TestFormat {
+    Json,
+    Junit,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TestKind {
+    Corpus,
+    Highlight,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TestStatus {
+    Passed,
+    Failed,
+    Skipped,
+    /// The test failed, and its expected output was updated to match.
+    Updated,
+}
+
+/// The outcome of a single corpus test, or of a single file of highlighting
+/// assertions.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct TestResult {
+    pub kind: TestKind,
+    pub name: String,
+    /// The names of the directories and files that contain the test, starting
+    /// from the top-level test directory.
+    pub groups: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub file: Option<PathBuf>,
+    /// The one-based line number at which the test starts.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line: Option<usize>,
+    /// The size of the test's input, for tests that parse an input.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bytes: Option<usize>,
+    pub status: TestStatus,
+    #[serde(serialize_with = "serialize_duration")]
+    pub duration: Duration,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expected: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub actual: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+#[derive(Default, Serialize)]
+struct Summary {
+    passed: usize,
+    failed: usize,
+    skipped: usize,
+    updated: usize,
+    #[serde(serialize_with = "serialize_duration")]
+    duration: Duration,
+}
+
+#[derive(Serialize)]
+struct Report<'a> {
+    summary: Summary,
+    tests: &'a [TestResult],
+}
+
+impl TestResult {
+    pub fn new(kind: TestKind, name: String, status: TestStatus) -> Self {
+        TestResult {
+            kind,
+            name,
+            groups: Vec::new(),
+            file: None,
+            line: None,
+            bytes: None,
+            status,
+            duration: Duration::default(),
+            expected: None,
+            actual: None,
+            message: None,
+        }
+    }
+
+    pub fn is_failure(&self) -> bool {
+        self.status == TestStatus::Failed
+    }
+}
+
+impl Summary {
+    fn new<'a>(results: impl IntoIterator<Item = &'a TestResult>) -> Self {
+        let mut summary = Summary::default();
+        for result in results {
+            match result.status {
+                TestStatus::Passed => summary.passed += 1,
+                TestStatus::Failed => summary.failed += 1,
+                TestStatus::Skipped => summary.skipped += 1,
+                TestStatus::Updated => summary.updated += 1,
+            }
+            summary.duration += result.duration;
+        }
+        summary
+    }
+}
+
+pub fn write_report(
+    output: &mut impl Write,
+    results: &[TestResult],
+    format: TestFormat,
+) -> Result<()> {
+    match format {
+        TestFormat::Json => {
+            let report = Report {
+                summary: Summary::new(results),
+                tests: results,
+            };
+            serde_json::to_writer_pretty(&mut *output, &report)?;
+            writeln!(output)?;
+        }
+        TestFormat::Junit => write_junit_report(output, results)?,
+    }
+    Ok(())
+}
+
+// Write the results as JUnit XML, with one test suite for each group of tests.
+fn write_junit_report(output: &mut impl Write, results: &[TestResult]) -> Result<()> {
+    let mut suites = Vec::<(String, Vec<&TestResult>)>::new();
+    for result in results {
+        let suite_name = match result.kind {
+            TestKind::Corpus => result.groups.join("/"),
+            TestKind::Highlight => "highlight".to_string(),
+        };
+        match suites.iter_mut().find(|(name, _)| *name == suite_name) {
+            Some((_, suite)) => suite.push(result),
+            None => suites.push((suite_name, vec![result])),
+        }
+    }
+
+    let summary = Summary::new(results);
+    writeln!(output, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    writeln!(
+        output,
+        r#"<testsuites name="tree-sitter" tests="{}" failures="{}" skipped="{}" time="{:.6}">"#,
+        results.len(),
+        summary.failed,
+        summary.skipped,
+        summary.duration.as_secs_f64(),
+    )?;
+    for (suite_name, suite) in &suites {
+        let summary = Summary::new(suite.iter().copied());
+        writeln!(
+            output,
+            r#"  <testsuite name="{}" tests="{}" failures="{}" skipped="{}" time="{:.6}">"#,
+            escape_xml(suite_name),
+            suite.len(),
+            summary.failed,
+            summary.skipped,
+            summary.duration.as_secs_f64(),
+        )?;
+        for result in suite {
+            write!(
+                output,
+                r#"    <testcase name="{}" classname="{}" time="{:.6}""#,
+                escape_xml(&result.name),
+                escape_xml(suite_name),
+                result.duration.as_secs_f64(),
+            )?;
+            if let Some(file) = &result.file {
+                write!(output, r#" file="{}""#, escape_xml(&file.to_string_lossy()))?;
+            }
+            if let Some(line) = result.line {
+                write!(output, r#" line="{}""#, line)?;
+            }
+            match result.status {
+                TestStatus::Passed | TestStatus::Updated => writeln!(output, "/>")?,
+                TestStatus::Skipped => {
+                    writeln!(output, ">")?;
+                    writeln!(output, "      <skipped/>")?;
+                    writeln!(output, "    </testcase>")?;
+                }
+                TestStatus::Failed => {
+                    let message = result.message.as_deref().unwrap_or("test failed");
+                    let mut details = String::new();
+                    if let Some(expected) = &result.expected {
+                        details += &format!("expected: {}\n", expected);
+                    }
+                    if let Some(actual) = &result.actual {
+                        details += &format!("actual: {}\n", actual);
+                    }
+                    writeln!(output, ">")?;
+                    writeln!(
+                        output,
+                        r#"      <failure message="{}">{}</failure>"#,
+                        escape_xml(message),
+                        escape_xml(&details),
+                    )?;
+                    writeln!(output, "    </testcase>")?;
+                }
+            }
+        }
+        writeln!(output, "  </testsuite>")?;
+    }
+    writeln!(output, "</testsuites>")?;
+    Ok(())
+}
+
+fn escape_xml(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => result += "&amp;",
+            '<' => result += "&lt;",
+            '>' => result += "&gt;",
+            '"' => result += "&quot;",
+            '\'' => result += "&apos;",
+            '\t' | '\n' | '\r' => result.push(c),
+            // Other control characters are not allowed in XML documents.
+            c if (c as u32) < 0x20 => {}
+            c => result.push(c),
+        }
+    }
+    result
+}
+
+fn serialize_duration<S: Serializer>(
+    duration: &Duration,
+    serializer: S,
+) -> std::result::Result<S::Ok, S::Error> {
+    serializer.serialize_f64(duration.as_secs_f64())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_results() -> Vec<TestResult> {
+        let mut passed = TestResult::new(TestKind::Corpus, "First".to_string(), TestStatus::Passed);
+        passed.groups = vec!["statements".to_string()];
+        passed.file = Some(PathBuf::from("corpus/statements.txt"));
+        passed.line = Some(1);
+        passed.duration = Duration::from_millis(2);
+
+        let mut failed = TestResult::new(
+            TestKind::Corpus,
+            "Second <b>".to_string(),
+            TestStatus::Failed,
+        );
+        failed.groups = vec!["statements".to_string()];
+        failed.file = Some(PathBuf::from("corpus/statements.txt"));
+        failed.line = Some(8);
+        failed.duration = Duration::from_millis(1);
+        failed.expected = Some("(program (a))".to_string());
+        failed.actual = Some("(program)".to_string());
+        failed.message = Some("the syntax tree did not match".to_string());
+
+        let skipped = TestResult::new(TestKind::Corpus, "Third".to_string(), TestStatus::Skipped);
+
+        let mut highlight = TestResult::new(
+            TestKind::Highlight,
+            "keywords.js".to_string(),
+            TestStatus::Passed,
+        );
+        highlight.message = Some("3 assertions".to_string());
+
+        vec![passed, failed, skipped, highlight]
+    }
+
+    #[test]
+    fn test_write_json_report() {
+        let mut buffer = Vec::new();
+        write_report(&mut buffer, &get_results(), TestFormat::Json).unwrap();
+        let report: serde_json::Value = serde_json::from_slice(&buffer).unwrap();
+
+        assert_eq!(
+            report["summary"],
+            serde_json::json!({
+                "passed": 2,
+                "failed": 1,
+                "skipped": 1,
+                "updated": 0,
+                "duration": 0.003,
+            })
+        );
+        assert_eq!(
+            report["tests"][1],
+            serde_json::json!({
+                "kind": "corpus",
+                "name": "Second <b>",
+                "groups": ["statements"],
+                "file": "corpus/statements.txt",
+                "line": 8,
+                "status": "failed",
+                "duration": 0.001,
+                "expected": "(program (a))",
+                "actual": "(program)",
+                "message": "the syntax tree did not match",
+            })
+        );
+        assert_eq!(
+            report["tests"][3],
+            serde_json::json!({
+                "kind": "highlight",
+                "name": "keywords.js",
+                "groups": [],
+                "status": "passed",
+                "duration": 0.0,
+                "message": "3 assertions",
+            })
+        );
+    }
+
+    #[test]
+    fn test_write_junit_report() {
+        let mut buffer = Vec::new();
+        write_report(&mut buffer, &get_results(), TestFormat::Junit).unwrap();
+        assert_eq!(
+            String::from_utf8(buffer).unwrap(),
+            r#"
+<?xml version="1.0" encoding="UTF-8"?>
+<testsuites name="tree-sitter" tests="4" failures="1" skipped="1" time="0.003000">
+  <testsuite name="statements" tests="2" failures="1" skipped="0" time="0.003000">
+    <testcase name="First" classname="statements" time="0.002000" file="corpus/statements.txt" line="1"/>
+    <testcase name="Second &lt;b&gt;" classname="statements" time="0.001000" file="corpus/statements.txt" line="8">
+      <failure message="the syntax tree did not match">expected: (program (a))
+actual: (program)
+</failure>
+    </testcase>
+  </testsuite>
+  <testsuite name="" tests="1" failures="0" skipped="1" time="0.000000">
+    <testcase name="Third" classname="" time="0.000000">
+      <skipped/>
+    </testcase>
+  </testsuite>
+  <testsuite name="highlight" tests="1" failures="0" skipped="0" time="0.000000">
+    <testcase name="keywords.js" classname="highlight" time="0.000000"/>
+  </testsuite>
+</testsuites>
+"#
+            .trim_start()
+        );
+    }
+
+    #[test]
+    fn test_escape_xml() {
+        assert_eq!(
+            escape_xml("(MISSING \"<\") & 'a'\u{1}\n"),
+            "(MISSING &quot;&lt;&quot;) &amp; &apos;a&apos;\n"
+        );
+    }
+}
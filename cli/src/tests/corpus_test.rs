@@ -1,16 +1,15 @@
-use super::helpers::edits::{get_random_edit, invert_edit};
 use super::helpers::fixtures::{fixtures_dir, get_language, get_test_language};
-use super::helpers::random::Rand;
-use super::helpers::scope_sequence::ScopeSequence;
+use crate::fuzz::edits::{get_random_edit, invert_edit};
+use crate::fuzz::random::Rand;
+use crate::fuzz::{check_changed_ranges, check_consistent_sizes, EDIT_COUNT};
 use crate::generate;
 use crate::parse::perform_edit;
 use crate::test::{parse_tests, print_diff, print_diff_key, strip_sexp_fields, TestEntry};
 use crate::util;
 use lazy_static::lazy_static;
 use std::{env, fs, time, usize};
-use tree_sitter::{allocations, LogType, Node, Parser, Tree};
+use tree_sitter::{allocations, LogType, Parser};
 
-const EDIT_COUNT: usize = 3;
 const TRIAL_COUNT: usize = 10;
 const LANGUAGES: &'static [&'static str] = &[
     "bash",
@@ -134,7 +133,7 @@ fn test_real_language_corpus_files() {
                     let mut tree2 = parser.parse(&input, Some(&tree)).unwrap();
 
                     // Check that the new tree is consistent.
-                    check_consistent_sizes(&tree2, &input);
+                    check_consistent_sizes(&tree2, &input).unwrap();
                     if let Err(message) = check_changed_ranges(&tree, &tree2, &input) {
                         println!(
                             "\nUnexpected scope change in trial {}\n{}\n\n",
@@ -173,7 +172,7 @@ fn test_real_language_corpus_files() {
                     }
 
                     // Check that the edited tree is consistent.
-                    check_consistent_sizes(&tree3, &input);
+                    check_consistent_sizes(&tree3, &input).unwrap();
                     if let Err(message) = check_changed_ranges(&tree2, &tree3, &input) {
                         eprintln!(
                             "Unexpected scope change in trial {}\n{}\n\n",
@@ -298,69 +297,6 @@ fn test_feature_corpus_files() {
     }
 }
 
-fn check_consistent_sizes(tree: &Tree, input: &Vec<u8>) {
-    fn check(node: Node, line_offsets: &Vec<usize>) {
-        let start_byte = node.start_byte();
-        let end_byte = node.end_byte();
-        let start_point = node.start_position();
-        let end_point = node.end_position();
-
-        assert!(start_byte <= end_byte);
-        assert!(start_point <= end_point);
-        assert_eq!(
-            start_byte,
-            line_offsets[start_point.row] + start_point.column
-        );
-        assert_eq!(end_byte, line_offsets[end_point.row] + end_point.column);
-
-        let mut last_child_end_byte = start_byte;
-        let mut last_child_end_point = start_point;
-        let mut some_child_has_changes = false;
-        let mut actual_named_child_count = 0;
-        for i in 0..node.child_count() {
-            let child = node.child(i).unwrap();
-            assert!(child.start_byte() >= last_child_end_byte);
-            assert!(child.start_position() >= last_child_end_point);
-            check(child, line_offsets);
-            if child.has_changes() {
-                some_child_has_changes = true;
-            }
-            if child.is_named() {
-                actual_named_child_count += 1;
-            }
-            last_child_end_byte = child.end_byte();
-            last_child_end_point = child.end_position();
-        }
-
-        assert_eq!(actual_named_child_count, node.named_child_count());
-
-        if node.child_count() > 0 {
-            assert!(end_byte >= last_child_end_byte);
-            assert!(end_point >= last_child_end_point);
-        }
-
-        if some_child_has_changes {
-            assert!(node.has_changes());
-        }
-    }
-
-    let mut line_offsets = vec![0];
-    for (i, c) in input.iter().enumerate() {
-        if *c == '\n' as u8 {
-            line_offsets.push(i + 1);
-        }
-    }
-
-    check(tree.root_node(), &line_offsets);
-}
-
-fn check_changed_ranges(old_tree: &Tree, new_tree: &Tree, input: &Vec<u8>) -> Result<(), String> {
-    let changed_ranges = old_tree.changed_ranges(new_tree).collect();
-    let old_scope_sequence = ScopeSequence::new(old_tree);
-    let new_scope_sequence = ScopeSequence::new(new_tree);
-    old_scope_sequence.check_changes(&new_scope_sequence, &input, &changed_ranges)
-}
-
 fn get_parser(session: &mut Option<util::LogSession>, log_filename: &str) -> Parser {
     let mut parser = Parser::new();
 
@@ -387,7 +323,12 @@ fn flatten_tests(test: TestEntry) -> Vec<(String, Vec<u8>, String, bool)> {
                 input,
                 output,
                 has_fields,
+                attributes,
+                ..
             } => {
+                if attributes.skip {
+                    return;
+                }
                 if !prefix.is_empty() {
                     name.insert_str(0, " - ");
                     name.insert_str(0, prefix);
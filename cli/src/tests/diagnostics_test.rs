@@ -0,0 +1,84 @@
+use super::helpers::fixtures::get_language;
+use tree_sitter::diagnostics::{diagnostics, DiagnosticKind};
+use tree_sitter::{Parser, Point, Tree};
+
+#[test]
+fn test_diagnostics_for_valid_code() {
+    let tree = parse("javascript", "foo(1, 2);\n");
+    assert_eq!(diagnostics(&tree), vec![]);
+}
+
+#[test]
+fn test_diagnostics_for_missing_nodes() {
+    let source = "[1, 2";
+    let tree = parse("json", source);
+    let diagnostics = diagnostics(&tree);
+    assert_eq!(diagnostics.len(), 1);
+
+    let diagnostic = &diagnostics[0];
+    assert!(diagnostic.node.is_missing());
+    assert_eq!(diagnostic.kind, DiagnosticKind::Missing { kind: "]" });
+    assert_eq!(diagnostic.range.start_byte, 5);
+    assert_eq!(diagnostic.range.end_byte, 5);
+    assert_eq!(diagnostic.range.start_point, Point::new(0, 5));
+    assert_eq!(diagnostic.message(source.as_bytes()), "missing `]`");
+}
+
+#[test]
+fn test_diagnostics_for_unexpected_tokens() {
+    let source = "foo(1 2);\n";
+    let tree = parse("javascript", source);
+    let diagnostics = diagnostics(&tree);
+    assert_eq!(diagnostics.len(), 1);
+
+    let diagnostic = &diagnostics[0];
+    assert!(diagnostic.node.is_error());
+    assert_eq!(diagnostic.range.start_point, Point::new(0, 6));
+    assert_eq!(diagnostic.range.end_point, Point::new(0, 7));
+    match diagnostic.kind {
+        DiagnosticKind::Unexpected { token: Some(token) } => {
+            assert_eq!(token.kind(), "number");
+            assert_eq!(token.utf8_text(source.as_bytes()).unwrap(), "2");
+        }
+        kind => panic!("unexpected diagnostic kind {:?}", kind),
+    }
+
+    // The argument list could have been continued or closed at this point.
+    let language = tree.language();
+    let expected = diagnostic
+        .expected
+        .iter()
+        .map(|id| language.node_kind_for_id(*id).unwrap())
+        .collect::<Vec<_>>();
+    assert!(expected.contains(&","));
+    assert!(expected.contains(&")"));
+    assert!(!expected.contains(&"number"));
+
+    let message = diagnostic.message(source.as_bytes());
+    assert!(message.starts_with("unexpected `2`, expected one of "));
+    assert!(message.contains("`,`"));
+    assert!(message.contains("`)`"));
+}
+
+#[test]
+fn test_diagnostics_are_in_document_order() {
+    let source = "fn a() { let x = 1 }\nfn b() { let y = (2; }\n";
+    let tree = parse("rust", source);
+    let diagnostics = diagnostics(&tree);
+    assert_eq!(
+        diagnostics
+            .iter()
+            .map(|d| (d.range.start_point.row, d.message(source.as_bytes())))
+            .collect::<Vec<_>>(),
+        vec![
+            (0, "missing `;`".to_string()),
+            (1, "missing `)`".to_string())
+        ]
+    );
+}
+
+fn parse(language: &str, source: &str) -> Tree {
+    let mut parser = Parser::new();
+    parser.set_language(get_language(language)).unwrap();
+    parser.parse(source, None).unwrap()
+}
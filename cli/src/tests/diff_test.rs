@@ -0,0 +1,198 @@
+use super::helpers::fixtures::get_language;
+use crate::parse::{perform_edit, Edit as SourceEdit};
+use tree_sitter::diff::{diff, Edit};
+use tree_sitter::{Node, Parser, Tree};
+
+#[test]
+fn test_diff_identical_trees() {
+    let source = "function a(b) { return b + 1; }\nconst c = [1, 2];\n";
+    let (old_tree, new_tree) = parse_pair("javascript", source, source);
+
+    let result = diff(&old_tree, source.as_bytes(), &new_tree, source.as_bytes());
+    assert_eq!(result.edits, vec![]);
+    assert_eq!(result.matches.len(), node_count(old_tree.root_node()));
+    for (old, new) in result.matches {
+        assert_eq!(old.kind(), new.kind());
+        assert_eq!(old.byte_range(), new.byte_range());
+    }
+}
+
+#[test]
+fn test_diff_updated_leaves() {
+    let old_source = "let a = 1;\nlet b = 2;\n";
+    let new_source = "let a = 1;\nlet b = 3;\n";
+    let (old_tree, new_tree) = parse_pair("javascript", old_source, new_source);
+
+    let result = diff(
+        &old_tree,
+        old_source.as_bytes(),
+        &new_tree,
+        new_source.as_bytes(),
+    );
+    assert_eq!(
+        describe_edits(&result.edits, old_source, new_source),
+        vec!["update number 2 -> 3"]
+    );
+}
+
+#[test]
+fn test_diff_inserted_and_deleted_nodes() {
+    let old_source = "a();\nb();\n";
+    let new_source = "a();\nx.y;\nb();\n";
+    let (old_tree, new_tree) = parse_pair("javascript", old_source, new_source);
+
+    let result = diff(
+        &old_tree,
+        old_source.as_bytes(),
+        &new_tree,
+        new_source.as_bytes(),
+    );
+    assert_eq!(
+        describe_edits(&result.edits, old_source, new_source),
+        vec![
+            "insert expression_statement into program at 1",
+            "insert member_expression into expression_statement at 0",
+            "insert identifier into member_expression at 0",
+            "insert . into member_expression at 1",
+            "insert property_identifier into member_expression at 2",
+            "insert ; into expression_statement at 1",
+        ]
+    );
+
+    // Swapping the trees turns the insertions into deletions, children first.
+    let result = diff(
+        &new_tree,
+        new_source.as_bytes(),
+        &old_tree,
+        old_source.as_bytes(),
+    );
+    assert_eq!(
+        describe_edits(&result.edits, new_source, old_source),
+        vec![
+            "delete identifier x",
+            "delete . .",
+            "delete property_identifier y",
+            "delete member_expression x.y",
+            "delete ; ;",
+            "delete expression_statement x.y;",
+        ]
+    );
+}
+
+#[test]
+fn test_diff_moved_nodes() {
+    let old_source = "function a() { one(); }\nfunction b() { two(); }\n";
+    let new_source = "function b() { two(); }\nfunction a() { one(); }\n";
+    let (old_tree, new_tree) = parse_pair("javascript", old_source, new_source);
+
+    let result = diff(
+        &old_tree,
+        old_source.as_bytes(),
+        &new_tree,
+        new_source.as_bytes(),
+    );
+    assert_eq!(
+        describe_edits(&result.edits, old_source, new_source),
+        vec!["move function_declaration into program at 0"]
+    );
+
+    // Moving a node into a different parent.
+    let old_source = "if (a) { b(); }\nc();\n";
+    let new_source = "if (a) { b(); c(); }\n";
+    let (old_tree, new_tree) = parse_pair("javascript", old_source, new_source);
+    let result = diff(
+        &old_tree,
+        old_source.as_bytes(),
+        &new_tree,
+        new_source.as_bytes(),
+    );
+    assert_eq!(
+        describe_edits(&result.edits, old_source, new_source),
+        vec!["move expression_statement into statement_block at 2"]
+    );
+}
+
+#[test]
+fn test_diff_uses_node_ids_of_reused_subtrees() {
+    let mut source = b"function a() { one(); }\nfunction b() { two(); }\n".to_vec();
+    let old_source = source.clone();
+
+    let mut parser = Parser::new();
+    parser.set_language(get_language("javascript")).unwrap();
+    let mut old_tree = parser.parse(&source, None).unwrap();
+
+    let position = source.iter().position(|c| *c == b'w').unwrap();
+    perform_edit(
+        &mut old_tree,
+        &mut source,
+        &SourceEdit {
+            position,
+            deleted_length: 1,
+            inserted_text: b"o".to_vec(),
+        },
+    );
+    let new_tree = parser.parse(&source, Some(&old_tree)).unwrap();
+
+    let result = diff(&old_tree, &old_source, &new_tree, &source);
+    assert_eq!(
+        describe_edits(&result.edits, str_of(&old_source), str_of(&source)),
+        vec!["update identifier two -> too"]
+    );
+
+    fn first_function(tree: &Tree) -> Node {
+        tree.root_node().child(0).unwrap()
+    }
+    assert_eq!(
+        first_function(&old_tree).id(),
+        first_function(&new_tree).id()
+    );
+    assert!(result
+        .matches
+        .contains(&(first_function(&old_tree), first_function(&new_tree))));
+}
+
+fn parse_pair(language: &str, old_source: &str, new_source: &str) -> (Tree, Tree) {
+    let mut parser = Parser::new();
+    parser.set_language(get_language(language)).unwrap();
+    (
+        parser.parse(old_source, None).unwrap(),
+        parser.parse(new_source, None).unwrap(),
+    )
+}
+
+fn node_count(node: Node) -> usize {
+    1 + node
+        .children(&mut node.walk())
+        .map(node_count)
+        .sum::<usize>()
+}
+
+fn str_of(source: &[u8]) -> &str {
+    std::str::from_utf8(source).unwrap()
+}
+
+fn describe_edits(edits: &[Edit], old_source: &str, new_source: &str) -> Vec<String> {
+    let text = |node: Node, source: &str| source[node.byte_range()].to_string();
+    edits
+        .iter()
+        .map(|edit| match edit {
+            Edit::Insert {
+                node,
+                parent,
+                index,
+            } => format!("insert {} into {} at {}", node.kind(), parent.kind(), index),
+            Edit::Delete { node } => {
+                format!("delete {} {}", node.kind(), text(*node, old_source))
+            }
+            Edit::Update { old, new } => format!(
+                "update {} {} -> {}",
+                old.kind(),
+                text(*old, old_source),
+                text(*new, new_source)
+            ),
+            Edit::Move {
+                new, parent, index, ..
+            } => format!("move {} into {} at {}", new.kind(), parent.kind(), index),
+        })
+        .collect()
+}
@@ -19,6 +19,10 @@ pub fn fixtures_dir<'a>() -> &'static Path {
     &FIXTURES_DIR
 }
 
+pub fn scratch_dir<'a>() -> &'static Path {
+    &SCRATCH_DIR
+}
+
 pub fn get_language(name: &str) -> Language {
     TEST_LOADER
         .load_language_at_path(&GRAMMARS_DIR.join(name).join("src"), &HEADER_DIR)
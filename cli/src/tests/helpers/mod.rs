@@ -1,4 +1,2 @@
 pub(super) mod edits;
-pub(super) mod fixtures;
-pub(super) mod random;
-pub(super) mod scope_sequence;
+pub(crate) mod fixtures;
@@ -6,8 +6,10 @@ use std::sync::{
     Arc,
 };
 use std::{fs, ptr, slice, str};
+use tree_sitter::QueryErrorKind;
 use tree_sitter_highlight::{
-    c, Error, Highlight, HighlightConfiguration, HighlightEvent, Highlighter, HtmlRenderer,
+    c, Error, Highlight, HighlightConfiguration, HighlightEvent, HighlightQueryError, Highlighter,
+    HtmlRenderer, QuerySection,
 };
 
 lazy_static! {
@@ -589,6 +591,50 @@ fn test_highlighting_via_c_api() {
     c::ts_highlight_buffer_delete(buffer);
 }
 
+#[test]
+fn test_highlight_config_query_error_location() {
+    let language = get_language("javascript");
+
+    let highlights_query =
+        "(identifier) @variable\n(function_declaration\n  name: (identifier) @function\n  (bogus_node))";
+    let HighlightQueryError { section, error } = HighlightConfiguration::new(
+        language,
+        highlights_query,
+        "(comment) @injection.content\n",
+        "(statement_block) @local.scope\n",
+    )
+    .err()
+    .unwrap();
+    assert_eq!(section, QuerySection::Highlights);
+    assert_eq!(error.kind, QueryErrorKind::NodeType);
+    assert_eq!((error.row, error.column), (3, 3));
+    assert_eq!(error.offset, highlights_query.find("bogus_node").unwrap());
+
+    let error = HighlightConfiguration::new(
+        language,
+        "(identifier) @variable",
+        "(comment) @injection.content\n",
+        "(statement_block) @local.scope\n(bogus_node) @local.definition\n",
+    )
+    .err()
+    .unwrap();
+    assert_eq!(error.section, QuerySection::Locals);
+    assert_eq!(error.error.kind, QueryErrorKind::NodeType);
+    assert_eq!((error.error.row, error.error.column), (1, 1));
+
+    let error = HighlightConfiguration::new(
+        language,
+        "(identifier) @variable",
+        "(comment) @injection.content)\n",
+        "",
+    )
+    .err()
+    .unwrap();
+    assert_eq!(error.section, QuerySection::Injections);
+    assert_eq!(error.error.kind, QueryErrorKind::Syntax);
+    assert_eq!((error.error.row, error.error.column), (0, 28));
+}
+
 #[test]
 fn test_decode_utf8_lossy() {
     use tree_sitter::LossyUtf8;
@@ -0,0 +1,230 @@
+use super::helpers::fixtures::{get_language, get_test_language};
+use crate::generate::{generate_parser_for_grammar, generate_parser_in_directory};
+use std::fs;
+use tree_sitter::{Language, Parser};
+
+const GRAMMAR_WITH_SUPERTYPES: &'static str = r#"{
+    "name": "test_supertypes",
+    "extras": [{"type": "PATTERN", "value": "\\s"}],
+    "supertypes": ["_expression", "_literal"],
+    "rules": {
+        "program": {
+            "type": "REPEAT",
+            "content": {"type": "SYMBOL", "name": "_expression"}
+        },
+        "_expression": {
+            "type": "CHOICE",
+            "members": [
+                {"type": "SYMBOL", "name": "identifier"},
+                {"type": "SYMBOL", "name": "_literal"},
+                {"type": "SYMBOL", "name": "parenthesized_expression"}
+            ]
+        },
+        "_literal": {
+            "type": "CHOICE",
+            "members": [
+                {"type": "SYMBOL", "name": "number"},
+                {"type": "SYMBOL", "name": "string"}
+            ]
+        },
+        "parenthesized_expression": {
+            "type": "SEQ",
+            "members": [
+                {"type": "STRING", "value": "("},
+                {"type": "SYMBOL", "name": "_expression"},
+                {"type": "STRING", "value": ")"}
+            ]
+        },
+        "identifier": {"type": "PATTERN", "value": "[a-z]+"},
+        "number": {"type": "PATTERN", "value": "\\d+"},
+        "string": {"type": "PATTERN", "value": "'[a-z]*'"}
+    }
+}"#;
+
+#[test]
+fn test_lookahead_iterator() {
+    let mut parser = Parser::new();
+    let language = get_language("rust");
+    parser.set_language(language).unwrap();
+
+    let tree = parser.parse("struct Stuff {}", None).unwrap();
+
+    let mut cursor = tree.walk();
+
+    assert!(cursor.goto_first_child()); // struct
+    assert!(cursor.goto_first_child()); // struct keyword
+
+    let next_state = cursor.node().next_parse_state();
+    assert_ne!(next_state, 0);
+    assert_eq!(
+        next_state,
+        language.next_state(cursor.node().parse_state(), cursor.node().grammar_id())
+    );
+    assert!((next_state as usize) < language.parse_state_count());
+    assert!(cursor.goto_next_sibling()); // type_identifier
+    assert_eq!(next_state, cursor.node().parse_state());
+    assert_eq!(cursor.node().grammar_name(), "identifier");
+    assert_ne!(cursor.node().grammar_id(), cursor.node().kind_id());
+
+    let expected_symbols = ["identifier", "block_comment", "line_comment"];
+    let mut lookahead = language.lookahead_iterator(next_state).unwrap();
+    assert_eq!(lookahead.language(), language);
+    assert!(lookahead.iter_names().eq(expected_symbols.iter().cloned()));
+
+    lookahead.reset_state(next_state);
+    assert!(lookahead.iter_names().eq(expected_symbols.iter().cloned()));
+
+    lookahead.reset(language, next_state);
+    assert!(lookahead
+        .map(|s| language.node_kind_for_id(s).unwrap())
+        .eq(expected_symbols.iter().cloned()));
+}
+
+#[test]
+fn test_lookahead_iterator_with_invalid_state() {
+    let language = get_language("rust");
+    let state_count = language.parse_state_count();
+    assert!(language.lookahead_iterator(state_count as u16).is_none());
+
+    let mut lookahead = language.lookahead_iterator(1).unwrap();
+    assert!(!lookahead.reset_state(state_count as u16));
+    assert!(!lookahead.reset(language, state_count as u16));
+}
+
+#[test]
+fn test_lookahead_iterator_after_node() {
+    let mut parser = Parser::new();
+    let language = get_language("json");
+    parser.set_language(language).unwrap();
+
+    let tree = parser.parse("{\"a\": 1}", None).unwrap();
+    let pair = tree.root_node().child(0).unwrap().child(1).unwrap();
+    let colon = pair.child(1).unwrap();
+    assert_eq!(colon.kind(), ":");
+
+    let mut lookahead = language
+        .lookahead_iterator(colon.next_parse_state())
+        .unwrap();
+    let names = lookahead.iter_names().collect::<Vec<_>>();
+    for expected_name in &["{", "[", "number", "true", "false", "null"] {
+        assert!(
+            names.contains(expected_name),
+            "expected {:?} to contain {:?}",
+            names,
+            expected_name
+        );
+    }
+}
+
+#[test]
+fn test_language_supertypes_and_subtypes() {
+    let (parser_name, parser_code) = generate_parser_for_grammar(GRAMMAR_WITH_SUPERTYPES).unwrap();
+    let language = get_test_language(&parser_name, &parser_code, None);
+
+    let expression = language.id_for_node_kind("_expression", true);
+    let literal = language.id_for_node_kind("_literal", true);
+    let identifier = language.id_for_node_kind("identifier", true);
+    let number = language.id_for_node_kind("number", true);
+    let string = language.id_for_node_kind("string", true);
+    let parenthesized_expression = language.id_for_node_kind("parenthesized_expression", true);
+
+    let mut supertypes = language.supertypes().to_vec();
+    supertypes.sort_unstable();
+    let mut expected_supertypes = vec![expression, literal];
+    expected_supertypes.sort_unstable();
+    assert_eq!(supertypes, expected_supertypes);
+
+    let mut subtypes = language.subtypes(expression).to_vec();
+    subtypes.sort_unstable();
+    let mut expected_subtypes = vec![literal, identifier, parenthesized_expression];
+    expected_subtypes.sort_unstable();
+    assert_eq!(subtypes, expected_subtypes);
+
+    let mut subtypes = language.subtypes(literal).to_vec();
+    subtypes.sort_unstable();
+    let mut expected_subtypes = vec![number, string];
+    expected_subtypes.sort_unstable();
+    assert_eq!(subtypes, expected_subtypes);
+
+    assert!(language.subtypes(identifier).is_empty());
+    assert!(language.subtypes(u16::MAX).is_empty());
+
+    let mut parser = Parser::new();
+    parser.set_language(language).unwrap();
+    let tree = parser.parse("a (1) 'b'", None).unwrap();
+    let root = tree.root_node();
+    let identifier_node = root.named_child(0).unwrap();
+    let parenthesized_node = root.named_child(1).unwrap();
+    let number_node = parenthesized_node.named_child(0).unwrap();
+    let string_node = root.named_child(2).unwrap();
+
+    assert!(identifier_node.is_subtype_of(expression));
+    assert!(!identifier_node.is_subtype_of(literal));
+    assert!(parenthesized_node.is_subtype_of(expression));
+    assert!(number_node.is_subtype_of(literal));
+    assert!(number_node.is_subtype_of(expression));
+    assert!(string_node.is_subtype_of(expression));
+    assert!(!root.is_subtype_of(expression));
+}
+
+#[test]
+fn test_language_supertypes_from_generated_directory() {
+    // Generate the parser in the same way as `tree-sitter generate`, which
+    // uses the previous ABI version unless `--next-abi` is passed. That version
+    // has no supertype tables.
+    let language = generate_language_in_directory(
+        "test_supertypes_default_abi",
+        tree_sitter::LANGUAGE_VERSION - 1,
+    );
+    assert_eq!(language.version(), tree_sitter::LANGUAGE_VERSION - 1);
+    assert!(language.supertypes().is_empty());
+    assert!(language
+        .subtypes(language.id_for_node_kind("_expression", true))
+        .is_empty());
+
+    // Generate the parser in the same way as `tree-sitter generate --next-abi`.
+    let language = generate_language_in_directory(
+        "test_supertypes_next_abi",
+        tree_sitter::LANGUAGE_VERSION,
+    );
+    assert_eq!(language.version(), tree_sitter::LANGUAGE_VERSION);
+
+    let expression = language.id_for_node_kind("_expression", true);
+    let literal = language.id_for_node_kind("_literal", true);
+    let mut supertypes = language.supertypes().to_vec();
+    supertypes.sort_unstable();
+    let mut expected_supertypes = vec![expression, literal];
+    expected_supertypes.sort_unstable();
+    assert_eq!(supertypes, expected_supertypes);
+    assert_eq!(language.subtypes(literal).len(), 2);
+
+    let mut parser = Parser::new();
+    parser.set_language(language).unwrap();
+    let tree = parser.parse("1", None).unwrap();
+    assert!(tree
+        .root_node()
+        .named_child(0)
+        .unwrap()
+        .is_subtype_of(expression));
+}
+
+fn generate_language_in_directory(name: &str, abi_version: usize) -> Language {
+    let dir = tempfile::tempdir().unwrap();
+    let grammar_path = dir.path().join("grammar.json");
+    fs::write(
+        &grammar_path,
+        GRAMMAR_WITH_SUPERTYPES.replace("test_supertypes", name),
+    )
+    .unwrap();
+    generate_parser_in_directory(
+        &dir.path().to_path_buf(),
+        grammar_path.to_str(),
+        abi_version,
+        false,
+        false,
+        None,
+    )
+    .unwrap();
+    let parser_code = fs::read_to_string(dir.path().join("src").join("parser.c")).unwrap();
+    get_test_language(name, &parser_code, None)
+}
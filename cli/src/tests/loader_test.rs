@@ -0,0 +1,165 @@
+use super::helpers::fixtures::{get_language, scratch_dir};
+use crate::loader::{LanguageConfiguration, Loader};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const BASE_TAGS_QUERY: &'static str = "
+(function_declaration name: (identifier) @name) @definition.function
+";
+
+const DERIVED_TAGS_QUERY: &'static str = "; inherits: base
+(class_declaration name: (identifier) @name) @definition.class
+";
+
+#[test]
+fn test_loader_highlight_config_with_inherited_queries() {
+    let dir = write_languages(
+        "inherited-highlights",
+        &[
+            ("base", "(identifier) @variable\n", BASE_TAGS_QUERY),
+            (
+                "derived",
+                "; inherits: base\n\n(number) @number\n",
+                DERIVED_TAGS_QUERY,
+            ),
+            (
+                "twice-derived",
+                ";; inherits: derived, base\n(true) @constant\n",
+                "",
+            ),
+        ],
+    );
+
+    let mut loader = Loader::new(scratch_dir().to_owned());
+    loader.find_all_languages(&vec![dir.clone()]).unwrap();
+    let language = get_language("javascript");
+
+    let configuration = find_configuration(&loader, &dir, "derived");
+    let config = configuration.highlight_config(language).unwrap().unwrap();
+    assert_eq!(
+        config.query.capture_names(),
+        &["variable".to_string(), "number".to_string()]
+    );
+    let config = configuration.tags_config(language).unwrap().unwrap();
+    assert_eq!(config.syntax_type_name(0), "function");
+    assert_eq!(config.syntax_type_name(1), "class");
+
+    // Queries that are inherited along several paths are only included once.
+    let configuration = find_configuration(&loader, &dir, "twice-derived");
+    let config = configuration.highlight_config(language).unwrap().unwrap();
+    assert_eq!(config.query.pattern_count(), 3);
+}
+
+#[test]
+fn test_loader_query_errors_in_inherited_queries() {
+    let dir = write_languages(
+        "inherited-errors",
+        &[
+            ("base", "(identifier) @variable\n\n(bogus) @x\n", ""),
+            ("derived", "; inherits: base\n(number) @number\n", ""),
+            ("unknown", "; inherits: nonexistent\n", ""),
+            ("cycle-a", "; inherits: cycle-b\n", ""),
+            ("cycle-b", "; inherits: cycle-a\n", ""),
+        ],
+    );
+
+    let mut loader = Loader::new(scratch_dir().to_owned());
+    loader.find_all_languages(&vec![dir.clone()]).unwrap();
+    let language = get_language("javascript");
+
+    // The location of the error is reported within the inherited file.
+    let configuration = find_configuration(&loader, &dir, "derived");
+    let error = configuration.highlight_config(language).err().unwrap();
+    let base_path = dir
+        .join("tree-sitter-base")
+        .join("queries")
+        .join("highlights.scm");
+    assert_eq!(
+        error.message(),
+        format!(
+            "Query error at {}:3:2. Invalid node type bogus\n  |\n3 | (bogus) @x\n  |  ^^^^^",
+            base_path.to_string_lossy()
+        )
+    );
+
+    let configuration = find_configuration(&loader, &dir, "unknown");
+    let error = configuration.highlight_config(language).err().unwrap();
+    assert_eq!(
+        error.message(),
+        "Query file \"highlights.scm\" inherits from unknown language \"nonexistent\""
+    );
+
+    let configuration = find_configuration(&loader, &dir, "cycle-a");
+    let error = configuration.highlight_config(language).err().unwrap();
+    assert!(error.message().contains("inherits from itself"));
+}
+
+#[test]
+fn test_loader_query_errors_in_locals_queries() {
+    let dir = write_languages(
+        "locals-errors",
+        &[("with-locals", "(identifier) @variable\n", "")],
+    );
+    fs::write(
+        dir.join("tree-sitter-with-locals")
+            .join("queries")
+            .join("locals.scm"),
+        "(function_declaration) @local.scope\n\n  ((identifier) @local.reference (#eq? @local.reference))\n",
+    )
+    .unwrap();
+
+    let mut loader = Loader::new(scratch_dir().to_owned());
+    loader.find_all_languages(&vec![dir.clone()]).unwrap();
+    let language = get_language("javascript");
+
+    let configuration = find_configuration(&loader, &dir, "with-locals");
+    let error = configuration.highlight_config(language).err().unwrap();
+    assert_eq!(
+        error.message(),
+        [
+            "Query error at locals.scm:3:3. Invalid predicate: Wrong number of arguments to #eq? predicate. Expected 2, got 1.",
+            "  |",
+            "3 |   ((identifier) @local.reference (#eq? @local.reference))",
+            "  |   ^",
+        ]
+        .join("\n")
+    );
+}
+
+// Write a set of languages with the given highlight and tag queries into a
+// scratch directory.
+fn write_languages(name: &str, languages: &[(&str, &str, &str)]) -> PathBuf {
+    let dir = scratch_dir().join(name);
+    if dir.exists() {
+        fs::remove_dir_all(&dir).unwrap();
+    }
+    for (language_name, highlights, tags) in languages {
+        let language_dir = dir.join(format!("tree-sitter-{}", language_name));
+        fs::create_dir_all(language_dir.join("src")).unwrap();
+        fs::create_dir_all(language_dir.join("queries")).unwrap();
+        fs::write(language_dir.join("src").join("grammar.json"), "{}").unwrap();
+        fs::write(
+            language_dir.join("queries").join("highlights.scm"),
+            highlights,
+        )
+        .unwrap();
+        if !tags.is_empty() {
+            fs::write(language_dir.join("queries").join("tags.scm"), tags).unwrap();
+        }
+    }
+    dir
+}
+
+fn find_configuration<'a>(
+    loader: &'a Loader,
+    dir: &Path,
+    language_name: &str,
+) -> &'a LanguageConfiguration<'a> {
+    let root_path = dir.join(format!("tree-sitter-{}", language_name));
+    loader
+        .get_all_language_configurations()
+        .into_iter()
+        .find(|(configuration, _)| configuration.root_path == root_path)
+        .unwrap()
+        .0
+}
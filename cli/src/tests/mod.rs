@@ -1,10 +1,16 @@
 mod corpus_test;
-mod helpers;
+mod diagnostics_test;
+mod diff_test;
+pub(crate) mod helpers;
 mod highlight_test;
+mod language_test;
+mod loader_test;
 mod node_test;
 mod parser_test;
 mod pathological_test;
+mod query_ast_test;
 mod query_test;
+mod query_validation_test;
 mod tags_test;
 mod test_highlight_test;
 mod tree_test;
@@ -1,10 +1,11 @@
-use super::helpers::edits::get_random_edit;
 use super::helpers::fixtures::{fixtures_dir, get_language, get_test_language};
-use super::helpers::random::Rand;
+use crate::fuzz::edits::get_random_edit;
+use crate::fuzz::random::Rand;
 use crate::generate::generate_parser_for_grammar;
-use crate::parse::perform_edit;
+use crate::parse::{perform_edit, Edit};
 use std::fs;
-use tree_sitter::{Node, Parser, Point, Tree};
+use std::sync::Arc;
+use tree_sitter::{Node, NodeLocator, OwnedNode, Parser, Point, Tree};
 
 const JSON_EXAMPLE: &'static str = r#"
 
@@ -807,3 +808,127 @@ fn parse_json_example() -> Tree {
     parser.set_language(get_language("json")).unwrap();
     parser.parse(JSON_EXAMPLE, None).unwrap()
 }
+
+#[test]
+fn test_owned_node() {
+    let mut parser = Parser::new();
+    parser.set_language(get_language("javascript")).unwrap();
+    let tree = Arc::new(parser.parse("a(b, c);", None).unwrap());
+
+    let owned_nodes = {
+        let arguments = tree
+            .root_node()
+            .descendant_for_byte_range(2, 2)
+            .unwrap()
+            .parent()
+            .unwrap();
+        assert_eq!(arguments.kind(), "arguments");
+        let mut cursor = arguments.walk();
+        arguments
+            .named_children(&mut cursor)
+            .map(|node| OwnedNode::new(tree.clone(), node))
+            .collect::<Vec<_>>()
+    };
+    drop(tree);
+
+    assert_eq!(
+        owned_nodes
+            .iter()
+            .map(|n| n.node().byte_range())
+            .collect::<Vec<_>>(),
+        vec![2..3, 5..6]
+    );
+    assert_eq!(owned_nodes[0].clone(), owned_nodes[0]);
+    assert_ne!(owned_nodes[0], owned_nodes[1]);
+    assert_eq!(
+        OwnedNode::root(owned_nodes[0].tree().clone()).node().kind(),
+        "program"
+    );
+}
+
+#[test]
+#[should_panic(expected = "Node does not belong to the given tree")]
+fn test_owned_node_from_another_tree() {
+    let mut parser = Parser::new();
+    parser.set_language(get_language("javascript")).unwrap();
+    let tree1 = Arc::new(parser.parse("a;", None).unwrap());
+    let tree2 = parser.parse("b;", None).unwrap();
+    OwnedNode::new(tree1, tree2.root_node());
+}
+
+#[test]
+fn test_owned_node_try_new() {
+    let mut parser = Parser::new();
+    parser.set_language(get_language("javascript")).unwrap();
+    let tree1 = parser.parse("a;", None).unwrap();
+    let tree2 = parser.parse("b;", None).unwrap();
+
+    // A node from an unedited copy of the tree is accepted.
+    let copy = tree1.clone();
+    let node = copy.root_node().child(0).unwrap();
+    let owned_node = OwnedNode::try_new(Arc::new(tree1.clone()), node).unwrap();
+    assert_eq!(owned_node.node().byte_range(), node.byte_range());
+    assert_eq!(owned_node.node().parent().unwrap().kind(), "program");
+
+    assert!(OwnedNode::try_new(Arc::new(tree1), tree2.root_node()).is_none());
+}
+
+#[test]
+fn test_node_locator_after_edit_and_reparse() {
+    let mut source = b"function a() { one(); }\nfunction b() { two(); three(); }\n".to_vec();
+
+    let mut parser = Parser::new();
+    parser.set_language(get_language("javascript")).unwrap();
+    let mut tree = parser.parse(&source, None).unwrap();
+
+    let three = tree
+        .root_node()
+        .descendant_for_byte_range(source.len() - 9, source.len() - 9)
+        .unwrap()
+        .parent()
+        .unwrap();
+    assert_eq!(three.utf8_text(&source).unwrap(), "three()");
+    let mut locator = NodeLocator::new(three).unwrap();
+    assert_eq!(locator.locate(&tree), Some(three));
+
+    // Insert a sibling statement before the node, and a new function before its ancestor.
+    for (position, text) in &[(39, "zero(); "), (0, "function z() {}\n")] {
+        let edit = perform_edit(
+            &mut tree,
+            &mut source,
+            &Edit {
+                position: *position,
+                deleted_length: 0,
+                inserted_text: text.as_bytes().to_vec(),
+            },
+        );
+        locator.edit(&edit);
+        tree = parser.parse(&source, Some(&tree)).unwrap();
+    }
+    assert_eq!(
+        std::str::from_utf8(&source).unwrap(),
+        "function z() {}\nfunction a() { one(); }\nfunction b() { zero(); two(); three(); }\n"
+    );
+    let node = locator.locate(&tree).unwrap();
+    assert_eq!(node.kind(), "call_expression");
+    assert_eq!(node.utf8_text(&source).unwrap(), "three()");
+
+    // When the node's ancestors are removed, the locator no longer finds anything.
+    let position = std::str::from_utf8(&source)
+        .unwrap()
+        .find("function b")
+        .unwrap();
+    let deleted_length = source.len() - position;
+    let edit = perform_edit(
+        &mut tree,
+        &mut source,
+        &Edit {
+            position,
+            deleted_length,
+            inserted_text: Vec::new(),
+        },
+    );
+    locator.edit(&edit);
+    tree = parser.parse(&source, Some(&tree)).unwrap();
+    assert_eq!(locator.locate(&tree), None);
+}
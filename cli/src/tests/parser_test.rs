@@ -3,8 +3,11 @@ use super::helpers::fixtures::{get_language, get_test_grammar, get_test_language
 use crate::generate::generate_parser_for_grammar;
 use crate::parse::{perform_edit, Edit};
 use std::sync::atomic::{AtomicUsize, Ordering};
-use std::{thread, time};
-use tree_sitter::{allocations, IncludedRangesError, InputEdit, LogType, Parser, Point, Range};
+use std::{io, thread, time};
+use tree_sitter::{
+    allocations, IncludedRangesError, InputEdit, LogType, ParseHaltReason, ParseInput, Parser,
+    Point, Range,
+};
 
 #[test]
 fn test_parsing_simple_string() {
@@ -177,6 +180,105 @@ fn test_parsing_with_custom_utf16_input() {
     assert_eq!(root.child(0).unwrap().kind(), "function_item");
 }
 
+#[test]
+fn test_parsing_with_custom_chunked_input() {
+    // A rope-like text buffer, which stores its text in separate chunks.
+    struct Chunks<'a>(Vec<&'a [u8]>);
+
+    impl<'a> ParseInput for Chunks<'a> {
+        fn read(&mut self, byte_offset: usize, _: Point) -> &[u8] {
+            let mut chunk_start = 0;
+            for chunk in &self.0 {
+                if byte_offset < chunk_start + chunk.len() {
+                    return &chunk[(byte_offset - chunk_start)..];
+                }
+                chunk_start += chunk.len();
+            }
+            &[]
+        }
+    }
+
+    let mut parser = Parser::new();
+    parser.set_language(get_language("rust")).unwrap();
+
+    let text = b"pub fn foo() { 1 }";
+    let tree = parser
+        .parse_input(&mut Chunks(text.chunks(3).collect()), None)
+        .unwrap();
+    assert_eq!(
+        tree.root_node().to_sexp(),
+        "(source_file (function_item (visibility_modifier) name: (identifier) parameters: (parameters) body: (block (integer_literal))))"
+    );
+}
+
+#[test]
+fn test_parsing_with_a_reader() {
+    let mut parser = Parser::new();
+    parser.set_language(get_language("javascript")).unwrap();
+
+    // The source is larger than the reader's chunk size, and the parser needs
+    // to revisit earlier text when parsing incrementally.
+    let mut source = "function a(b) { return b + [1, 2, 3]; }\n".repeat(5000);
+    let tree = parser
+        .parse_reader(io::Cursor::new(source.as_bytes()), None)
+        .unwrap()
+        .unwrap();
+    assert_eq!(tree.root_node().child_count(), 5000);
+    assert_eq!(
+        tree.root_node().to_sexp(),
+        parser.parse(&source, None).unwrap().root_node().to_sexp()
+    );
+
+    let mut source_bytes = source.into_bytes();
+    let mut tree = tree;
+    perform_edit(
+        &mut tree,
+        &mut source_bytes,
+        &Edit {
+            position: 150_000,
+            deleted_length: 0,
+            inserted_text: b"c();".to_vec(),
+        },
+    );
+    source = String::from_utf8(source_bytes).unwrap();
+    let new_tree = parser
+        .parse_reader(io::Cursor::new(source.as_bytes()), Some(&tree))
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        new_tree.root_node().to_sexp(),
+        parser.parse(&source, None).unwrap().root_node().to_sexp()
+    );
+}
+
+#[test]
+fn test_parsing_with_a_reader_that_fails() {
+    struct FailingReader(io::Cursor<&'static [u8]>);
+
+    impl io::Read for FailingReader {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            if self.0.position() > 0 {
+                Err(io::Error::new(io::ErrorKind::Other, "disconnected"))
+            } else {
+                self.0.read(&mut buf[0..4])
+            }
+        }
+    }
+
+    impl io::Seek for FailingReader {
+        fn seek(&mut self, position: io::SeekFrom) -> io::Result<u64> {
+            self.0.seek(position)
+        }
+    }
+
+    let mut parser = Parser::new();
+    parser.set_language(get_language("javascript")).unwrap();
+    let error = parser
+        .parse_reader(FailingReader(io::Cursor::new(b"a + b + c;")), None)
+        .unwrap_err();
+    assert_eq!(error.to_string(), "disconnected");
+}
+
 #[test]
 fn test_parsing_with_callback_returning_owned_strings() {
     let mut parser = Parser::new();
@@ -581,6 +683,7 @@ fn test_parsing_cancelled_by_another_thread() {
     // Parsing returns None because it was cancelled.
     cancel_thread.join().unwrap();
     assert!(tree.is_none());
+    assert_eq!(parser.halt_reason(), Some(ParseHaltReason::Cancelled));
 }
 
 // Timeouts
@@ -605,6 +708,7 @@ fn test_parsing_with_a_timeout() {
     );
     assert!(tree.is_none());
     assert!(start_time.elapsed().as_micros() < 500);
+    assert_eq!(parser.halt_reason(), Some(ParseHaltReason::Timeout));
 
     // Continue parsing, but pause after 300 microseconds of processing.
     parser.set_timeout_micros(1000);
@@ -640,6 +744,7 @@ fn test_parsing_with_a_timeout() {
         )
         .unwrap();
     assert_eq!(tree.root_node().child(0).unwrap().kind(), "array");
+    assert_eq!(parser.halt_reason(), None);
 }
 
 #[test]
@@ -747,6 +852,68 @@ fn test_parsing_with_timeout_and_no_completion() {
     });
 }
 
+#[test]
+fn test_parsing_with_a_progress_callback() {
+    let mut parser = Parser::new();
+    parser.set_language(get_language("json")).unwrap();
+
+    let source = format!("[{}0]", "0, ".repeat(5000));
+    let offsets = std::rc::Rc::new(std::cell::RefCell::new(Vec::new()));
+
+    // Pause once the parser has made it past the first thousand bytes.
+    let recorded_offsets = offsets.clone();
+    parser.set_progress_callback(Some(Box::new(move |offset| {
+        recorded_offsets.borrow_mut().push(offset);
+        offset > 1000
+    })));
+    let tree = parser.parse(&source, None);
+    assert!(tree.is_none());
+    assert_eq!(parser.halt_reason(), Some(ParseHaltReason::Paused));
+
+    let paused_at = *offsets.borrow().last().unwrap();
+    assert!(paused_at > 1000);
+    assert!(paused_at < source.len());
+    assert!(offsets.borrow().windows(2).all(|w| w[0] <= w[1]));
+
+    // Resume the parse. The callback continues to observe offsets beyond
+    // the point where the parse was paused.
+    offsets.borrow_mut().clear();
+    parser.set_progress_callback(Some(Box::new({
+        let offsets = offsets.clone();
+        move |offset| {
+            offsets.borrow_mut().push(offset);
+            false
+        }
+    })));
+    let tree = parser.parse(&source, None).unwrap();
+    assert_eq!(parser.halt_reason(), None);
+    assert!(offsets.borrow()[0] >= paused_at);
+    assert_eq!(tree.root_node().child(0).unwrap().named_child_count(), 5001);
+    assert!(!tree.root_node().has_error());
+}
+
+#[test]
+fn test_parsing_with_a_progress_callback_and_a_reset() {
+    allocations::record(|| {
+        let mut parser = Parser::new();
+        parser.set_language(get_language("json")).unwrap();
+
+        let source = format!("[{}0]", "0, ".repeat(1000));
+        parser.set_progress_callback(Some(Box::new(|_| true)));
+        assert!(parser.parse(&source, None).is_none());
+        assert_eq!(parser.halt_reason(), Some(ParseHaltReason::Paused));
+
+        // Resetting discards the partial parse, along with the halt reason.
+        parser.reset();
+        assert_eq!(parser.halt_reason(), None);
+
+        parser.set_progress_callback(None);
+        assert!(parser.progress_callback().is_none());
+        let tree = parser.parse("[null]", None).unwrap();
+        assert_eq!(tree.root_node().to_sexp(), "(document (array (null)))");
+    });
+}
+
 // Included Ranges
 
 #[test]
@@ -0,0 +1,249 @@
+use super::helpers::fixtures::{fixtures_dir, get_language};
+use std::ffi::OsStr;
+use std::fs;
+use tree_sitter::query_ast::{
+    parse, Item, NodePattern, Pattern, PatternKind, Predicate, PredicateArg, Quantifier,
+};
+use tree_sitter::{Query, QueryError, QueryErrorKind};
+use walkdir::WalkDir;
+
+#[test]
+fn test_query_ast_parsing() {
+    let ast = parse(
+        r#"
+        ; Calls
+        (call_expression
+          function: (member_expression !computed) @callee
+          arguments: (arguments . (string)? @first-arg !(spread_element))) @call
+
+        [
+          "if"
+          (expression/identifier)+
+        ] @x @y
+        "#,
+    )
+    .unwrap();
+
+    let member_expression = Pattern::node(
+        "member_expression",
+        vec![Item::NegatedField("computed".to_string())],
+    )
+    .with_field("function")
+    .with_capture("callee");
+    let arguments = Pattern::node(
+        "arguments",
+        vec![
+            Item::Anchor,
+            Pattern::node("string", vec![])
+                .with_quantifier(Quantifier::ZeroOrOne)
+                .with_capture("first-arg")
+                .into(),
+            Item::NegatedChild(Pattern::node("spread_element", vec![])),
+        ],
+    )
+    .with_field("arguments");
+    let alternation = Pattern::new(PatternKind::Alternation(vec![
+        Pattern::new(PatternKind::Anonymous("if".to_string())).into(),
+        Pattern::new(PatternKind::Node(NodePattern {
+            kind: "expression".to_string(),
+            subtype: Some("identifier".to_string()),
+            children: vec![],
+        }))
+        .with_quantifier(Quantifier::OneOrMore)
+        .into(),
+    ]))
+    .with_capture("x")
+    .with_capture("y");
+
+    assert_eq!(
+        ast.items,
+        vec![
+            Item::Comment(" Calls".to_string()),
+            Pattern::node(
+                "call_expression",
+                vec![member_expression.into(), arguments.into()]
+            )
+            .with_capture("call")
+            .into(),
+            Item::BlankLine,
+            alternation.into(),
+        ]
+    );
+}
+
+#[test]
+fn test_query_ast_parsing_predicates() {
+    let ast =
+        parse(r#"((identifier) @a (#eq? @a "x\"y\n") (.match? @a b) (not-eq? @a "z"))"#).unwrap();
+    let predicate = |name: &str, arg: PredicateArg| {
+        Item::Predicate(Predicate {
+            name: name.to_string(),
+            args: vec![PredicateArg::Capture("a".to_string()), arg],
+        })
+    };
+    assert_eq!(
+        ast.items,
+        vec![Pattern::new(PatternKind::Group(vec![
+            Pattern::node("identifier", vec![]).with_capture("a").into(),
+            predicate("eq?", PredicateArg::String("x\"y\n".to_string())),
+            predicate("match?", PredicateArg::Symbol("b".to_string())),
+            predicate("not-eq?", PredicateArg::String("z".to_string())),
+        ]))
+        .into()]
+    );
+
+    // Predicates always use the `#` prefix when printed.
+    assert_eq!(
+        ast.to_string(),
+        [
+            r#"((identifier) @a"#,
+            r#" (#eq? @a "x\"y\n")"#,
+            r#" (#match? @a b)"#,
+            r#" (#not-eq? @a "z"))"#,
+            "",
+        ]
+        .join("\n")
+    );
+}
+
+#[test]
+fn test_query_ast_printing() {
+    let ast = parse(
+        r#"
+; Functions
+(function_declaration name: (identifier) @function)  (call_expression (identifier) @function.call)
+( (identifier)   @constant (#match? @constant "^[A-Z]") )
+
+
+[ "if" "else" ; control flow
+ (null) ] @keyword
+(pair key: [(string) (number)] @key
+  value: (_)*@value)
+(object . (pair) .)
+(
+  (comment)* @doc
+  .
+  (method_definition) @method)
+"#,
+    )
+    .unwrap();
+    assert_eq!(
+        ast.to_string(),
+        r#"; Functions
+(function_declaration
+  name: (identifier) @function)
+(call_expression (identifier) @function.call)
+((identifier) @constant
+ (#match? @constant "^[A-Z]"))
+
+[
+  "if"
+  "else"
+  ; control flow
+  (null)
+] @keyword
+(pair
+  key: [(string) (number)] @key
+  value: (_)* @value)
+(object
+  . (pair)
+  .)
+((comment)* @doc
+ . (method_definition) @method)
+"#
+    );
+}
+
+#[test]
+fn test_query_ast_identifiers_starting_with_underscore() {
+    let source = "(binary_expression _left: (_expression) @left _ @right)\n";
+    let ast = parse(source).unwrap();
+    assert_eq!(
+        ast.items,
+        vec![Pattern::node(
+            "binary_expression",
+            vec![
+                Pattern::node("_expression", vec![])
+                    .with_field("_left")
+                    .with_capture("left")
+                    .into(),
+                Pattern::new(PatternKind::Wildcard)
+                    .with_capture("right")
+                    .into(),
+            ]
+        )
+        .into()]
+    );
+    assert_eq!(parse(&ast.to_string()).unwrap(), ast);
+}
+
+#[test]
+fn test_query_ast_errors() {
+    assert_eq!(
+        parse("(identifier) @a\n(call_expression (identifier) @b").unwrap_err(),
+        QueryError {
+            row: 1,
+            column: 32,
+            offset: 48,
+            message: "Unexpected EOF".to_string(),
+            kind: QueryErrorKind::Syntax,
+        }
+    );
+    assert_eq!(
+        parse("(call_expression\n  function (identifier))").unwrap_err(),
+        QueryError {
+            row: 1,
+            column: 2,
+            offset: 19,
+            message: "  function (identifier))\n  ^".to_string(),
+            kind: QueryErrorKind::Syntax,
+        }
+    );
+    assert!(parse("[(a) .]").is_err());
+    assert!(parse("(a .)").is_err());
+    assert!(parse("(a) ]").is_err());
+    assert!(parse("\"abc").is_err());
+    assert!(parse("field: (#eq? @a b)").is_err());
+}
+
+#[test]
+fn test_query_ast_round_trip_fixture_queries() {
+    let scm_paths = WalkDir::new(fixtures_dir())
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.path().extension().and_then(OsStr::to_str) == Some("scm"))
+        .map(|e| e.into_path())
+        .collect::<Vec<_>>();
+    assert!(!scm_paths.is_empty());
+
+    for path in scm_paths {
+        let source = fs::read_to_string(&path).unwrap();
+        let ast = parse(&source).unwrap_or_else(|e| panic!("{:?}: {:?}", path, e));
+        let formatted = ast.to_string();
+        let reparsed = parse(&formatted).unwrap_or_else(|e| panic!("{:?}: {:?}", path, e));
+        assert_eq!(ast, reparsed, "{:?}", path);
+        assert_eq!(reparsed.to_string(), formatted, "{:?}", path);
+
+        // The formatted query compiles to the same patterns and captures as the original.
+        let grammar_dir = path.parent().unwrap().parent().unwrap();
+        if grammar_dir.join("src").join("grammar.json").exists() {
+            let language_name = grammar_dir.file_name().unwrap().to_str().unwrap();
+            let language = get_language(language_name);
+            if let Ok(query) = Query::new(language, &source) {
+                let formatted_query = Query::new(language, &formatted).unwrap();
+                assert_eq!(
+                    formatted_query.pattern_count(),
+                    query.pattern_count(),
+                    "{:?}",
+                    path
+                );
+                assert_eq!(
+                    formatted_query.capture_names(),
+                    query.capture_names(),
+                    "{:?}",
+                    path
+                );
+            }
+        }
+    }
+}
@@ -2,9 +2,11 @@ use super::helpers::fixtures::get_language;
 use lazy_static::lazy_static;
 use std::env;
 use std::fmt::Write;
+use std::str;
 use tree_sitter::{
-    allocations, Language, Node, Parser, Point, Query, QueryCapture, QueryCursor, QueryError,
-    QueryErrorKind, QueryMatch, QueryPredicate, QueryPredicateArg, QueryProperty,
+    allocations, CaptureQuantifier, Language, Node, Parser, Point, Query, QueryCapture,
+    QueryCaptures, QueryCursor, QueryError, QueryErrorKind, QueryMatch, QueryPredicate,
+    QueryPredicateArg, QueryProperty, TextPredicateArg, TextPredicateError, TextProvider,
 };
 
 lazy_static! {
@@ -144,6 +146,54 @@ fn test_query_errors_on_invalid_syntax() {
             .join("\n")
         );
 
+        // Negated child patterns can't have captures or quantifiers.
+        assert_eq!(
+            Query::new(language, r#"(class_body !(method_definition) @method)"#)
+                .unwrap_err()
+                .message,
+            [
+                r#"(class_body !(method_definition) @method)"#,
+                r#"             ^"#
+            ]
+            .join("\n")
+        );
+        assert_eq!(
+            Query::new(language, r#"(class_body !(method_definition)*)"#)
+                .unwrap_err()
+                .message,
+            [r#"(class_body !(method_definition)*)"#, r#"             ^"#].join("\n")
+        );
+        assert_eq!(
+            Query::new(
+                language,
+                r#"(class_body !((method_definition) (#set! foo)))"#
+            )
+            .unwrap_err(),
+            QueryError {
+                row: 0,
+                column: 13,
+                offset: 13,
+                kind: QueryErrorKind::Syntax,
+                message: [
+                    r#"(class_body !((method_definition) (#set! foo)))"#,
+                    r#"             ^"#
+                ]
+                .join("\n")
+            }
+        );
+
+        // Anchors within an alternation must precede a branch.
+        assert_eq!(
+            Query::new(language, r#"(array [(number) .])"#)
+                .unwrap_err()
+                .message,
+            [
+                r#"(array [(number) .])"#, //
+                r#"                  ^"#
+            ]
+            .join("\n")
+        );
+
         // tree-sitter/tree-sitter/issues/968
         assert_eq!(
             Query::new(get_language("c"), r#"(parameter_list [ ")" @foo)"#)
@@ -276,9 +326,49 @@ fn test_query_errors_on_invalid_predicates() {
                 message: "ok".to_string(),
             }
         );
+        assert_eq!(
+            Query::new(language, "((identifier) @id (#any-of? @id \"a\" @id))").unwrap_err(),
+            QueryError {
+                kind: QueryErrorKind::Predicate,
+                row: 0,
+                column: 0,
+                offset: 0,
+                message: "Arguments to #any-of? predicate must be literals. Got capture @id."
+                    .to_string()
+            }
+        );
+
+        // Predicate errors are reported at the start of the pattern that contains them.
+        assert_eq!(
+            Query::new(language, "(identifier) @a\n  ((identifier) @id (#eq? @id))").unwrap_err(),
+            QueryError {
+                kind: QueryErrorKind::Predicate,
+                row: 1,
+                column: 2,
+                offset: 18,
+                message: "Wrong number of arguments to #eq? predicate. Expected 2, got 1."
+                    .to_string()
+            }
+        );
     });
 }
 
+#[test]
+fn test_query_error_relative_to_section() {
+    let language = get_language("javascript");
+    let first_section = "(identifier) @a\n";
+    let source = format!("{}(number) @b\n  (strng) @c\n", first_section);
+    let error = Query::new(language, &source).unwrap_err();
+    assert_eq!(error.row, 2);
+
+    let (section_index, error) = error.relative_to_section(&source, &[0, first_section.len()]);
+    assert_eq!(section_index, 1);
+    assert_eq!(error.row, 1);
+    assert_eq!(error.column, 3);
+    assert_eq!(error.offset, "(number) @b\n  (".len());
+    assert_eq!(error.kind, QueryErrorKind::NodeType);
+}
+
 #[test]
 fn test_query_errors_on_impossible_patterns() {
     let js_lang = get_language("javascript");
@@ -991,6 +1081,91 @@ fn test_query_matches_with_negated_fields() {
     });
 }
 
+#[test]
+fn test_query_matches_with_negated_child_patterns() {
+    allocations::record(|| {
+        let language = get_language("javascript");
+        let query = Query::new(
+            language,
+            r#"
+            (class_declaration
+                name: (identifier) @class_without_static_methods
+                body: (class_body !(method_definition "static")))
+
+            (function_declaration
+                name: (identifier) @function_without_return
+                body: (statement_block !(return_statement)))
+
+            (function_declaration
+                name: (identifier) @function_without_plain_calls
+                body: (statement_block
+                    !(expression_statement (call_expression function: (identifier)))))
+
+            ; Negated child patterns can be nested.
+            (class_declaration
+                name: (identifier) @class_without_empty_methods
+                body: (class_body !(method_definition body: (statement_block !(_)))))
+            "#,
+        )
+        .unwrap();
+
+        assert_query_matches(
+            language,
+            &query,
+            "
+            class A { a() {} static b() {} }
+            class B { c() { d(); } }
+            function e() { return 1; }
+            function f() { g(); }
+            function h() { i.j(); }
+            ",
+            &[
+                (0, vec![("class_without_static_methods", "B")]),
+                (3, vec![("class_without_empty_methods", "B")]),
+                (2, vec![("function_without_plain_calls", "e")]),
+                (1, vec![("function_without_return", "f")]),
+                (1, vec![("function_without_return", "h")]),
+                (2, vec![("function_without_plain_calls", "h")]),
+            ],
+        );
+    });
+}
+
+#[test]
+fn test_query_matches_with_anchored_alternations() {
+    allocations::record(|| {
+        let language = get_language("javascript");
+        let query = Query::new(
+            language,
+            r#"
+            (array . [(number) (string)] @first)
+            (array [(number) (string)] @last .)
+            (array
+                (identifier) @id
+                [. (number) @adjacent_number (string) @later_string])
+            (array . ((number) @first_number (string) @string))
+            "#,
+        )
+        .unwrap();
+
+        assert_query_matches(
+            language,
+            &query,
+            r#"[1, "a", b, 2, "c"]; ["d", 3];"#,
+            &[
+                (0, vec![("first", "1")]),
+                (3, vec![("first_number", "1"), ("string", "\"a\"")]),
+                (2, vec![("id", "b"), ("adjacent_number", "2")]),
+                (1, vec![("last", "\"c\"")]),
+                (2, vec![("id", "b"), ("later_string", "\"c\"")]),
+                (3, vec![("first_number", "1"), ("string", "\"c\"")]),
+                (0, vec![("first", "\"d\"")]),
+                (1, vec![("last", "3")]),
+            ],
+        );
+    });
+}
+
 #[test]
 fn test_query_matches_with_field_at_root() {
     allocations::record(|| {
@@ -1696,6 +1871,182 @@ fn test_query_matches_with_alternatives_and_too_many_permutations_to_track() {
     });
 }
 
+#[test]
+fn test_query_matches_with_a_match_limit() {
+    allocations::record(|| {
+        let language = get_language("javascript");
+        let query = Query::new(
+            language,
+            "
+            (array (identifier) @pre (identifier) @post)
+        ",
+        )
+        .unwrap();
+
+        let mut source = "hello, ".repeat(50);
+        source.insert(0, '[');
+        source.push_str("];");
+
+        let mut parser = Parser::new();
+        parser.set_language(language).unwrap();
+        let tree = parser.parse(&source, None).unwrap();
+
+        // With a large enough limit, every permutation is tracked.
+        let mut cursor = QueryCursor::new();
+        assert_eq!(cursor.match_limit(), 32);
+        cursor.set_match_limit(2048);
+        assert_eq!(cursor.match_limit(), 2048);
+        let match_count = cursor
+            .matches(&query, tree.root_node(), to_callback(&source))
+            .count();
+        assert_eq!(match_count, 50 * 49 / 2);
+        assert_eq!(cursor.did_exceed_match_limit(), false);
+        assert_eq!(cursor.stats().patterns[0].matches_dropped, 0);
+
+        // With a small limit, in-progress matches are dropped.
+        cursor.set_match_limit(4);
+        let match_count = cursor
+            .matches(&query, tree.root_node(), to_callback(&source))
+            .count();
+        assert!(match_count < 50 * 49 / 2);
+        assert_eq!(cursor.did_exceed_match_limit(), true);
+        let stats = cursor.stats();
+        assert_eq!(stats.patterns[0].matches_produced, match_count);
+        assert!(stats.patterns[0].matches_dropped > 0);
+
+        // The limit is clamped to a valid range.
+        cursor.set_match_limit(0);
+        assert_eq!(cursor.match_limit(), 1);
+        cursor.set_match_limit(u32::MAX);
+        assert_eq!(cursor.match_limit(), 65535);
+    });
+}
+
+#[test]
+fn test_query_matches_with_a_max_start_depth() {
+    allocations::record(|| {
+        let language = get_language("javascript");
+        let query = Query::new(
+            language,
+            "
+            (expression_statement) @statement
+            (_ (identifier) @child)
+            ",
+        )
+        .unwrap();
+
+        let source = "a;
+function b() { c; if (d) { e; } }
+";
+        let mut parser = Parser::new();
+        parser.set_language(language).unwrap();
+        let tree = parser.parse(&source, None).unwrap();
+
+        let mut cursor = QueryCursor::new();
+        let matches = cursor.matches(&query, tree.root_node(), to_callback(source));
+        assert_eq!(
+            collect_matches(matches, &query, source),
+            &[
+                (0, vec![("statement", "a;")]),
+                (1, vec![("child", "a")]),
+                (1, vec![("child", "b")]),
+                (0, vec![("statement", "c;")]),
+                (1, vec![("child", "c")]),
+                (1, vec![("child", "d")]),
+                (0, vec![("statement", "e;")]),
+                (1, vec![("child", "e")]),
+            ]
+        );
+        let unlimited_nodes_visited = cursor.stats().nodes_visited;
+
+        // Only patterns starting at the children of the root node can match.
+        cursor.set_max_start_depth(Some(1));
+        let matches = cursor.matches(&query, tree.root_node(), to_callback(source));
+        assert_eq!(
+            collect_matches(matches, &query, source),
+            &[
+                (0, vec![("statement", "a;")]),
+                (1, vec![("child", "a")]),
+                (1, vec![("child", "b")]),
+            ]
+        );
+        assert!(cursor.stats().nodes_visited < unlimited_nodes_visited);
+
+        // Only the root node itself can match.
+        cursor.set_max_start_depth(Some(0));
+        let matches = cursor.matches(&query, tree.root_node(), to_callback(source));
+        assert_eq!(collect_matches(matches, &query, source), &[]);
+
+        cursor.set_max_start_depth(None);
+        let matches = cursor.matches(&query, tree.root_node(), to_callback(source));
+        assert_eq!(collect_matches(matches, &query, source).len(), 8);
+    });
+}
+
+#[test]
+fn test_query_cursor_stats() {
+    allocations::record(|| {
+        let language = get_language("javascript");
+        let query = Query::new(
+            language,
+            "
+            (identifier) @variable
+            (call_expression function: (identifier) @function)
+            ((identifier) @constant (#match? @constant \"^[A-Z]\"))
+            ",
+        )
+        .unwrap();
+
+        let source = "a(b, C);";
+        let mut parser = Parser::new();
+        parser.set_language(language).unwrap();
+        let tree = parser.parse(&source, None).unwrap();
+
+        let mut cursor = QueryCursor::new();
+        assert_eq!(cursor.stats().nodes_visited, 0);
+        assert_eq!(cursor.stats().patterns, vec![]);
+
+        let matches = cursor.matches(&query, tree.root_node(), to_callback(source));
+        assert_eq!(
+            collect_matches(matches, &query, source),
+            &[
+                (1, vec![("function", "a")]),
+                (0, vec![("variable", "a")]),
+                (0, vec![("variable", "b")]),
+                (0, vec![("variable", "C")]),
+                (2, vec![("constant", "C")]),
+            ]
+        );
+
+        let stats = cursor.stats();
+        let mut node_count = 0;
+        let mut tree_cursor = tree.walk();
+        loop {
+            node_count += 1;
+            if !tree_cursor.goto_first_child() {
+                while !tree_cursor.goto_next_sibling() {
+                    if !tree_cursor.goto_parent() {
+                        break;
+                    }
+                }
+            }
+            if tree_cursor.node() == tree.root_node() {
+                break;
+            }
+        }
+        assert_eq!(stats.nodes_visited, node_count);
+        assert_eq!(stats.patterns.len(), 3);
+
+        // Text predicates are evaluated after matches are produced.
+        assert_eq!(stats.patterns[0].matches_produced, 3);
+        assert_eq!(stats.patterns[1].matches_produced, 1);
+        assert_eq!(stats.patterns[2].matches_produced, 3);
+        assert_eq!(stats.patterns[0].states_created, 3);
+        assert_eq!(stats.patterns[1].states_created, 1);
+        assert!(stats.patterns.iter().all(|p| p.matches_dropped == 0));
+    });
+}
+
 #[test]
 fn test_query_matches_with_anonymous_tokens() {
     allocations::record(|| {
@@ -2492,6 +2843,295 @@ fn test_query_captures_with_predicates() {
     });
 }
 
+#[test]
+fn test_query_matches_with_any_of_predicate() {
+    allocations::record(|| {
+        let language = get_language("javascript");
+        let query = Query::new(
+            language,
+            r#"
+            ((identifier) @builtin
+             (#any-of? @builtin "require" "module" "exports"))
+
+            ((identifier) @other
+             (#not-any-of? @other "require" "module" "exports" "a"))
+            "#,
+        )
+        .unwrap();
+
+        assert_query_matches(
+            language,
+            &query,
+            "const a = require('b'); module.exports = c;",
+            &[
+                (0, vec![("builtin", "require")]),
+                (0, vec![("builtin", "module")]),
+                (1, vec![("other", "c")]),
+            ],
+        );
+    });
+}
+
+#[test]
+fn test_query_matches_with_predicates_on_missing_captures() {
+    allocations::record(|| {
+        let language = get_language("javascript");
+        let query = Query::new(
+            language,
+            r#"
+            ((return_statement (_)? @eq) (#eq? @eq "1"))
+            ((return_statement (_)? @match) (#match? @match "^1$"))
+            ((return_statement (_)? @any-eq) (#any-eq? @any-eq "1"))
+            ((return_statement (_)? @any-match) (#any-match? @any-match "^1$"))
+            "#,
+        )
+        .unwrap();
+
+        // When the optional capture is missing, `#eq?` and `#match?` are satisfied,
+        // but `#any-eq?` and `#any-match?` are not.
+        assert_query_matches(
+            language,
+            &query,
+            "function a() { return; return 1; return 2; }",
+            &[
+                (0, vec![]),
+                (1, vec![]),
+                (0, vec![("eq", "1")]),
+                (1, vec![("match", "1")]),
+                (2, vec![("any-eq", "1")]),
+                (3, vec![("any-match", "1")]),
+            ],
+        );
+    });
+}
+
+#[test]
+fn test_query_matches_with_predicates_on_quantified_captures() {
+    allocations::record(|| {
+        let language = get_language("javascript");
+        let query = Query::new(
+            language,
+            r#"
+            (((comment)+ @all-lowercase . (function_declaration))
+             (#match? @all-lowercase "^// [a-z]$"))
+
+            (((comment)+ @any-uppercase . (function_declaration))
+             (#any-match? @any-uppercase "^// [A-Z]$"))
+
+            (((comment)+ @all-x . (function_declaration))
+             (#eq? @all-x "// x"))
+
+            (((comment)+ @any-x . (function_declaration))
+             (#any-eq? @any-x "// x"))
+
+            (((comment)+ @not-all-x . (function_declaration))
+             (#any-not-eq? @not-all-x "// x"))
+            "#,
+        )
+        .unwrap();
+
+        assert_query_matches(
+            language,
+            &query,
+            "
+            // a
+            // b
+            function one() {}
+            // X
+            // y
+            function two() {}
+            // x
+            // x
+            function three() {}
+            ",
+            &[
+                (
+                    0,
+                    vec![("all-lowercase", "// a"), ("all-lowercase", "// b")],
+                ),
+                (4, vec![("not-all-x", "// a"), ("not-all-x", "// b")]),
+                (
+                    1,
+                    vec![("any-uppercase", "// X"), ("any-uppercase", "// y")],
+                ),
+                (4, vec![("not-all-x", "// X"), ("not-all-x", "// y")]),
+                (
+                    0,
+                    vec![("all-lowercase", "// x"), ("all-lowercase", "// x")],
+                ),
+                (2, vec![("all-x", "// x"), ("all-x", "// x")]),
+                (3, vec![("any-x", "// x"), ("any-x", "// x")]),
+            ],
+        );
+    });
+}
+
+#[test]
+fn test_query_matches_with_custom_text_predicates() {
+    allocations::record(|| {
+        let language = get_language("javascript");
+        let mut query = Query::new(
+            language,
+            r#"
+            ((identifier) @id
+             (#contains? @id "oo"))
+
+            ((call_expression
+               function: (identifier) @function
+               arguments: (arguments (string) @arg))
+             (#same-length? @function @arg))
+
+            ((identifier) @unregistered
+             (#unregistered? @unregistered))
+            "#,
+        )
+        .unwrap();
+
+        query
+            .register_text_predicate("contains?", |args| match args {
+                [TextPredicateArg::Capture(nodes), TextPredicateArg::String(s)] => nodes
+                    .iter()
+                    .all(|(_, text)| str::from_utf8(text).unwrap().contains(s)),
+                _ => false,
+            })
+            .unwrap();
+        query
+            .register_text_predicate("same-length?", |args| match args {
+                [TextPredicateArg::Capture(a), TextPredicateArg::Capture(b)] => {
+                    a[0].0.kind() == "identifier" && a[0].1.len() + 2 == b[0].1.len()
+                }
+                _ => false,
+            })
+            .unwrap();
+
+        // Predicates that are evaluated natively can't be overridden.
+        for operator in &["eq?", "not-match?", "any-of?", "set!"] {
+            assert_eq!(
+                query.register_text_predicate(operator, |_| true),
+                Err(TextPredicateError(operator.to_string()))
+            );
+        }
+
+        assert_query_matches(
+            language,
+            &query,
+            "foo('bar'); food('x'); baz('qux');",
+            &[
+                (0, vec![("id", "foo")]),
+                (2, vec![("unregistered", "foo")]),
+                (1, vec![("function", "foo"), ("arg", "'bar'")]),
+                (0, vec![("id", "food")]),
+                (2, vec![("unregistered", "food")]),
+                (2, vec![("unregistered", "baz")]),
+                (1, vec![("function", "baz"), ("arg", "'qux'")]),
+            ],
+        );
+    });
+}
+
+#[test]
+fn test_query_matches_with_chunked_text_provider() {
+    // A rope-like text buffer, which stores its text in separate chunks.
+    struct Chunks<'a>(Vec<&'a [u8]>);
+
+    impl<'a, 'b, 'tree> TextProvider<'tree> for &'b Chunks<'a> {
+        type Chunk = &'a [u8];
+        type Chunks = std::vec::IntoIter<&'a [u8]>;
+
+        fn text(&mut self, node: Node<'tree>) -> Self::Chunks {
+            let range = node.byte_range();
+            let mut result = Vec::new();
+            let mut chunk_start = 0;
+            for chunk in &self.0 {
+                let chunk_end = chunk_start + chunk.len();
+                if chunk_end > range.start && chunk_start < range.end {
+                    let start = range.start.max(chunk_start) - chunk_start;
+                    let end = range.end.min(chunk_end) - chunk_start;
+                    result.push(&chunk[start..end]);
+                }
+                chunk_start = chunk_end;
+            }
+            result.into_iter()
+        }
+    }
+
+    allocations::record(|| {
+        let language = get_language("javascript");
+        let query = Query::new(
+            language,
+            r#"
+            ((identifier) @a (#eq? @a "abcdefg"))
+            ((identifier) @b (#match? @b "^x+$"))
+            ((identifier) @c (#any-of? @c "hello" "world"))
+            (assignment_expression
+                left: (identifier) @d1
+                right: (identifier) @d2
+                (#eq? @d1 @d2))
+            "#,
+        )
+        .unwrap();
+
+        let source = "abcdefg; abcdefgh; xxxxx; xxy; world; hello2; abcd = abcd; abc = abcd;";
+        let mut parser = Parser::new();
+        parser.set_language(language).unwrap();
+        let tree = parser.parse(&source, None).unwrap();
+
+        let expected = [
+            (0, vec![("a", "abcdefg")]),
+            (1, vec![("b", "xxxxx")]),
+            (2, vec![("c", "world")]),
+            (3, vec![("d1", "abcd"), ("d2", "abcd")]),
+        ];
+        for chunk_size in &[1, 2, 3, 5, 100] {
+            let chunks = Chunks(source.as_bytes().chunks(*chunk_size).collect());
+            let mut cursor = QueryCursor::new();
+            let matches = cursor.matches_with_text_provider(&query, tree.root_node(), &chunks);
+            assert_eq!(
+                collect_matches(matches, &query, source),
+                expected,
+                "chunk size {}",
+                chunk_size
+            );
+        }
+
+        let mut cursor = QueryCursor::new();
+        let matches =
+            cursor.matches_with_text_provider(&query, tree.root_node(), source.as_bytes());
+        assert_eq!(collect_matches(matches, &query, source), expected);
+
+        let chunks = Chunks(source.as_bytes().chunks(3).collect());
+        let mut cursor = QueryCursor::new();
+        let captures = cursor.captures_with_text_provider(&query, tree.root_node(), &chunks);
+        let expected_captures = collect_captures(captures, &query, source);
+        assert_eq!(
+            expected_captures,
+            [
+                ("a", "abcdefg"),
+                ("b", "xxxxx"),
+                ("c", "world"),
+                ("d1", "abcd"),
+                ("d2", "abcd"),
+            ]
+        );
+
+        // Closures whose parameter type is inferred can still be passed as text callbacks.
+        let mut cursor = QueryCursor::new();
+        let matches = cursor.matches(&query, tree.root_node(), |node| {
+            &source.as_bytes()[node.byte_range()]
+        });
+        assert_eq!(collect_matches(matches, &query, source), expected);
+
+        let mut cursor = QueryCursor::new();
+        let captures: QueryCaptures<Vec<u8>> = cursor.captures(&query, tree.root_node(), |node| {
+            source.as_bytes()[node.byte_range()].to_vec()
+        });
+        assert_eq!(
+            collect_captures(captures, &query, source),
+            expected_captures
+        );
+    });
+}
+
 #[test]
 fn test_query_captures_with_quoted_predicate_args() {
     allocations::record(|| {
@@ -3036,6 +3676,65 @@ fn test_query_capture_names() {
     });
 }
 
+#[test]
+fn test_query_capture_quantifiers() {
+    allocations::record(|| {
+        use CaptureQuantifier::*;
+
+        let language = get_language("javascript");
+        let query = Query::new(
+            language,
+            r#"
+            ; single, optional, and repeated captures
+            (array (number) @single)
+            (array (number)? @optional)
+            (array (number)* @zero-or-more)
+            (array (number)+ @one-or-more)
+
+            ; captures within repeated groups
+            (array ((number) @single (string)? @optional)+)
+
+            ; captures within alternations
+            (array [(number) @single (string) @optional])
+            [(true) @single (false) @single]
+
+            ; the same capture in several places
+            (array (number) @one-or-more (number) @one-or-more)
+            (array (number) @zero-or-more (array (number) @zero-or-more)*)
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            query.capture_names(),
+            &[
+                "single".to_string(),
+                "optional".to_string(),
+                "zero-or-more".to_string(),
+                "one-or-more".to_string(),
+            ]
+        );
+        assert_eq!(query.capture_quantifiers(0), &[One, Zero, Zero, Zero]);
+        assert_eq!(query.capture_quantifiers(1), &[Zero, ZeroOrOne, Zero, Zero]);
+        assert_eq!(
+            query.capture_quantifiers(2),
+            &[Zero, Zero, ZeroOrMore, Zero]
+        );
+        assert_eq!(query.capture_quantifiers(3), &[Zero, Zero, Zero, OneOrMore]);
+        assert_eq!(
+            query.capture_quantifiers(4),
+            &[OneOrMore, ZeroOrMore, Zero, Zero]
+        );
+        assert_eq!(
+            query.capture_quantifiers(5),
+            &[ZeroOrOne, ZeroOrOne, Zero, Zero]
+        );
+        assert_eq!(query.capture_quantifiers(6), &[One, Zero, Zero, Zero]);
+        assert_eq!(query.capture_quantifiers(7), &[Zero, Zero, Zero, OneOrMore]);
+        assert_eq!(query.capture_quantifiers(8), &[Zero, Zero, OneOrMore, Zero]);
+    });
+}
+
 #[test]
 fn test_query_lifetime_is_separate_from_nodes_lifetime() {
     allocations::record(|| {
@@ -3492,6 +4191,153 @@ fn test_query_step_is_definite() {
     });
 }
 
+#[test]
+fn test_query_pattern_is_rooted_and_non_local() {
+    struct Row {
+        description: &'static str,
+        pattern: &'static str,
+        is_rooted: bool,
+        is_non_local: bool,
+    }
+
+    let rows = &[
+        Row {
+            description: "a single node",
+            pattern: r#"(function_declaration name: (identifier))"#,
+            is_rooted: true,
+            is_non_local: false,
+        },
+        Row {
+            description: "an alternation of single nodes",
+            pattern: r#"[(string) (number)] @literal"#,
+            is_rooted: true,
+            is_non_local: false,
+        },
+        Row {
+            description: "a wildcard with a child",
+            pattern: r#"(_ (comment))"#,
+            is_rooted: true,
+            is_non_local: false,
+        },
+        Row {
+            description: "a sequence of statements",
+            pattern: r#"((comment) @doc . (function_declaration))"#,
+            is_rooted: false,
+            is_non_local: true,
+        },
+        Row {
+            description: "a repetition of statements",
+            pattern: r#"(expression_statement)+"#,
+            is_rooted: false,
+            is_non_local: true,
+        },
+        Row {
+            description: "a sequence starting with a wildcard",
+            pattern: r#"((_) (identifier))"#,
+            is_rooted: false,
+            is_non_local: true,
+        },
+        Row {
+            description: "a sequence of nodes that never occur in a repetition",
+            pattern: r#"((program) (program))"#,
+            is_rooted: false,
+            is_non_local: false,
+        },
+    ];
+
+    allocations::record(|| {
+        let language = get_language("javascript");
+        for row in rows.iter() {
+            let query = Query::new(language, row.pattern).unwrap();
+            assert_eq!(
+                (query.is_pattern_rooted(0), query.is_pattern_non_local(0)),
+                (row.is_rooted, row.is_non_local),
+                "Description: {}, Pattern: {:?}",
+                row.description,
+                row.pattern,
+            );
+        }
+    });
+}
+
+#[test]
+fn test_query_step_offsets_for_pattern() {
+    allocations::record(|| {
+        let language = get_language("javascript");
+        let source = r#"
+            (object "{" (pair) "}")
+            [(string) (number)] @literal
+            ((identifier) @id (#eq? @id "x"))
+        "#;
+        let query = Query::new(language, source).unwrap();
+
+        let steps = (0..query.pattern_count())
+            .map(|i| {
+                query
+                    .step_offsets_for_pattern(i)
+                    .into_iter()
+                    .map(|offset| {
+                        let text = source[offset..].split_ascii_whitespace().next().unwrap();
+                        (text, query.step_is_definite(offset))
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            steps,
+            &[
+                vec![
+                    ("(object", false),
+                    ("\"{\"", false),
+                    ("(pair)", false),
+                    ("\"}\")", true),
+                ],
+                vec![("[(string)", false), ("(number)]", false)],
+                vec![("((identifier)", false)],
+            ]
+        );
+    });
+}
+
+#[test]
+fn test_query_lenient_with_impossible_patterns() {
+    allocations::record(|| {
+        let language = get_language("javascript");
+        let source = r#"
+            (function_declaration name: (identifier) @name)
+            (function_declaration name: (statement_block) @body)
+            [
+                (class_declaration name: (identifier) @name)
+                (class_declaration name: (number) @number)
+            ]
+        "#;
+
+        assert_eq!(
+            Query::new(language, source).unwrap_err().kind,
+            QueryErrorKind::Structure
+        );
+
+        let query = Query::new_lenient(language, source).unwrap();
+        assert_eq!(
+            (0..query.pattern_count())
+                .map(|i| query.is_pattern_impossible(i))
+                .collect::<Vec<_>>(),
+            &[false, true, true]
+        );
+
+        let source = "function a() {}\nclass B {}";
+        let mut parser = Parser::new();
+        parser.set_language(language).unwrap();
+        let tree = parser.parse(source, None).unwrap();
+        let mut cursor = QueryCursor::new();
+        let matches = cursor.matches(&query, tree.root_node(), to_callback(source));
+        assert_eq!(
+            collect_matches(matches, &query, source),
+            &[(0, vec![("name", "a")])],
+        );
+    });
+}
+
 fn assert_query_matches(
     language: Language,
     query: &Query,
@@ -0,0 +1,130 @@
+use super::helpers::fixtures::{fixtures_dir, get_language, get_language_queries_path};
+use std::fs;
+use tree_sitter::query_validation::{validate_query, ImpossiblePattern, NodeTypes};
+use tree_sitter::{Query, QueryErrorKind};
+
+#[test]
+fn test_query_validation_children() {
+    let node_types = get_node_types("javascript");
+    let source = r#"
+(identifier (string))
+(statement_block (expression_statement (call_expression)))
+(program (identifier))
+(array (identifier) (spread_element))
+"#;
+    assert_eq!(
+        validate_query(&node_types, source).unwrap(),
+        &[
+            ImpossiblePattern {
+                pattern_index: 0,
+                byte_range: 13..21,
+                row: 1,
+                column: 12,
+                message: "`identifier` nodes cannot have `string` children".to_string(),
+            },
+            ImpossiblePattern {
+                pattern_index: 2,
+                byte_range: 91..103,
+                row: 3,
+                column: 9,
+                message: "`program` nodes cannot have `identifier` children".to_string(),
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_query_validation_fields() {
+    let node_types = get_node_types("javascript");
+    let source = r#"
+(call_expression function: (string))
+(call_expression
+  arguments: (identifier) @args)
+(member_expression property: [(property_identifier) (number)])
+(identifier label: (_))
+"#;
+    let errors = validate_query(&node_types, source).unwrap();
+    assert_eq!(
+        errors
+            .iter()
+            .map(|e| (e.pattern_index, e.row, e.column, e.message.as_str()))
+            .collect::<Vec<_>>(),
+        &[
+            (
+                1,
+                3,
+                13,
+                "the `arguments` field of `call_expression` nodes cannot contain `identifier` nodes"
+            ),
+            (
+                2,
+                4,
+                52,
+                "the `property` field of `member_expression` nodes cannot contain `number` nodes"
+            ),
+            (3, 5, 19, "`identifier` nodes have no `label` field"),
+        ]
+    );
+
+    // The location spans the pattern and its captures, but not its field name.
+    assert_eq!(&source[errors[0].byte_range.clone()], "(identifier) @args");
+}
+
+#[test]
+fn test_query_validation_supertypes_and_extras() {
+    let node_types = get_node_types("javascript");
+    let source = r#"
+(binary_expression left: (expression/identifier) right: (_))
+(expression_statement (expression))
+(statement_block (statement) (comment))
+(expression_statement (comment))
+(identifier (comment))
+(arguments (_) ["," ")"] (ERROR))
+((identifier) . (string))
+"#;
+    assert_eq!(
+        validate_query(&node_types, source)
+            .unwrap()
+            .iter()
+            .map(|e| e.message.as_str())
+            .collect::<Vec<_>>(),
+        &["`identifier` nodes cannot have `comment` children"]
+    );
+}
+
+#[test]
+fn test_query_validation_syntax_errors() {
+    let node_types = get_node_types("javascript");
+    let error = validate_query(&node_types, "(identifier (string)").unwrap_err();
+    assert_eq!(error.kind, QueryErrorKind::Syntax);
+}
+
+#[test]
+fn test_query_validation_fixture_queries() {
+    for language_name in &["javascript", "python", "ruby", "rust", "go", "c"] {
+        let node_types = get_node_types(language_name);
+        let language = get_language(language_name);
+        let queries_path = get_language_queries_path(language_name);
+        for entry in fs::read_dir(&queries_path).unwrap() {
+            let path = entry.unwrap().path();
+            let source = fs::read_to_string(&path).unwrap();
+            if Query::new(language, &source).is_ok() {
+                assert_eq!(
+                    validate_query(&node_types, &source).unwrap(),
+                    &[],
+                    "{:?}",
+                    path
+                );
+            }
+        }
+    }
+}
+
+fn get_node_types(language_name: &str) -> NodeTypes {
+    let path = fixtures_dir()
+        .join("grammars")
+        .join(language_name)
+        .join("src")
+        .join("node-types.json");
+    NodeTypes::from_json(&fs::read_to_string(path).unwrap()).unwrap()
+}
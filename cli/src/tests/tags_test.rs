@@ -3,9 +3,9 @@ use std::ffi::CStr;
 use std::ffi::CString;
 use std::sync::Arc;
 use std::{fs, ptr, slice, str};
-use tree_sitter::{allocations, Point};
+use tree_sitter::{allocations, Point, QueryErrorKind};
 use tree_sitter_tags::c_lib as c;
-use tree_sitter_tags::{Error, TagsConfiguration, TagsContext};
+use tree_sitter_tags::{Error, QuerySection, TagsConfiguration, TagsContext};
 
 const PYTHON_TAG_QUERY: &'static str = r#"
 (
@@ -297,6 +297,25 @@ fn test_invalid_capture() {
     assert_eq!(e, Error::InvalidCapture("method".to_string()));
 }
 
+#[test]
+fn test_invalid_query_location() {
+    let language = get_language("python");
+    let locals_query = "(function_definition) @local.scope\n";
+    let tags_query =
+        "(class_definition\n  name: (identifier) @name) @definition.class\n(bogus) @name";
+    let e = TagsConfiguration::new(language, tags_query, locals_query)
+        .expect_err("expected Query error");
+    match e {
+        Error::Query(section, error) => {
+            assert_eq!(section, QuerySection::Tags);
+            assert_eq!(error.kind, QueryErrorKind::NodeType);
+            assert_eq!((error.row, error.column), (2, 1));
+            assert_eq!(error.offset, tags_query.find("bogus").unwrap());
+        }
+        _ => panic!("Expected a query error, got {:?}", e),
+    }
+}
+
 #[test]
 fn test_tags_with_parse_error() {
     let language = get_language("python");
@@ -1,8 +1,13 @@
-use super::helpers::edits::invert_edit;
 use super::helpers::fixtures::get_language;
+use crate::fuzz::edits::invert_edit;
 use crate::parse::{perform_edit, Edit};
 use std::str;
-use tree_sitter::{InputEdit, Parser, Point, Range, Tree};
+use std::sync::Arc;
+use std::thread;
+use tree_sitter::{
+    allocations, InputEdit, Node, OwnedNode, Parser, Point, Query, QueryCursor, Range, Tree,
+    TreeDeserializationError,
+};
 
 #[test]
 fn test_tree_edit() {
@@ -223,6 +228,215 @@ fn test_tree_cursor() {
     assert!(cursor.goto_next_sibling());
     assert_eq!(cursor.node().kind(), "field_declaration_list");
     assert_eq!(cursor.node().is_named(), true);
+    assert_eq!(cursor.depth(), 2);
+
+    assert!(cursor.goto_last_child());
+    assert_eq!(cursor.node().kind(), "}");
+    assert!(cursor.node().is_missing());
+    assert_eq!(cursor.node().start_position(), Point::new(2, 24));
+    assert_eq!(cursor.depth(), 3);
+
+    assert!(cursor.goto_previous_sibling());
+    assert_eq!(cursor.node().kind(), "field_declaration");
+    assert_eq!(cursor.node().start_position(), Point::new(2, 20));
+
+    assert!(cursor.goto_previous_sibling());
+    assert_eq!(cursor.node().kind(), "{");
+    assert_eq!(cursor.node().start_position(), Point::new(1, 29));
+    assert!(!cursor.goto_previous_sibling());
+
+    assert!(cursor.goto_parent());
+    assert!(cursor.goto_previous_sibling());
+    assert_eq!(cursor.node().kind(), "type_identifier");
+    assert!(cursor.goto_previous_sibling());
+    assert_eq!(cursor.node().kind(), "struct");
+    assert!(!cursor.goto_previous_sibling());
+    assert_eq!(cursor.depth(), 2);
+
+    assert!(cursor.goto_parent());
+    assert!(cursor.goto_parent());
+    assert_eq!(cursor.node().kind(), "source_file");
+    assert_eq!(cursor.depth(), 0);
+    assert!(!cursor.goto_previous_sibling());
+}
+
+#[test]
+fn test_tree_cursor_previous_sibling_with_hidden_nodes() {
+    let mut parser = Parser::new();
+    parser.set_language(get_language("javascript")).unwrap();
+
+    let source = "a;\nfunction b() { c(); }\nclass D {}\n// e\nf;";
+    let tree = parser.parse(source, None).unwrap();
+    let root = tree.root_node();
+
+    // Walking backwards must visit the same nodes, at the same positions,
+    // as walking forwards.
+    for parent in (0..root.named_child_count())
+        .map(|i| root.named_child(i).unwrap())
+        .chain(Some(root))
+    {
+        let mut cursor = parent.walk();
+        let mut forward_nodes = Vec::new();
+        if cursor.goto_first_child() {
+            forward_nodes.push(cursor.node());
+            while cursor.goto_next_sibling() {
+                forward_nodes.push(cursor.node());
+            }
+        }
+
+        let mut cursor = parent.walk();
+        let mut backward_nodes = Vec::new();
+        if cursor.goto_last_child() {
+            backward_nodes.push(cursor.node());
+            while cursor.goto_previous_sibling() {
+                backward_nodes.push(cursor.node());
+            }
+        }
+        backward_nodes.reverse();
+
+        assert_eq!(forward_nodes, backward_nodes);
+        for (forward, backward) in forward_nodes.iter().zip(backward_nodes.iter()) {
+            assert_eq!(forward.start_position(), backward.start_position());
+            assert_eq!(forward.end_byte(), backward.end_byte());
+        }
+    }
+}
+
+#[test]
+fn test_tree_cursor_goto_first_child_for_point() {
+    let mut parser = Parser::new();
+    parser.set_language(get_language("javascript")).unwrap();
+
+    let tree = parser
+        .parse("[\n  one,\n  {\n    two: tree\n  },\n]", None)
+        .unwrap();
+
+    let mut cursor = tree.walk();
+    assert_eq!(cursor.node().kind(), "program");
+    assert_eq!(cursor.goto_first_child_for_point(Point::new(1, 2)), Some(0));
+    assert_eq!(cursor.node().kind(), "expression_statement");
+    assert_eq!(cursor.goto_first_child_for_point(Point::new(1, 2)), Some(0));
+    assert_eq!(cursor.node().kind(), "array");
+
+    assert_eq!(cursor.goto_first_child_for_point(Point::new(2, 3)), Some(3));
+    assert_eq!(cursor.node().kind(), "object");
+    assert_eq!(cursor.node().start_position(), Point::new(2, 2));
+
+    assert_eq!(cursor.goto_first_child_for_point(Point::new(3, 9)), Some(1));
+    assert_eq!(cursor.node().kind(), "pair");
+    assert_eq!(cursor.goto_first_child_for_point(Point::new(3, 9)), Some(2));
+    assert_eq!(cursor.node().kind(), "identifier");
+    assert_eq!(cursor.goto_first_child_for_point(Point::new(3, 9)), None);
+
+    let mut cursor = tree.walk();
+    assert!(cursor.goto_first_child());
+    assert!(cursor.goto_first_child());
+    assert_eq!(cursor.goto_first_child_for_point(Point::new(6, 0)), None);
+    assert_eq!(cursor.node().kind(), "array");
+}
+
+#[test]
+fn test_tree_cursor_descendant_index_and_depth() {
+    let mut parser = Parser::new();
+    parser.set_language(get_language("javascript")).unwrap();
+
+    let tree = parser
+        .parse(
+            "function a(b) { if (c) { d(); } }\nclass E { f() {} }",
+            None,
+        )
+        .unwrap();
+
+    // Collect all of the nodes in pre-order, along with their depths.
+    let mut nodes = Vec::new();
+    let mut cursor = tree.walk();
+    loop {
+        assert_eq!(cursor.descendant_index(), nodes.len());
+        nodes.push((cursor.node(), cursor.depth()));
+        if cursor.goto_first_child() {
+            continue;
+        }
+        while !cursor.goto_next_sibling() {
+            if !cursor.goto_parent() {
+                break;
+            }
+        }
+        if cursor.node() == tree.root_node() {
+            break;
+        }
+    }
+    assert_eq!(nodes[0].0, tree.root_node());
+    assert_eq!(nodes[0].1, 0);
+
+    // Jump to every node in reverse order, and then back to the start.
+    for (index, (node, depth)) in nodes.iter().enumerate().rev() {
+        assert!(cursor.goto_descendant(index));
+        assert_eq!(cursor.node(), *node);
+        assert_eq!(cursor.node().start_position(), node.start_position());
+        assert_eq!(cursor.depth(), *depth);
+        assert_eq!(cursor.descendant_index(), index);
+    }
+
+    // Jump between nodes in both directions.
+    for &index in &[7, 2, 11, 0, nodes.len() - 1, 3] {
+        assert!(cursor.goto_descendant(index));
+        assert_eq!(cursor.node(), nodes[index].0);
+        assert_eq!(cursor.depth(), nodes[index].1);
+        assert_eq!(cursor.descendant_index(), index);
+    }
+
+    // Indices beyond the last descendant are rejected, without moving the cursor.
+    for &index in &[nodes.len(), nodes.len() + 10, usize::MAX] {
+        assert!(!cursor.goto_descendant(index));
+        assert_eq!(cursor.node(), nodes[3].0);
+        assert_eq!(cursor.descendant_index(), 3);
+    }
+
+    // Indices are relative to the node that the cursor was created with.
+    let class_node = nodes
+        .iter()
+        .find(|(n, _)| n.kind() == "class_declaration")
+        .unwrap()
+        .0;
+    let mut cursor = class_node.walk();
+    assert_eq!(cursor.descendant_index(), 0);
+    assert_eq!(cursor.depth(), 0);
+    assert!(cursor.goto_descendant(2));
+    assert_eq!(cursor.node().kind(), "identifier");
+    assert_eq!(cursor.depth(), 1);
+    let class_index = nodes.iter().position(|(n, _)| *n == class_node).unwrap();
+    let class_depth = nodes[class_index].1;
+    let class_descendant_count = nodes[(class_index + 1)..]
+        .iter()
+        .take_while(|(_, depth)| *depth > class_depth)
+        .count()
+        + 1;
+    assert!(cursor.goto_descendant(class_descendant_count - 1));
+    assert!(!cursor.goto_descendant(class_descendant_count));
+    assert_eq!(cursor.descendant_index(), class_descendant_count - 1);
+}
+
+#[test]
+fn test_tree_cursor_clone() {
+    let mut parser = Parser::new();
+    parser.set_language(get_language("rust")).unwrap();
+    let tree = parser.parse("fn a() { b(); }", None).unwrap();
+
+    let mut cursor = tree.walk();
+    assert!(cursor.goto_first_child());
+    assert!(cursor.goto_first_child());
+
+    let mut copy = cursor.clone();
+    assert_eq!(copy.node(), cursor.node());
+    assert_eq!(copy.depth(), cursor.depth());
+    assert_eq!(copy.descendant_index(), cursor.descendant_index());
+
+    assert!(copy.goto_next_sibling());
+    assert_eq!(copy.node().kind(), "identifier");
+    assert_eq!(cursor.node().kind(), "fn");
+    assert!(copy.goto_parent());
+    assert_eq!(copy.node().kind(), "function_item");
+    assert_eq!(cursor.node().kind(), "fn");
 }
 
 #[test]
@@ -381,6 +595,242 @@ fn test_get_changed_ranges() {
     }
 }
 
+#[test]
+fn test_tree_serialize_and_deserialize() {
+    allocations::record(|| {
+        let source = "function a(b) { return b + ; }\n// hi\nconst c = [1, `${d}`, 3];\n";
+
+        let mut parser = Parser::new();
+        parser.set_language(get_language("javascript")).unwrap();
+        let tree = parser.parse(source, None).unwrap();
+        assert!(tree.root_node().has_error());
+
+        let bytes = tree.serialize();
+        let restored = Tree::deserialize(get_language("javascript"), &bytes).unwrap();
+        assert_eq!(restored.root_node().to_sexp(), tree.root_node().to_sexp());
+        assert_eq!(restored.serialize(), bytes);
+
+        let nodes = |tree: &Tree| {
+            let mut result = Vec::new();
+            collect_node_summaries(tree.root_node(), &mut result);
+            result
+        };
+        assert_eq!(nodes(&restored), nodes(&tree));
+    });
+}
+
+#[test]
+fn test_tree_deserialize_and_reparse_incrementally() {
+    let mut source = b"def a():\n    return 1\n\nb = [1, 2]\n".to_vec();
+
+    let mut parser = Parser::new();
+    parser.set_language(get_language("python")).unwrap();
+    let mut tree = parser.parse(&source, None).unwrap();
+    let mut restored = Tree::deserialize(get_language("python"), &tree.serialize()).unwrap();
+
+    let edit = Edit {
+        position: index_of(&source, "return 1") + 7,
+        deleted_length: 1,
+        inserted_text: b"c(\n        1)".to_vec(),
+    };
+    let restored_edit = Edit {
+        position: edit.position,
+        deleted_length: edit.deleted_length,
+        inserted_text: edit.inserted_text.clone(),
+    };
+    let mut restored_source = source.clone();
+    let ranges_from_original = get_changed_ranges(&mut parser, &mut tree, &mut source, edit);
+    let ranges_from_restored = get_changed_ranges(
+        &mut parser,
+        &mut restored,
+        &mut restored_source,
+        restored_edit,
+    );
+
+    assert_eq!(ranges_from_restored, ranges_from_original);
+    assert_eq!(restored.root_node().to_sexp(), tree.root_node().to_sexp());
+    assert_eq!(
+        restored.root_node().to_sexp(),
+        parser.parse(&source, None).unwrap().root_node().to_sexp()
+    );
+}
+
+#[test]
+fn test_tree_deserialize_errors() {
+    allocations::record(|| {
+        let mut parser = Parser::new();
+        parser.set_language(get_language("json")).unwrap();
+        let tree = parser.parse("[1, {\"a\": null}, \"b\"]", None).unwrap();
+        let bytes = tree.serialize();
+
+        assert_eq!(
+            Tree::deserialize(get_language("javascript"), &bytes).unwrap_err(),
+            TreeDeserializationError::Language
+        );
+
+        // The header starts with a magic number, the format version, the
+        // language version, a hash of the grammar's symbols and a hash of its
+        // parse table.
+        let mut modified_bytes = bytes.clone();
+        modified_bytes[4] += 1;
+        assert_eq!(
+            Tree::deserialize(get_language("json"), &modified_bytes).unwrap_err(),
+            TreeDeserializationError::FormatVersion
+        );
+        let mut modified_bytes = bytes.clone();
+        modified_bytes[8] += 1;
+        assert_eq!(
+            Tree::deserialize(get_language("json"), &modified_bytes).unwrap_err(),
+            TreeDeserializationError::LanguageVersion
+        );
+        let mut modified_bytes = bytes.clone();
+        modified_bytes[12] ^= 1;
+        assert_eq!(
+            Tree::deserialize(get_language("json"), &modified_bytes).unwrap_err(),
+            TreeDeserializationError::Language
+        );
+        let mut modified_bytes = bytes.clone();
+        modified_bytes[16] ^= 1;
+        assert_eq!(
+            Tree::deserialize(get_language("json"), &modified_bytes).unwrap_err(),
+            TreeDeserializationError::LanguageVersion
+        );
+
+        // The header is followed by the single included range, and then by the
+        // root node. Child counts that don't match the node's children are
+        // rejected.
+        let root_offset = 24 + 24;
+        let visible_child_count_offset = root_offset + 43;
+        assert_eq!(bytes[root_offset], 1);
+        assert_eq!(bytes[visible_child_count_offset], 1);
+        let mut modified_bytes = bytes.clone();
+        modified_bytes[visible_child_count_offset] = 200;
+        assert_eq!(
+            Tree::deserialize(get_language("json"), &modified_bytes).unwrap_err(),
+            TreeDeserializationError::InvalidData
+        );
+
+        // Flags that are derived from a node's children must match them as well.
+        // Otherwise, reusing the tree when reparsing could follow a path to an
+        // external token that doesn't exist.
+        let flags_offset = root_offset + 1;
+        let repeat_depth_offset = root_offset + 59;
+        assert_eq!(bytes[flags_offset] & (1 << 6 | 1 << 7), 0);
+        assert_eq!(bytes[repeat_depth_offset], 0);
+        for (offset, value) in &[
+            (flags_offset, bytes[flags_offset] | 1 << 6),
+            (flags_offset, bytes[flags_offset] | 1 << 7),
+            (repeat_depth_offset, 1),
+        ] {
+            let mut modified_bytes = bytes.clone();
+            modified_bytes[*offset] = *value;
+            match Tree::deserialize(get_language("json"), &modified_bytes) {
+                Err(error) => assert_eq!(error, TreeDeserializationError::InvalidData),
+                Ok(old_tree) => {
+                    parser.parse("[1, {\"a\": null}, \"b\", 2]", Some(&old_tree));
+                    panic!("Expected tampered tree to be rejected");
+                }
+            }
+        }
+
+        let mut modified_bytes = bytes.clone();
+        modified_bytes.push(0);
+        assert_eq!(
+            Tree::deserialize(get_language("json"), &modified_bytes).unwrap_err(),
+            TreeDeserializationError::InvalidData
+        );
+
+        for length in 0..bytes.len() {
+            assert_eq!(
+                Tree::deserialize(get_language("json"), &bytes[0..length]).unwrap_err(),
+                TreeDeserializationError::InvalidData,
+                "length {}",
+                length
+            );
+        }
+    });
+}
+
+#[test]
+fn test_tree_shared_between_threads() {
+    let source = "function a(b) { return b + c(d, e); }\n".repeat(20);
+
+    let mut parser = Parser::new();
+    parser.set_language(get_language("javascript")).unwrap();
+    let tree = Arc::new(parser.parse(&source, None).unwrap());
+    let source = Arc::new(source);
+    let query = Arc::new(
+        Query::new(
+            get_language("javascript"),
+            "(call_expression function: (identifier) @fn)",
+        )
+        .unwrap(),
+    );
+
+    // Each thread queries the same tree, and walks from each captured node back up to
+    // the root, producing a list of ancestor kinds.
+    let ancestor_kinds = |tree: &Tree, query: &Query, source: &str| {
+        let mut cursor = QueryCursor::new();
+        cursor
+            .matches(query, tree.root_node(), |n: Node| &source[n.byte_range()])
+            .map(|m| {
+                let mut kinds = Vec::new();
+                let mut node = m.captures[0].node;
+                while let Some(parent) = node.parent() {
+                    kinds.push(parent.kind());
+                    node = parent;
+                }
+                kinds
+            })
+            .collect::<Vec<_>>()
+    };
+    let expected = ancestor_kinds(&tree, &query, &source);
+    assert_eq!(expected.len(), 20);
+
+    let threads = (0..4)
+        .map(|_| {
+            let tree = tree.clone();
+            let query = query.clone();
+            let source = source.clone();
+            thread::spawn(move || {
+                (0..10)
+                    .map(|_| ancestor_kinds(&tree, &query, &source))
+                    .collect::<Vec<_>>()
+            })
+        })
+        .collect::<Vec<_>>();
+    for thread in threads {
+        for result in thread.join().unwrap() {
+            assert_eq!(result, expected);
+        }
+    }
+
+    // Owned nodes can be sent to other threads along with their tree.
+    let root = OwnedNode::root(tree);
+    let kind = thread::spawn(move || root.node().child(0).unwrap().kind())
+        .join()
+        .unwrap();
+    assert_eq!(kind, "function_declaration");
+}
+
+fn collect_node_summaries(
+    node: Node,
+    result: &mut Vec<(u16, std::ops::Range<usize>, Point, Point, bool, bool, bool)>,
+) {
+    result.push((
+        node.kind_id(),
+        node.byte_range(),
+        node.start_position(),
+        node.end_position(),
+        node.is_named(),
+        node.is_missing(),
+        node.is_extra(),
+    ));
+    for child in node.children(&mut node.walk()) {
+        collect_node_summaries(child, result);
+    }
+}
+
 fn index_of(text: &Vec<u8>, substring: &str) -> usize {
     str::from_utf8(text.as_slice())
         .unwrap()
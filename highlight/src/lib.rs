@@ -28,6 +28,24 @@ pub enum Error {
     Unknown,
 }
 
+/// Identifies one of the queries that make up a `HighlightConfiguration`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum QuerySection {
+    Injections,
+    Locals,
+    Highlights,
+}
+
+/// Represents an invalid query passed to `HighlightConfiguration::new`.
+///
+/// The row, column and offset of the `error` are relative to the start of the query
+/// identified by `section`.
+#[derive(Debug, PartialEq, Eq)]
+pub struct HighlightQueryError {
+    pub section: QuerySection,
+    pub error: QueryError,
+}
+
 /// Represents a single step in rendering a syntax-highlighted document.
 #[derive(Copy, Clone, Debug)]
 pub enum HighlightEvent {
@@ -233,12 +251,14 @@ impl HighlightConfiguration {
     ///   definitions and references. This can be empty if local variable tracking is not needed.
     ///
     /// Returns a `HighlightConfiguration` that can then be used with the `highlight` method.
+    /// If one of the queries is invalid, the returned error identifies that query, and the
+    /// error's location is relative to the start of it.
     pub fn new(
         language: Language,
         highlights_query: &str,
         injection_query: &str,
         locals_query: &str,
-    ) -> Result<Self, QueryError> {
+    ) -> Result<Self, HighlightQueryError> {
         // Concatenate the query strings, keeping track of the start offset of each section.
         let mut query_source = String::new();
         query_source.push_str(injection_query);
@@ -249,7 +269,18 @@ impl HighlightConfiguration {
 
         // Construct a single query by concatenating the three query strings, but record the
         // range of pattern indices that belong to each individual string.
-        let mut query = Query::new(language, &query_source)?;
+        let mut query = Query::new(language, &query_source).map_err(|error| {
+            let (section_index, error) = error.relative_to_section(
+                &query_source,
+                &[0, locals_query_offset, highlights_query_offset],
+            );
+            let section = [
+                QuerySection::Injections,
+                QuerySection::Locals,
+                QuerySection::Highlights,
+            ][section_index];
+            HighlightQueryError { section, error }
+        })?;
         let mut locals_pattern_index = 0;
         let mut highlights_pattern_index = 0;
         for i in 0..(query.pattern_count()) {
@@ -266,7 +297,11 @@ impl HighlightConfiguration {
 
         // Construct a separate query just for dealing with the 'combined injections'.
         // Disable the combined injection patterns in the main query.
-        let mut combined_injections_query = Query::new(language, injection_query)?;
+        let mut combined_injections_query =
+            Query::new(language, injection_query).map_err(|error| HighlightQueryError {
+                section: QuerySection::Injections,
+                error,
+            })?;
         let mut has_combined_queries = false;
         for pattern_index in 0..locals_pattern_index {
             let settings = query.property_settings(pattern_index);
@@ -2,6 +2,7 @@
 
 pub type __darwin_size_t = ::std::os::raw::c_ulong;
 pub type FILE = [u64; 19usize];
+pub type TSStateId = u16;
 pub type TSSymbol = u16;
 pub type TSFieldId = u16;
 #[repr(C)]
@@ -29,6 +30,11 @@ pub struct TSQuery {
 pub struct TSQueryCursor {
     _unused: [u8; 0],
 }
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct TSLookaheadIterator {
+    _unused: [u8; 0],
+}
 pub const TSInputEncoding_TSInputEncodingUTF8: TSInputEncoding = 0;
 pub const TSInputEncoding_TSInputEncodingUTF16: TSInputEncoding = 1;
 pub type TSInputEncoding = u32;
@@ -81,6 +87,19 @@ pub struct TSLogger {
 }
 #[repr(C)]
 #[derive(Debug, Copy, Clone)]
+pub struct TSParseProgressCallback {
+    pub payload: *mut ::std::os::raw::c_void,
+    pub progress: ::std::option::Option<
+        unsafe extern "C" fn(payload: *mut ::std::os::raw::c_void, byte_offset: u32) -> bool,
+    >,
+}
+pub const TSParseHaltReason_TSParseHaltReasonNone: TSParseHaltReason = 0;
+pub const TSParseHaltReason_TSParseHaltReasonTimeout: TSParseHaltReason = 1;
+pub const TSParseHaltReason_TSParseHaltReasonCancelled: TSParseHaltReason = 2;
+pub const TSParseHaltReason_TSParseHaltReasonPaused: TSParseHaltReason = 3;
+pub type TSParseHaltReason = u32;
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
 pub struct TSInputEdit {
     pub start_byte: u32,
     pub old_end_byte: u32,
@@ -117,6 +136,19 @@ pub struct TSQueryMatch {
     pub capture_count: u16,
     pub captures: *const TSQueryCapture,
 }
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct TSQueryPatternStats {
+    pub states_created: u32,
+    pub matches_produced: u32,
+    pub matches_dropped: u32,
+}
+pub const TSQuantifier_TSQuantifierZero: TSQuantifier = 0;
+pub const TSQuantifier_TSQuantifierZeroOrOne: TSQuantifier = 1;
+pub const TSQuantifier_TSQuantifierZeroOrMore: TSQuantifier = 2;
+pub const TSQuantifier_TSQuantifierOne: TSQuantifier = 3;
+pub const TSQuantifier_TSQuantifierOneOrMore: TSQuantifier = 4;
+pub type TSQuantifier = u32;
 pub const TSQueryPredicateStepType_TSQueryPredicateStepTypeDone: TSQueryPredicateStepType = 0;
 pub const TSQueryPredicateStepType_TSQueryPredicateStepTypeCapture: TSQueryPredicateStepType = 1;
 pub const TSQueryPredicateStepType_TSQueryPredicateStepTypeString: TSQueryPredicateStepType = 2;
@@ -127,6 +159,16 @@ pub struct TSQueryPredicateStep {
     pub type_: TSQueryPredicateStepType,
     pub value_id: u32,
 }
+pub const TSTreeDeserializationError_TSTreeDeserializationErrorNone: TSTreeDeserializationError = 0;
+pub const TSTreeDeserializationError_TSTreeDeserializationErrorInvalidData:
+    TSTreeDeserializationError = 1;
+pub const TSTreeDeserializationError_TSTreeDeserializationErrorFormatVersion:
+    TSTreeDeserializationError = 2;
+pub const TSTreeDeserializationError_TSTreeDeserializationErrorLanguageVersion:
+    TSTreeDeserializationError = 3;
+pub const TSTreeDeserializationError_TSTreeDeserializationErrorLanguage:
+    TSTreeDeserializationError = 4;
+pub type TSTreeDeserializationError = u32;
 pub const TSQueryError_TSQueryErrorNone: TSQueryError = 0;
 pub const TSQueryError_TSQueryErrorSyntax: TSQueryError = 1;
 pub const TSQueryError_TSQueryErrorNodeType: TSQueryError = 2;
@@ -230,6 +272,13 @@ extern "C" {
     #[doc = "    earlier call to `ts_parser_set_cancellation_flag`. You can resume parsing"]
     #[doc = "    from where the parser left out by calling `ts_parser_parse` again with"]
     #[doc = "    the same arguments."]
+    #[doc = " 4. Parsing was paused by a progress callback that was set by an earlier"]
+    #[doc = "    call to `ts_parser_set_progress_callback`. You can resume parsing in the"]
+    #[doc = "    same way as after a timeout."]
+    #[doc = ""]
+    #[doc = " Use `ts_parser_halt_reason` to distinguish between the last three cases."]
+    #[doc = " When resuming, the `old_tree` argument is ignored, because the parser"]
+    #[doc = " continues to use the tree that was passed to the call that halted."]
     pub fn ts_parser_parse(
         self_: *mut TSParser,
         old_tree: *const TSTree,
@@ -295,6 +344,35 @@ extern "C" {
     #[doc = " Get the parser\'s current cancellation flag pointer."]
     pub fn ts_parser_cancellation_flag(self_: *const TSParser) -> *const usize;
 }
+extern "C" {
+    #[doc = " Set the callback that a parser should use to report its progress during"]
+    #[doc = " parsing."]
+    #[doc = ""]
+    #[doc = " The callback is invoked periodically with the byte offset that the parser"]
+    #[doc = " has reached. If it returns true, the parser will halt early, returning NULL,"]
+    #[doc = " and the parse can be resumed later. See `ts_parser_parse` for more"]
+    #[doc = " information."]
+    #[doc = ""]
+    #[doc = " The parser does not take ownership over the callback payload. If a callback"]
+    #[doc = " was previously assigned, the caller is responsible for releasing any memory"]
+    #[doc = " owned by the previous callback."]
+    pub fn ts_parser_set_progress_callback(
+        self_: *mut TSParser,
+        callback: TSParseProgressCallback,
+    );
+}
+extern "C" {
+    #[doc = " Get the parser\'s current progress callback."]
+    pub fn ts_parser_progress_callback(self_: *const TSParser) -> TSParseProgressCallback;
+}
+extern "C" {
+    #[doc = " Get the reason that the parser\'s most recent call to `ts_parser_parse` halted"]
+    #[doc = " before finishing."]
+    #[doc = ""]
+    #[doc = " This returns `TSParseHaltReasonNone` if the last parse completed, or if the"]
+    #[doc = " parser has been reset since then."]
+    pub fn ts_parser_halt_reason(self_: *const TSParser) -> TSParseHaltReason;
+}
 extern "C" {
     #[doc = " Set the logger that a parser should use during parsing."]
     #[doc = ""]
@@ -317,8 +395,9 @@ extern "C" {
 extern "C" {
     #[doc = " Create a shallow copy of the syntax tree. This is very fast."]
     #[doc = ""]
-    #[doc = " You need to copy a syntax tree in order to use it on more than one thread at"]
-    #[doc = " a time, as syntax trees are not thread safe."]
+    #[doc = " A syntax tree can be read from several threads at once, for example by"]
+    #[doc = " running a separate query cursor on each thread. You need to copy a syntax"]
+    #[doc = " tree in order to edit it while it is being used on another thread."]
     pub fn ts_tree_copy(self_: *const TSTree) -> *mut TSTree;
 }
 extern "C" {
@@ -364,6 +443,32 @@ extern "C" {
     #[doc = " Write a DOT graph describing the syntax tree to the given file."]
     pub fn ts_tree_print_dot_graph(arg1: *const TSTree, arg2: *mut FILE);
 }
+extern "C" {
+    #[doc = " Serialize the syntax tree into a compact binary format, so that it can"]
+    #[doc = " be stored and later restored with `ts_tree_deserialize`."]
+    #[doc = ""]
+    #[doc = " The returned buffer is allocated using `malloc` and the caller is"]
+    #[doc = " responsible for freeing it using `free`. The length of the buffer will"]
+    #[doc = " be written to the given `length` pointer."]
+    pub fn ts_tree_serialize(self_: *const TSTree, length: *mut u32) -> *mut ::std::os::raw::c_char;
+}
+extern "C" {
+    #[doc = " Restore a syntax tree that was serialized with `ts_tree_serialize`."]
+    #[doc = ""]
+    #[doc = " The tree must be restored using the same language that was used to parse"]
+    #[doc = " it. The restored tree can be passed as the `old_tree` when parsing, in"]
+    #[doc = " order to reparse an edited document incrementally."]
+    #[doc = ""]
+    #[doc = " If the data cannot be loaded, this returns `NULL` and writes the reason"]
+    #[doc = " to the `error` parameter. Trees that were produced by a different version"]
+    #[doc = " of the language are rejected with `TSTreeDeserializationErrorLanguageVersion`."]
+    pub fn ts_tree_deserialize(
+        language: *const TSLanguage,
+        data: *const ::std::os::raw::c_char,
+        length: u32,
+        error: *mut TSTreeDeserializationError,
+    ) -> *mut TSTree;
+}
 extern "C" {
     #[doc = " Get the node\'s type as a null-terminated string."]
     pub fn ts_node_type(arg1: TSNode) -> *const ::std::os::raw::c_char;
@@ -372,6 +477,17 @@ extern "C" {
     #[doc = " Get the node\'s type as a numerical id."]
     pub fn ts_node_symbol(arg1: TSNode) -> TSSymbol;
 }
+extern "C" {
+    #[doc = " Get the node's type as it appears in the grammar ignoring aliases as a"]
+    #[doc = " null-terminated string."]
+    pub fn ts_node_grammar_type(arg1: TSNode) -> *const ::std::os::raw::c_char;
+}
+extern "C" {
+    #[doc = " Get the node's type as a numerical id as it appears in the grammar ignoring"]
+    #[doc = " aliases. This should be used in `ts_language_next_state` instead of"]
+    #[doc = " `ts_node_symbol`."]
+    pub fn ts_node_grammar_symbol(arg1: TSNode) -> TSSymbol;
+}
 extern "C" {
     #[doc = " Get the node\'s start byte."]
     pub fn ts_node_start_byte(arg1: TSNode) -> u32;
@@ -425,6 +541,14 @@ extern "C" {
     #[doc = " Check if the node is a syntax error or contains any syntax errors."]
     pub fn ts_node_has_error(arg1: TSNode) -> bool;
 }
+extern "C" {
+    #[doc = " Get this node's parse state."]
+    pub fn ts_node_parse_state(arg1: TSNode) -> TSStateId;
+}
+extern "C" {
+    #[doc = " Get the parse state after this node."]
+    pub fn ts_node_next_parse_state(arg1: TSNode) -> TSStateId;
+}
 extern "C" {
     #[doc = " Get the node\'s immediate parent."]
     pub fn ts_node_parent(arg1: TSNode) -> TSNode;
@@ -572,6 +696,17 @@ extern "C" {
     #[doc = " if there was no next sibling node."]
     pub fn ts_tree_cursor_goto_next_sibling(arg1: *mut TSTreeCursor) -> bool;
 }
+extern "C" {
+    #[doc = " Move the cursor to the previous sibling of its current node."]
+    #[doc = ""]
+    #[doc = " This returns `true` if the cursor successfully moved, and returns `false`"]
+    #[doc = " if there was no previous sibling node."]
+    #[doc = ""]
+    #[doc = " Note that this function may be slower than"]
+    #[doc = " `ts_tree_cursor_goto_next_sibling`, because the position of the previous"]
+    #[doc = " sibling must be recomputed by scanning from the start of the parent node."]
+    pub fn ts_tree_cursor_goto_previous_sibling(arg1: *mut TSTreeCursor) -> bool;
+}
 extern "C" {
     #[doc = " Move the cursor to the first child of its current node."]
     #[doc = ""]
@@ -579,6 +714,33 @@ extern "C" {
     #[doc = " if there were no children."]
     pub fn ts_tree_cursor_goto_first_child(arg1: *mut TSTreeCursor) -> bool;
 }
+extern "C" {
+    #[doc = " Move the cursor to the last child of its current node."]
+    #[doc = ""]
+    #[doc = " This returns `true` if the cursor successfully moved, and returns `false`"]
+    #[doc = " if there were no children."]
+    #[doc = ""]
+    #[doc = " Note that this function may be slower than `ts_tree_cursor_goto_first_child`,"]
+    #[doc = " because it needs to iterate through all the children to compute the child's"]
+    #[doc = " position."]
+    pub fn ts_tree_cursor_goto_last_child(arg1: *mut TSTreeCursor) -> bool;
+}
+extern "C" {
+    #[doc = " Move the cursor to the node that is the nth descendant of the original node"]
+    #[doc = " that the cursor was constructed with, where zero represents the original"]
+    #[doc = " node itself."]
+    pub fn ts_tree_cursor_goto_descendant(arg1: *mut TSTreeCursor, arg2: u32) -> bool;
+}
+extern "C" {
+    #[doc = " Get the index of the cursor's current node out of all of the descendants of"]
+    #[doc = " the original node that the cursor was constructed with."]
+    pub fn ts_tree_cursor_current_descendant_index(arg1: *const TSTreeCursor) -> u32;
+}
+extern "C" {
+    #[doc = " Get the depth of the cursor's current node relative to the original node"]
+    #[doc = " that the cursor was constructed with."]
+    pub fn ts_tree_cursor_current_depth(arg1: *const TSTreeCursor) -> u32;
+}
 extern "C" {
     #[doc = " Move the cursor to the first child of its current node that extends beyond"]
     #[doc = " the given byte offset."]
@@ -587,6 +749,14 @@ extern "C" {
     #[doc = " if no such child was found."]
     pub fn ts_tree_cursor_goto_first_child_for_byte(arg1: *mut TSTreeCursor, arg2: u32) -> i64;
 }
+extern "C" {
+    #[doc = " Move the cursor to the first child of its current node that extends beyond"]
+    #[doc = " the given point."]
+    #[doc = ""]
+    #[doc = " This returns the index of the child node if one was found, and returns -1"]
+    #[doc = " if no such child was found."]
+    pub fn ts_tree_cursor_goto_first_child_for_point(arg1: *mut TSTreeCursor, arg2: TSPoint) -> i64;
+}
 extern "C" {
     pub fn ts_tree_cursor_copy(arg1: *const TSTreeCursor) -> TSTreeCursor;
 }
@@ -608,6 +778,20 @@ extern "C" {
         error_type: *mut TSQueryError,
     ) -> *mut TSQuery;
 }
+extern "C" {
+    #[doc = " Create a new query like `ts_query_new`, but without failing when some of"]
+    #[doc = " the patterns can never match, given the structure of the language\'s grammar."]
+    #[doc = " Instead, those patterns are disabled, and they can be identified using"]
+    #[doc = " `ts_query_is_pattern_impossible`. Other errors are reported in the same way"]
+    #[doc = " as in `ts_query_new`."]
+    pub fn ts_query_new_lenient(
+        language: *const TSLanguage,
+        source: *const ::std::os::raw::c_char,
+        source_len: u32,
+        error_offset: *mut u32,
+        error_type: *mut TSQueryError,
+    ) -> *mut TSQuery;
+}
 extern "C" {
     #[doc = " Delete a query, freeing all of the memory that it used."]
     pub fn ts_query_delete(arg1: *mut TSQuery);
@@ -651,6 +835,44 @@ extern "C" {
     ) -> *const TSQueryPredicateStep;
 }
 extern "C" {
+    #[doc = " Check if the given pattern in the query has a single root node."]
+    pub fn ts_query_is_pattern_rooted(self_: *const TSQuery, pattern_index: u32) -> bool;
+}
+extern "C" {
+    #[doc = " Check if the given pattern in the query is \'non-local\'."]
+    #[doc = ""]
+    #[doc = " A non-local pattern has multiple root nodes, and can match a sequence of"]
+    #[doc = " sibling nodes that the grammar produces using a repetition. Such a pattern"]
+    #[doc = " can span any number of siblings, so it can\'t be matched by looking at a"]
+    #[doc = " single node and its descendants."]
+    pub fn ts_query_is_pattern_non_local(self_: *const TSQuery, pattern_index: u32) -> bool;
+}
+extern "C" {
+    #[doc = " Check if the given pattern in the query can never match, given the"]
+    #[doc = " structure of the language\'s grammar. This can only be true for queries"]
+    #[doc = " created with `ts_query_new_lenient`. Impossible patterns are disabled."]
+    pub fn ts_query_is_pattern_impossible(self_: *const TSQuery, pattern_index: u32) -> bool;
+}
+extern "C" {
+    #[doc = " Get the number of steps in the given pattern, and the byte offset of one of"]
+    #[doc = " those steps in the query\'s source."]
+    #[doc = ""]
+    #[doc = " Each step corresponds to a node or an alternation within the pattern. The"]
+    #[doc = " byte offsets can be passed to `ts_query_step_is_definite`."]
+    pub fn ts_query_step_count_for_pattern(self_: *const TSQuery, pattern_index: u32) -> u32;
+}
+extern "C" {
+    pub fn ts_query_step_offset_for_pattern(
+        self_: *const TSQuery,
+        pattern_index: u32,
+        step_index: u32,
+    ) -> u32;
+}
+extern "C" {
+    #[doc = " Check if the step at the given byte offset in the query is \'definite\'."]
+    #[doc = ""]
+    #[doc = " A step is definite if its pattern is guaranteed to match successfully once"]
+    #[doc = " it has reached that step."]
     pub fn ts_query_step_is_definite(self_: *const TSQuery, byte_offset: u32) -> bool;
 }
 extern "C" {
@@ -663,6 +885,15 @@ extern "C" {
         length: *mut u32,
     ) -> *const ::std::os::raw::c_char;
 }
+extern "C" {
+    #[doc = " Get the quantifier of the query's captures. Each capture is associated"]
+    #[doc = " with a numeric id based on the order that it appeared in the query's source."]
+    pub fn ts_query_capture_quantifier_for_id(
+        arg1: *const TSQuery,
+        pattern_id: u32,
+        capture_id: u32,
+    ) -> TSQuantifier;
+}
 extern "C" {
     pub fn ts_query_string_value_for_id(
         arg1: *const TSQuery,
@@ -721,15 +952,49 @@ extern "C" {
     pub fn ts_query_cursor_exec(arg1: *mut TSQueryCursor, arg2: *const TSQuery, arg3: TSNode);
 }
 extern "C" {
-    #[doc = " Check if this cursor has exceeded its maximum number of in-progress"]
-    #[doc = " matches."]
+    #[doc = " Manage the maximum number of in-progress matches allowed by this query"]
+    #[doc = " cursor."]
     #[doc = ""]
-    #[doc = " Currently, query cursors have a fixed capacity for storing lists"]
-    #[doc = " of in-progress captures. If this capacity is exceeded, then the"]
-    #[doc = " earliest-starting match will silently be dropped to make room for"]
-    #[doc = " further matches."]
+    #[doc = " Query cursors have a limited capacity for storing lists of in-progress"]
+    #[doc = " captures. If this capacity is exceeded, then the earliest-starting match"]
+    #[doc = " will silently be dropped to make room for further matches. The limit"]
+    #[doc = " defaults to 32, and can be at most 65535."]
     pub fn ts_query_cursor_did_exceed_match_limit(arg1: *const TSQueryCursor) -> bool;
 }
+extern "C" {
+    pub fn ts_query_cursor_match_limit(arg1: *const TSQueryCursor) -> u32;
+}
+extern "C" {
+    pub fn ts_query_cursor_set_match_limit(arg1: *mut TSQueryCursor, arg2: u32);
+}
+extern "C" {
+    #[doc = " Set the maximum depth, relative to the node passed to `ts_query_cursor_exec`,"]
+    #[doc = " at which the query cursor will start matching patterns. A depth of zero only"]
+    #[doc = " allows patterns to match the node itself. By default, there is no limit,"]
+    #[doc = " which is represented by `UINT32_MAX`."]
+    pub fn ts_query_cursor_set_max_start_depth(arg1: *mut TSQueryCursor, arg2: u32);
+}
+extern "C" {
+    pub fn ts_query_cursor_max_start_depth(arg1: *const TSQueryCursor) -> u32;
+}
+extern "C" {
+    #[doc = " Get statistics about the work done by the cursor since the last call to"]
+    #[doc = " `ts_query_cursor_exec`."]
+    #[doc = ""]
+    #[doc = " `ts_query_cursor_nodes_visited` returns the number of nodes that the cursor"]
+    #[doc = " has processed. `ts_query_cursor_pattern_stats` returns an array with one"]
+    #[doc = " element per pattern in the query, and writes the length of the array to"]
+    #[doc = " the `length` pointer. Each element counts the states that were created for"]
+    #[doc = " the pattern, the matches that were produced for it, and the in-progress"]
+    #[doc = " matches that were dropped because of the match limit."]
+    pub fn ts_query_cursor_nodes_visited(arg1: *const TSQueryCursor) -> u32;
+}
+extern "C" {
+    pub fn ts_query_cursor_pattern_stats(
+        arg1: *const TSQueryCursor,
+        length: *mut u32,
+    ) -> *const TSQueryPatternStats;
+}
 extern "C" {
     #[doc = " Set the range of bytes or (row, column) positions in which the query"]
     #[doc = " will be executed."]
@@ -813,6 +1078,104 @@ extern "C" {
     #[doc = " See also `ts_parser_set_language`."]
     pub fn ts_language_version(arg1: *const TSLanguage) -> u32;
 }
+extern "C" {
+    #[doc = " Get a list of all supertype symbols for the language."]
+    #[doc = ""]
+    #[doc = " The length of the returned array is written to `length`. Languages that were"]
+    #[doc = " generated with an ABI version older than 14 do not contain any supertype"]
+    #[doc = " information, so this returns `NULL` for them."]
+    pub fn ts_language_supertypes(self_: *const TSLanguage, length: *mut u32) -> *const TSSymbol;
+}
+extern "C" {
+    #[doc = " Get a list of all subtype symbol ids for a given supertype symbol."]
+    #[doc = ""]
+    #[doc = " The subtypes are listed as they appear in the `node-types.json` file, so a"]
+    #[doc = " subtype may itself be a supertype. The length of the returned array is"]
+    #[doc = " written to `length`. If the given symbol is not a supertype, this returns"]
+    #[doc = " `NULL`."]
+    pub fn ts_language_subtypes(
+        self_: *const TSLanguage,
+        supertype: TSSymbol,
+        length: *mut u32,
+    ) -> *const TSSymbol;
+}
+extern "C" {
+    #[doc = " Get the number of valid states in this language."]
+    pub fn ts_language_state_count(arg1: *const TSLanguage) -> u32;
+}
+extern "C" {
+    #[doc = " Get the next parse state. Combine this with lookahead iterators to generate"]
+    #[doc = " completion suggestions or valid symbols in error nodes. Use"]
+    #[doc = " `ts_node_grammar_symbol` for valid symbols."]
+    pub fn ts_language_next_state(
+        arg1: *const TSLanguage,
+        arg2: TSStateId,
+        arg3: TSSymbol,
+    ) -> TSStateId;
+}
+extern "C" {
+    #[doc = " Create a new lookahead iterator for the given language and parse state."]
+    #[doc = ""]
+    #[doc = " This returns `NULL` if state is invalid for the language."]
+    #[doc = ""]
+    #[doc = " Repeatedly using `ts_lookahead_iterator_next` and"]
+    #[doc = " `ts_lookahead_iterator_current_symbol` will generate valid symbols in the"]
+    #[doc = " given parse state. Newly created lookahead iterators will contain the `ERROR`"]
+    #[doc = " symbol."]
+    #[doc = ""]
+    #[doc = " Lookahead iterators can be useful to generate suggestions and improve syntax"]
+    #[doc = " error diagnostics. To get symbols valid in an ERROR node, use the lookahead"]
+    #[doc = " iterator on its first leaf node state. For `MISSING` nodes, a lookahead"]
+    #[doc = " iterator created on the previous non-extra leaf node may be appropriate."]
+    pub fn ts_lookahead_iterator_new(
+        arg1: *const TSLanguage,
+        arg2: TSStateId,
+    ) -> *mut TSLookaheadIterator;
+}
+extern "C" {
+    #[doc = " Delete a lookahead iterator freeing all the memory used."]
+    pub fn ts_lookahead_iterator_delete(arg1: *mut TSLookaheadIterator);
+}
+extern "C" {
+    #[doc = " Reset the lookahead iterator to another state."]
+    #[doc = ""]
+    #[doc = " This returns `true` if the iterator was reset to the given state and `false`"]
+    #[doc = " otherwise."]
+    pub fn ts_lookahead_iterator_reset_state(arg1: *mut TSLookaheadIterator, arg2: TSStateId)
+        -> bool;
+}
+extern "C" {
+    #[doc = " Reset the lookahead iterator."]
+    #[doc = ""]
+    #[doc = " This returns `true` if the language was set successfully and `false`"]
+    #[doc = " otherwise."]
+    pub fn ts_lookahead_iterator_reset(
+        arg1: *mut TSLookaheadIterator,
+        arg2: *const TSLanguage,
+        arg3: TSStateId,
+    ) -> bool;
+}
+extern "C" {
+    #[doc = " Get the current language of the lookahead iterator."]
+    pub fn ts_lookahead_iterator_language(arg1: *const TSLookaheadIterator) -> *const TSLanguage;
+}
+extern "C" {
+    #[doc = " Advance the lookahead iterator to the next symbol."]
+    #[doc = ""]
+    #[doc = " This returns `true` if there is a new symbol and `false` otherwise."]
+    pub fn ts_lookahead_iterator_next(arg1: *mut TSLookaheadIterator) -> bool;
+}
+extern "C" {
+    #[doc = " Get the current symbol of the lookahead iterator."]
+    pub fn ts_lookahead_iterator_current_symbol(arg1: *const TSLookaheadIterator) -> TSSymbol;
+}
+extern "C" {
+    #[doc = " Get the current symbol type of the lookahead iterator as a null terminated"]
+    #[doc = " string."]
+    pub fn ts_lookahead_iterator_current_symbol_name(
+        arg1: *const TSLookaheadIterator,
+    ) -> *const ::std::os::raw::c_char;
+}
 
-pub const TREE_SITTER_LANGUAGE_VERSION: usize = 13;
+pub const TREE_SITTER_LANGUAGE_VERSION: usize = 14;
 pub const TREE_SITTER_MIN_COMPATIBLE_LANGUAGE_VERSION: usize = 13;
@@ -0,0 +1,238 @@
+//! Structured descriptions of the syntax errors in a tree.
+//!
+//! The [diagnostics] function walks a syntax tree and produces one [Diagnostic]
+//! for every `ERROR` and `MISSING` node that it contains. Besides the location
+//! of the error, each diagnostic describes what the parser found there, and
+//! which kinds of nodes would have been valid instead. The latter are derived
+//! from the language's parse table, using the parse state in which the error
+//! was detected.
+
+use super::{Language, Node, Range, Tree};
+
+/// The parse state that every parse begins in.
+const START_STATE: u16 = 1;
+
+/// The parse state that the parser uses during error recovery. Tokens that were
+/// skipped during error recovery are assigned this state, so it says nothing
+/// about what was expected at that point.
+const ERROR_STATE: u16 = 0;
+
+/// The parse state assigned to nodes that were not created by the parser.
+const NONE_STATE: u16 = u16::MAX;
+
+/// A syntax error found by [diagnostics].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Diagnostic<'tree> {
+    /// The `ERROR` or `MISSING` node that represents the error.
+    pub node: Node<'tree>,
+    /// The range of the source code that the error spans. For a `MISSING` node,
+    /// this is an empty range at the position where the node was expected.
+    pub range: Range,
+    /// What the parser found at the location of the error.
+    pub kind: DiagnosticKind<'tree>,
+    /// The ids of the node kinds that would have been valid at the location of
+    /// the error. Only visible node kinds are included, and kinds that share the
+    /// same name are only included once. Use [Language::node_kind_for_id] to get
+    /// their names.
+    ///
+    /// Because of the way that the parser recovers from errors, an `ERROR` node
+    /// may begin before the point where the error was actually detected. In that
+    /// case, these are the kinds that were valid at the start of the `ERROR` node.
+    /// This list is empty if that parse state is not known.
+    pub expected: Vec<u16>,
+}
+
+/// The different kinds of syntax errors.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DiagnosticKind<'tree> {
+    /// The parser skipped over some tokens that it could not handle, and wrapped
+    /// them in an `ERROR` node. The token is the first leaf of the `ERROR` node,
+    /// which is the `ERROR` node itself if it has no children, or `None` if the
+    /// `ERROR` node is empty.
+    Unexpected { token: Option<Node<'tree>> },
+    /// The parser inserted a zero-width node of the given kind in order to
+    /// recover from the error.
+    Missing { kind: &'static str },
+}
+
+/// Find all of the syntax errors in a tree.
+///
+/// The diagnostics are returned in document order. The contents of an `ERROR`
+/// node are reported as part of a single diagnostic, even if they contain other
+/// `ERROR` or `MISSING` nodes.
+pub fn diagnostics(tree: &Tree) -> Vec<Diagnostic<'_>> {
+    let language = tree.language();
+    let mut result = Vec::new();
+    let mut previous_leaf = None;
+    let mut cursor = tree.walk();
+    let mut did_visit_children = false;
+    loop {
+        let node = cursor.node();
+        if !did_visit_children {
+            if node.is_error() || node.is_missing() {
+                result.push(diagnostic_for_node(language, node, previous_leaf));
+                previous_leaf = last_leaf(node);
+            } else if node.has_error() && cursor.goto_first_child() {
+                continue;
+            } else if let Some(leaf) = last_leaf(node) {
+                previous_leaf = Some(leaf);
+            }
+        }
+
+        if cursor.goto_next_sibling() {
+            did_visit_children = false;
+        } else if cursor.goto_parent() {
+            did_visit_children = true;
+        } else {
+            break;
+        }
+    }
+    result
+}
+
+impl<'tree> Diagnostic<'tree> {
+    /// Get a human-readable description of the error, using the given source
+    /// code to display the text of unexpected tokens.
+    pub fn message(&self, source: &[u8]) -> String {
+        let language = self.node.language();
+        let mut result = match self.kind {
+            DiagnosticKind::Unexpected { token: Some(token) } => {
+                let text = token
+                    .utf8_text(source)
+                    .map_or("", |text| text.lines().next().unwrap_or(""));
+                if text.is_empty() {
+                    format!("unexpected {}", token.kind())
+                } else {
+                    format!("unexpected `{}`", text)
+                }
+            }
+            DiagnosticKind::Unexpected { token: None } => "unexpected input".to_string(),
+            DiagnosticKind::Missing { .. } => {
+                format!("missing {}", describe_kind(language, self.node.kind_id()))
+            }
+        };
+
+        // If the unexpected token was itself valid at that point, then the parser
+        // only detected the error later on, and the expected kinds are not helpful.
+        if let DiagnosticKind::Unexpected { token } = self.kind {
+            let token_is_expected = token
+                .filter(|token| {
+                    self.expected.iter().any(|id| {
+                        language.node_kind_for_id(*id) == Some(token.kind())
+                            && language.node_kind_is_named(*id) == token.is_named()
+                    })
+                })
+                .is_some();
+            if !self.expected.is_empty() && !token_is_expected {
+                result += if self.expected.len() == 1 {
+                    ", expected "
+                } else {
+                    ", expected one of "
+                };
+                for (i, id) in self.expected.iter().enumerate() {
+                    if i > 0 {
+                        result += ", ";
+                    }
+                    result += &describe_kind(language, *id);
+                }
+            }
+        }
+        result
+    }
+}
+
+fn describe_kind(language: Language, id: u16) -> String {
+    let name = language.node_kind_for_id(id).unwrap_or("");
+    if language.node_kind_is_named(id) {
+        name.to_string()
+    } else {
+        format!("`{}`", name.replace('\n', "\\n"))
+    }
+}
+
+fn diagnostic_for_node<'tree>(
+    language: Language,
+    node: Node<'tree>,
+    previous_leaf: Option<Node<'tree>>,
+) -> Diagnostic<'tree> {
+    // Use the state in which the parser encountered the error if it was recorded,
+    // and otherwise, the state that the parser moved to after the preceding leaf.
+    let (kind, state) = if node.is_missing() {
+        (
+            DiagnosticKind::Missing { kind: node.kind() },
+            node.parse_state(),
+        )
+    } else {
+        let token = Some(first_leaf(node)).filter(|leaf| leaf.end_byte() > leaf.start_byte());
+        let state = token.map_or(ERROR_STATE, |token| token.parse_state());
+        (DiagnosticKind::Unexpected { token }, state)
+    };
+    let state = Some(state)
+        .filter(|state| *state != ERROR_STATE && *state != NONE_STATE)
+        .or_else(|| previous_leaf.map(|leaf| leaf.next_parse_state()));
+
+    let mut range = node.range();
+    if node.is_missing() {
+        range.start_byte = range.end_byte;
+        range.start_point = range.end_point;
+    }
+
+    Diagnostic {
+        node,
+        range,
+        kind,
+        expected: expected_kinds(language, state.unwrap_or(START_STATE)),
+    }
+}
+
+fn expected_kinds(language: Language, state: u16) -> Vec<u16> {
+    let mut result = Vec::new();
+    let mut names = Vec::new();
+    if state == ERROR_STATE || state == NONE_STATE {
+        return result;
+    }
+    if let Some(lookahead) = language.lookahead_iterator(state) {
+        for id in lookahead {
+            if !language.node_kind_is_visible(id) {
+                continue;
+            }
+            let name = (
+                language.node_kind_for_id(id),
+                language.node_kind_is_named(id),
+            );
+            if !names.contains(&name) {
+                names.push(name);
+                result.push(id);
+            }
+        }
+    }
+    result
+}
+
+fn first_leaf(node: Node<'_>) -> Node<'_> {
+    let mut leaf = node;
+    while let Some(child) = leaf.child(0) {
+        leaf = child;
+    }
+    leaf
+}
+
+fn last_leaf(node: Node<'_>) -> Option<Node<'_>> {
+    let mut leaf = node;
+    loop {
+        let mut cursor = leaf.walk();
+        let child = leaf
+            .children(&mut cursor)
+            .filter(|child| !child.is_extra())
+            .last();
+        match child {
+            Some(child) => leaf = child,
+            None => break,
+        }
+    }
+    if leaf.is_extra() {
+        None
+    } else {
+        Some(leaf)
+    }
+}
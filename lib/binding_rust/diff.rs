@@ -0,0 +1,471 @@
+//! Structural differences between syntax trees.
+//!
+//! The [diff] function compares two syntax trees of the same language, and produces
+//! a mapping between their nodes along with an edit script describing how the old
+//! tree can be transformed into the new one. The matching algorithm is based on
+//! [GumTree](https://github.com/GumTreeDiff/gumtree):
+//!
+//! 1. Subtrees that were reused by the incremental parser are matched by their ids.
+//! 2. The largest isomorphic subtrees are matched from the top down.
+//! 3. Remaining nodes are matched from the bottom up, based on the number of matched
+//!    descendants that they share.
+
+use super::{Node, Tree};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+
+/// Subtrees smaller than this are not matched during the top-down phase, because
+/// small subtrees like identifiers and punctuation occur too often to be matched
+/// reliably without context.
+const MIN_HEIGHT: usize = 2;
+
+/// The minimum ratio of shared matched descendants for two nodes to be matched
+/// during the bottom-up phase.
+const MIN_DICE: f64 = 0.5;
+
+/// A single operation in an edit script produced by [diff].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Edit<'tree> {
+    /// A node in the new tree that has no counterpart in the old tree. It was inserted
+    /// as the `index`th child of `parent`.
+    Insert {
+        node: Node<'tree>,
+        parent: Node<'tree>,
+        index: usize,
+    },
+    /// A node in the old tree that has no counterpart in the new tree.
+    Delete { node: Node<'tree> },
+    /// A pair of matched leaf nodes whose text differs.
+    Update { old: Node<'tree>, new: Node<'tree> },
+    /// A pair of matched nodes that have a different parent in the new tree, or whose
+    /// position has changed relative to their matched siblings. The node was moved to
+    /// be the `index`th child of `parent`.
+    Move {
+        old: Node<'tree>,
+        new: Node<'tree>,
+        parent: Node<'tree>,
+        index: usize,
+    },
+}
+
+/// The result of comparing two syntax trees with [diff].
+#[derive(Debug)]
+pub struct Diff<'tree> {
+    /// Pairs of corresponding nodes from the old and new trees, in the order that
+    /// the old nodes occur in the old tree.
+    pub matches: Vec<(Node<'tree>, Node<'tree>)>,
+
+    /// The operations that transform the old tree into the new tree. Insertions,
+    /// moves and updates are listed in the order of the new tree, followed by
+    /// deletions in post-order of the old tree.
+    pub edits: Vec<Edit<'tree>>,
+}
+
+/// Compare two syntax trees of the same language, along with their source code.
+///
+/// If `new_tree` was produced by incrementally reparsing `old_tree`, any subtrees that
+/// were reused by the parser are matched directly, without needing to compare their
+/// contents. The root nodes are always matched with each other.
+pub fn diff<'tree>(
+    old_tree: &'tree Tree,
+    old_source: &[u8],
+    new_tree: &'tree Tree,
+    new_source: &[u8],
+) -> Diff<'tree> {
+    let old = FlatTree::new(old_tree, old_source);
+    let new = FlatTree::new(new_tree, new_source);
+    let mut mapping = Mapping::new(old.len(), new.len());
+
+    mapping.insert(0, 0);
+    match_reused_subtrees(&old, &new, &mut mapping);
+    match_isomorphic_subtrees(&old, &new, &mut mapping);
+    match_containers(&old, &new, &mut mapping);
+
+    let edits = generate_edits(&old, &new, &mapping);
+    let matches = (0..old.len())
+        .filter_map(|i| mapping.old_to_new[i].map(|j| (old.nodes[i], new.nodes[j])))
+        .collect();
+    Diff { matches, edits }
+}
+
+/// A syntax tree's nodes in pre-order, along with the summary information needed to
+/// compare them.
+struct FlatTree<'tree, 'a> {
+    source: &'a [u8],
+    nodes: Vec<Node<'tree>>,
+    parents: Vec<Option<usize>>,
+    children: Vec<Vec<usize>>,
+    child_indices: Vec<usize>,
+    sizes: Vec<usize>,
+    heights: Vec<usize>,
+    hashes: Vec<u64>,
+}
+
+impl<'tree, 'a> FlatTree<'tree, 'a> {
+    fn new(tree: &'tree Tree, source: &'a [u8]) -> Self {
+        let mut result = FlatTree {
+            source,
+            nodes: Vec::new(),
+            parents: Vec::new(),
+            children: Vec::new(),
+            child_indices: Vec::new(),
+            sizes: Vec::new(),
+            heights: Vec::new(),
+            hashes: Vec::new(),
+        };
+
+        let mut cursor = tree.walk();
+        let mut stack: Vec<usize> = Vec::new();
+        loop {
+            let index = result.nodes.len();
+            let parent = stack.last().cloned();
+            result.nodes.push(cursor.node());
+            result.parents.push(parent);
+            result.children.push(Vec::new());
+            if let Some(parent) = parent {
+                result.child_indices.push(result.children[parent].len());
+                result.children[parent].push(index);
+            } else {
+                result.child_indices.push(0);
+            }
+
+            if cursor.goto_first_child() {
+                stack.push(index);
+                continue;
+            }
+            while !cursor.goto_next_sibling() {
+                if !cursor.goto_parent() {
+                    break;
+                }
+                stack.pop();
+            }
+            if stack.is_empty() {
+                break;
+            }
+        }
+
+        // Children always follow their parent in pre-order, so the summaries can be
+        // computed in a single backwards pass.
+        let len = result.nodes.len();
+        result.sizes = vec![1; len];
+        result.heights = vec![1; len];
+        result.hashes = vec![0; len];
+        for i in (0..len).rev() {
+            let mut hasher = DefaultHasher::new();
+            result.nodes[i].kind_id().hash(&mut hasher);
+            result.label(i).hash(&mut hasher);
+            for &child in &result.children[i] {
+                result.sizes[i] += result.sizes[child];
+                result.heights[i] = result.heights[i].max(result.heights[child] + 1);
+                result.hashes[child].hash(&mut hasher);
+            }
+            result.hashes[i] = hasher.finish();
+        }
+        result
+    }
+
+    fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// The text of a leaf node. Interior nodes are compared based only on their
+    /// structure, so they have no label.
+    fn label(&self, index: usize) -> &'a [u8] {
+        if self.children[index].is_empty() {
+            let range = self.nodes[index].byte_range();
+            self.source.get(range).unwrap_or(&[])
+        } else {
+            &[]
+        }
+    }
+
+    fn is_descendant(&self, index: usize, ancestor: usize) -> bool {
+        index >= ancestor && index < ancestor + self.sizes[ancestor]
+    }
+}
+
+struct Mapping {
+    old_to_new: Vec<Option<usize>>,
+    new_to_old: Vec<Option<usize>>,
+}
+
+impl Mapping {
+    fn new(old_len: usize, new_len: usize) -> Self {
+        Mapping {
+            old_to_new: vec![None; old_len],
+            new_to_old: vec![None; new_len],
+        }
+    }
+
+    fn insert(&mut self, old: usize, new: usize) {
+        self.old_to_new[old] = Some(new);
+        self.new_to_old[new] = Some(old);
+    }
+
+    fn is_free(&self, old: usize, new: usize) -> bool {
+        self.old_to_new[old].is_none() && self.new_to_old[new].is_none()
+    }
+
+    /// Match two isomorphic subtrees, which occupy ranges of the same length in
+    /// their respective pre-order sequences.
+    fn insert_subtree(&mut self, old: &FlatTree, old_root: usize, new_root: usize) {
+        for offset in 0..old.sizes[old_root] {
+            if self.is_free(old_root + offset, new_root + offset) {
+                self.insert(old_root + offset, new_root + offset);
+            }
+        }
+    }
+}
+
+/// Subtrees that were reused during an incremental parse are shared between the two
+/// trees, so their nodes have the same ids.
+fn match_reused_subtrees(old: &FlatTree, new: &FlatTree, mapping: &mut Mapping) {
+    let new_indices_by_id = (0..new.len())
+        .map(|j| (new.nodes[j].id(), j))
+        .collect::<HashMap<_, _>>();
+    let mut i = 1;
+    while i < old.len() {
+        if let Some(&j) = new_indices_by_id.get(&old.nodes[i].id()) {
+            if j > 0 && old.hashes[i] == new.hashes[j] && old.sizes[i] == new.sizes[j] {
+                mapping.insert_subtree(old, i, j);
+                i += old.sizes[i];
+                continue;
+            }
+        }
+        i += 1;
+    }
+}
+
+/// Match the largest identical subtrees first. When a subtree occurs more than once,
+/// prefer candidates whose parents are already matched with each other, and otherwise
+/// pair the occurrences up in document order.
+fn match_isomorphic_subtrees(old: &FlatTree, new: &FlatTree, mapping: &mut Mapping) {
+    let mut new_indices_by_hash = HashMap::<u64, Vec<usize>>::new();
+    for j in 0..new.len() {
+        if new.heights[j] >= MIN_HEIGHT && new.sizes[j] < new.len() {
+            new_indices_by_hash
+                .entry(new.hashes[j])
+                .or_default()
+                .push(j);
+        }
+    }
+
+    let mut old_indices = (1..old.len())
+        .filter(|&i| old.heights[i] >= MIN_HEIGHT)
+        .collect::<Vec<_>>();
+    old_indices.sort_by_key(|&i| (std::cmp::Reverse(old.heights[i]), i));
+
+    for i in old_indices {
+        if mapping.old_to_new[i].is_some() {
+            continue;
+        }
+        let candidates = match new_indices_by_hash.get(&old.hashes[i]) {
+            Some(candidates) => candidates,
+            None => continue,
+        };
+        let parent_match = old.parents[i].and_then(|p| mapping.old_to_new[p]);
+        let free_candidates = candidates
+            .iter()
+            .cloned()
+            .filter(|&j| mapping.new_to_old[j].is_none() && old.sizes[i] == new.sizes[j]);
+        let mut best = None;
+        for j in free_candidates {
+            if parent_match.is_some() && new.parents[j] == parent_match {
+                best = Some(j);
+                break;
+            }
+            if best.is_none() {
+                best = Some(j);
+            }
+        }
+        if let Some(j) = best {
+            mapping.insert_subtree(old, i, j);
+        }
+    }
+}
+
+/// Match interior nodes whose descendants have mostly been matched with each other,
+/// and then match any remaining children of each matched pair.
+fn match_containers(old: &FlatTree, new: &FlatTree, mapping: &mut Mapping) {
+    for i in (1..old.len()).rev() {
+        if mapping.old_to_new[i].is_some() || old.children[i].is_empty() {
+            continue;
+        }
+
+        let mut best = None;
+        let mut best_dice = MIN_DICE;
+        let mut candidates = Vec::new();
+        for d in i + 1..i + old.sizes[i] {
+            let mut ancestor = mapping.old_to_new[d].and_then(|j| new.parents[j]);
+            while let Some(j) = ancestor {
+                if mapping.new_to_old[j].is_none()
+                    && new.nodes[j].kind_id() == old.nodes[i].kind_id()
+                    && !candidates.contains(&j)
+                {
+                    candidates.push(j);
+                }
+                ancestor = new.parents[j];
+            }
+        }
+        for j in candidates {
+            let common = (i + 1..i + old.sizes[i])
+                .filter(|&d| match mapping.old_to_new[d] {
+                    Some(e) => e != j && new.is_descendant(e, j),
+                    None => false,
+                })
+                .count();
+            let dice = 2.0 * common as f64 / ((old.sizes[i] - 1) + (new.sizes[j] - 1)) as f64;
+            if dice > best_dice {
+                best = Some(j);
+                best_dice = dice;
+            }
+        }
+        if let Some(j) = best {
+            mapping.insert(i, j);
+        }
+    }
+
+    // Recover matches among the children of matched nodes, preferring identical
+    // subtrees and then nodes of the same kind, in document order.
+    for i in 0..old.len() {
+        let j = match mapping.old_to_new[i] {
+            Some(j) => j,
+            None => continue,
+        };
+        for same_content in &[true, false] {
+            let mut next_new_child = 0;
+            for &old_child in &old.children[i] {
+                if mapping.old_to_new[old_child].is_some() {
+                    continue;
+                }
+                let new_children = &new.children[j];
+                let found = (next_new_child..new_children.len()).find(|&k| {
+                    let new_child = new_children[k];
+                    mapping.new_to_old[new_child].is_none()
+                        && old.nodes[old_child].kind_id() == new.nodes[new_child].kind_id()
+                        && (!same_content || old.hashes[old_child] == new.hashes[new_child])
+                });
+                if let Some(k) = found {
+                    let new_child = new_children[k];
+                    if *same_content {
+                        mapping.insert_subtree(old, old_child, new_child);
+                    } else {
+                        mapping.insert(old_child, new_child);
+                    }
+                    next_new_child = k + 1;
+                }
+            }
+        }
+    }
+}
+
+fn generate_edits<'tree>(
+    old: &FlatTree<'tree, '_>,
+    new: &FlatTree<'tree, '_>,
+    mapping: &Mapping,
+) -> Vec<Edit<'tree>> {
+    let mut result = Vec::new();
+
+    // Within each pair of matched parents, the children that stay in place are those
+    // in the longest sequence whose order is the same in both trees.
+    let mut in_order = vec![true; new.len()];
+    for j in 0..new.len() {
+        if let Some(i) = mapping.new_to_old[j] {
+            let aligned_children = new.children[j]
+                .iter()
+                .filter_map(|&c| {
+                    let old_child = mapping.new_to_old[c]?;
+                    if old.parents[old_child] == Some(i) {
+                        Some((c, old.child_indices[old_child]))
+                    } else {
+                        None
+                    }
+                })
+                .collect::<Vec<_>>();
+            let keys = aligned_children.iter().map(|c| c.1).collect::<Vec<_>>();
+            for (c, _) in &aligned_children {
+                in_order[*c] = false;
+            }
+            for k in longest_increasing_subsequence(&keys) {
+                in_order[aligned_children[k].0] = true;
+            }
+        }
+    }
+
+    for (j, &node) in new.nodes.iter().enumerate() {
+        match mapping.new_to_old[j] {
+            None => {
+                if let Some(parent) = new.parents[j] {
+                    result.push(Edit::Insert {
+                        node,
+                        parent: new.nodes[parent],
+                        index: new.child_indices[j],
+                    });
+                }
+            }
+            Some(i) => {
+                if let Some(parent) = new.parents[j] {
+                    let parent_moved = old.parents[i] != mapping.new_to_old[parent];
+                    if parent_moved || !in_order[j] {
+                        result.push(Edit::Move {
+                            old: old.nodes[i],
+                            new: node,
+                            parent: new.nodes[parent],
+                            index: new.child_indices[j],
+                        });
+                    }
+                }
+                if old.children[i].is_empty()
+                    && new.children[j].is_empty()
+                    && old.label(i) != new.label(j)
+                {
+                    result.push(Edit::Update {
+                        old: old.nodes[i],
+                        new: node,
+                    });
+                }
+            }
+        }
+    }
+
+    // Deletions are listed children-first, so that each node is empty when it
+    // is deleted.
+    let mut deletions = (0..old.len())
+        .filter(|&i| mapping.old_to_new[i].is_none())
+        .collect::<Vec<_>>();
+    deletions.sort_by_key(|&i| (i + old.sizes[i], std::cmp::Reverse(i)));
+    result.extend(
+        deletions
+            .into_iter()
+            .map(|i| Edit::Delete { node: old.nodes[i] }),
+    );
+    result
+}
+
+/// Return the positions of the elements that form the longest strictly increasing
+/// subsequence of `keys`.
+fn longest_increasing_subsequence(keys: &[usize]) -> Vec<usize> {
+    let mut tails: Vec<usize> = Vec::new();
+    let mut predecessors = vec![None; keys.len()];
+    for (i, key) in keys.iter().enumerate() {
+        let position = tails.partition_point(|&t| keys[t] < *key);
+        if position > 0 {
+            predecessors[i] = Some(tails[position - 1]);
+        }
+        if position == tails.len() {
+            tails.push(i);
+        } else {
+            tails[position] = i;
+        }
+    }
+
+    let mut result = Vec::with_capacity(tails.len());
+    let mut current = tails.last().cloned();
+    while let Some(i) = current {
+        result.push(i);
+        current = predecessors[i];
+    }
+    result.reverse();
+    result
+}
@@ -4,10 +4,17 @@ mod util;
 #[cfg(feature = "allocation-tracking")]
 pub mod allocations;
 
+pub mod diagnostics;
+pub mod diff;
+pub mod query_ast;
+pub mod query_validation;
+
 #[cfg(unix)]
 use std::os::unix::io::AsRawFd;
 
+use std::collections::HashMap;
 use std::ffi::CStr;
+use std::io::{self, Read, Seek, SeekFrom};
 use std::marker::PhantomData;
 use std::mem::MaybeUninit;
 use std::os::raw::{c_char, c_void};
@@ -37,6 +44,10 @@ pub const PARSER_HEADER: &'static str = include_str!("../include/tree_sitter/par
 pub struct Language(*const ffi::TSLanguage);
 
 /// A tree that represents the syntactic structure of a source code file.
+///
+/// A `Tree` can be shared between threads and read concurrently, for example by wrapping
+/// it in an `Arc` and running a separate [QueryCursor] on each thread. Cloning a tree is
+/// cheap, because the clone shares its nodes with the original tree.
 pub struct Tree(NonNull<ffi::TSTree>);
 
 /// A position in a multi-line text document, in terms of rows and columns.
@@ -74,6 +85,37 @@ pub struct InputEdit {
 #[repr(transparent)]
 pub struct Node<'a>(ffi::TSNode, PhantomData<&'a ()>);
 
+/// A syntax node that holds a shared reference to its `Tree`, rather than borrowing it.
+///
+/// This makes it possible to store nodes in long-lived data structures, or to move them
+/// into closures and tasks that outlive the current scope.
+#[derive(Clone)]
+pub struct OwnedNode {
+    tree: Arc<Tree>,
+    node: ffi::TSNode,
+}
+
+/// A description of a node's position in a syntax tree, in terms of the path from the
+/// root node, which can be used to find the corresponding node in a different tree.
+///
+/// Unlike a [Node], a locator can be used after the tree has been edited and reparsed.
+/// Call [NodeLocator::edit] for each edit, so that the locator can follow the node when
+/// preceding siblings are added or removed.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct NodeLocator {
+    root_kind_id: u16,
+    steps: Vec<NodeLocatorStep>,
+    start_byte: usize,
+    end_byte: usize,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+struct NodeLocatorStep {
+    kind_id: u16,
+    field_id: Option<u16>,
+    index: usize,
+}
+
 /// A stateful object that this is used to produce a `Tree` based on some source code.
 pub struct Parser(NonNull<ffi::TSParser>, Option<Arc<AtomicUsize>>);
 
@@ -87,23 +129,101 @@ pub enum LogType {
 /// A callback that receives log messages during parser.
 type Logger<'a> = Box<dyn FnMut(LogType, &str) + 'a>;
 
+/// A callback that receives the parser's current byte offset during parsing,
+/// and returns `true` to pause the parse.
+type ProgressCallback<'a> = Box<dyn FnMut(usize) -> bool + 'a>;
+
+/// The reason that a call to [Parser::parse] halted before finishing.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ParseHaltReason {
+    /// The timeout set with [Parser::set_timeout_micros] expired.
+    Timeout,
+    /// The cancellation flag set with [Parser::set_cancellation_flag] was flipped.
+    Cancelled,
+    /// The callback set with [Parser::set_progress_callback] requested a pause.
+    Paused,
+}
+
 /// A stateful object for walking a syntax `Tree` efficiently.
 pub struct TreeCursor<'a>(ffi::TSTreeCursor, PhantomData<&'a ()>);
 
+/// A stateful object for iterating over the symbols that are valid in a
+/// particular parse state.
+pub struct LookaheadIterator(NonNull<ffi::TSLookaheadIterator>);
+
+/// An iterator over the names of the symbols produced by a [LookaheadIterator].
+pub struct LookaheadNamesIterator<'a>(&'a mut LookaheadIterator);
+
 /// A set of patterns that match nodes in a syntax tree.
+///
+/// The text predicates `#eq?`, `#not-eq?`, `#match?`, `#not-match?`, `#any-of?` and
+/// `#not-any-of?` must hold for every node of a capture, while their `#any-` variants
+/// must hold for at least one node. So if an optional or quantified capture did not
+/// capture any nodes in a match, comparing it with a string or a regex succeeds for the
+/// former predicates, and fails for the `#any-` predicates. Comparing two captures with
+/// `#eq?` or `#not-eq?` also compares their number of nodes.
 #[derive(Debug)]
 pub struct Query {
     ptr: NonNull<ffi::TSQuery>,
     capture_names: Vec<String>,
+    capture_quantifiers: Vec<Box<[CaptureQuantifier]>>,
     text_predicates: Vec<Box<[TextPredicate]>>,
     property_settings: Vec<Box<[QueryProperty]>>,
     property_predicates: Vec<Box<[(QueryProperty, bool)]>>,
     general_predicates: Vec<Box<[QueryPredicate]>>,
+    custom_text_predicates: HashMap<Box<str>, TextPredicateEvaluator>,
+}
+
+/// The number of nodes that a capture can match within a single match of a
+/// pattern, as determined by the quantifiers (`?`, `*`, `+`) in the pattern.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum CaptureQuantifier {
+    Zero,
+    ZeroOrOne,
+    ZeroOrMore,
+    One,
+    OneOrMore,
+}
+
+impl From<ffi::TSQuantifier> for CaptureQuantifier {
+    fn from(value: ffi::TSQuantifier) -> Self {
+        match value {
+            ffi::TSQuantifier_TSQuantifierZero => CaptureQuantifier::Zero,
+            ffi::TSQuantifier_TSQuantifierZeroOrOne => CaptureQuantifier::ZeroOrOne,
+            ffi::TSQuantifier_TSQuantifierZeroOrMore => CaptureQuantifier::ZeroOrMore,
+            ffi::TSQuantifier_TSQuantifierOne => CaptureQuantifier::One,
+            ffi::TSQuantifier_TSQuantifierOneOrMore => CaptureQuantifier::OneOrMore,
+            _ => panic!("Unrecognized quantifier: {}", value),
+        }
+    }
 }
 
 /// A stateful object for executing a `Query` on a syntax `Tree`.
 pub struct QueryCursor(NonNull<ffi::TSQueryCursor>);
 
+/// Statistics about the work done by a [QueryCursor] during its last execution.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct QueryCursorStats {
+    /// The number of syntax nodes that the cursor processed.
+    pub nodes_visited: usize,
+    /// The statistics for each pattern in the query, indexed by pattern index.
+    pub patterns: Vec<QueryPatternStats>,
+}
+
+/// Statistics about the work done for a single pattern by a [QueryCursor].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct QueryPatternStats {
+    /// The number of in-progress matches that were started for the pattern,
+    /// including copies made to explore alternative matches.
+    pub states_created: usize,
+    /// The number of complete matches found for the pattern, before the query's
+    /// text predicates were evaluated.
+    pub matches_produced: usize,
+    /// The number of in-progress matches that were discarded because the cursor
+    /// reached its [match limit](QueryCursor::set_match_limit).
+    pub matches_dropped: usize,
+}
+
 /// A key-value pair associated with a particular pattern in a `Query`.
 #[derive(Debug, PartialEq, Eq)]
 pub struct QueryProperty {
@@ -125,6 +245,24 @@ pub struct QueryPredicate {
     pub args: Vec<QueryPredicateArg>,
 }
 
+/// The value of one argument to a custom text predicate, within a particular
+/// `QueryMatch`.
+///
+/// See [Query::register_text_predicate].
+#[derive(Debug)]
+pub enum TextPredicateArg<'a, 'tree> {
+    /// The nodes that were captured with a given capture name, along with their
+    /// text. A capture that is quantified in the pattern may have any number of
+    /// nodes.
+    Capture(Vec<(Node<'tree>, &'a [u8])>),
+    /// A string literal.
+    String(&'a str),
+}
+
+type TextPredicateFn = dyn Fn(&[TextPredicateArg]) -> bool + Send + Sync;
+
+struct TextPredicateEvaluator(Box<TextPredicateFn>);
+
 /// A match of a `Query` to a particular set of `Node`s.
 pub struct QueryMatch<'a> {
     pub pattern_index: usize,
@@ -134,10 +272,41 @@ pub struct QueryMatch<'a> {
 }
 
 /// A sequence of `QueryCapture`s within a `QueryMatch`.
-pub struct QueryCaptures<'a, 'tree: 'a, T: AsRef<[u8]>> {
+///
+/// The text of captured nodes is provided in chunks of type `T` by the text provider `P`.
+pub struct QueryCaptures<'a, 'tree: 'a, T: AsRef<[u8]>, P = Box<dyn FnMut(Node<'tree>) -> T + 'a>> {
     ptr: *mut ffi::TSQueryCursor,
     query: &'a Query,
-    text_callback: Box<dyn FnMut(Node<'tree>) -> T + 'a>,
+    text_provider: P,
+    _chunk: PhantomData<(Node<'tree>, T)>,
+}
+
+/// A source of UTF8 text for the parser, which provides the text in chunks.
+///
+/// Implement this for data structures like ropes, whose text is not stored in a single
+/// contiguous buffer, and pass them to [Parser::parse_input].
+///
+/// The parser reads its input synchronously, so asynchronous sources like `AsyncRead`
+/// are not supported. Their text must be loaded, for example into a rope, before parsing.
+pub trait ParseInput {
+    /// Get a chunk of text starting at the given byte offset and position. The chunk can
+    /// be of any length. If the offset is at the end of the text, return an empty slice.
+    fn read(&mut self, byte_offset: usize, position: Point) -> &[u8];
+}
+
+/// A source of text for the nodes that are captured by a query, which is used to
+/// evaluate the query's text predicates.
+///
+/// This is implemented for byte slices containing the entire source code, and for
+/// closures that return the text of a node. Implement it for data structures like
+/// ropes in order to provide each node's text in several chunks, and pass them to
+/// [QueryCursor::matches_with_text_provider] or [QueryCursor::captures_with_text_provider].
+pub trait TextProvider<'tree> {
+    type Chunk: AsRef<[u8]>;
+    type Chunks: Iterator<Item = Self::Chunk>;
+
+    /// Get the text of the given node, as a sequence of chunks.
+    fn text(&mut self, node: Node<'tree>) -> Self::Chunks;
 }
 
 /// A particular `Node` that has been captured with a particular name within a `Query`.
@@ -158,6 +327,24 @@ pub struct LanguageError {
 #[derive(Debug, PartialEq, Eq)]
 pub struct IncludedRangesError(pub usize);
 
+/// An error that occurred in `Query::register_text_predicate`, because the given
+/// operator is one of the predicates that are evaluated natively.
+#[derive(Debug, PartialEq, Eq)]
+pub struct TextPredicateError(pub String);
+
+/// An error that occurred in `Tree::deserialize`.
+#[derive(Debug, PartialEq, Eq)]
+pub enum TreeDeserializationError {
+    /// The data is not a serialized syntax tree, or it has been corrupted.
+    InvalidData,
+    /// The data was written using an unsupported version of the serialization format.
+    FormatVersion,
+    /// The tree was produced by a different version of the language.
+    LanguageVersion,
+    /// The tree was produced by a different language.
+    Language,
+}
+
 /// An error that occurred when trying to create a `Query`.
 #[derive(Debug, PartialEq, Eq)]
 pub struct QueryError {
@@ -178,11 +365,16 @@ pub enum QueryErrorKind {
     Structure,
 }
 
+/// A predicate that is evaluated natively against the text of the captured nodes.
+///
+/// The boolean fields indicate whether the predicate is positive, and whether *all*
+/// of a quantified capture's nodes must satisfy it, rather than *any* of them.
 #[derive(Debug)]
 enum TextPredicate {
-    CaptureEqString(u32, String, bool),
-    CaptureEqCapture(u32, u32, bool),
-    CaptureMatchString(u32, regex::bytes::Regex, bool),
+    CaptureEqString(u32, String, bool, bool),
+    CaptureEqCapture(u32, u32, bool, bool),
+    CaptureMatchString(u32, regex::bytes::Regex, bool, bool),
+    CaptureAnyString(u32, Box<[String]>, bool),
 }
 
 // TODO: Remove this struct at at some point. If `core::str::lossy::Utf8Lossy`
@@ -269,6 +461,73 @@ impl Language {
             Some(id)
         }
     }
+
+    /// Get the ids of all of the supertype node kinds in this language.
+    ///
+    /// Supertypes are the hidden rules listed in the grammar's `supertypes` array.
+    /// Languages generated with an older ABI version do not record any supertype
+    /// information, and return an empty slice.
+    pub fn supertypes(&self) -> &'static [u16] {
+        let mut length = 0u32;
+        unsafe {
+            let ptr = ffi::ts_language_supertypes(self.0, &mut length as *mut u32);
+            if length > 0 {
+                slice::from_raw_parts(ptr, length as usize)
+            } else {
+                &[]
+            }
+        }
+    }
+
+    /// Get the ids of the node kinds that are direct subtypes of the given
+    /// supertype.
+    ///
+    /// A subtype may itself be a supertype. If the given id is not a supertype,
+    /// this returns an empty slice.
+    pub fn subtypes(&self, supertype: u16) -> &'static [u16] {
+        let mut length = 0u32;
+        unsafe {
+            let ptr = ffi::ts_language_subtypes(self.0, supertype, &mut length as *mut u32);
+            if length > 0 {
+                slice::from_raw_parts(ptr, length as usize)
+            } else {
+                &[]
+            }
+        }
+    }
+
+    /// Get the number of valid parse states in this language.
+    pub fn parse_state_count(&self) -> usize {
+        unsafe { ffi::ts_language_state_count(self.0) as usize }
+    }
+
+    /// Get the parse state that the parser moves to after consuming a node
+    /// with the given grammar symbol id in the given parse state.
+    ///
+    /// Combine this with [LookaheadIterator] to generate completion suggestions
+    /// or to find the symbols that are valid in error nodes. Use
+    /// [Node::grammar_id] rather than [Node::kind_id] to obtain the symbol id.
+    pub fn next_state(&self, state: u16, id: u16) -> u16 {
+        unsafe { ffi::ts_language_next_state(self.0, state, id) }
+    }
+
+    /// Create a new lookahead iterator for this language and parse state.
+    ///
+    /// This returns `None` if the state is invalid for this language.
+    ///
+    /// Iterating the [LookaheadIterator] will yield the ids of all of the symbols
+    /// that are valid in the given parse state. Newly created lookahead iterators
+    /// will return the `ERROR` symbol from [LookaheadIterator::current_symbol].
+    ///
+    /// Lookahead iterators can be useful to generate suggestions and improve
+    /// syntax error diagnostics. To get the symbols that are valid in an `ERROR`
+    /// node, use the lookahead iterator on its first leaf node state. For
+    /// `MISSING` nodes, a lookahead iterator created on the previous non-extra
+    /// leaf node may be appropriate.
+    pub fn lookahead_iterator(&self, state: u16) -> Option<LookaheadIterator> {
+        let ptr = unsafe { ffi::ts_lookahead_iterator_new(self.0, state) };
+        NonNull::new(ptr).map(LookaheadIterator)
+    }
 }
 
 impl Parser {
@@ -359,6 +618,61 @@ impl Parser {
         unsafe { ffi::ts_parser_set_logger(self.0.as_ptr(), c_logger) };
     }
 
+    /// Get the parser's current progress callback.
+    pub fn progress_callback(&self) -> Option<&ProgressCallback<'_>> {
+        let callback = unsafe { ffi::ts_parser_progress_callback(self.0.as_ptr()) };
+        unsafe { (callback.payload as *mut ProgressCallback).as_ref() }
+    }
+
+    /// Set the callback that a parser should use to report its progress during
+    /// parsing.
+    ///
+    /// The callback is invoked periodically with the byte offset that the parser
+    /// has reached. If it returns `true`, then parsing will halt early, returning
+    /// `None`, and [halt_reason](Parser::halt_reason) will return
+    /// [ParseHaltReason::Paused]. See [parse](Parser::parse) for how to resume.
+    pub fn set_progress_callback(&mut self, callback: Option<ProgressCallback>) {
+        let prev_callback = unsafe { ffi::ts_parser_progress_callback(self.0.as_ptr()) };
+        if !prev_callback.payload.is_null() {
+            drop(unsafe { Box::from_raw(prev_callback.payload as *mut ProgressCallback) });
+        }
+
+        let c_callback;
+        if let Some(callback) = callback {
+            let container = Box::new(callback);
+
+            unsafe extern "C" fn progress(payload: *mut c_void, byte_offset: u32) -> bool {
+                let callback = (payload as *mut ProgressCallback).as_mut().unwrap();
+                callback(byte_offset as usize)
+            }
+
+            c_callback = ffi::TSParseProgressCallback {
+                payload: Box::into_raw(container) as *mut c_void,
+                progress: Some(progress),
+            };
+        } else {
+            c_callback = ffi::TSParseProgressCallback {
+                payload: ptr::null_mut(),
+                progress: None,
+            };
+        }
+
+        unsafe { ffi::ts_parser_set_progress_callback(self.0.as_ptr(), c_callback) };
+    }
+
+    /// Get the reason that the most recent parse halted before finishing.
+    ///
+    /// Returns `None` if the most recent parse completed, or if the parser has
+    /// been [reset](Parser::reset) since then.
+    pub fn halt_reason(&self) -> Option<ParseHaltReason> {
+        match unsafe { ffi::ts_parser_halt_reason(self.0.as_ptr()) } {
+            ffi::TSParseHaltReason_TSParseHaltReasonTimeout => Some(ParseHaltReason::Timeout),
+            ffi::TSParseHaltReason_TSParseHaltReasonCancelled => Some(ParseHaltReason::Cancelled),
+            ffi::TSParseHaltReason_TSParseHaltReasonPaused => Some(ParseHaltReason::Paused),
+            _ => None,
+        }
+    }
+
     /// Set the destination to which the parser should write debugging graphs
     /// during parsing. The graphs are formatted in the DOT language. You may want
     /// to pipe these graphs directly to a `dot(1)` process in order to generate
@@ -387,13 +701,14 @@ impl Parser {
     ///  * The parser has not yet had a language assigned with [Parser::set_language]
     ///  * The timeout set with [Parser::set_timeout_micros] expired
     ///  * The cancellation flag set with [Parser::set_cancellation_flag] was flipped
+    ///  * The callback set with [Parser::set_progress_callback] requested a pause
+    ///
+    /// In the last three cases, [Parser::halt_reason] reports why parsing halted,
+    /// and the parser keeps its partial state. Calling `parse` again with the same
+    /// text resumes where it left off; `old_tree` is ignored when resuming. Call
+    /// [Parser::reset] to discard the partial state instead.
     pub fn parse(&mut self, text: impl AsRef<[u8]>, old_tree: Option<&Tree>) -> Option<Tree> {
-        let bytes = text.as_ref();
-        let len = bytes.len();
-        self.parse_with(
-            &mut |i, _| if i < len { &bytes[i..] } else { &[] },
-            old_tree,
-        )
+        self.parse_input(&mut text.as_ref(), old_tree)
     }
 
     /// Parse a slice of UTF16 text.
@@ -433,30 +748,60 @@ impl Parser {
         callback: &mut F,
         old_tree: Option<&Tree>,
     ) -> Option<Tree> {
-        // A pointer to this payload is passed on every call to the `read` C function.
-        // The payload contains two things:
-        // 1. A reference to the rust `callback`.
-        // 2. The text that was returned from the previous call to `callback`.
-        //    This allows the callback to return owned values like vectors.
-        let mut payload: (&mut F, Option<T>) = (callback, None);
+        // Store the text that was returned from the previous call to `callback`.
+        // This allows the callback to return owned values like vectors.
+        struct CallbackInput<'a, T, F> {
+            callback: &'a mut F,
+            text: Option<T>,
+        }
 
-        // This C function is passed to Tree-sitter as the input callback.
-        unsafe extern "C" fn read<'a, T: AsRef<[u8]>, F: FnMut(usize, Point) -> T>(
+        impl<'a, T: AsRef<[u8]>, F: FnMut(usize, Point) -> T> ParseInput for CallbackInput<'a, T, F> {
+            fn read(&mut self, byte_offset: usize, position: Point) -> &[u8] {
+                self.text = Some((self.callback)(byte_offset, position));
+                self.text.as_ref().unwrap().as_ref()
+            }
+        }
+
+        self.parse_input(
+            &mut CallbackInput {
+                callback,
+                text: None,
+            },
+            old_tree,
+        )
+    }
+
+    /// Parse UTF8 text provided in chunks by a [ParseInput].
+    ///
+    /// # Arguments:
+    /// * `input` The source of the text, such as a rope.
+    /// * `old_tree` A previous syntax tree parsed from the same document.
+    ///   If the text of the document has changed since `old_tree` was
+    ///   created, then you must edit `old_tree` to match the new text using
+    ///   [Tree::edit].
+    pub fn parse_input<I: ParseInput>(
+        &mut self,
+        input: &mut I,
+        old_tree: Option<&Tree>,
+    ) -> Option<Tree> {
+        // This C function is passed to Tree-sitter as the input callback. A pointer
+        // to the input is passed on every call. The returned chunk borrows the input,
+        // and Tree-sitter only uses it until the next call.
+        unsafe extern "C" fn read<I: ParseInput>(
             payload: *mut c_void,
             byte_offset: u32,
             position: ffi::TSPoint,
             bytes_read: *mut u32,
         ) -> *const c_char {
-            let (callback, text) = (payload as *mut (&mut F, Option<T>)).as_mut().unwrap();
-            *text = Some(callback(byte_offset as usize, position.into()));
-            let slice = text.as_ref().unwrap().as_ref();
+            let input = (payload as *mut I).as_mut().unwrap();
+            let slice = input.read(byte_offset as usize, position.into());
             *bytes_read = slice.len() as u32;
-            return slice.as_ptr() as *const c_char;
+            slice.as_ptr() as *const c_char
         }
 
         let c_input = ffi::TSInput {
-            payload: &mut payload as *mut (&mut F, Option<T>) as *mut c_void,
-            read: Some(read::<T, F>),
+            payload: input as *mut I as *mut c_void,
+            read: Some(read::<I>),
             encoding: ffi::TSInputEncoding_TSInputEncodingUTF8,
         };
 
@@ -467,6 +812,40 @@ impl Parser {
         }
     }
 
+    /// Parse UTF8 text from a reader, such as a file.
+    ///
+    /// The text is read in chunks as the parser needs it, seeking within the reader when
+    /// the parser needs to revisit earlier text, so the whole document is never loaded
+    /// into memory at once.
+    ///
+    /// # Arguments:
+    /// * `reader` The source of the text.
+    /// * `old_tree` A previous syntax tree parsed from the same document.
+    ///   If the text of the document has changed since `old_tree` was
+    ///   created, then you must edit `old_tree` to match the new text using
+    ///   [Tree::edit].
+    ///
+    /// Returns an error if reading from the reader failed. Otherwise, returns the same
+    /// result as [Parser::parse].
+    pub fn parse_reader<R: Read + Seek>(
+        &mut self,
+        reader: R,
+        old_tree: Option<&Tree>,
+    ) -> io::Result<Option<Tree>> {
+        let mut input = ReaderInput {
+            reader,
+            buffer: Vec::new(),
+            buffer_offset: 0,
+            reader_offset: None,
+            error: None,
+        };
+        let tree = self.parse_input(&mut input, old_tree);
+        match input.error {
+            Some(error) => Err(error),
+            None => Ok(tree),
+        }
+    }
+
     /// Parse UTF16 text provided in chunks by a callback.
     ///
     /// # Arguments:
@@ -676,10 +1055,104 @@ impl Parser {
     }
 }
 
+/// The number of bytes that [Parser::parse_reader] reads at a time.
+const READER_CHUNK_SIZE: usize = 64 * 1024;
+
+const NATIVE_PREDICATE_OPERATORS: &[&str] = &[
+    "eq?",
+    "not-eq?",
+    "any-eq?",
+    "any-not-eq?",
+    "match?",
+    "not-match?",
+    "any-match?",
+    "any-not-match?",
+    "any-of?",
+    "not-any-of?",
+    "is?",
+    "is-not?",
+    "set!",
+];
+
+/// A [ParseInput] that reads text from an `io::Read` in chunks.
+struct ReaderInput<R> {
+    reader: R,
+    buffer: Vec<u8>,
+    buffer_offset: usize,
+    reader_offset: Option<usize>,
+    error: Option<io::Error>,
+}
+
+impl<R: Read + Seek> ReaderInput<R> {
+    fn fill_buffer(&mut self, byte_offset: usize) -> io::Result<()> {
+        if self.reader_offset != Some(byte_offset) {
+            self.reader.seek(SeekFrom::Start(byte_offset as u64))?;
+        }
+        self.buffer.resize(READER_CHUNK_SIZE, 0);
+        let length = loop {
+            match self.reader.read(&mut self.buffer) {
+                Ok(length) => break length,
+                Err(error) if error.kind() == io::ErrorKind::Interrupted => continue,
+                Err(error) => return Err(error),
+            }
+        };
+        self.buffer.truncate(length);
+        self.buffer_offset = byte_offset;
+        self.reader_offset = Some(byte_offset + length);
+        Ok(())
+    }
+}
+
+impl<R: Read + Seek> ParseInput for ReaderInput<R> {
+    fn read(&mut self, byte_offset: usize, _: Point) -> &[u8] {
+        if self.error.is_some() {
+            return &[];
+        }
+        let buffer_end = self.buffer_offset + self.buffer.len();
+        if byte_offset < self.buffer_offset || byte_offset >= buffer_end {
+            if let Err(error) = self.fill_buffer(byte_offset) {
+                self.reader_offset = None;
+                self.error = Some(error);
+                return &[];
+            }
+        }
+        &self.buffer[(byte_offset - self.buffer_offset)..]
+    }
+}
+
+impl ParseInput for &[u8] {
+    fn read(&mut self, byte_offset: usize, _: Point) -> &[u8] {
+        self.get(byte_offset..).unwrap_or(&[])
+    }
+}
+
+impl<'a, 'tree> TextProvider<'tree> for &'a [u8] {
+    type Chunk = &'a [u8];
+    type Chunks = iter::Once<&'a [u8]>;
+
+    fn text(&mut self, node: Node<'tree>) -> Self::Chunks {
+        iter::once(&self[node.byte_range()])
+    }
+}
+
+impl<'tree, F, T> TextProvider<'tree> for F
+where
+    F: FnMut(Node<'tree>) -> T,
+    T: AsRef<[u8]>,
+{
+    type Chunk = T;
+    type Chunks = iter::Once<T>;
+
+    fn text(&mut self, node: Node<'tree>) -> Self::Chunks {
+        iter::once(self(node))
+    }
+}
+
 impl Drop for Parser {
     fn drop(&mut self) {
         self.stop_printing_dot_graphs();
         self.set_logger(None);
+        self.set_progress_callback(None);
         unsafe { ffi::ts_parser_delete(self.0.as_ptr()) }
     }
 }
@@ -728,6 +1201,53 @@ impl Tree {
             util::CBufferIter::new(ptr, count).map(|r| r.into())
         }
     }
+
+    /// Serialize this syntax tree into a compact binary format.
+    ///
+    /// The tree can be restored later using [Tree::deserialize], as long as the same
+    /// version of the same language is used.
+    pub fn serialize(&self) -> Vec<u8> {
+        let mut length = 0u32;
+        unsafe {
+            let ptr = ffi::ts_tree_serialize(self.0.as_ptr(), &mut length);
+            let result = slice::from_raw_parts(ptr as *const u8, length as usize).to_vec();
+            util::free_ptr(ptr as *mut c_void);
+            result
+        }
+    }
+
+    /// Restore a syntax tree that was serialized with [Tree::serialize].
+    ///
+    /// The restored tree can be passed to [Parser::parse] as the `old_tree`, in order
+    /// to reparse an edited document incrementally.
+    pub fn deserialize(language: Language, bytes: &[u8]) -> Result<Tree, TreeDeserializationError> {
+        // Serialized trees are never larger than 4GiB, because their length is stored
+        // as a `u32`.
+        if bytes.len() > u32::MAX as usize {
+            return Err(TreeDeserializationError::InvalidData);
+        }
+        let mut error = ffi::TSTreeDeserializationError_TSTreeDeserializationErrorNone;
+        let ptr = unsafe {
+            ffi::ts_tree_deserialize(
+                language.0,
+                bytes.as_ptr() as *const c_char,
+                bytes.len() as u32,
+                &mut error,
+            )
+        };
+        NonNull::new(ptr).map(Tree).ok_or(match error {
+            ffi::TSTreeDeserializationError_TSTreeDeserializationErrorFormatVersion => {
+                TreeDeserializationError::FormatVersion
+            }
+            ffi::TSTreeDeserializationError_TSTreeDeserializationErrorLanguageVersion => {
+                TreeDeserializationError::LanguageVersion
+            }
+            ffi::TSTreeDeserializationError_TSTreeDeserializationErrorLanguage => {
+                TreeDeserializationError::Language
+            }
+            _ => TreeDeserializationError::InvalidData,
+        })
+    }
 }
 
 impl fmt::Debug for Tree {
@@ -779,11 +1299,49 @@ impl<'tree> Node<'tree> {
             .unwrap()
     }
 
+    /// Get this node's symbol id as it appears in the grammar, ignoring aliases.
+    ///
+    /// This should be used in [Language::next_state] instead of [Node::kind_id].
+    pub fn grammar_id(&self) -> u16 {
+        unsafe { ffi::ts_node_grammar_symbol(self.0) }
+    }
+
+    /// Get this node's type as it appears in the grammar, ignoring aliases.
+    pub fn grammar_name(&self) -> &'static str {
+        unsafe { CStr::from_ptr(ffi::ts_node_grammar_type(self.0)) }
+            .to_str()
+            .unwrap()
+    }
+
     /// Get the [Language] that was used to parse this node's syntax tree.
     pub fn language(&self) -> Language {
         Language(unsafe { ffi::ts_tree_language(self.0.tree) })
     }
 
+    /// Check if this node's kind is a subtype of the given supertype, either
+    /// directly or through other intermediate supertypes.
+    ///
+    /// See also [Language::supertypes] and [Language::subtypes].
+    pub fn is_subtype_of(&self, supertype: u16) -> bool {
+        let language = self.language();
+        let kind_id = self.kind_id();
+        let mut supertypes_to_visit = vec![supertype];
+        let mut visited_supertypes = Vec::new();
+        while let Some(supertype) = supertypes_to_visit.pop() {
+            if visited_supertypes.contains(&supertype) {
+                continue;
+            }
+            visited_supertypes.push(supertype);
+            for subtype in language.subtypes(supertype) {
+                if *subtype == kind_id {
+                    return true;
+                }
+                supertypes_to_visit.push(*subtype);
+            }
+        }
+        false
+    }
+
     /// Check if this node is *named*.
     ///
     /// Named nodes correspond to named rules in the grammar, whereas *anonymous* nodes
@@ -827,6 +1385,22 @@ impl<'tree> Node<'tree> {
         unsafe { ffi::ts_node_is_missing(self.0) }
     }
 
+    /// Get the parse state in which this node was created.
+    ///
+    /// Pass this to [Language::lookahead_iterator] to find the symbols that were
+    /// valid at the start of this node.
+    pub fn parse_state(&self) -> u16 {
+        unsafe { ffi::ts_node_parse_state(self.0) }
+    }
+
+    /// Get the parse state that the parser moved to after this node.
+    ///
+    /// Pass this to [Language::lookahead_iterator] to find the symbols that are
+    /// valid immediately after this node.
+    pub fn next_parse_state(&self) -> u16 {
+        unsafe { ffi::ts_node_next_parse_state(self.0) }
+    }
+
     /// Get the byte offsets where this node starts.
     pub fn start_byte(&self) -> usize {
         unsafe { ffi::ts_node_start_byte(self.0) as usize }
@@ -1092,6 +1666,185 @@ impl<'tree> Node<'tree> {
     }
 }
 
+impl OwnedNode {
+    /// Create an owned handle to the given node, which must belong to the given tree,
+    /// or to an unedited copy of it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the node belongs to a different tree. Use [OwnedNode::try_new] to
+    /// handle that case instead.
+    pub fn new(tree: Arc<Tree>, node: Node) -> Self {
+        Self::try_new(tree, node).expect("Node does not belong to the given tree")
+    }
+
+    /// Create an owned handle to the given node, or return `None` if the node doesn't
+    /// belong to the given tree, or to an unedited copy of it.
+    pub fn try_new(tree: Arc<Tree>, node: Node) -> Option<Self> {
+        let mut node = node.0;
+        let tree_ptr = tree.0.as_ptr() as *const ffi::TSTree;
+        if node.tree != tree_ptr {
+            // A copy made with `Tree::clone` shares its root subtree with the original
+            // tree. The root node's id points to that 8-byte subtree within each `TSTree`.
+            let root =
+                |tree| unsafe { (ffi::ts_tree_root_node(tree).id as *const u64).read_unaligned() };
+            if root(node.tree) != root(tree_ptr) {
+                return None;
+            }
+            node.tree = tree_ptr;
+        }
+        Some(OwnedNode { tree, node })
+    }
+
+    /// Create an owned handle to the root node of the given tree.
+    pub fn root(tree: Arc<Tree>) -> Self {
+        let node = unsafe { ffi::ts_tree_root_node(tree.0.as_ptr()) };
+        OwnedNode { tree, node }
+    }
+
+    /// Get the syntax tree that this node belongs to.
+    pub fn tree(&self) -> &Arc<Tree> {
+        &self.tree
+    }
+
+    /// Get a [Node] that borrows this handle's tree.
+    pub fn node(&self) -> Node<'_> {
+        Node(self.node, PhantomData)
+    }
+
+    /// Get a [NodeLocator] that can find this node again in another tree.
+    ///
+    /// See [NodeLocator::new] for when this returns `None`.
+    pub fn locator(&self) -> Option<NodeLocator> {
+        NodeLocator::new(self.node())
+    }
+}
+
+impl PartialEq for OwnedNode {
+    fn eq(&self, other: &Self) -> bool {
+        self.node() == other.node()
+    }
+}
+
+impl Eq for OwnedNode {}
+
+impl hash::Hash for OwnedNode {
+    fn hash<H: hash::Hasher>(&self, state: &mut H) {
+        self.node().hash(state)
+    }
+}
+
+impl fmt::Debug for OwnedNode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        self.node().fmt(f)
+    }
+}
+
+impl NodeLocator {
+    /// Create a locator describing the path from the root of the node's tree to the node.
+    ///
+    /// Returns `None` if the node or one of its ancestors can't be found among the
+    /// children of its parent, in which case no path to the node can be described.
+    pub fn new(node: Node) -> Option<Self> {
+        let mut steps = Vec::new();
+        let mut current = node;
+        while let Some(parent) = current.parent() {
+            let mut cursor = parent.walk();
+            let mut step = None;
+            let mut counts = HashMap::new();
+            if cursor.goto_first_child() {
+                loop {
+                    let child = cursor.node();
+                    let key = (child.kind_id(), cursor.field_id());
+                    let count = counts.entry(key).or_insert(0);
+                    if child == current {
+                        step = Some(NodeLocatorStep {
+                            kind_id: key.0,
+                            field_id: key.1,
+                            index: *count,
+                        });
+                        break;
+                    }
+                    *count += 1;
+                    if !cursor.goto_next_sibling() {
+                        break;
+                    }
+                }
+            }
+            steps.push(step?);
+            current = parent;
+        }
+        steps.reverse();
+
+        Some(NodeLocator {
+            root_kind_id: current.kind_id(),
+            steps,
+            start_byte: node.start_byte(),
+            end_byte: node.end_byte(),
+        })
+    }
+
+    /// Update this locator to account for an edit to the source code.
+    ///
+    /// Call this in the same way as [Tree::edit], so that the locator's byte range
+    /// continues to match the node's position in the edited document.
+    pub fn edit(&mut self, edit: &InputEdit) {
+        let edit_offset = |byte: usize| {
+            if edit.old_end_byte <= byte {
+                edit.new_end_byte + (byte - edit.old_end_byte)
+            } else if edit.start_byte < byte {
+                edit.new_end_byte
+            } else {
+                byte
+            }
+        };
+        self.start_byte = edit_offset(self.start_byte);
+        self.end_byte = edit_offset(self.end_byte);
+    }
+
+    /// Find the node that this locator describes within the given tree.
+    ///
+    /// At each step of the path, the child with the expected type and field is chosen.
+    /// When there are several such children, the one containing the locator's byte range
+    /// is preferred, followed by the one at the original position among them. Returns
+    /// `None` if the tree no longer contains a matching path.
+    pub fn locate<'tree>(&self, tree: &'tree Tree) -> Option<Node<'tree>> {
+        let mut node = tree.root_node();
+        if node.kind_id() != self.root_kind_id {
+            return None;
+        }
+
+        let mut cursor = node.walk();
+        let mut candidates = Vec::new();
+        for step in &self.steps {
+            candidates.clear();
+            cursor.reset(node);
+            if cursor.goto_first_child() {
+                loop {
+                    let child = cursor.node();
+                    if child.kind_id() == step.kind_id && cursor.field_id() == step.field_id {
+                        candidates.push(child);
+                    }
+                    if !cursor.goto_next_sibling() {
+                        break;
+                    }
+                }
+            }
+
+            let containing = candidates
+                .iter()
+                .enumerate()
+                .filter(|(_, c)| c.start_byte() <= self.start_byte && self.end_byte <= c.end_byte())
+                .min_by_key(|(i, _)| (*i as isize - step.index as isize).abs());
+            node = match containing {
+                Some((_, child)) => *child,
+                None => *candidates.get(step.index)?,
+            };
+        }
+        Some(node)
+    }
+}
+
 impl<'a> PartialEq for Node<'a> {
     fn eq(&self, other: &Self) -> bool {
         self.0.id == other.0.id
@@ -1157,6 +1910,21 @@ impl<'a> TreeCursor<'a> {
         }
     }
 
+    /// Get the depth of the cursor's current node relative to the original
+    /// node that the cursor was constructed with.
+    pub fn depth(&self) -> u32 {
+        unsafe { ffi::ts_tree_cursor_current_depth(&self.0) }
+    }
+
+    /// Get the index of the cursor's current node out of all of the
+    /// descendants of the original node that the cursor was constructed with.
+    ///
+    /// The original node itself has index zero, and the rest of the nodes are
+    /// numbered in pre-order.
+    pub fn descendant_index(&self) -> usize {
+        unsafe { ffi::ts_tree_cursor_current_descendant_index(&self.0) as usize }
+    }
+
     /// Move this cursor to the first child of its current node.
     ///
     /// This returns `true` if the cursor successfully moved, and returns `false`
@@ -1165,6 +1933,18 @@ impl<'a> TreeCursor<'a> {
         return unsafe { ffi::ts_tree_cursor_goto_first_child(&mut self.0) };
     }
 
+    /// Move this cursor to the last child of its current node.
+    ///
+    /// This returns `true` if the cursor successfully moved, and returns `false`
+    /// if there were no children.
+    ///
+    /// Note that this function may be slower than [goto_first_child](TreeCursor::goto_first_child),
+    /// because it needs to iterate through all the children to compute the child's
+    /// position.
+    pub fn goto_last_child(&mut self) -> bool {
+        unsafe { ffi::ts_tree_cursor_goto_last_child(&mut self.0) }
+    }
+
     /// Move this cursor to the parent of its current node.
     ///
     /// This returns `true` if the cursor successfully moved, and returns `false`
@@ -1181,6 +1961,34 @@ impl<'a> TreeCursor<'a> {
         return unsafe { ffi::ts_tree_cursor_goto_next_sibling(&mut self.0) };
     }
 
+    /// Move this cursor to the previous sibling of its current node.
+    ///
+    /// This returns `true` if the cursor successfully moved, and returns `false`
+    /// if there was no previous sibling node.
+    ///
+    /// Note that this function may be slower than [goto_next_sibling](TreeCursor::goto_next_sibling),
+    /// because the position of the previous sibling must be recomputed from the
+    /// start of the parent node.
+    pub fn goto_previous_sibling(&mut self) -> bool {
+        unsafe { ffi::ts_tree_cursor_goto_previous_sibling(&mut self.0) }
+    }
+
+    /// Move this cursor to the node that is the nth descendant of the original
+    /// node that the cursor was constructed with, where zero represents the
+    /// original node itself.
+    ///
+    /// This returns `true` if the cursor successfully moved, and returns `false`
+    /// if the original node has no descendant with the given index, in which case
+    /// the cursor is left where it was.
+    ///
+    /// See also [descendant_index](TreeCursor::descendant_index).
+    pub fn goto_descendant(&mut self, descendant_index: usize) -> bool {
+        if descendant_index > u32::MAX as usize {
+            return false;
+        }
+        unsafe { ffi::ts_tree_cursor_goto_descendant(&mut self.0, descendant_index as u32) }
+    }
+
     /// Move this cursor to the first child of its current node that extends beyond
     /// the given byte offset.
     ///
@@ -1196,6 +2004,21 @@ impl<'a> TreeCursor<'a> {
         }
     }
 
+    /// Move this cursor to the first child of its current node that extends beyond
+    /// the given point.
+    ///
+    /// This returns the index of the child node if one was found, and returns `None`
+    /// if no such child was found.
+    pub fn goto_first_child_for_point(&mut self, point: Point) -> Option<usize> {
+        let result =
+            unsafe { ffi::ts_tree_cursor_goto_first_child_for_point(&mut self.0, point.into()) };
+        if result < 0 {
+            None
+        } else {
+            Some(result as usize)
+        }
+    }
+
     /// Re-initialize this tree cursor to start at a different node.
     pub fn reset(&mut self, node: Node<'a>) {
         unsafe { ffi::ts_tree_cursor_reset(&mut self.0, node.0) };
@@ -1214,6 +2037,107 @@ impl<'a> Drop for TreeCursor<'a> {
     }
 }
 
+impl LookaheadIterator {
+    /// Get the current language of the lookahead iterator.
+    pub fn language(&self) -> Language {
+        Language(unsafe { ffi::ts_lookahead_iterator_language(self.0.as_ptr()) })
+    }
+
+    /// Get the current symbol of the lookahead iterator.
+    pub fn current_symbol(&self) -> u16 {
+        unsafe { ffi::ts_lookahead_iterator_current_symbol(self.0.as_ptr()) }
+    }
+
+    /// Get the current symbol name of the lookahead iterator.
+    pub fn current_symbol_name(&self) -> &'static str {
+        unsafe {
+            CStr::from_ptr(ffi::ts_lookahead_iterator_current_symbol_name(
+                self.0.as_ptr(),
+            ))
+            .to_str()
+            .unwrap()
+        }
+    }
+
+    /// Reset the lookahead iterator to another state.
+    ///
+    /// This returns `true` if the iterator was reset to the given state and `false`
+    /// otherwise.
+    pub fn reset_state(&mut self, state: u16) -> bool {
+        unsafe { ffi::ts_lookahead_iterator_reset_state(self.0.as_ptr(), state) }
+    }
+
+    /// Reset the lookahead iterator to another language and state.
+    ///
+    /// This returns `true` if the iterator was reset to the given language and state
+    /// and `false` otherwise.
+    pub fn reset(&mut self, language: Language, state: u16) -> bool {
+        unsafe { ffi::ts_lookahead_iterator_reset(self.0.as_ptr(), language.0, state) }
+    }
+
+    /// Iterate over the symbol names instead of the symbol ids.
+    pub fn iter_names<'a>(&'a mut self) -> LookaheadNamesIterator<'a> {
+        LookaheadNamesIterator(self)
+    }
+}
+
+impl Iterator for LookaheadIterator {
+    type Item = u16;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if unsafe { ffi::ts_lookahead_iterator_next(self.0.as_ptr()) } {
+            Some(self.current_symbol())
+        } else {
+            None
+        }
+    }
+}
+
+impl<'a> Iterator for LookaheadNamesIterator<'a> {
+    type Item = &'static str;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if unsafe { ffi::ts_lookahead_iterator_next(self.0 .0.as_ptr()) } {
+            Some(self.0.current_symbol_name())
+        } else {
+            None
+        }
+    }
+}
+
+impl Drop for LookaheadIterator {
+    fn drop(&mut self) {
+        unsafe { ffi::ts_lookahead_iterator_delete(self.0.as_ptr()) }
+    }
+}
+
+impl QueryError {
+    /// Make the location of an error in a query that was formed by concatenating
+    /// several sections relative to the section that contains it.
+    ///
+    /// `section_starts` contains the byte offset of each section within `source`,
+    /// in ascending order. Returns the index of the section that contains the error,
+    /// along with the adjusted error.
+    pub fn relative_to_section(mut self, source: &str, section_starts: &[usize]) -> (usize, Self) {
+        let (section_index, section_start) = section_starts
+            .iter()
+            .copied()
+            .enumerate()
+            .rev()
+            .find(|(_, start)| *start <= self.offset)
+            .unwrap_or((0, 0));
+        let prefix = &source.as_bytes()[section_start..self.offset.min(source.len())];
+        let line_start = prefix
+            .iter()
+            .rposition(|c| *c == b'\n')
+            .map_or(0, |i| i + 1);
+        self.offset = prefix.len();
+        self.row = prefix.iter().filter(|c| **c == b'\n').count();
+        self.column = prefix.len() - line_start;
+        (section_index, self)
+    }
+}
+
 impl Query {
     /// Create a new query from a string containing one or more S-expression
     /// patterns.
@@ -1222,13 +2146,32 @@ impl Query {
     /// on syntax nodes parsed with that language. References to Queries can be
     /// shared between multiple threads.
     pub fn new(language: Language, source: &str) -> Result<Self, QueryError> {
+        Self::compile(language, source, false)
+    }
+
+    /// Create a new query like [Query::new], but without failing when some of
+    /// the patterns can never match, given the structure of the language's
+    /// grammar.
+    ///
+    /// Instead, those patterns are disabled, and they can be identified using
+    /// [Query::is_pattern_impossible].
+    pub fn new_lenient(language: Language, source: &str) -> Result<Self, QueryError> {
+        Self::compile(language, source, true)
+    }
+
+    fn compile(language: Language, source: &str, lenient: bool) -> Result<Self, QueryError> {
         let mut error_offset = 0u32;
         let mut error_type: ffi::TSQueryError = 0;
         let bytes = source.as_bytes();
 
         // Compile the query.
         let ptr = unsafe {
-            ffi::ts_query_new(
+            let new = if lenient {
+                ffi::ts_query_new_lenient
+            } else {
+                ffi::ts_query_new
+            };
+            new(
                 language.0,
                 bytes.as_ptr() as *const c_char,
                 bytes.len() as u32,
@@ -1303,10 +2246,12 @@ impl Query {
         let mut result = Query {
             ptr: unsafe { NonNull::new_unchecked(ptr) },
             capture_names: Vec::with_capacity(capture_count as usize),
+            capture_quantifiers: Vec::with_capacity(pattern_count),
             text_predicates: Vec::with_capacity(pattern_count),
             property_predicates: Vec::with_capacity(pattern_count),
             property_settings: Vec::with_capacity(pattern_count),
             general_predicates: Vec::with_capacity(pattern_count),
+            custom_text_predicates: HashMap::new(),
         };
 
         // Build a vector of strings to store the capture names.
@@ -1321,6 +2266,16 @@ impl Query {
             }
         }
 
+        // Build a vector to store the capture quantifiers of each pattern.
+        for i in 0..pattern_count {
+            let quantifiers = (0..capture_count)
+                .map(|j| unsafe {
+                    ffi::ts_query_capture_quantifier_for_id(ptr, i as u32, j).into()
+                })
+                .collect::<Vec<_>>();
+            result.capture_quantifiers.push(quantifiers.into());
+        }
+
         // Build a vector of strings to represent literal values used in predicates.
         let string_values = (0..string_count)
             .map(|i| unsafe {
@@ -1347,12 +2302,10 @@ impl Query {
                 }
             };
 
-            let byte_offset = unsafe { ffi::ts_query_start_byte_for_pattern(ptr, i as u32) };
-            let row = source
-                .char_indices()
-                .take_while(|(i, _)| *i < byte_offset as usize)
-                .filter(|(_, c)| *c == '\n')
-                .count();
+            // Predicate errors are reported at the start of the pattern.
+            let offset = unsafe { ffi::ts_query_start_byte_for_pattern(ptr, i as u32) } as usize;
+            let line_start = source[..offset].rfind('\n').map_or(0, |i| i + 1);
+            let start = Point::new(source[..offset].matches('\n').count(), offset - line_start);
 
             let type_done = ffi::TSQueryPredicateStepType_TSQueryPredicateStepTypeDone;
             let type_capture = ffi::TSQueryPredicateStepType_TSQueryPredicateStepTypeCapture;
@@ -1369,7 +2322,8 @@ impl Query {
 
                 if p[0].type_ != type_string {
                     return Err(predicate_error(
-                        row,
+                        start,
+                        offset,
                         format!(
                             "Expected predicate to start with a function name. Got @{}.",
                             result.capture_names[p[0].value_id as usize],
@@ -1380,72 +2334,128 @@ impl Query {
                 // Build a predicate for each of the known predicate function names.
                 let operator_name = &string_values[p[0].value_id as usize];
                 match operator_name.as_str() {
-                    "eq?" | "not-eq?" => {
+                    "eq?" | "not-eq?" | "any-eq?" | "any-not-eq?" => {
                         if p.len() != 3 {
                             return Err(predicate_error(
-                                row,
+                                start,
+                                offset,
                                 format!(
-                                "Wrong number of arguments to #eq? predicate. Expected 2, got {}.",
+                                "Wrong number of arguments to #{} predicate. Expected 2, got {}.",
+                                operator_name,
                                 p.len() - 1
                             ),
                             ));
                         }
                         if p[1].type_ != type_capture {
-                            return Err(predicate_error(row, format!(
-                                "First argument to #eq? predicate must be a capture name. Got literal \"{}\".",
+                            return Err(predicate_error(start, offset, format!(
+                                "First argument to #{} predicate must be a capture name. Got literal \"{}\".",
+                                operator_name,
                                 string_values[p[1].value_id as usize],
                             )));
                         }
 
-                        let is_positive = operator_name == "eq?";
+                        let is_positive = operator_name == "eq?" || operator_name == "any-eq?";
+                        let match_all = !operator_name.starts_with("any-");
                         text_predicates.push(if p[2].type_ == type_capture {
                             TextPredicate::CaptureEqCapture(
                                 p[1].value_id,
                                 p[2].value_id,
                                 is_positive,
+                                match_all,
                             )
                         } else {
                             TextPredicate::CaptureEqString(
                                 p[1].value_id,
                                 string_values[p[2].value_id as usize].clone(),
                                 is_positive,
+                                match_all,
                             )
                         });
                     }
 
-                    "match?" | "not-match?" => {
+                    "match?" | "not-match?" | "any-match?" | "any-not-match?" => {
                         if p.len() != 3 {
-                            return Err(predicate_error(row, format!(
-                                "Wrong number of arguments to #match? predicate. Expected 2, got {}.",
+                            return Err(predicate_error(
+                                start,
+                                offset,
+                                format!(
+                                "Wrong number of arguments to #{} predicate. Expected 2, got {}.",
+                                operator_name,
                                 p.len() - 1
-                            )));
+                            ),
+                            ));
                         }
                         if p[1].type_ != type_capture {
-                            return Err(predicate_error(row, format!(
-                                "First argument to #match? predicate must be a capture name. Got literal \"{}\".",
+                            return Err(predicate_error(start, offset, format!(
+                                "First argument to #{} predicate must be a capture name. Got literal \"{}\".",
+                                operator_name,
                                 string_values[p[1].value_id as usize],
                             )));
                         }
                         if p[2].type_ == type_capture {
-                            return Err(predicate_error(row, format!(
-                                "Second argument to #match? predicate must be a literal. Got capture @{}.",
+                            return Err(predicate_error(start, offset, format!(
+                                "Second argument to #{} predicate must be a literal. Got capture @{}.",
+                                operator_name,
                                 result.capture_names[p[2].value_id as usize],
                             )));
                         }
 
-                        let is_positive = operator_name == "match?";
+                        let is_positive =
+                            operator_name == "match?" || operator_name == "any-match?";
+                        let match_all = !operator_name.starts_with("any-");
                         let regex = &string_values[p[2].value_id as usize];
                         text_predicates.push(TextPredicate::CaptureMatchString(
                             p[1].value_id,
                             regex::bytes::Regex::new(regex).map_err(|_| {
-                                predicate_error(row, format!("Invalid regex '{}'", regex))
+                                predicate_error(start, offset, format!("Invalid regex '{}'", regex))
                             })?,
                             is_positive,
+                            match_all,
+                        ));
+                    }
+
+                    "any-of?" | "not-any-of?" => {
+                        if p.len() < 2 {
+                            return Err(predicate_error(start, offset, format!(
+                                "Wrong number of arguments to #{} predicate. Expected at least 1, got {}.",
+                                operator_name,
+                                p.len() - 1
+                            )));
+                        }
+                        if p[1].type_ != type_capture {
+                            return Err(predicate_error(start, offset, format!(
+                                "First argument to #{} predicate must be a capture name. Got literal \"{}\".",
+                                operator_name,
+                                string_values[p[1].value_id as usize],
+                            )));
+                        }
+
+                        let mut values = Vec::with_capacity(p.len() - 2);
+                        for arg in &p[2..] {
+                            if arg.type_ == type_capture {
+                                return Err(predicate_error(
+                                    start,
+                                    offset,
+                                    format!(
+                                    "Arguments to #{} predicate must be literals. Got capture @{}.",
+                                    operator_name,
+                                    result.capture_names[arg.value_id as usize],
+                                ),
+                                ));
+                            }
+                            values.push(string_values[arg.value_id as usize].clone());
+                        }
+
+                        text_predicates.push(TextPredicate::CaptureAnyString(
+                            p[1].value_id,
+                            values.into_boxed_slice(),
+                            operator_name == "any-of?",
                         ));
                     }
 
                     "set!" => property_settings.push(Self::parse_property(
-                        row,
+                        start,
+                        offset,
                         &operator_name,
                         &result.capture_names,
                         &string_values,
@@ -1454,7 +2464,8 @@ impl Query {
 
                     "is?" | "is-not?" => property_predicates.push((
                         Self::parse_property(
-                            row,
+                            start,
+                            offset,
                             &operator_name,
                             &result.capture_names,
                             &string_values,
@@ -1499,13 +2510,7 @@ impl Query {
 
     /// Get the byte offset where the given pattern starts in the query's source.
     pub fn start_byte_for_pattern(&self, pattern_index: usize) -> usize {
-        if pattern_index >= self.text_predicates.len() {
-            panic!(
-                "Pattern index is {} but the pattern count is {}",
-                pattern_index,
-                self.text_predicates.len(),
-            );
-        }
+        self.assert_pattern_index(pattern_index);
         unsafe {
             ffi::ts_query_start_byte_for_pattern(self.ptr.as_ptr(), pattern_index as u32) as usize
         }
@@ -1521,6 +2526,15 @@ impl Query {
         &self.capture_names
     }
 
+    /// Get the quantifiers of the captures used in the given pattern index.
+    ///
+    /// The returned slice is indexed by capture id, like [capture_names](Query::capture_names).
+    /// Captures that don't appear in the pattern have the quantifier
+    /// [CaptureQuantifier::Zero].
+    pub fn capture_quantifiers(&self, index: usize) -> &[CaptureQuantifier] {
+        &self.capture_quantifiers[index]
+    }
+
     /// Get the properties that are checked for the given pattern index.
     ///
     /// This includes predicates with the operators `is?` and `is-not?`.
@@ -1538,14 +2552,42 @@ impl Query {
     /// Get the other user-defined predicates associated with the given index.
     ///
     /// This includes predicate with operators other than:
-    /// * `match?`
-    /// * `eq?` and `not-eq?`
+    /// * `match?`, `not-match?`, `any-match?` and `any-not-match?`
+    /// * `eq?`, `not-eq?`, `any-eq?` and `any-not-eq?`
+    /// * `any-of?` and `not-any-of?`
     /// * `is?` and `is-not?`
     /// * `set!`
+    ///
+    /// Predicates whose operator has been registered with
+    /// [register_text_predicate](Query::register_text_predicate) are included as well.
     pub fn general_predicates(&self, index: usize) -> &[QueryPredicate] {
         &self.general_predicates[index]
     }
 
+    /// Register a function that evaluates a custom text predicate.
+    ///
+    /// When iterating over matches or captures with a [QueryCursor], any match
+    /// containing a predicate with the given operator (e.g. `"contains?"` for
+    /// `#contains?`) is passed to the evaluator, and is skipped unless the evaluator
+    /// returns `true`. The evaluator receives the predicate's arguments, with each
+    /// capture argument resolved to the captured nodes and their text.
+    ///
+    /// The operators that are evaluated natively, listed in
+    /// [general_predicates](Query::general_predicates), cannot be overridden, and
+    /// registering one of them returns an error.
+    pub fn register_text_predicate(
+        &mut self,
+        operator: &str,
+        evaluator: impl Fn(&[TextPredicateArg]) -> bool + Send + Sync + 'static,
+    ) -> Result<(), TextPredicateError> {
+        if NATIVE_PREDICATE_OPERATORS.contains(&operator) {
+            return Err(TextPredicateError(operator.to_string()));
+        }
+        self.custom_text_predicates
+            .insert(operator.into(), TextPredicateEvaluator(Box::new(evaluator)));
+        Ok(())
+    }
+
     /// Disable a certain capture within a query.
     ///
     /// This prevents the capture from being returned in matches, and also avoids any
@@ -1576,8 +2618,67 @@ impl Query {
         unsafe { ffi::ts_query_step_is_definite(self.ptr.as_ptr(), byte_offset as u32) }
     }
 
+    /// Check if a given pattern within a query has a single root node.
+    pub fn is_pattern_rooted(&self, pattern_index: usize) -> bool {
+        self.assert_pattern_index(pattern_index);
+        unsafe { ffi::ts_query_is_pattern_rooted(self.ptr.as_ptr(), pattern_index as u32) }
+    }
+
+    /// Check if a given pattern within a query is 'non-local'.
+    ///
+    /// A non-local pattern has multiple root nodes, and can match a sequence of
+    /// sibling nodes that the grammar produces using a repetition, such as the
+    /// statements in a block.
+    pub fn is_pattern_non_local(&self, pattern_index: usize) -> bool {
+        self.assert_pattern_index(pattern_index);
+        unsafe { ffi::ts_query_is_pattern_non_local(self.ptr.as_ptr(), pattern_index as u32) }
+    }
+
+    /// Check if a given pattern within a query can never match, given the
+    /// structure of the language's grammar.
+    ///
+    /// This can only be true for queries created with [Query::new_lenient].
+    /// Impossible patterns are disabled.
+    pub fn is_pattern_impossible(&self, pattern_index: usize) -> bool {
+        self.assert_pattern_index(pattern_index);
+        unsafe { ffi::ts_query_is_pattern_impossible(self.ptr.as_ptr(), pattern_index as u32) }
+    }
+
+    /// Get the byte offsets of the steps within a given pattern.
+    ///
+    /// Each step corresponds to a node or an alternation within the pattern.
+    /// Use [Query::step_is_definite] to find out which of these steps are
+    /// guaranteed to match once the pattern has reached them.
+    pub fn step_offsets_for_pattern(&self, pattern_index: usize) -> Vec<usize> {
+        self.assert_pattern_index(pattern_index);
+        unsafe {
+            let count =
+                ffi::ts_query_step_count_for_pattern(self.ptr.as_ptr(), pattern_index as u32);
+            (0..count)
+                .map(|i| {
+                    ffi::ts_query_step_offset_for_pattern(
+                        self.ptr.as_ptr(),
+                        pattern_index as u32,
+                        i,
+                    ) as usize
+                })
+                .collect()
+        }
+    }
+
+    fn assert_pattern_index(&self, pattern_index: usize) {
+        if pattern_index >= self.text_predicates.len() {
+            panic!(
+                "Pattern index is {} but the pattern count is {}",
+                pattern_index,
+                self.text_predicates.len(),
+            );
+        }
+    }
+
     fn parse_property(
-        row: usize,
+        start: Point,
+        offset: usize,
         function_name: &str,
         capture_names: &[String],
         string_values: &[String],
@@ -1585,7 +2686,8 @@ impl Query {
     ) -> Result<QueryProperty, QueryError> {
         if args.len() == 0 || args.len() > 3 {
             return Err(predicate_error(
-                row,
+                start,
+                offset,
                 format!(
                     "Wrong number of arguments to {} predicate. Expected 1 to 3, got {}.",
                     function_name,
@@ -1602,7 +2704,8 @@ impl Query {
             if arg.type_ == ffi::TSQueryPredicateStepType_TSQueryPredicateStepTypeCapture {
                 if capture_id.is_some() {
                     return Err(predicate_error(
-                        row,
+                        start,
+                        offset,
                         format!(
                             "Invalid arguments to {} predicate. Unexpected second capture name @{}",
                             function_name, capture_names[arg.value_id as usize]
@@ -1616,7 +2719,8 @@ impl Query {
                 value = Some(string_values[arg.value_id as usize].as_str());
             } else {
                 return Err(predicate_error(
-                    row,
+                    start,
+                    offset,
                     format!(
                         "Invalid arguments to {} predicate. Unexpected third argument @{}",
                         function_name, string_values[arg.value_id as usize]
@@ -1629,7 +2733,8 @@ impl Query {
             Ok(QueryProperty::new(key, value, capture_id))
         } else {
             return Err(predicate_error(
-                row,
+                start,
+                offset,
                 format!(
                     "Invalid arguments to {} predicate. Missing key argument",
                     function_name,
@@ -1653,16 +2758,87 @@ impl<'a> QueryCursor {
         unsafe { ffi::ts_query_cursor_did_exceed_match_limit(self.0.as_ptr()) }
     }
 
+    /// Return the maximum number of in-progress matches for this cursor.
+    pub fn match_limit(&self) -> u32 {
+        unsafe { ffi::ts_query_cursor_match_limit(self.0.as_ptr()) }
+    }
+
+    /// Set the maximum number of in-progress matches for this cursor.
+    ///
+    /// When this limit is exceeded, the earliest-starting in-progress match is
+    /// dropped to make room for further matches. The limit defaults to 32, and is
+    /// clamped to the range `1..=65535`.
+    pub fn set_match_limit(&mut self, limit: u32) -> &mut Self {
+        unsafe { ffi::ts_query_cursor_set_match_limit(self.0.as_ptr(), limit) };
+        self
+    }
+
+    /// Set the maximum depth, relative to the node that the query is executed on,
+    /// at which patterns can start matching.
+    ///
+    /// A depth of zero only allows patterns to match the node itself. Passing `None`
+    /// removes the limit, which is the default.
+    pub fn set_max_start_depth(&mut self, max_start_depth: Option<u32>) -> &mut Self {
+        unsafe {
+            ffi::ts_query_cursor_set_max_start_depth(
+                self.0.as_ptr(),
+                max_start_depth.unwrap_or(u32::MAX),
+            )
+        };
+        self
+    }
+
+    /// Get statistics about the work that this cursor did during its last execution.
+    ///
+    /// This can be used to find the patterns in a query that are expensive to run.
+    pub fn stats(&self) -> QueryCursorStats {
+        unsafe {
+            let mut length = 0u32;
+            let ptr = ffi::ts_query_cursor_pattern_stats(self.0.as_ptr(), &mut length as *mut u32);
+            let patterns = if length > 0 {
+                slice::from_raw_parts(ptr, length as usize)
+                    .iter()
+                    .map(|stats| QueryPatternStats {
+                        states_created: stats.states_created as usize,
+                        matches_produced: stats.matches_produced as usize,
+                        matches_dropped: stats.matches_dropped as usize,
+                    })
+                    .collect()
+            } else {
+                Vec::new()
+            };
+            QueryCursorStats {
+                nodes_visited: ffi::ts_query_cursor_nodes_visited(self.0.as_ptr()) as usize,
+                patterns,
+            }
+        }
+    }
+
     /// Iterate over all of the matches in the order that they were found.
     ///
     /// Each match contains the index of the pattern that matched, and a list of captures.
     /// Because multiple patterns can match the same set of nodes, one match may contain
     /// captures that appear *before* some of the captures from a previous match.
+    ///
+    /// The `text_callback` returns the text of a captured node, for evaluating the query's
+    /// text predicates.
     pub fn matches<'tree: 'a, T: AsRef<[u8]>>(
         &'a mut self,
         query: &'a Query,
         node: Node<'tree>,
-        mut text_callback: impl FnMut(Node<'tree>) -> T + 'a,
+        text_callback: impl FnMut(Node<'tree>) -> T + 'a,
+    ) -> impl Iterator<Item = QueryMatch<'tree>> + 'a {
+        self.matches_with_text_provider(query, node, text_callback)
+    }
+
+    /// Iterate over all of the matches in the order that they were found, like
+    /// [QueryCursor::matches], reading the text of captured nodes from the given
+    /// [TextProvider].
+    pub fn matches_with_text_provider<'tree: 'a, P: TextProvider<'tree> + 'a>(
+        &'a mut self,
+        query: &'a Query,
+        node: Node<'tree>,
+        mut text_provider: P,
     ) -> impl Iterator<Item = QueryMatch<'tree>> + 'a {
         let ptr = self.0.as_ptr();
         unsafe { ffi::ts_query_cursor_exec(ptr, query.ptr.as_ptr(), node.0) };
@@ -1671,7 +2847,7 @@ impl<'a> QueryCursor {
                 let mut m = MaybeUninit::<ffi::TSQueryMatch>::uninit();
                 if ffi::ts_query_cursor_next_match(ptr, m.as_mut_ptr()) {
                     let result = QueryMatch::new(m.assume_init(), ptr);
-                    if result.satisfies_text_predicates(query, &mut text_callback) {
+                    if result.satisfies_text_predicates(query, &mut text_provider) {
                         return Some(result);
                     }
                 } else {
@@ -1685,18 +2861,35 @@ impl<'a> QueryCursor {
     ///
     /// This is useful if don't care about which pattern matched, and just want a single,
     /// ordered sequence of captures.
+    ///
+    /// The `text_callback` returns the text of a captured node, for evaluating the query's
+    /// text predicates.
     pub fn captures<'tree, T: AsRef<[u8]>>(
         &'a mut self,
         query: &'a Query,
         node: Node<'tree>,
         text_callback: impl FnMut(Node<'tree>) -> T + 'a,
     ) -> QueryCaptures<'a, 'tree, T> {
+        let text_callback: Box<dyn FnMut(Node<'tree>) -> T + 'a> = Box::new(text_callback);
+        self.captures_with_text_provider(query, node, text_callback)
+    }
+
+    /// Iterate over all of the individual captures in the order that they appear, like
+    /// [QueryCursor::captures], reading the text of captured nodes from the given
+    /// [TextProvider].
+    pub fn captures_with_text_provider<'tree, P: TextProvider<'tree>>(
+        &'a mut self,
+        query: &'a Query,
+        node: Node<'tree>,
+        text_provider: P,
+    ) -> QueryCaptures<'a, 'tree, P::Chunk, P> {
         let ptr = self.0.as_ptr();
         unsafe { ffi::ts_query_cursor_exec(ptr, query.ptr.as_ptr(), node.0) };
         QueryCaptures {
             ptr,
             query,
-            text_callback: Box::new(text_callback),
+            text_provider,
+            _chunk: PhantomData,
         }
     }
 
@@ -1740,37 +2933,173 @@ impl<'a> QueryMatch<'a> {
         }
     }
 
-    fn satisfies_text_predicates<T: AsRef<[u8]>>(
+    fn satisfies_text_predicates(
         &self,
         query: &Query,
-        text_callback: &mut impl FnMut(Node<'a>) -> T,
+        text_provider: &mut impl TextProvider<'a>,
     ) -> bool {
-        query.text_predicates[self.pattern_index]
+        // Quantified captures can have several nodes. Depending on the predicate,
+        // either all of the nodes or any of the nodes must satisfy it.
+        fn check<I: Iterator<Item = bool>>(mut results: I, match_all: bool) -> bool {
+            if match_all {
+                results.all(|result| result)
+            } else {
+                results.any(|result| result)
+            }
+        }
+
+        let satisfies_native_predicates =
+            query.text_predicates[self.pattern_index]
+                .iter()
+                .all(|predicate| match predicate {
+                    TextPredicate::CaptureEqCapture(i, j, is_positive, match_all) => {
+                        let nodes1 = self.nodes_for_capture_index(*i).collect::<Vec<_>>();
+                        let nodes2 = self.nodes_for_capture_index(*j).collect::<Vec<_>>();
+                        if *match_all && nodes1.len() != nodes2.len() {
+                            return !*is_positive;
+                        }
+                        let results = nodes1.into_iter().zip(nodes2).map(|(node1, node2)| {
+                            let text1 = text_provider.text(node1);
+                            let text2 = text_provider.text(node2);
+                            chunks_eq(text1, text2) == *is_positive
+                        });
+                        check(results, *match_all)
+                    }
+                    TextPredicate::CaptureEqString(i, s, is_positive, match_all) => {
+                        let results = self.nodes_for_capture_index(*i).map(|node| {
+                            let text = text_provider.text(node);
+                            chunks_eq(text, iter::once(s.as_bytes())) == *is_positive
+                        });
+                        check(results, *match_all)
+                    }
+                    TextPredicate::CaptureMatchString(i, r, is_positive, match_all) => {
+                        let results = self.nodes_for_capture_index(*i).map(|node| {
+                            with_contiguous_text(text_provider.text(node), |text| {
+                                r.is_match(text) == *is_positive
+                            })
+                        });
+                        check(results, *match_all)
+                    }
+                    TextPredicate::CaptureAnyString(i, values, is_positive) => {
+                        let results = self.nodes_for_capture_index(*i).map(|node| {
+                            with_contiguous_text(text_provider.text(node), |text| {
+                                values.iter().any(|v| text == v.as_bytes()) == *is_positive
+                            })
+                        });
+                        check(results, true)
+                    }
+                });
+        if !satisfies_native_predicates {
+            return false;
+        }
+
+        if query.custom_text_predicates.is_empty() {
+            return true;
+        }
+        query.general_predicates[self.pattern_index]
             .iter()
-            .all(|predicate| match predicate {
-                TextPredicate::CaptureEqCapture(i, j, is_positive) => {
-                    let node1 = self.capture_for_index(*i).unwrap();
-                    let node2 = self.capture_for_index(*j).unwrap();
-                    (text_callback(node1).as_ref() == text_callback(node2).as_ref()) == *is_positive
-                }
-                TextPredicate::CaptureEqString(i, s, is_positive) => {
-                    let node = self.capture_for_index(*i).unwrap();
-                    (text_callback(node).as_ref() == s.as_bytes()) == *is_positive
-                }
-                TextPredicate::CaptureMatchString(i, r, is_positive) => {
-                    let node = self.capture_for_index(*i).unwrap();
-                    r.is_match(text_callback(node).as_ref()) == *is_positive
-                }
+            .all(|predicate| {
+                let evaluator = match query.custom_text_predicates.get(&predicate.operator) {
+                    Some(evaluator) => evaluator,
+                    None => return true,
+                };
+
+                // Compute the text of every captured node before building the arguments,
+                // because the arguments borrow the text.
+                let texts = predicate
+                    .args
+                    .iter()
+                    .map(|arg| match arg {
+                        QueryPredicateArg::Capture(i) => self
+                            .nodes_for_capture_index(*i)
+                            .map(|node| {
+                                let text =
+                                    with_contiguous_text(text_provider.text(node), |t| t.to_vec());
+                                (node, text)
+                            })
+                            .collect::<Vec<_>>(),
+                        QueryPredicateArg::String(_) => Vec::new(),
+                    })
+                    .collect::<Vec<_>>();
+                let args = predicate
+                    .args
+                    .iter()
+                    .zip(texts.iter())
+                    .map(|(arg, texts)| match arg {
+                        QueryPredicateArg::Capture(_) => TextPredicateArg::Capture(
+                            texts
+                                .iter()
+                                .map(|(node, text)| (*node, text.as_slice()))
+                                .collect(),
+                        ),
+                        QueryPredicateArg::String(s) => TextPredicateArg::String(s),
+                    })
+                    .collect::<Vec<_>>();
+                (evaluator.0)(&args)
             })
     }
 
-    fn capture_for_index(&self, capture_index: u32) -> Option<Node<'a>> {
-        for c in self.captures {
-            if c.index == capture_index {
-                return Some(c.node);
+    fn nodes_for_capture_index(&self, capture_index: u32) -> impl Iterator<Item = Node<'a>> + '_ {
+        self.captures
+            .iter()
+            .filter(move |c| c.index == capture_index)
+            .map(|c| c.node)
+    }
+}
+
+/// Check if two sequences of text chunks have the same contents, regardless of
+/// how the text is split into chunks.
+fn chunks_eq<A: AsRef<[u8]>, B: AsRef<[u8]>>(
+    mut chunks1: impl Iterator<Item = A>,
+    mut chunks2: impl Iterator<Item = B>,
+) -> bool {
+    let (mut chunk1, mut offset1) = (chunks1.next(), 0);
+    let (mut chunk2, mut offset2) = (chunks2.next(), 0);
+    loop {
+        while matches!(&chunk1, Some(c) if offset1 == c.as_ref().len()) {
+            chunk1 = chunks1.next();
+            offset1 = 0;
+        }
+        while matches!(&chunk2, Some(c) if offset2 == c.as_ref().len()) {
+            chunk2 = chunks2.next();
+            offset2 = 0;
+        }
+        match (&chunk1, &chunk2) {
+            (Some(c1), Some(c2)) => {
+                let (text1, text2) = (&c1.as_ref()[offset1..], &c2.as_ref()[offset2..]);
+                let length = text1.len().min(text2.len());
+                if text1[..length] != text2[..length] {
+                    return false;
+                }
+                offset1 += length;
+                offset2 += length;
+            }
+            (None, None) => return true,
+            _ => return false,
+        }
+    }
+}
+
+/// Call the given function with a node's text as a single slice, only copying the
+/// text if it was split into several chunks.
+fn with_contiguous_text<C: AsRef<[u8]>, R>(
+    mut chunks: impl Iterator<Item = C>,
+    f: impl FnOnce(&[u8]) -> R,
+) -> R {
+    let first = match chunks.next() {
+        Some(first) => first,
+        None => return f(&[]),
+    };
+    match chunks.next() {
+        None => f(first.as_ref()),
+        Some(second) => {
+            let mut text = first.as_ref().to_vec();
+            text.extend_from_slice(second.as_ref());
+            for chunk in chunks {
+                text.extend_from_slice(chunk.as_ref());
             }
+            f(&text)
         }
-        None
     }
 }
 
@@ -1784,7 +3113,11 @@ impl QueryProperty {
     }
 }
 
-impl<'a, 'tree: 'a, T: AsRef<[u8]>> Iterator for QueryCaptures<'a, 'tree, T> {
+impl<'a, 'tree: 'a, T, P> Iterator for QueryCaptures<'a, 'tree, T, P>
+where
+    T: AsRef<[u8]>,
+    P: TextProvider<'tree, Chunk = T>,
+{
     type Item = (QueryMatch<'tree>, usize);
 
     fn next(&mut self) -> Option<Self::Item> {
@@ -1798,7 +3131,7 @@ impl<'a, 'tree: 'a, T: AsRef<[u8]>> Iterator for QueryCaptures<'a, 'tree, T> {
                     &mut capture_index as *mut u32,
                 ) {
                     let result = QueryMatch::new(m.assume_init(), self.ptr);
-                    if result.satisfies_text_predicates(self.query, &mut self.text_callback) {
+                    if result.satisfies_text_predicates(self.query, &mut self.text_provider) {
                         return Some((result, capture_index as usize));
                     } else {
                         result.remove();
@@ -1821,6 +3154,12 @@ impl<'a> fmt::Debug for QueryMatch<'a> {
     }
 }
 
+impl fmt::Debug for TextPredicateEvaluator {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "TextPredicateEvaluator")
+    }
+}
+
 impl PartialEq for Query {
     fn eq(&self, other: &Self) -> bool {
         self.ptr == other.ptr
@@ -1950,12 +3289,12 @@ impl<'a> Iterator for LossyUtf8<'a> {
     }
 }
 
-fn predicate_error(row: usize, message: String) -> QueryError {
+fn predicate_error(start: Point, offset: usize, message: String) -> QueryError {
     QueryError {
         kind: QueryErrorKind::Predicate,
-        row,
-        column: 0,
-        offset: 0,
+        row: start.row,
+        column: start.column,
+        offset,
         message,
     }
 }
@@ -1966,6 +3305,12 @@ impl fmt::Display for IncludedRangesError {
     }
 }
 
+impl fmt::Display for TextPredicateError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "The #{} predicate is evaluated natively", self.0)
+    }
+}
+
 impl fmt::Display for LanguageError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(
@@ -1976,6 +3321,23 @@ impl fmt::Display for LanguageError {
     }
 }
 
+impl fmt::Display for TreeDeserializationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                TreeDeserializationError::InvalidData => "Invalid serialized tree data",
+                TreeDeserializationError::FormatVersion => "Unsupported serialized tree format",
+                TreeDeserializationError::LanguageVersion =>
+                    "Serialized tree was produced by a different version of the language",
+                TreeDeserializationError::Language =>
+                    "Serialized tree was produced by a different language",
+            }
+        )
+    }
+}
+
 impl fmt::Display for QueryError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(
@@ -1999,11 +3361,20 @@ impl fmt::Display for QueryError {
 impl error::Error for IncludedRangesError {}
 impl error::Error for LanguageError {}
 impl error::Error for QueryError {}
+impl error::Error for TextPredicateError {}
+impl error::Error for TreeDeserializationError {}
 
 unsafe impl Send for Language {}
 unsafe impl Send for Parser {}
 unsafe impl Send for Query {}
 unsafe impl Send for Tree {}
 unsafe impl Send for QueryCursor {}
+unsafe impl Send for LookaheadIterator {}
+unsafe impl Send for Node<'_> {}
+unsafe impl Send for OwnedNode {}
+unsafe impl Send for TreeCursor<'_> {}
 unsafe impl Sync for Language {}
+unsafe impl Sync for Node<'_> {}
+unsafe impl Sync for OwnedNode {}
 unsafe impl Sync for Query {}
+unsafe impl Sync for Tree {}
@@ -0,0 +1,806 @@
+//! A syntax tree for the source code of queries.
+//!
+//! The [parse] function turns query source code into a [QueryAst], without
+//! consulting any language. Node kinds and field names are therefore not
+//! validated; that only happens when the query is compiled with [Query::new].
+//! A [QueryAst] can be printed back to source code using its `Display`
+//! implementation, which produces a canonical format:
+//!
+//! * Each top-level item starts on its own line. Blank lines between top-level
+//!   items are preserved, but runs of blank lines are collapsed into one.
+//! * A named node is printed on one line if it has at most one child, and that
+//!   child has no field and can itself be printed on one line. Otherwise, each
+//!   child is printed on its own line, indented by two spaces.
+//! * A grouped sequence with more than one item prints its first item right
+//!   after the opening parenthesis, and aligns the others with it.
+//! * An alternation at the top level with more than one branch prints each
+//!   branch on its own line. Nested alternations are printed on one line if
+//!   they fit.
+//! * Quantifiers are printed before captures, and predicates always use the
+//!   `#` prefix.
+//!
+//! Comments are preserved wherever a pattern could appear in their place. Comments
+//! in other positions, such as between a field name and its pattern, are dropped.
+//!
+//! [Query::new]: super::Query::new
+
+use super::{QueryError, QueryErrorKind};
+use std::fmt::{self, Write};
+use std::ops::Range;
+
+/// Lines that are longer than this are broken up, where the format allows it.
+const MAX_LINE_LENGTH: usize = 80;
+
+/// The parsed source code of a query.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct QueryAst {
+    pub items: Vec<Item>,
+}
+
+/// An element of a query, or of the list of children of a pattern.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Item {
+    Pattern(Pattern),
+    Predicate(Predicate),
+    /// A `.` that anchors the adjacent patterns to the start or end of their
+    /// parent's children, or to each other.
+    Anchor,
+    /// A `!field` assertion that the parent node has no child with the field.
+    NegatedField(String),
+    /// A `!(pattern)` assertion that the parent node has no child matching the
+    /// pattern.
+    NegatedChild(Pattern),
+    /// A comment, without its leading `;`.
+    Comment(String),
+    /// A blank line separating two top-level items.
+    BlankLine,
+}
+
+/// A pattern, along with its field name, quantifiers and captures.
+///
+/// Patterns are compared without regard to their location, so that the same
+/// pattern compares equal regardless of how its source code was formatted.
+#[derive(Clone, Debug)]
+pub struct Pattern {
+    pub field: Option<String>,
+    pub kind: PatternKind,
+    pub quantifiers: Vec<Quantifier>,
+    pub captures: Vec<String>,
+    /// The location of the pattern in the source code that it was parsed from,
+    /// not including its field name. This is empty for patterns that were not
+    /// parsed.
+    pub byte_range: Range<usize>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PatternKind {
+    /// A named node such as `(identifier)`, or `(_)` for any named node.
+    Node(NodePattern),
+    /// An anonymous node such as `"if"`. The string is unescaped.
+    Anonymous(String),
+    /// The `_` wildcard, which matches any node.
+    Wildcard,
+    /// A parenthesized sequence of sibling patterns.
+    Group(Vec<Item>),
+    /// A bracketed list of alternative patterns.
+    Alternation(Vec<Item>),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NodePattern {
+    pub kind: String,
+    /// The kind in `(supertype/subtype)` syntax.
+    pub subtype: Option<String>,
+    pub children: Vec<Item>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Quantifier {
+    ZeroOrOne,
+    ZeroOrMore,
+    OneOrMore,
+}
+
+/// A predicate such as `(#eq? @name "value")`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Predicate {
+    /// The predicate's name, without the `#` prefix.
+    pub name: String,
+    pub args: Vec<PredicateArg>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PredicateArg {
+    Capture(String),
+    /// A double-quoted string. The string is unescaped.
+    String(String),
+    /// A bare identifier.
+    Symbol(String),
+}
+
+/// Parse the source code of a query.
+///
+/// Returns a `QueryError` of kind [QueryErrorKind::Syntax] if the source code
+/// is not syntactically valid.
+pub fn parse(source: &str) -> Result<QueryAst, QueryError> {
+    let mut parser = Parser { source, offset: 0 };
+    let mut items = Vec::new();
+    loop {
+        parser.skip_trivia_into(&mut items, true);
+        if parser.peek().is_none() {
+            break;
+        }
+        match parser.parse_item(Context::TopLevel)? {
+            Some(item) => items.push(item),
+            None => return Err(parser.error()),
+        }
+    }
+    if items.last() == Some(&Item::BlankLine) {
+        items.pop();
+    }
+    Ok(QueryAst { items })
+}
+
+impl Pattern {
+    pub fn new(kind: PatternKind) -> Self {
+        Pattern {
+            field: None,
+            kind,
+            quantifiers: Vec::new(),
+            captures: Vec::new(),
+            byte_range: 0..0,
+        }
+    }
+
+    /// Create a pattern that matches a named node of the given kind with the
+    /// given children.
+    pub fn node(kind: &str, children: Vec<Item>) -> Self {
+        Self::new(PatternKind::Node(NodePattern {
+            kind: kind.to_string(),
+            subtype: None,
+            children,
+        }))
+    }
+
+    pub fn with_field(mut self, field: &str) -> Self {
+        self.field = Some(field.to_string());
+        self
+    }
+
+    pub fn with_quantifier(mut self, quantifier: Quantifier) -> Self {
+        self.quantifiers.push(quantifier);
+        self
+    }
+
+    pub fn with_capture(mut self, capture: &str) -> Self {
+        self.captures.push(capture.to_string());
+        self
+    }
+}
+
+impl PartialEq for Pattern {
+    fn eq(&self, other: &Self) -> bool {
+        self.field == other.field
+            && self.kind == other.kind
+            && self.quantifiers == other.quantifiers
+            && self.captures == other.captures
+    }
+}
+
+impl Eq for Pattern {}
+
+impl From<Pattern> for Item {
+    fn from(pattern: Pattern) -> Self {
+        Item::Pattern(pattern)
+    }
+}
+
+impl From<Predicate> for Item {
+    fn from(predicate: Predicate) -> Self {
+        Item::Predicate(predicate)
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Context {
+    TopLevel,
+    Node,
+    Group,
+    Alternation,
+}
+
+struct Parser<'a> {
+    source: &'a str,
+    offset: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<char> {
+        self.source[self.offset..].chars().next()
+    }
+
+    fn peek_second(&self) -> Option<char> {
+        self.source[self.offset..].chars().nth(1)
+    }
+
+    fn advance(&mut self) {
+        if let Some(c) = self.peek() {
+            self.offset += c.len_utf8();
+        }
+    }
+
+    fn skip_trivia(&mut self) {
+        let mut comments = Vec::new();
+        self.skip_trivia_into(&mut comments, false);
+    }
+
+    // Get the next character after any whitespace and comments, without
+    // consuming them.
+    fn peek_after_trivia(&mut self) -> Option<char> {
+        let offset = self.offset;
+        self.skip_trivia();
+        let result = self.peek();
+        self.offset = offset;
+        result
+    }
+
+    // Skip whitespace and comments, adding the comments to the given list of
+    // items. At the top level, blank lines are recorded too.
+    fn skip_trivia_into(&mut self, items: &mut Vec<Item>, is_top_level: bool) {
+        let mut newline_count = 0;
+        while let Some(c) = self.peek() {
+            if c == '\n' {
+                newline_count += 1;
+                self.advance();
+            } else if c.is_whitespace() {
+                self.advance();
+            } else if c == ';' {
+                if is_top_level && newline_count > 1 && !items.is_empty() {
+                    items.push(Item::BlankLine);
+                }
+                newline_count = 0;
+                self.advance();
+                let start = self.offset;
+                while self.peek().filter(|c| *c != '\n').is_some() {
+                    self.advance();
+                }
+                items.push(Item::Comment(self.source[start..self.offset].to_string()));
+            } else {
+                break;
+            }
+        }
+        if is_top_level
+            && newline_count > 1
+            && !items.is_empty()
+            && self.peek().is_some()
+            && items.last() != Some(&Item::BlankLine)
+        {
+            items.push(Item::BlankLine);
+        }
+    }
+
+    fn is_identifier_start(c: char) -> bool {
+        c.is_alphanumeric() || c == '_' || c == '-'
+    }
+
+    fn is_identifier_char(c: char) -> bool {
+        Self::is_identifier_start(c) || c == '.' || c == '?' || c == '!'
+    }
+
+    fn parse_identifier(&mut self) -> Result<String, QueryError> {
+        let start = self.offset;
+        if self
+            .peek()
+            .filter(|c| Self::is_identifier_start(*c))
+            .is_none()
+        {
+            return Err(self.error());
+        }
+        self.advance();
+        while self
+            .peek()
+            .filter(|c| Self::is_identifier_char(*c))
+            .is_some()
+        {
+            self.advance();
+        }
+        Ok(self.source[start..self.offset].to_string())
+    }
+
+    fn parse_string(&mut self) -> Result<String, QueryError> {
+        let start = self.offset;
+        self.advance();
+        let mut result = String::new();
+        loop {
+            match self.peek() {
+                Some('"') => {
+                    self.advance();
+                    return Ok(result);
+                }
+                Some('\\') => {
+                    self.advance();
+                    match self.peek() {
+                        Some('n') => result.push('\n'),
+                        Some('r') => result.push('\r'),
+                        Some('t') => result.push('\t'),
+                        Some('0') => result.push('\0'),
+                        Some(c) => result.push(c),
+                        None => break,
+                    }
+                    self.advance();
+                }
+                Some('\n') | None => break,
+                Some(c) => {
+                    result.push(c);
+                    self.advance();
+                }
+            }
+        }
+        self.offset = start;
+        Err(self.error())
+    }
+
+    // Parse one item in the given context. Returns `None` if the next character
+    // closes the enclosing list instead.
+    fn parse_item(&mut self, context: Context) -> Result<Option<Item>, QueryError> {
+        match self.peek() {
+            Some(')') | Some(']') => return Ok(None),
+            Some('.') if context != Context::TopLevel => {
+                self.advance();
+                return Ok(Some(Item::Anchor));
+            }
+            Some('!') if context == Context::Node => {
+                self.advance();
+                if let Some('(') | Some('"') = self.peek() {
+                    return match self.parse_pattern()? {
+                        Some(Item::Pattern(pattern)) => Ok(Some(Item::NegatedChild(pattern))),
+                        _ => Err(self.error()),
+                    };
+                }
+                self.skip_trivia();
+                return Ok(Some(Item::NegatedField(self.parse_identifier()?)));
+            }
+            _ => {}
+        }
+        match self.parse_pattern()? {
+            Some(item) => Ok(Some(item)),
+            None => Err(self.error()),
+        }
+    }
+
+    // Parse a pattern with its suffixes, or a predicate.
+    fn parse_pattern(&mut self) -> Result<Option<Item>, QueryError> {
+        let start = self.offset;
+        let kind = match self.peek() {
+            Some('[') => {
+                self.advance();
+                let items = self.parse_items(']', Context::Alternation)?;
+                if !items.iter().any(|item| matches!(item, Item::Pattern(_))) {
+                    return Err(self.error());
+                }
+                PatternKind::Alternation(items)
+            }
+            Some('(') => {
+                self.advance();
+                self.skip_trivia();
+                match self.peek() {
+                    // A parenthesis followed by a pattern starts a grouped sequence.
+                    Some('(') | Some('"') | Some('[') => {
+                        PatternKind::Group(self.parse_items(')', Context::Group)?)
+                    }
+
+                    // A dot or pound character starts a predicate.
+                    Some('.') | Some('#') => {
+                        self.advance();
+                        return self.parse_predicate().map(Some);
+                    }
+
+                    // For backward compatibility, `*` is a named wildcard.
+                    Some('*') => {
+                        self.advance();
+                        self.parse_node("_".to_string())?
+                    }
+
+                    _ => {
+                        let start = self.offset;
+                        let name = self.parse_identifier()?;
+
+                        // For backward compatibility, predicates can omit the `#`.
+                        if name.ends_with('!') || name.ends_with('?') {
+                            self.offset = start;
+                            return self.parse_predicate().map(Some);
+                        }
+                        self.parse_node(name)?
+                    }
+                }
+            }
+
+            // For backward compatibility, `*` is a wildcard. An underscore is only
+            // a wildcard on its own, rather than at the start of an identifier.
+            Some('*') => {
+                self.advance();
+                PatternKind::Wildcard
+            }
+            Some('_')
+                if self
+                    .peek_second()
+                    .filter(|c| Self::is_identifier_char(*c))
+                    .is_none() =>
+            {
+                self.advance();
+                PatternKind::Wildcard
+            }
+            Some('"') => PatternKind::Anonymous(self.parse_string()?),
+            Some(c) if Self::is_identifier_start(c) => {
+                let start = self.offset;
+                let field = self.parse_identifier()?;
+                self.skip_trivia();
+                if self.peek() != Some(':') {
+                    self.offset = start;
+                    return Err(self.error());
+                }
+                self.advance();
+                self.skip_trivia();
+                return match self.parse_pattern()? {
+                    Some(Item::Pattern(mut pattern)) if pattern.field.is_none() => {
+                        pattern.field = Some(field);
+                        Ok(Some(Item::Pattern(pattern)))
+                    }
+                    _ => Err(self.error()),
+                };
+            }
+            _ => return Ok(None),
+        };
+
+        let mut pattern = Pattern::new(kind);
+        pattern.byte_range = start..self.offset;
+        loop {
+            let quantifier = match self.peek_after_trivia() {
+                Some('?') => Quantifier::ZeroOrOne,
+                Some('*') => Quantifier::ZeroOrMore,
+                Some('+') => Quantifier::OneOrMore,
+                Some('@') => {
+                    self.skip_trivia();
+                    self.advance();
+                    pattern.captures.push(self.parse_identifier()?);
+                    pattern.byte_range.end = self.offset;
+                    continue;
+                }
+                _ => break,
+            };
+            self.skip_trivia();
+            self.advance();
+            pattern.quantifiers.push(quantifier);
+            pattern.byte_range.end = self.offset;
+        }
+        Ok(Some(Item::Pattern(pattern)))
+    }
+
+    // Parse the rest of a named node, after its kind.
+    fn parse_node(&mut self, kind: String) -> Result<PatternKind, QueryError> {
+        let mut subtype = None;
+        if self.peek_after_trivia() == Some('/') {
+            self.skip_trivia();
+            self.advance();
+            subtype = Some(self.parse_identifier()?);
+        }
+        let children = self.parse_items(')', Context::Node)?;
+        Ok(PatternKind::Node(NodePattern {
+            kind,
+            subtype,
+            children,
+        }))
+    }
+
+    // Parse a list of items up to and including the given closing delimiter.
+    fn parse_items(&mut self, close: char, context: Context) -> Result<Vec<Item>, QueryError> {
+        let mut items = Vec::new();
+        loop {
+            self.skip_trivia_into(&mut items, false);
+            match self.parse_item(context)? {
+                Some(item) => items.push(item),
+                None if self.peek() == Some(close) => break,
+                None => return Err(self.error()),
+            }
+        }
+
+        // An anchor within an alternation must be followed by a branch, and an
+        // anchor at the end of a node's children must follow a child.
+        let anchor_is_trailing = items
+            .iter()
+            .rev()
+            .find(|item| !matches!(item, Item::Comment(_)))
+            == Some(&Item::Anchor);
+        let has_child = items.iter().any(|item| matches!(item, Item::Pattern(_)));
+        if anchor_is_trailing
+            && (context == Context::Alternation || (context == Context::Node && !has_child))
+        {
+            return Err(self.error());
+        }
+        self.advance();
+        Ok(items)
+    }
+
+    fn parse_predicate(&mut self) -> Result<Item, QueryError> {
+        let name = self.parse_identifier()?;
+        let mut args = Vec::new();
+        loop {
+            self.skip_trivia();
+            match self.peek() {
+                Some(')') => {
+                    self.advance();
+                    break;
+                }
+                Some('@') => {
+                    self.advance();
+                    args.push(PredicateArg::Capture(self.parse_identifier()?));
+                }
+                Some('"') => args.push(PredicateArg::String(self.parse_string()?)),
+                _ => args.push(PredicateArg::Symbol(self.parse_identifier()?)),
+            }
+        }
+        Ok(Item::Predicate(Predicate { name, args }))
+    }
+
+    fn error(&self) -> QueryError {
+        let line_start = self.source[..self.offset].rfind('\n').map_or(0, |i| i + 1);
+        let line_end = self.source[self.offset..]
+            .find('\n')
+            .map_or(self.source.len(), |i| self.offset + i);
+        let column = self.offset - line_start;
+        let message = if self.offset < self.source.len() {
+            format!(
+                "{}\n{}^",
+                &self.source[line_start..line_end],
+                " ".repeat(column)
+            )
+        } else {
+            "Unexpected EOF".to_string()
+        };
+        QueryError {
+            row: self.source[..self.offset].matches('\n').count(),
+            column,
+            offset: self.offset,
+            message,
+            kind: QueryErrorKind::Syntax,
+        }
+    }
+}
+
+impl fmt::Display for QueryAst {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut output = String::new();
+        for item in &self.items {
+            if *item != Item::BlankLine {
+                write_item(&mut output, item, 0, true);
+            }
+            output.push('\n');
+        }
+        f.write_str(&output)
+    }
+}
+
+impl fmt::Display for Item {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut output = String::new();
+        write_item(&mut output, self, 0, false);
+        f.write_str(&output)
+    }
+}
+
+impl fmt::Display for Pattern {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut output = String::new();
+        write_pattern(&mut output, self, 0, false);
+        f.write_str(&output)
+    }
+}
+
+impl fmt::Display for Predicate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "(#{}", self.name)?;
+        for arg in &self.args {
+            match arg {
+                PredicateArg::Capture(name) => write!(f, " @{}", name)?,
+                PredicateArg::String(string) => write!(f, " {}", quote(string))?,
+                PredicateArg::Symbol(symbol) => write!(f, " {}", symbol)?,
+            }
+        }
+        write!(f, ")")
+    }
+}
+
+fn quote(string: &str) -> String {
+    let mut result = String::with_capacity(string.len() + 2);
+    result.push('"');
+    for c in string.chars() {
+        match c {
+            '"' => result.push_str("\\\""),
+            '\\' => result.push_str("\\\\"),
+            '\n' => result.push_str("\\n"),
+            '\r' => result.push_str("\\r"),
+            '\t' => result.push_str("\\t"),
+            '\0' => result.push_str("\\0"),
+            _ => result.push(c),
+        }
+    }
+    result.push('"');
+    result
+}
+
+fn column(output: &str) -> usize {
+    output.len() - output.rfind('\n').map_or(0, |i| i + 1)
+}
+
+fn new_line(output: &mut String, indent: usize) {
+    output.push('\n');
+    output.extend((0..indent).map(|_| ' '));
+}
+
+// Render an item on a single line, if it contains no comments.
+fn inline_item(item: &Item) -> Option<String> {
+    match item {
+        Item::Pattern(pattern) => inline_pattern(pattern),
+        Item::NegatedChild(pattern) => inline_pattern(pattern).map(|s| format!("!{}", s)),
+        Item::Comment(_) | Item::BlankLine => None,
+        _ => Some(item.to_string()),
+    }
+}
+
+fn inline_pattern(pattern: &Pattern) -> Option<String> {
+    let mut result = String::new();
+    if let Some(field) = &pattern.field {
+        write!(&mut result, "{}: ", field).unwrap();
+    }
+    match &pattern.kind {
+        PatternKind::Node(node) => {
+            write!(&mut result, "({}", node.kind).unwrap();
+            if let Some(subtype) = &node.subtype {
+                write!(&mut result, "/{}", subtype).unwrap();
+            }
+            for child in &node.children {
+                write!(&mut result, " {}", inline_item(child)?).unwrap();
+            }
+            result.push(')');
+        }
+        PatternKind::Group(items) => {
+            write!(&mut result, "({})", inline_items(items)?).unwrap();
+        }
+        PatternKind::Alternation(items) => {
+            write!(&mut result, "[{}]", inline_items(items)?).unwrap();
+        }
+        PatternKind::Anonymous(string) => result += &quote(string),
+        PatternKind::Wildcard => result.push('_'),
+    }
+    write_suffixes(&mut result, pattern);
+    Some(result)
+}
+
+fn inline_items(items: &[Item]) -> Option<String> {
+    let items = items.iter().map(inline_item).collect::<Option<Vec<_>>>()?;
+    Some(items.join(" "))
+}
+
+fn write_suffixes(output: &mut String, pattern: &Pattern) {
+    for quantifier in &pattern.quantifiers {
+        output.push(match quantifier {
+            Quantifier::ZeroOrOne => '?',
+            Quantifier::ZeroOrMore => '*',
+            Quantifier::OneOrMore => '+',
+        });
+    }
+    for capture in &pattern.captures {
+        write!(output, " @{}", capture).unwrap();
+    }
+}
+
+// Write an item at the current position of the output. Any subsequent lines
+// are indented relative to the given indentation.
+fn write_item(output: &mut String, item: &Item, indent: usize, is_top_level: bool) {
+    match item {
+        Item::Pattern(pattern) => write_pattern(output, pattern, indent, is_top_level),
+        Item::Predicate(predicate) => write!(output, "{}", predicate).unwrap(),
+        Item::Anchor => output.push('.'),
+        Item::NegatedField(field) => write!(output, "!{}", field).unwrap(),
+        Item::NegatedChild(pattern) => {
+            output.push('!');
+            write_pattern(output, pattern, indent, false);
+        }
+        Item::Comment(text) => write!(output, ";{}", text).unwrap(),
+        Item::BlankLine => {}
+    }
+}
+
+fn write_pattern(output: &mut String, pattern: &Pattern, indent: usize, is_top_level: bool) {
+    if let Some(field) = &pattern.field {
+        write!(output, "{}: ", field).unwrap();
+    }
+    match &pattern.kind {
+        PatternKind::Node(node) => {
+            write!(output, "({}", node.kind).unwrap();
+            if let Some(subtype) = &node.subtype {
+                write!(output, "/{}", subtype).unwrap();
+            }
+            let inline_child = match node.children.as_slice() {
+                [] => Some(String::new()),
+                [child @ Item::Pattern(Pattern { field: None, .. })] => {
+                    inline_item(child).map(|child| format!(" {}", child))
+                }
+                _ => None,
+            };
+            match inline_child {
+                Some(child) if fits(output, &child) => output.push_str(&child),
+                _ => {
+                    write_items(output, &node.children, indent + 2, indent + 2, true);
+                    if let Some(Item::Comment(_)) = node.children.last() {
+                        new_line(output, indent);
+                    }
+                }
+            }
+            output.push(')');
+        }
+        PatternKind::Group(items) => {
+            output.push('(');
+            match inline_items(items) {
+                Some(inline) if items.len() == 1 && fits(output, &inline) => {
+                    output.push_str(&inline)
+                }
+                _ => {
+                    write_items(output, items, indent, indent + 1, false);
+                    if let Some(Item::Comment(_)) = items.last() {
+                        new_line(output, indent);
+                    }
+                }
+            }
+            output.push(')');
+        }
+        PatternKind::Alternation(items) => {
+            output.push('[');
+            match inline_items(items) {
+                Some(inline) if (!is_top_level || items.len() == 1) && fits(output, &inline) => {
+                    output.push_str(&inline)
+                }
+                _ => {
+                    write_items(output, items, indent + 2, indent + 2, true);
+                    new_line(output, indent);
+                }
+            }
+            output.push(']');
+        }
+        PatternKind::Anonymous(string) => output.push_str(&quote(string)),
+        PatternKind::Wildcard => output.push('_'),
+    }
+    write_suffixes(output, pattern);
+}
+
+// Write a list of items, each on its own line. The first item is written on a
+// new line only if `break_first` is true. Anchors are written on the same line
+// as the item that follows them.
+fn write_items(
+    output: &mut String,
+    items: &[Item],
+    first_indent: usize,
+    indent: usize,
+    break_first: bool,
+) {
+    let mut is_first = true;
+    let mut after_anchor = false;
+    for item in items {
+        if after_anchor && !matches!(item, Item::Comment(_)) {
+            output.push(' ');
+        } else if is_first && !break_first {
+            // The first item follows the opening delimiter directly.
+        } else {
+            new_line(output, if is_first { first_indent } else { indent });
+        }
+        let item_indent = if is_first { first_indent } else { indent };
+        write_item(output, item, item_indent, false);
+        after_anchor = *item == Item::Anchor;
+        is_first = false;
+    }
+}
+
+fn fits(output: &str, text: &str) -> bool {
+    // Leave room for a closing delimiter.
+    column(output) + text.len() < MAX_LINE_LENGTH
+}
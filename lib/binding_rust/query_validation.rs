@@ -0,0 +1,380 @@
+//! Checking queries against the structure of a language's syntax trees.
+//!
+//! [Query::new] rejects unknown node kinds and field names, along with the
+//! patterns that the language's parse table rules out. The [validate_query]
+//! function additionally checks each pattern against the language's node types:
+//! the parent, child and field relationships that are described in its
+//! `node-types.json` file. This catches patterns like `(identifier (string))`,
+//! which compile but can never match, because `identifier` nodes have no
+//! children.
+//!
+//! The node types are described by a [NodeTypes] value. With the `node-types`
+//! feature enabled, it can be read from a `node-types.json` file with
+//! [NodeTypes::from_json]. Otherwise, it is built up one relationship at a time.
+//!
+//! [Query::new]: super::Query::new
+
+use super::query_ast::{self, Item, Pattern, PatternKind};
+use super::QueryError;
+use std::collections::{HashMap, HashSet};
+use std::ops::Range;
+
+#[cfg(feature = "node-types")]
+use serde_derive::Deserialize;
+
+/// A node kind, along with whether it is named.
+type Kind = (String, bool);
+
+/// The relationships between the node kinds of a language.
+#[derive(Clone, Debug, Default)]
+pub struct NodeTypes {
+    kinds: HashSet<Kind>,
+    subtypes: HashMap<String, Vec<Kind>>,
+    children: HashMap<String, Vec<Kind>>,
+    fields: HashMap<String, HashMap<String, Vec<Kind>>>,
+}
+
+/// The description of a node kind in a `node-types.json` file.
+#[cfg(feature = "node-types")]
+#[derive(Deserialize)]
+struct NodeInfoJSON {
+    #[serde(rename = "type")]
+    kind: String,
+    named: bool,
+    fields: Option<HashMap<String, FieldInfoJSON>>,
+    children: Option<FieldInfoJSON>,
+    subtypes: Option<Vec<NodeTypeJSON>>,
+}
+
+#[cfg(feature = "node-types")]
+#[derive(Deserialize)]
+struct NodeTypeJSON {
+    #[serde(rename = "type")]
+    kind: String,
+    named: bool,
+}
+
+#[cfg(feature = "node-types")]
+#[derive(Deserialize)]
+struct FieldInfoJSON {
+    types: Vec<NodeTypeJSON>,
+}
+
+/// A part of a query that can never match, found by [validate_query].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ImpossiblePattern {
+    /// The index of the pattern within the query.
+    pub pattern_index: usize,
+    /// The location of the sub-pattern that can never match.
+    pub byte_range: Range<usize>,
+    pub row: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl NodeTypes {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Read the node types from the contents of a language's `node-types.json` file.
+    ///
+    /// This requires the `node-types` feature.
+    #[cfg(feature = "node-types")]
+    pub fn from_json(json: &str) -> Result<Self, serde_json::Error> {
+        let node_infos: Vec<NodeInfoJSON> = serde_json::from_str(json)?;
+        let mut result = Self::new();
+        for info in node_infos {
+            result.add_node_type(&info.kind, info.named);
+            for subtype in info.subtypes.iter().flatten() {
+                result.add_subtype(&info.kind, &subtype.kind, subtype.named);
+            }
+            for child in info.children.iter().flat_map(|c| &c.types) {
+                result.add_child_type(&info.kind, &child.kind, child.named);
+            }
+            for (field, field_info) in info.fields.iter().flatten() {
+                for child in &field_info.types {
+                    result.add_field_type(&info.kind, field, &child.kind, child.named);
+                }
+            }
+        }
+        Ok(result)
+    }
+
+    /// Add a node kind that can appear in the language's syntax trees.
+    pub fn add_node_type(&mut self, kind: &str, is_named: bool) {
+        self.kinds.insert((kind.to_string(), is_named));
+    }
+
+    /// Record that `supertype` nodes are always one of the given kind of node.
+    pub fn add_subtype(&mut self, supertype: &str, kind: &str, is_named: bool) {
+        self.add_node_type(supertype, true);
+        self.subtypes
+            .entry(supertype.to_string())
+            .or_default()
+            .push((kind.to_string(), is_named));
+    }
+
+    /// Record that `parent` nodes can have children of the given kind that are
+    /// not associated with any field.
+    pub fn add_child_type(&mut self, parent: &str, kind: &str, is_named: bool) {
+        self.add_node_type(parent, true);
+        self.children
+            .entry(parent.to_string())
+            .or_default()
+            .push((kind.to_string(), is_named));
+    }
+
+    /// Record that `parent` nodes can have children of the given kind in the
+    /// given field.
+    pub fn add_field_type(&mut self, parent: &str, field: &str, kind: &str, is_named: bool) {
+        self.add_node_type(parent, true);
+        self.fields
+            .entry(parent.to_string())
+            .or_default()
+            .entry(field.to_string())
+            .or_default()
+            .push((kind.to_string(), is_named));
+    }
+
+    // Get the kinds of nodes that a node of the given kind can actually be,
+    // expanding supertypes into their subtypes.
+    fn concrete_kinds(&self, kind: &Kind, result: &mut HashSet<Kind>) {
+        match self.subtypes.get(&kind.0).filter(|_| kind.1) {
+            Some(subtypes) => {
+                for subtype in subtypes {
+                    if !result.contains(subtype) {
+                        result.insert(subtype.clone());
+                        self.concrete_kinds(subtype, result);
+                    }
+                }
+            }
+            None => {
+                result.insert(kind.clone());
+            }
+        }
+    }
+
+    fn has_children(&self, kind: &str) -> bool {
+        self.children.contains_key(kind) || self.fields.contains_key(kind)
+    }
+}
+
+/// Check the patterns in the given query source code against the given node
+/// types, and return the parts of the patterns that can never match.
+///
+/// Node kinds and fields that don't appear in the node types are not checked,
+/// because they are reported by [Query::new]. Neither are anonymous children
+/// without a field, because the node types only describe named children.
+/// Node kinds that never appear as a child of any other node, such as comments,
+/// are assumed to be extras, which can appear within any node that has children.
+///
+/// [Query::new]: super::Query::new
+pub fn validate_query(
+    node_types: &NodeTypes,
+    source: &str,
+) -> Result<Vec<ImpossiblePattern>, QueryError> {
+    let ast = query_ast::parse(source)?;
+
+    let mut child_kinds = HashSet::new();
+    for kinds in node_types
+        .children
+        .values()
+        .chain(
+            node_types
+                .fields
+                .values()
+                .flat_map(|fields| fields.values()),
+        )
+        .chain(node_types.subtypes.values())
+    {
+        for kind in kinds {
+            node_types.concrete_kinds(kind, &mut child_kinds);
+        }
+    }
+
+    let mut validator = Validator {
+        node_types,
+        child_kinds,
+        source,
+        pattern_index: 0,
+        result: Vec::new(),
+    };
+    for item in &ast.items {
+        match item {
+            Item::Pattern(pattern) => validator.check_pattern(pattern),
+            Item::Predicate(_) => {}
+            _ => continue,
+        }
+        validator.pattern_index += 1;
+    }
+    Ok(validator.result)
+}
+
+struct Validator<'a> {
+    node_types: &'a NodeTypes,
+    child_kinds: HashSet<Kind>,
+    source: &'a str,
+    pattern_index: usize,
+    result: Vec<ImpossiblePattern>,
+}
+
+impl<'a> Validator<'a> {
+    fn check_pattern(&mut self, pattern: &Pattern) {
+        match &pattern.kind {
+            PatternKind::Node(node) => {
+                let parent = node.subtype.as_ref().unwrap_or(&node.kind);
+                let parent = (parent.clone(), true);
+                let check_children = self.node_types.kinds.contains(&parent);
+                for child in &node.children {
+                    if let Item::Pattern(child) = child {
+                        if check_children {
+                            self.check_child(&parent.0, None, child);
+                        }
+                        self.check_pattern(child);
+                    }
+                }
+            }
+            PatternKind::Group(items) | PatternKind::Alternation(items) => {
+                for item in items {
+                    if let Item::Pattern(pattern) = item {
+                        self.check_pattern(pattern);
+                    }
+                }
+            }
+            PatternKind::Anonymous(_) | PatternKind::Wildcard => {}
+        }
+    }
+
+    // Check that a pattern can match a child of the given kind of parent node,
+    // within the given field.
+    fn check_child(&mut self, parent: &str, field: Option<&str>, child: &Pattern) {
+        let field = child.field.as_deref().or(field);
+        let kind = match &child.kind {
+            PatternKind::Alternation(items) => {
+                for item in items {
+                    if let Item::Pattern(branch) = item {
+                        self.check_child(parent, field, branch);
+                    }
+                }
+                return;
+            }
+            PatternKind::Group(items) => {
+                let mut field = field;
+                for item in items {
+                    if let Item::Pattern(pattern) = item {
+                        self.check_child(parent, field, pattern);
+                        field = None;
+                    }
+                }
+                return;
+            }
+            PatternKind::Node(node) => {
+                Some((node.subtype.as_ref().unwrap_or(&node.kind).clone(), true))
+            }
+            PatternKind::Anonymous(name) => Some((name.clone(), false)),
+            PatternKind::Wildcard => None,
+        };
+
+        let mut parent_kinds = HashSet::new();
+        self.node_types
+            .concrete_kinds(&(parent.to_string(), true), &mut parent_kinds);
+
+        // Find the kinds of nodes that can appear in this position.
+        let mut allowed_kinds = HashSet::new();
+        let mut has_field = false;
+        for (parent_kind, _) in &parent_kinds {
+            let fields = self.node_types.fields.get(parent_kind);
+            let kinds = match field {
+                Some(field) => fields.and_then(|fields| fields.get(field)),
+                None => self.node_types.children.get(parent_kind),
+            };
+            if let Some(kinds) = kinds {
+                has_field = true;
+                for kind in kinds {
+                    self.node_types.concrete_kinds(kind, &mut allowed_kinds);
+                }
+            }
+            if field.is_none() {
+                for kinds in fields.iter().flat_map(|fields| fields.values()) {
+                    for kind in kinds {
+                        self.node_types.concrete_kinds(kind, &mut allowed_kinds);
+                    }
+                }
+            }
+        }
+
+        if let Some(field) = field {
+            if !has_field {
+                let message = format!("`{}` nodes have no `{}` field", parent, field);
+                self.add_error(child, message);
+                return;
+            }
+        }
+
+        let kind = match kind {
+            Some(kind) => kind,
+            None => return,
+        };
+        if kind.0 == "ERROR" || kind.0 == "MISSING" {
+            return;
+        }
+        if kind.0 == "_" {
+            if !allowed_kinds.iter().any(|kind| kind.1)
+                && (field.is_some()
+                    || !parent_kinds
+                        .iter()
+                        .any(|k| self.node_types.has_children(&k.0)))
+            {
+                let message = format!("`{}` nodes cannot have named children", parent);
+                self.add_error(child, message);
+            }
+            return;
+        }
+        if !self.node_types.kinds.contains(&kind) || (field.is_none() && !kind.1) {
+            return;
+        }
+
+        let mut child_kinds = HashSet::new();
+        self.node_types.concrete_kinds(&kind, &mut child_kinds);
+        if child_kinds.iter().any(|kind| allowed_kinds.contains(kind)) {
+            return;
+        }
+
+        // Extras can appear within any node that has children.
+        if field.is_none()
+            && !self.child_kinds.contains(&kind)
+            && parent_kinds
+                .iter()
+                .any(|k| self.node_types.has_children(&k.0))
+        {
+            return;
+        }
+
+        let child_name = if kind.1 {
+            format!("`{}`", kind.0)
+        } else {
+            format!("`\"{}\"`", kind.0)
+        };
+        let message = match field {
+            Some(field) => format!(
+                "the `{}` field of `{}` nodes cannot contain {} nodes",
+                field, parent, child_name
+            ),
+            None => format!("`{}` nodes cannot have {} children", parent, child_name),
+        };
+        self.add_error(child, message);
+    }
+
+    fn add_error(&mut self, pattern: &Pattern, message: String) {
+        let offset = pattern.byte_range.start;
+        let line_start = self.source[..offset].rfind('\n').map_or(0, |i| i + 1);
+        self.result.push(ImpossiblePattern {
+            pattern_index: self.pattern_index,
+            byte_range: pattern.byte_range.clone(),
+            row: self.source[..offset].matches('\n').count(),
+            column: offset - line_start,
+            message,
+        });
+    }
+}
@@ -21,7 +21,7 @@ extern "C" {
  * The Tree-sitter library is generally backwards-compatible with languages
  * generated using older CLI versions, but is not forwards-compatible.
  */
-#define TREE_SITTER_LANGUAGE_VERSION 13
+#define TREE_SITTER_LANGUAGE_VERSION 14
 
 /**
  * The earliest ABI version that is supported by the current version of the
@@ -33,6 +33,7 @@ extern "C" {
 /* Section - Types */
 /*******************/
 
+typedef uint16_t TSStateId;
 typedef uint16_t TSSymbol;
 typedef uint16_t TSFieldId;
 typedef struct TSLanguage TSLanguage;
@@ -40,6 +41,7 @@ typedef struct TSParser TSParser;
 typedef struct TSTree TSTree;
 typedef struct TSQuery TSQuery;
 typedef struct TSQueryCursor TSQueryCursor;
+typedef struct TSLookaheadIterator TSLookaheadIterator;
 
 typedef enum {
   TSInputEncodingUTF8,
@@ -80,6 +82,18 @@ typedef struct {
   void (*log)(void *payload, TSLogType, const char *);
 } TSLogger;
 
+typedef struct {
+  void *payload;
+  bool (*progress)(void *payload, uint32_t byte_offset);
+} TSParseProgressCallback;
+
+typedef enum {
+  TSParseHaltReasonNone,
+  TSParseHaltReasonTimeout,
+  TSParseHaltReasonCancelled,
+  TSParseHaltReasonPaused,
+} TSParseHaltReason;
+
 typedef struct {
   uint32_t start_byte;
   uint32_t old_end_byte;
@@ -113,6 +127,20 @@ typedef struct {
   const TSQueryCapture *captures;
 } TSQueryMatch;
 
+typedef struct {
+  uint32_t states_created;
+  uint32_t matches_produced;
+  uint32_t matches_dropped;
+} TSQueryPatternStats;
+
+typedef enum {
+  TSQuantifierZero = 0, // must match the array initialization value
+  TSQuantifierZeroOrOne,
+  TSQuantifierZeroOrMore,
+  TSQuantifierOne,
+  TSQuantifierOneOrMore,
+} TSQuantifier;
+
 typedef enum {
   TSQueryPredicateStepTypeDone,
   TSQueryPredicateStepTypeCapture,
@@ -124,6 +152,14 @@ typedef struct {
   uint32_t value_id;
 } TSQueryPredicateStep;
 
+typedef enum {
+  TSTreeDeserializationErrorNone = 0,
+  TSTreeDeserializationErrorInvalidData,
+  TSTreeDeserializationErrorFormatVersion,
+  TSTreeDeserializationErrorLanguageVersion,
+  TSTreeDeserializationErrorLanguage,
+} TSTreeDeserializationError;
+
 typedef enum {
   TSQueryErrorNone = 0,
   TSQueryErrorSyntax,
@@ -242,6 +278,13 @@ const TSRange *ts_parser_included_ranges(
  *    earlier call to `ts_parser_set_cancellation_flag`. You can resume parsing
  *    from where the parser left out by calling `ts_parser_parse` again with
  *    the same arguments.
+ * 4. Parsing was paused by a progress callback that was set by an earlier
+ *    call to `ts_parser_set_progress_callback`. You can resume parsing in the
+ *    same way as after a timeout.
+ *
+ * Use `ts_parser_halt_reason` to distinguish between the last three cases.
+ * When resuming, the `old_tree` argument is ignored, because the parser
+ * continues to use the tree that was passed to the call that halted.
  */
 TSTree *ts_parser_parse(
   TSParser *self,
@@ -315,6 +358,35 @@ void ts_parser_set_cancellation_flag(TSParser *self, const size_t *flag);
  */
 const size_t *ts_parser_cancellation_flag(const TSParser *self);
 
+/**
+ * Set the callback that a parser should use to report its progress during
+ * parsing.
+ *
+ * The callback is invoked periodically with the byte offset that the parser
+ * has reached. If it returns true, the parser will halt early, returning NULL,
+ * and the parse can be resumed later. See `ts_parser_parse` for more
+ * information.
+ *
+ * The parser does not take ownership over the callback payload. If a callback
+ * was previously assigned, the caller is responsible for releasing any memory
+ * owned by the previous callback.
+ */
+void ts_parser_set_progress_callback(TSParser *self, TSParseProgressCallback callback);
+
+/**
+ * Get the parser's current progress callback.
+ */
+TSParseProgressCallback ts_parser_progress_callback(const TSParser *self);
+
+/**
+ * Get the reason that the parser's most recent call to `ts_parser_parse` halted
+ * before finishing.
+ *
+ * This returns `TSParseHaltReasonNone` if the last parse completed, or if the
+ * parser has been reset since then.
+ */
+TSParseHaltReason ts_parser_halt_reason(const TSParser *self);
+
 /**
  * Set the logger that a parser should use during parsing.
  *
@@ -344,8 +416,9 @@ void ts_parser_print_dot_graphs(TSParser *self, int file);
 /**
  * Create a shallow copy of the syntax tree. This is very fast.
  *
- * You need to copy a syntax tree in order to use it on more than one thread at
- * a time, as syntax trees are not thread safe.
+ * A syntax tree can be read from several threads at once, for example by
+ * running a separate query cursor on each thread. You need to copy a syntax
+ * tree in order to edit it while it is being used on another thread.
  */
 TSTree *ts_tree_copy(const TSTree *self);
 
@@ -398,6 +471,34 @@ TSRange *ts_tree_get_changed_ranges(
  */
 void ts_tree_print_dot_graph(const TSTree *, FILE *);
 
+/**
+ * Serialize the syntax tree into a compact binary format, so that it can
+ * be stored and later restored with `ts_tree_deserialize`.
+ *
+ * The returned buffer is allocated using `malloc` and the caller is
+ * responsible for freeing it using `free`. The length of the buffer will
+ * be written to the given `length` pointer.
+ */
+char *ts_tree_serialize(const TSTree *self, uint32_t *length);
+
+/**
+ * Restore a syntax tree that was serialized with `ts_tree_serialize`.
+ *
+ * The tree must be restored using the same language that was used to parse
+ * it. The restored tree can be passed as the `old_tree` when parsing, in
+ * order to reparse an edited document incrementally.
+ *
+ * If the data cannot be loaded, this returns `NULL` and writes the reason
+ * to the `error` parameter. Trees that were produced by a different version
+ * of the language are rejected with `TSTreeDeserializationErrorLanguageVersion`.
+ */
+TSTree *ts_tree_deserialize(
+  const TSLanguage *language,
+  const char *data,
+  uint32_t length,
+  TSTreeDeserializationError *error
+);
+
 /******************/
 /* Section - Node */
 /******************/
@@ -412,6 +513,19 @@ const char *ts_node_type(TSNode);
  */
 TSSymbol ts_node_symbol(TSNode);
 
+/**
+ * Get the node's type as it appears in the grammar ignoring aliases as a
+ * null-terminated string.
+ */
+const char *ts_node_grammar_type(TSNode);
+
+/**
+ * Get the node's type as a numerical id as it appears in the grammar ignoring
+ * aliases. This should be used in `ts_language_next_state` instead of
+ * `ts_node_symbol`.
+ */
+TSSymbol ts_node_grammar_symbol(TSNode);
+
 /**
  * Get the node's start byte.
  */
@@ -476,6 +590,16 @@ bool ts_node_has_changes(TSNode);
  */
 bool ts_node_has_error(TSNode);
 
+/**
+ * Get this node's parse state.
+ */
+TSStateId ts_node_parse_state(TSNode);
+
+/**
+ * Get the parse state after this node.
+ */
+TSStateId ts_node_next_parse_state(TSNode);
+
 /**
  * Get the node's immediate parent.
  */
@@ -635,6 +759,18 @@ bool ts_tree_cursor_goto_parent(TSTreeCursor *);
  */
 bool ts_tree_cursor_goto_next_sibling(TSTreeCursor *);
 
+/**
+ * Move the cursor to the previous sibling of its current node.
+ *
+ * This returns `true` if the cursor successfully moved, and returns `false`
+ * if there was no previous sibling node.
+ *
+ * Note that this function may be slower than
+ * `ts_tree_cursor_goto_next_sibling`, because the position of the previous
+ * sibling must be recomputed by scanning from the start of the parent node.
+ */
+bool ts_tree_cursor_goto_previous_sibling(TSTreeCursor *);
+
 /**
  * Move the cursor to the first child of its current node.
  *
@@ -643,6 +779,41 @@ bool ts_tree_cursor_goto_next_sibling(TSTreeCursor *);
  */
 bool ts_tree_cursor_goto_first_child(TSTreeCursor *);
 
+/**
+ * Move the cursor to the last child of its current node.
+ *
+ * This returns `true` if the cursor successfully moved, and returns `false`
+ * if there were no children.
+ *
+ * Note that this function may be slower than `ts_tree_cursor_goto_first_child`,
+ * because it needs to iterate through all the children to compute the child's
+ * position.
+ */
+bool ts_tree_cursor_goto_last_child(TSTreeCursor *);
+
+/**
+ * Move the cursor to the node that is the nth descendant of the original node
+ * that the cursor was constructed with, where zero represents the original
+ * node itself.
+ *
+ * This returns `true` if the cursor successfully moved, and returns `false` if
+ * the original node has no descendant with the given index. In that case, the
+ * cursor is not moved.
+ */
+bool ts_tree_cursor_goto_descendant(TSTreeCursor *, uint32_t);
+
+/**
+ * Get the index of the cursor's current node out of all of the descendants of
+ * the original node that the cursor was constructed with.
+ */
+uint32_t ts_tree_cursor_current_descendant_index(const TSTreeCursor *);
+
+/**
+ * Get the depth of the cursor's current node relative to the original node
+ * that the cursor was constructed with.
+ */
+uint32_t ts_tree_cursor_current_depth(const TSTreeCursor *);
+
 /**
  * Move the cursor to the first child of its current node that extends beyond
  * the given byte offset.
@@ -652,6 +823,15 @@ bool ts_tree_cursor_goto_first_child(TSTreeCursor *);
  */
 int64_t ts_tree_cursor_goto_first_child_for_byte(TSTreeCursor *, uint32_t);
 
+/**
+ * Move the cursor to the first child of its current node that extends beyond
+ * the given point.
+ *
+ * This returns the index of the child node if one was found, and returns -1
+ * if no such child was found.
+ */
+int64_t ts_tree_cursor_goto_first_child_for_point(TSTreeCursor *, TSPoint);
+
 TSTreeCursor ts_tree_cursor_copy(const TSTreeCursor *);
 
 /*******************/
@@ -677,6 +857,21 @@ TSQuery *ts_query_new(
   TSQueryError *error_type
 );
 
+/**
+ * Create a new query like `ts_query_new`, but without failing when some of
+ * the patterns can never match, given the structure of the language's grammar.
+ * Instead, those patterns are disabled, and they can be identified using
+ * `ts_query_is_pattern_impossible`. Other errors are reported in the same way
+ * as in `ts_query_new`.
+ */
+TSQuery *ts_query_new_lenient(
+  const TSLanguage *language,
+  const char *source,
+  uint32_t source_len,
+  uint32_t *error_offset,
+  TSQueryError *error_type
+);
+
 /**
  * Delete a query, freeing all of the memory that it used.
  */
@@ -719,6 +914,60 @@ const TSQueryPredicateStep *ts_query_predicates_for_pattern(
   uint32_t *length
 );
 
+/**
+ * Check if the given pattern in the query has a single root node.
+ */
+bool ts_query_is_pattern_rooted(
+  const TSQuery *self,
+  uint32_t pattern_index
+);
+
+/**
+ * Check if the given pattern in the query is 'non-local'.
+ *
+ * A non-local pattern has multiple root nodes, and can match a sequence of
+ * sibling nodes that the grammar produces using a repetition. Such a pattern
+ * can span any number of siblings, so it can't be matched by looking at a
+ * single node and its descendants.
+ */
+bool ts_query_is_pattern_non_local(
+  const TSQuery *self,
+  uint32_t pattern_index
+);
+
+/**
+ * Check if the given pattern in the query can never match, given the
+ * structure of the language's grammar. This can only be true for queries
+ * created with `ts_query_new_lenient`. Impossible patterns are disabled.
+ */
+bool ts_query_is_pattern_impossible(
+  const TSQuery *self,
+  uint32_t pattern_index
+);
+
+/**
+ * Get the number of steps in the given pattern, and the byte offset of one of
+ * those steps in the query's source.
+ *
+ * Each step corresponds to a node or an alternation within the pattern. The
+ * byte offsets can be passed to `ts_query_step_is_definite`.
+ */
+uint32_t ts_query_step_count_for_pattern(
+  const TSQuery *self,
+  uint32_t pattern_index
+);
+uint32_t ts_query_step_offset_for_pattern(
+  const TSQuery *self,
+  uint32_t pattern_index,
+  uint32_t step_index
+);
+
+/**
+ * Check if the step at the given byte offset in the query is 'definite'.
+ *
+ * A step is definite if its pattern is guaranteed to match successfully once
+ * it has reached that step.
+ */
 bool ts_query_step_is_definite(
   const TSQuery *self,
   uint32_t byte_offset
@@ -734,6 +983,17 @@ const char *ts_query_capture_name_for_id(
   uint32_t id,
   uint32_t *length
 );
+
+/**
+ * Get the quantifier of the query's captures. Each capture is associated
+ * with a numeric id based on the order that it appeared in the query's source.
+ */
+TSQuantifier ts_query_capture_quantifier_for_id(
+  const TSQuery *,
+  uint32_t pattern_id,
+  uint32_t capture_id
+);
+
 const char *ts_query_string_value_for_id(
   const TSQuery *,
   uint32_t id,
@@ -792,15 +1052,43 @@ void ts_query_cursor_delete(TSQueryCursor *);
 void ts_query_cursor_exec(TSQueryCursor *, const TSQuery *, TSNode);
 
 /**
- * Check if this cursor has exceeded its maximum number of in-progress
- * matches.
+ * Manage the maximum number of in-progress matches allowed by this query
+ * cursor.
  *
- * Currently, query cursors have a fixed capacity for storing lists
- * of in-progress captures. If this capacity is exceeded, then the
- * earliest-starting match will silently be dropped to make room for
- * further matches.
+ * Query cursors have a limited capacity for storing lists of in-progress
+ * captures. If this capacity is exceeded, then the earliest-starting match
+ * will silently be dropped to make room for further matches. The limit
+ * defaults to 32, and can be at most 65535.
  */
 bool ts_query_cursor_did_exceed_match_limit(const TSQueryCursor *);
+uint32_t ts_query_cursor_match_limit(const TSQueryCursor *);
+void ts_query_cursor_set_match_limit(TSQueryCursor *, uint32_t);
+
+/**
+ * Set the maximum depth, relative to the node passed to `ts_query_cursor_exec`,
+ * at which the query cursor will start matching patterns. A depth of zero only
+ * allows patterns to match the node itself. By default, there is no limit,
+ * which is represented by `UINT32_MAX`.
+ */
+void ts_query_cursor_set_max_start_depth(TSQueryCursor *, uint32_t);
+uint32_t ts_query_cursor_max_start_depth(const TSQueryCursor *);
+
+/**
+ * Get statistics about the work done by the cursor since the last call to
+ * `ts_query_cursor_exec`.
+ *
+ * `ts_query_cursor_nodes_visited` returns the number of nodes that the cursor
+ * has processed. `ts_query_cursor_pattern_stats` returns an array with one
+ * element per pattern in the query, and writes the length of the array to
+ * the `length` pointer. Each element counts the states that were created for
+ * the pattern, the matches that were produced for it, and the in-progress
+ * matches that were dropped because of the match limit.
+ */
+uint32_t ts_query_cursor_nodes_visited(const TSQueryCursor *);
+const TSQueryPatternStats *ts_query_cursor_pattern_stats(
+  const TSQueryCursor *,
+  uint32_t *length
+);
 
 /**
  * Set the range of bytes or (row, column) positions in which the query
@@ -886,6 +1174,106 @@ TSSymbolType ts_language_symbol_type(const TSLanguage *, TSSymbol);
  */
 uint32_t ts_language_version(const TSLanguage *);
 
+/**
+ * Get a list of all supertype symbols for the language.
+ *
+ * The length of the returned array is written to `length`. Languages that were
+ * generated with an ABI version older than 14 do not contain any supertype
+ * information, so this returns `NULL` for them.
+ */
+const TSSymbol *ts_language_supertypes(const TSLanguage *self, uint32_t *length);
+
+/**
+ * Get a list of all subtype symbol ids for a given supertype symbol.
+ *
+ * The subtypes are listed as they appear in the `node-types.json` file, so a
+ * subtype may itself be a supertype. The length of the returned array is
+ * written to `length`. If the given symbol is not a supertype, this returns
+ * `NULL`.
+ */
+const TSSymbol *ts_language_subtypes(
+  const TSLanguage *self,
+  TSSymbol supertype,
+  uint32_t *length
+);
+
+/**
+ * Get the number of valid states in this language.
+ */
+uint32_t ts_language_state_count(const TSLanguage *);
+
+/**
+ * Get the next parse state. Combine this with lookahead iterators to generate
+ * completion suggestions or valid symbols in error nodes. Use
+ * `ts_node_grammar_symbol` for valid symbols.
+ */
+TSStateId ts_language_next_state(const TSLanguage *, TSStateId, TSSymbol);
+
+/********************************/
+/* Section - Lookahead Iterator */
+/********************************/
+
+/**
+ * Create a new lookahead iterator for the given language and parse state.
+ *
+ * This returns `NULL` if state is invalid for the language.
+ *
+ * Repeatedly using `ts_lookahead_iterator_next` and
+ * `ts_lookahead_iterator_current_symbol` will generate valid symbols in the
+ * given parse state. Newly created lookahead iterators will contain the `ERROR`
+ * symbol.
+ *
+ * Lookahead iterators can be useful to generate suggestions and improve syntax
+ * error diagnostics. To get symbols valid in an ERROR node, use the lookahead
+ * iterator on its first leaf node state. For `MISSING` nodes, a lookahead
+ * iterator created on the previous non-extra leaf node may be appropriate.
+ */
+TSLookaheadIterator *ts_lookahead_iterator_new(const TSLanguage *, TSStateId);
+
+/**
+ * Delete a lookahead iterator freeing all the memory used.
+ */
+void ts_lookahead_iterator_delete(TSLookaheadIterator *);
+
+/**
+ * Reset the lookahead iterator to another state.
+ *
+ * This returns `true` if the iterator was reset to the given state and `false`
+ * otherwise.
+ */
+bool ts_lookahead_iterator_reset_state(TSLookaheadIterator *, TSStateId);
+
+/**
+ * Reset the lookahead iterator.
+ *
+ * This returns `true` if the language was set successfully and `false`
+ * otherwise.
+ */
+bool ts_lookahead_iterator_reset(TSLookaheadIterator *, const TSLanguage *, TSStateId);
+
+/**
+ * Get the current language of the lookahead iterator.
+ */
+const TSLanguage *ts_lookahead_iterator_language(const TSLookaheadIterator *);
+
+/**
+ * Advance the lookahead iterator to the next symbol.
+ *
+ * This returns `true` if there is a new symbol and `false` otherwise.
+ */
+bool ts_lookahead_iterator_next(TSLookaheadIterator *);
+
+/**
+ * Get the current symbol of the lookahead iterator.
+ */
+TSSymbol ts_lookahead_iterator_current_symbol(const TSLookaheadIterator *);
+
+/**
+ * Get the current symbol type of the lookahead iterator as a null terminated
+ * string.
+ */
+const char *ts_lookahead_iterator_current_symbol_name(const TSLookaheadIterator *);
+
 #ifdef __cplusplus
 }
 #endif
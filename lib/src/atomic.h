@@ -1,6 +1,7 @@
 #ifndef TREE_SITTER_ATOMIC_H_
 #define TREE_SITTER_ATOMIC_H_
 
+#include <stdbool.h>
 #include <stdint.h>
 
 #ifdef __TINYC__
@@ -19,6 +20,16 @@ static inline uint32_t atomic_dec(volatile uint32_t *p) {
   return *p;
 }
 
+static inline bool atomic_try_lock(volatile uint32_t *p) {
+  if (*p) return false;
+  *p = 1;
+  return true;
+}
+
+static inline void atomic_unlock(volatile uint32_t *p) {
+  *p = 0;
+}
+
 #elif defined(_WIN32)
 
 #include <windows.h>
@@ -35,6 +46,14 @@ static inline uint32_t atomic_dec(volatile uint32_t *p) {
   return InterlockedDecrement((long volatile *)p);
 }
 
+static inline bool atomic_try_lock(volatile uint32_t *p) {
+  return InterlockedCompareExchange((long volatile *)p, 1, 0) == 0;
+}
+
+static inline void atomic_unlock(volatile uint32_t *p) {
+  InterlockedExchange((long volatile *)p, 0);
+}
+
 #else
 
 static inline size_t atomic_load(const volatile size_t *p) {
@@ -53,6 +72,14 @@ static inline uint32_t atomic_dec(volatile uint32_t *p) {
   return __sync_sub_and_fetch(p, 1u);
 }
 
+static inline bool atomic_try_lock(volatile uint32_t *p) {
+  return __sync_bool_compare_and_swap(p, 0u, 1u);
+}
+
+static inline void atomic_unlock(volatile uint32_t *p) {
+  __sync_lock_release(p);
+}
+
 #endif
 
 #endif  // TREE_SITTER_ATOMIC_H_
@@ -102,6 +102,8 @@ struct TSParser {
   unsigned accept_count;
   unsigned operation_count;
   const volatile size_t *cancellation_flag;
+  TSParseProgressCallback progress_callback;
+  TSParseHaltReason halt_reason;
   Subtree old_tree;
   TSRangeArray included_range_differences;
   unsigned included_range_difference_index;
@@ -1419,18 +1421,27 @@ static bool ts_parser__advance(
       }
     }
 
-    // If a cancellation flag or a timeout was provided, then check every
-    // time a fixed number of parse actions has been processed.
+    // If a cancellation flag, a timeout, or a progress callback was provided,
+    // then check every time a fixed number of parse actions has been processed.
     if (++self->operation_count == OP_COUNT_PER_TIMEOUT_CHECK) {
       self->operation_count = 0;
     }
-    if (
-      self->operation_count == 0 &&
-      ((self->cancellation_flag && atomic_load(self->cancellation_flag)) ||
-       (!clock_is_null(self->end_clock) && clock_is_gt(clock_now(), self->end_clock)))
-    ) {
-      ts_subtree_release(&self->tree_pool, lookahead);
-      return false;
+    if (self->operation_count == 0) {
+      if (self->cancellation_flag && atomic_load(self->cancellation_flag)) {
+        self->halt_reason = TSParseHaltReasonCancelled;
+      } else if (!clock_is_null(self->end_clock) && clock_is_gt(clock_now(), self->end_clock)) {
+        self->halt_reason = TSParseHaltReasonTimeout;
+      } else if (
+        self->progress_callback.progress &&
+        self->progress_callback.progress(self->progress_callback.payload, position)
+      ) {
+        self->halt_reason = TSParseHaltReasonPaused;
+      }
+      if (self->halt_reason != TSParseHaltReasonNone) {
+        LOG("halt reason:%u", self->halt_reason);
+        ts_subtree_release(&self->tree_pool, lookahead);
+        return false;
+      }
     }
 
     // Process each parse action for the current lookahead token in
@@ -1704,6 +1715,8 @@ TSParser *ts_parser_new(void) {
   self->reusable_node = reusable_node_new();
   self->dot_graph_file = NULL;
   self->cancellation_flag = NULL;
+  self->progress_callback = (TSParseProgressCallback) {NULL, NULL};
+  self->halt_reason = TSParseHaltReasonNone;
   self->timeout_duration = 0;
   self->end_clock = clock_null();
   self->operation_count = 0;
@@ -1792,6 +1805,18 @@ void ts_parser_set_cancellation_flag(TSParser *self, const size_t *flag) {
   self->cancellation_flag = (const volatile size_t *)flag;
 }
 
+void ts_parser_set_progress_callback(TSParser *self, TSParseProgressCallback callback) {
+  self->progress_callback = callback;
+}
+
+TSParseProgressCallback ts_parser_progress_callback(const TSParser *self) {
+  return self->progress_callback;
+}
+
+TSParseHaltReason ts_parser_halt_reason(const TSParser *self) {
+  return self->halt_reason;
+}
+
 uint64_t ts_parser_timeout_micros(const TSParser *self) {
   return duration_to_micros(self->timeout_duration);
 }
@@ -1831,6 +1856,7 @@ void ts_parser_reset(TSParser *self) {
     self->finished_tree = NULL_SUBTREE;
   }
   self->accept_count = 0;
+  self->halt_reason = TSParseHaltReasonNone;
 }
 
 TSTree *ts_parser_parse(
@@ -1841,6 +1867,7 @@ TSTree *ts_parser_parse(
   if (!self->language || !input.read) return NULL;
 
   ts_lexer_set_input(&self->lexer, input);
+  self->halt_reason = TSParseHaltReasonNone;
 
   array_clear(&self->included_range_differences);
   self->included_range_difference_index = 0;
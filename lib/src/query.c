@@ -1,7 +1,6 @@
 #include "tree_sitter/api.h"
 #include "./alloc.h"
 #include "./array.h"
-#include "./bits.h"
 #include "./language.h"
 #include "./point.h"
 #include "./tree_cursor.h"
@@ -12,11 +11,12 @@
 // #define LOG(...) fprintf(stderr, __VA_ARGS__)
 #define LOG(...)
 
-#define MAX_CAPTURE_LIST_COUNT 32
+#define DEFAULT_MATCH_LIMIT 32
 #define MAX_STEP_CAPTURE_COUNT 3
 #define MAX_STATE_PREDECESSOR_COUNT 100
 #define MAX_ANALYSIS_STATE_DEPTH 12
 #define MAX_NEGATED_FIELD_COUNT 8
+#define MAX_NEGATED_CHILD_COUNT 8
 
 /*
  * Stream - A sequence of unicode characters derived from a UTF8 string.
@@ -66,6 +66,12 @@ typedef struct {
  *   by other sibling nodes that weren't specified in the pattern.
  * - `is_last_child` - Indicates that the node matching this step cannot have any
  *   subsequent named siblings.
+ *
+ * Steps can also forbid certain children, using the `!` (or "negation") operator:
+ * - `negated_field_list_id` - The index of a zero-terminated list of fields, in
+ *   the query's `negated_fields` array, that the node must not have.
+ * - `negated_child_list_id` - The index of a list of sub-patterns, in the query's
+ *   `negated_child_patterns` array, that must not match any of the node's children.
  */
 typedef struct {
   TSSymbol symbol;
@@ -75,6 +81,7 @@ typedef struct {
   uint16_t depth;
   uint16_t alternative_index;
   uint16_t negated_field_list_id;
+  uint16_t negated_child_list_id;
   bool contains_captures: 1;
   bool is_immediate: 1;
   bool is_last_child: 1;
@@ -116,10 +123,37 @@ typedef struct {
   uint16_t pattern_index;
 } PatternEntry;
 
+/*
+ * CaptureQuantifiers - The quantifier of each of a pattern's captures,
+ * indexed by capture id. Captures that do not appear in the pattern have
+ * the quantifier `TSQuantifierZero`.
+ */
+typedef Array(uint8_t) CaptureQuantifiers;
+
+/*
+ * QueryPattern - Information about one of the patterns in a query. Fields:
+ * - `steps` - The pattern's steps, within the query's `steps` array.
+ * - `predicate_steps` - The pattern's predicates, within the query's
+ *    `predicate_steps` array.
+ * - `step_offsets` - The byte offsets of the pattern's steps, within the
+ *    query's `step_offsets` array.
+ * - `start_byte` - The byte offset where the pattern starts in the source.
+ * - `is_rooted` - Indicates that every match of the pattern has a single root
+ *    node.
+ * - `is_non_local` - Indicates that the pattern has multiple root nodes, and
+ *    that it can match a sequence of siblings that the grammar produces with
+ *    a repetition.
+ * - `is_impossible` - Indicates that the query analysis determined that the
+ *    pattern can never match, given the structure of the grammar.
+ */
 typedef struct {
   Slice steps;
   Slice predicate_steps;
+  Slice step_offsets;
   uint32_t start_byte;
+  bool is_rooted: 1;
+  bool is_non_local: 1;
+  bool is_impossible: 1;
 } QueryPattern;
 
 typedef struct {
@@ -169,13 +203,15 @@ typedef Array(TSQueryCapture) CaptureList;
 /*
  * CaptureListPool - A collection of *lists* of captures. Each query state needs
  * to maintain its own list of captures. To avoid repeated allocations, this struct
- * maintains a fixed set of capture lists, and keeps track of which ones are
- * currently in use by a query state.
+ * maintains a set of capture lists, and keeps track of which ones are currently
+ * in use by a query state. The number of lists is limited by the cursor's match
+ * limit. Lists that are not in use are marked with an invalid size of UINT32_MAX.
  */
 typedef struct {
-  CaptureList list[MAX_CAPTURE_LIST_COUNT];
+  Array(CaptureList) list;
   CaptureList empty_list;
-  uint32_t usage_map;
+  uint32_t max_capture_list_count;
+  uint32_t free_capture_list_count;
 } CaptureListPool;
 
 /*
@@ -238,8 +274,11 @@ struct TSQuery {
   Array(PatternEntry) pattern_map;
   Array(TSQueryPredicateStep) predicate_steps;
   Array(QueryPattern) patterns;
+  Array(CaptureQuantifiers) capture_quantifiers;
   Array(StepOffset) step_offsets;
   Array(TSFieldId) negated_fields;
+  Array(QueryStep) negated_child_steps;
+  Array(uint16_t) negated_child_patterns;
   Array(char) string_buffer;
   const TSLanguage *language;
   uint16_t wildcard_root_pattern_count;
@@ -251,6 +290,7 @@ struct TSQuery {
 struct TSQueryCursor {
   const TSQuery *query;
   TSTreeCursor cursor;
+  TSTreeCursor negation_cursor;
   Array(QueryState) states;
   Array(QueryState) finished_states;
   CaptureListPool capture_list_pool;
@@ -260,6 +300,10 @@ struct TSQueryCursor {
   uint32_t next_state_id;
   TSPoint start_point;
   TSPoint end_point;
+  uint32_t max_start_depth;
+  uint16_t max_root_step_depth;
+  uint32_t nodes_visited;
+  Array(TSQueryPatternStats) pattern_stats;
   bool ascending;
   bool halted;
   bool did_exceed_match_limit;
@@ -357,54 +401,65 @@ static uint32_t stream_offset(Stream *self) {
 
 static CaptureListPool capture_list_pool_new(void) {
   return (CaptureListPool) {
+    .list = array_new(),
     .empty_list = array_new(),
-    .usage_map = UINT32_MAX,
+    .max_capture_list_count = DEFAULT_MATCH_LIMIT,
+    .free_capture_list_count = 0,
   };
 }
 
 static void capture_list_pool_reset(CaptureListPool *self) {
-  self->usage_map = UINT32_MAX;
-  for (unsigned i = 0; i < MAX_CAPTURE_LIST_COUNT; i++) {
-    array_clear(&self->list[i]);
+  for (unsigned i = 0; i < self->list.size; i++) {
+    self->list.contents[i].size = UINT32_MAX;
   }
+  self->free_capture_list_count = self->list.size;
 }
 
 static void capture_list_pool_delete(CaptureListPool *self) {
-  for (unsigned i = 0; i < MAX_CAPTURE_LIST_COUNT; i++) {
-    array_delete(&self->list[i]);
+  for (unsigned i = 0; i < self->list.size; i++) {
+    array_delete(&self->list.contents[i]);
   }
+  array_delete(&self->list);
 }
 
 static const CaptureList *capture_list_pool_get(const CaptureListPool *self, uint16_t id) {
-  if (id >= MAX_CAPTURE_LIST_COUNT) return &self->empty_list;
-  return &self->list[id];
+  if (id >= self->list.size) return &self->empty_list;
+  return &self->list.contents[id];
 }
 
 static CaptureList *capture_list_pool_get_mut(CaptureListPool *self, uint16_t id) {
-  assert(id < MAX_CAPTURE_LIST_COUNT);
-  return &self->list[id];
+  assert(id < self->list.size);
+  return &self->list.contents[id];
 }
 
 static bool capture_list_pool_is_empty(const CaptureListPool *self) {
-  return self->usage_map == 0;
+  uint32_t used_count = self->list.size - self->free_capture_list_count;
+  return used_count >= self->max_capture_list_count;
 }
 
 static uint16_t capture_list_pool_acquire(CaptureListPool *self) {
-  // In the usage_map bitmask, ones represent free lists, and zeros represent
-  // lists that are in use. A free list id can quickly be found by counting
-  // the leading zeros in the usage map. An id of zero corresponds to the
-  // highest-order bit in the bitmask.
-  uint16_t id = count_leading_zeros(self->usage_map);
-  if (id >= MAX_CAPTURE_LIST_COUNT) return NONE;
-  self->usage_map &= ~bitmask_for_index(id);
-  array_clear(&self->list[id]);
+  if (capture_list_pool_is_empty(self)) return NONE;
+
+  // Reuse a list that was previously allocated, if one is free. Otherwise,
+  // allocate a new one.
+  if (self->free_capture_list_count > 0) {
+    for (unsigned i = 0; i < self->list.size; i++) {
+      if (self->list.contents[i].size == UINT32_MAX) {
+        array_clear(&self->list.contents[i]);
+        self->free_capture_list_count--;
+        return i;
+      }
+    }
+  }
+  uint32_t id = self->list.size;
+  array_push(&self->list, ((CaptureList) array_new()));
   return id;
 }
 
 static void capture_list_pool_release(CaptureListPool *self, uint16_t id) {
-  if (id >= MAX_CAPTURE_LIST_COUNT) return;
-  array_clear(&self->list[id]);
-  self->usage_map |= bitmask_for_index(id);
+  if (id >= self->list.size) return;
+  self->list.contents[id].size = UINT32_MAX;
+  self->free_capture_list_count++;
 }
 
 /**************
@@ -466,6 +521,115 @@ static uint16_t symbol_table_insert_name(
   return self->slices.size - 1;
 }
 
+/*********************
+ * CaptureQuantifiers
+ *********************/
+
+// The quantifier of a capture that appears in a sequence of two sub-patterns,
+// one with the `left` quantifier and the other with the `right` quantifier.
+static TSQuantifier quantifier_add(TSQuantifier left, TSQuantifier right) {
+  switch (left) {
+    case TSQuantifierZero:
+      return right;
+    case TSQuantifierZeroOrOne:
+    case TSQuantifierZeroOrMore:
+      switch (right) {
+        case TSQuantifierZero:
+          return left;
+        case TSQuantifierZeroOrOne:
+        case TSQuantifierZeroOrMore:
+          return TSQuantifierZeroOrMore;
+        case TSQuantifierOne:
+        case TSQuantifierOneOrMore:
+          return TSQuantifierOneOrMore;
+      }
+      break;
+    case TSQuantifierOne:
+      return right == TSQuantifierZero ? TSQuantifierOne : TSQuantifierOneOrMore;
+    case TSQuantifierOneOrMore:
+      return TSQuantifierOneOrMore;
+  }
+  return TSQuantifierZero;
+}
+
+// The quantifier of a capture that appears in either one of two alternative
+// sub-patterns, one with the `left` quantifier and the other with the `right`
+// quantifier.
+static TSQuantifier quantifier_join(TSQuantifier left, TSQuantifier right) {
+  bool can_be_zero =
+    left == TSQuantifierZero || left == TSQuantifierZeroOrOne || left == TSQuantifierZeroOrMore ||
+    right == TSQuantifierZero || right == TSQuantifierZeroOrOne || right == TSQuantifierZeroOrMore;
+  bool can_be_one = left != TSQuantifierZero || right != TSQuantifierZero;
+  bool can_be_many =
+    left == TSQuantifierZeroOrMore || left == TSQuantifierOneOrMore ||
+    right == TSQuantifierZeroOrMore || right == TSQuantifierOneOrMore;
+  if (!can_be_one) return TSQuantifierZero;
+  if (can_be_many) return can_be_zero ? TSQuantifierZeroOrMore : TSQuantifierOneOrMore;
+  return can_be_zero ? TSQuantifierZeroOrOne : TSQuantifierOne;
+}
+
+// The quantifier of a capture with the `left` quantifier, within a sub-pattern
+// that is repeated according to the `right` quantifier.
+static TSQuantifier quantifier_mul(TSQuantifier left, TSQuantifier right) {
+  if (left == TSQuantifierZero || right == TSQuantifierZero) return TSQuantifierZero;
+  if (left == TSQuantifierOne) return right;
+  if (right == TSQuantifierOne) return left;
+  bool can_be_zero =
+    left == TSQuantifierZeroOrOne || left == TSQuantifierZeroOrMore ||
+    right == TSQuantifierZeroOrOne || right == TSQuantifierZeroOrMore;
+  bool can_be_many =
+    left == TSQuantifierZeroOrMore || left == TSQuantifierOneOrMore ||
+    right == TSQuantifierZeroOrMore || right == TSQuantifierOneOrMore;
+  if (can_be_many) return can_be_zero ? TSQuantifierZeroOrMore : TSQuantifierOneOrMore;
+  return TSQuantifierZeroOrOne;
+}
+
+static TSQuantifier capture_quantifiers__get(const CaptureQuantifiers *self, uint16_t id) {
+  return id < self->size ? (TSQuantifier)self->contents[id] : TSQuantifierZero;
+}
+
+static void capture_quantifiers__add_for_id(
+  CaptureQuantifiers *self,
+  uint16_t id,
+  TSQuantifier quantifier
+) {
+  if (self->size <= id) array_grow_by(self, id + 1 - self->size);
+  self->contents[id] = quantifier_add(self->contents[id], quantifier);
+}
+
+// Combine the quantifiers of a sub-pattern that appears in sequence with
+// the sub-patterns that have already been recorded.
+static void capture_quantifiers__add_all(
+  CaptureQuantifiers *self,
+  const CaptureQuantifiers *other
+) {
+  if (self->size < other->size) array_grow_by(self, other->size - self->size);
+  for (uint32_t id = 0; id < other->size; id++) {
+    self->contents[id] = quantifier_add(self->contents[id], other->contents[id]);
+  }
+}
+
+// Combine the quantifiers of an alternative sub-pattern with those of the
+// previous alternatives.
+static void capture_quantifiers__join_all(
+  CaptureQuantifiers *self,
+  const CaptureQuantifiers *other
+) {
+  if (self->size < other->size) array_grow_by(self, other->size - self->size);
+  for (uint32_t id = 0; id < self->size; id++) {
+    self->contents[id] = quantifier_join(
+      self->contents[id],
+      capture_quantifiers__get(other, id)
+    );
+  }
+}
+
+static void capture_quantifiers__mul(CaptureQuantifiers *self, TSQuantifier quantifier) {
+  for (uint32_t id = 0; id < self->size; id++) {
+    self->contents[id] = quantifier_mul(self->contents[id], quantifier);
+  }
+}
+
 /************
  * QueryStep
  ************/
@@ -482,6 +646,7 @@ static QueryStep query_step__new(
     .capture_ids = {NONE, NONE, NONE},
     .alternative_index = NONE,
     .negated_field_list_id = 0,
+    .negated_child_list_id = 0,
     .contains_captures = false,
     .is_last_child = false,
     .is_pass_through = false,
@@ -719,6 +884,14 @@ static inline void ts_query__pattern_map_insert(
   }));
 }
 
+// Find the pattern that contains the given step.
+static QueryPattern *ts_query__pattern_for_step(TSQuery *self, uint32_t step_index) {
+  uint32_t index, exists;
+  array_search_sorted_by(&self->patterns, .steps.offset, step_index, &index, &exists);
+  if (!exists) index--;
+  return &self->patterns.contents[index];
+}
+
 static bool ts_query__analyze_patterns(TSQuery *self, unsigned *error_offset) {
   // Identify all of the patterns in the query that have child patterns, both at the
   // top level and nested within other larger patterns. Record the step index where
@@ -737,7 +910,7 @@ static bool ts_query__analyze_patterns(TSQuery *self, unsigned *error_offset) {
         array_push(&parent_step_indices, i);
       }
     }
-    if (step->depth > 0) {
+    if (step->depth > 0 && !step->negated_child_list_id) {
       step->is_definite = true;
     }
   }
@@ -774,7 +947,7 @@ static bool ts_query__analyze_patterns(TSQuery *self, unsigned *error_offset) {
   for (TSStateId state = 1; state < self->language->state_count; state++) {
     unsigned subgraph_index, exists;
     LookaheadIterator lookahead_iterator = ts_language_lookaheads(self->language, state);
-    while (ts_lookahead_iterator_next(&lookahead_iterator)) {
+    while (ts_lookahead_iterator__next(&lookahead_iterator)) {
       if (lookahead_iterator.action_count) {
         for (unsigned i = 0; i < lookahead_iterator.action_count; i++) {
           const TSParseAction *action = &lookahead_iterator.actions[i];
@@ -925,13 +1098,16 @@ static bool ts_query__analyze_patterns(TSQuery *self, unsigned *error_offset) {
     unsigned subgraph_index, exists;
     array_search_sorted_by(&subgraphs, .symbol, parent_symbol, &subgraph_index, &exists);
     if (!exists) {
-      unsigned first_child_step_index = parent_step_index + 1;
-      uint32_t i, exists;
-      array_search_sorted_by(&self->step_offsets, .step_index, first_child_step_index, &i, &exists);
-      assert(exists);
-      *error_offset = self->step_offsets.contents[i].byte_offset;
-      all_patterns_are_valid = false;
-      break;
+      if (all_patterns_are_valid) {
+        unsigned first_child_step_index = parent_step_index + 1;
+        uint32_t i, exists;
+        array_search_sorted_by(&self->step_offsets, .step_index, first_child_step_index, &i, &exists);
+        assert(exists);
+        *error_offset = self->step_offsets.contents[i].byte_offset;
+        all_patterns_are_valid = false;
+      }
+      ts_query__pattern_for_step(self, parent_step_index)->is_impossible = true;
+      continue;
     }
 
     // Initialize an analysis state at every parse state in the table where
@@ -1046,7 +1222,7 @@ static bool ts_query__analyze_patterns(TSQuery *self, unsigned *error_offset) {
         // Follow every possible path in the parse table, but only visit states that
         // are part of the subgraph for the current symbol.
         LookaheadIterator lookahead_iterator = ts_language_lookaheads(self->language, parse_state);
-        while (ts_lookahead_iterator_next(&lookahead_iterator)) {
+        while (ts_lookahead_iterator__next(&lookahead_iterator)) {
           TSSymbol sym = lookahead_iterator.symbol;
 
           TSStateId next_parse_state;
@@ -1239,17 +1415,121 @@ static bool ts_query__analyze_patterns(TSQuery *self, unsigned *error_offset) {
       }
     }
 
-    // If this pattern cannot match, store the pattern index so that it can be
-    // returned to the caller.
-    if (all_patterns_are_valid && !can_finish_pattern && !did_exceed_max_depth) {
+    // If this pattern cannot match, mark it as impossible. Store the offset of
+    // the first impossible step so that it can be returned to the caller.
+    if (!can_finish_pattern && !did_exceed_max_depth) {
       assert(final_step_indices.size > 0);
-      uint16_t impossible_step_index = *array_back(&final_step_indices);
-      uint32_t i, exists;
-      array_search_sorted_by(&self->step_offsets, .step_index, impossible_step_index, &i, &exists);
-      if (i >= self->step_offsets.size) i = self->step_offsets.size - 1;
-      *error_offset = self->step_offsets.contents[i].byte_offset;
-      all_patterns_are_valid = false;
-      break;
+      if (all_patterns_are_valid) {
+        uint16_t impossible_step_index = *array_back(&final_step_indices);
+        uint32_t i, exists;
+        array_search_sorted_by(&self->step_offsets, .step_index, impossible_step_index, &i, &exists);
+        if (i >= self->step_offsets.size) i = self->step_offsets.size - 1;
+        *error_offset = self->step_offsets.contents[i].byte_offset;
+        all_patterns_are_valid = false;
+      }
+      ts_query__pattern_for_step(self, parent_step_index)->is_impossible = true;
+    }
+  }
+
+  // For each pattern that has multiple root nodes, determine whether its first
+  // node can occur within a repetition in the grammar. If so, then the pattern
+  // can match a sequence of sibling nodes that are produced by that repetition.
+  // Repetitions are represented by hidden, unnamed symbols. The children of
+  // hidden nodes appear to belong to their parent, so also search through any
+  // hidden nodes that can begin in the same parse state as the repetition.
+  Array(uint16_t) subgraph_stack = array_new();
+  Array(uint16_t) visited_subgraph_indices = array_new();
+  for (unsigned i = 0; i < self->pattern_map.size; i++) {
+    PatternEntry *pattern_entry = &self->pattern_map.contents[i];
+    QueryPattern *pattern = &self->patterns.contents[pattern_entry->pattern_index];
+    if (pattern->is_rooted || pattern->is_non_local) continue;
+
+    // If the first node is a wildcard, then it can occur anywhere.
+    const QueryStep *first_step = &self->steps.contents[pattern_entry->step_index];
+    if (
+      first_step->depth != 0 ||
+      first_step->symbol == WILDCARD_SYMBOL ||
+      first_step->symbol == NAMED_WILDCARD_SYMBOL
+    ) {
+      pattern->is_non_local = true;
+      continue;
+    }
+
+    for (unsigned j = 0; j < subgraphs.size && !pattern->is_non_local; j++) {
+      AnalysisSubgraph *repetition_subgraph = &subgraphs.contents[j];
+      TSSymbolMetadata metadata = ts_language_symbol_metadata(self->language, repetition_subgraph->symbol);
+      if (metadata.visible || metadata.named) continue;
+
+      for (unsigned k = 0; k < repetition_subgraph->start_states.size && !pattern->is_non_local; k++) {
+        TSStateId parse_state = repetition_subgraph->start_states.contents[k];
+        array_clear(&subgraph_stack);
+        array_clear(&visited_subgraph_indices);
+        array_push(&subgraph_stack, j);
+        array_push(&visited_subgraph_indices, j);
+        while (subgraph_stack.size > 0 && !pattern->is_non_local) {
+          const AnalysisSubgraph *subgraph = &subgraphs.contents[array_pop(&subgraph_stack)];
+          LookaheadIterator lookahead_iterator = ts_language_lookaheads(self->language, parse_state);
+          while (ts_lookahead_iterator__next(&lookahead_iterator) && !pattern->is_non_local) {
+            TSSymbol sym = lookahead_iterator.symbol;
+
+            TSStateId next_parse_state;
+            if (lookahead_iterator.action_count) {
+              const TSParseAction *action = &lookahead_iterator.actions[lookahead_iterator.action_count - 1];
+              if (action->type != TSParseActionTypeShift) continue;
+
+              // Extra nodes can occur anywhere within the repetition.
+              if (action->shift.extra) {
+                if (self->language->public_symbol_map[sym] == first_step->symbol) {
+                  pattern->is_non_local = true;
+                }
+                continue;
+              }
+              next_parse_state = action->shift.state;
+            } else if (lookahead_iterator.next_state != 0) {
+              next_parse_state = lookahead_iterator.next_state;
+            } else {
+              continue;
+            }
+
+            // Only consider nodes that can be the first child of the current subgraph's symbol.
+            AnalysisSubgraphNode successor = {
+              .state = next_parse_state,
+              .child_index = 1,
+            };
+            unsigned node_index, exists;
+            array_search_sorted_with(
+              &subgraph->nodes,
+              analysis_subgraph_node__compare, &successor,
+              &node_index, &exists
+            );
+            while (node_index < subgraph->nodes.size) {
+              const AnalysisSubgraphNode *node = &subgraph->nodes.contents[node_index++];
+              if (node->state != successor.state || node->child_index != successor.child_index) break;
+
+              TSSymbol alias = ts_language_alias_at(self->language, node->production_id, 0);
+              TSSymbol visible_symbol = alias
+                ? alias
+                : self->language->symbol_metadata[sym].visible
+                  ? self->language->public_symbol_map[sym]
+                  : 0;
+              if (visible_symbol) {
+                if (visible_symbol == first_step->symbol) {
+                  pattern->is_non_local = true;
+                  break;
+                }
+              } else if (sym >= self->language->token_count) {
+                unsigned child_subgraph_index, index;
+                array_search_sorted_by(&subgraphs, .symbol, sym, &child_subgraph_index, &exists);
+                if (!exists) continue;
+                array_search_sorted_by(&visited_subgraph_indices, , child_subgraph_index, &index, &exists);
+                if (exists) continue;
+                array_insert(&visited_subgraph_indices, index, child_subgraph_index);
+                array_push(&subgraph_stack, child_subgraph_index);
+              }
+            }
+          }
+        }
+      }
     }
   }
 
@@ -1359,6 +1639,8 @@ static bool ts_query__analyze_patterns(TSQuery *self, unsigned *error_offset) {
   array_delete(&final_step_indices);
   array_delete(&parent_step_indices);
   array_delete(&predicate_capture_ids);
+  array_delete(&subgraph_stack);
+  array_delete(&visited_subgraph_indices);
   state_predecessor_map_delete(&predecessor_map);
 
   return all_patterns_are_valid;
@@ -1381,6 +1663,33 @@ static void ts_query__finalize_steps(TSQuery *self) {
   }
 }
 
+// Mark the steps that can match the last child within the child pattern that
+// starts at the given step. If the child pattern is an alternation or a grouped
+// sequence, then this is the last step at the child depth within each branch.
+static void ts_query__mark_last_child_steps(
+  TSQuery *self,
+  uint32_t start_step_index,
+  uint16_t child_depth
+) {
+  for (uint32_t i = start_step_index; i < self->steps.size; i++) {
+    QueryStep *step = &self->steps.contents[i];
+    if (step->depth != child_depth || step->is_pass_through || step->is_dead_end) continue;
+
+    uint32_t next_step_index = i + 1;
+    while (next_step_index < self->steps.size) {
+      QueryStep *next_step = &self->steps.contents[next_step_index];
+      if (next_step->depth <= child_depth && !next_step->is_pass_through) break;
+      next_step_index++;
+    }
+    if (
+      next_step_index == self->steps.size ||
+      self->steps.contents[next_step_index].is_dead_end
+    ) {
+      step->is_last_child = true;
+    }
+  }
+}
+
 static void ts_query__add_negated_fields(
   TSQuery *self,
   uint16_t step_index,
@@ -1433,6 +1742,76 @@ static void ts_query__add_negated_fields(
   array_push(&self->negated_fields, 0);
 }
 
+static TSQueryError ts_query__parse_pattern(
+  TSQuery *self,
+  Stream *stream,
+  uint32_t depth,
+  bool is_immediate,
+  CaptureQuantifiers *capture_quantifiers
+);
+
+// Parse a negated child pattern, which must not match any child of its parent
+// node. The pattern is parsed like any other child pattern, but then its steps
+// are moved into the `negated_child_steps` array, followed by a step that marks
+// the end of the pattern. Those steps are only used to check the children of a
+// node that matches the parent step. The index of the pattern's first step is
+// written to `step_index`.
+//
+// Negated patterns are matched directly against the children, so they can't
+// contain captures, quantifiers, alternations, or anchors.
+static TSQueryError ts_query__parse_negated_child_pattern(
+  TSQuery *self,
+  Stream *stream,
+  uint32_t depth,
+  uint16_t *step_index
+) {
+  const char *pattern_start = stream->input;
+  uint32_t start_step_index = self->steps.size;
+  uint32_t start_predicate_step_index = self->predicate_steps.size;
+  CaptureQuantifiers capture_quantifiers = array_new();
+  TSQueryError e = ts_query__parse_pattern(self, stream, depth, false, &capture_quantifiers);
+  array_delete(&capture_quantifiers);
+  if (e == PARENT_DONE) return TSQueryErrorSyntax;
+  if (e) return e;
+
+  // Predicates would apply to the enclosing pattern, and don't produce any steps.
+  if (
+    self->steps.size == start_step_index ||
+    self->predicate_steps.size != start_predicate_step_index
+  ) {
+    stream_reset(stream, pattern_start);
+    return TSQueryErrorSyntax;
+  }
+  for (unsigned i = start_step_index; i < self->steps.size; i++) {
+    QueryStep *step = &self->steps.contents[i];
+    if (
+      step->capture_ids[0] != NONE ||
+      step->alternative_index != NONE ||
+      step->is_immediate ||
+      step->is_last_child ||
+      (i > start_step_index && step->depth <= depth)
+    ) {
+      stream_reset(stream, pattern_start);
+      return TSQueryErrorSyntax;
+    }
+  }
+
+  *step_index = self->negated_child_steps.size;
+  for (unsigned i = start_step_index; i < self->steps.size; i++) {
+    QueryStep step = self->steps.contents[i];
+    step.depth -= depth;
+    array_push(&self->negated_child_steps, step);
+  }
+  array_push(&self->negated_child_steps, query_step__new(0, PATTERN_DONE_MARKER, false));
+
+  self->steps.size = start_step_index;
+  while (
+    self->step_offsets.size > 0 &&
+    array_back(&self->step_offsets)->step_index >= start_step_index
+  ) self->step_offsets.size--;
+  return 0;
+}
+
 static TSQueryError ts_query__parse_string_literal(
   TSQuery *self,
   Stream *stream
@@ -1597,7 +1976,8 @@ static TSQueryError ts_query__parse_pattern(
   TSQuery *self,
   Stream *stream,
   uint32_t depth,
-  bool is_immediate
+  bool is_immediate,
+  CaptureQuantifiers *capture_quantifiers
 ) {
   if (stream->next == 0) return TSQueryErrorSyntax;
   if (stream->next == ')' || stream->next == ']') return PARENT_DONE;
@@ -1621,29 +2001,58 @@ static TSQueryError ts_query__parse_pattern(
     stream_skip_whitespace(stream);
 
     // Parse each branch, and add a placeholder step in between the branches.
+    // A capture's quantifier is the combination of its quantifiers in each branch.
+    // Each branch can have its own anchor, so that only that branch must
+    // immediately follow the previous sibling.
     Array(uint32_t) branch_step_indices = array_new();
+    CaptureQuantifiers branch_capture_quantifiers = array_new();
     for (;;) {
+      bool branch_is_immediate = is_immediate;
+      bool branch_has_anchor = false;
+      if (stream->next == '.') {
+        branch_is_immediate = true;
+        branch_has_anchor = true;
+        stream_advance(stream);
+        stream_skip_whitespace(stream);
+      }
+
       uint32_t start_index = self->steps.size;
       TSQueryError e = ts_query__parse_pattern(
         self,
         stream,
         depth,
-        is_immediate
+        branch_is_immediate,
+        &branch_capture_quantifiers
       );
 
-      if (e == PARENT_DONE && stream->next == ']' && branch_step_indices.size > 0) {
+      if (
+        e == PARENT_DONE &&
+        stream->next == ']' &&
+        branch_step_indices.size > 0 &&
+        !branch_has_anchor
+      ) {
         stream_advance(stream);
         break;
       } else if (e) {
         if (e == PARENT_DONE) e = TSQueryErrorSyntax;
         array_delete(&branch_step_indices);
+        array_delete(&branch_capture_quantifiers);
         return e;
       }
 
+      if (branch_step_indices.size == 0) {
+        array_clear(capture_quantifiers);
+        array_push_all(capture_quantifiers, &branch_capture_quantifiers);
+      } else {
+        capture_quantifiers__join_all(capture_quantifiers, &branch_capture_quantifiers);
+      }
+      array_clear(&branch_capture_quantifiers);
+
       array_push(&branch_step_indices, start_index);
       array_push(&self->steps, query_step__new(0, depth, false));
     }
     (void)array_pop(&self->steps);
+    array_delete(&branch_capture_quantifiers);
 
     // For all of the branches except for the last one, add the subsequent branch as an
     // alternative, and link the end of the branch to the current end of the steps.
@@ -1670,7 +2079,8 @@ static TSQueryError ts_query__parse_pattern(
 
     // If this parenthesis is followed by a node, then it represents a grouped sequence.
     if (stream->next == '(' || stream->next == '"' || stream->next == '[') {
-      bool child_is_immediate = false;
+      bool child_is_immediate = is_immediate;
+      CaptureQuantifiers child_capture_quantifiers = array_new();
       for (;;) {
         if (stream->next == '.') {
           child_is_immediate = true;
@@ -1681,17 +2091,22 @@ static TSQueryError ts_query__parse_pattern(
           self,
           stream,
           depth,
-          child_is_immediate
+          child_is_immediate,
+          &child_capture_quantifiers
         );
         if (e == PARENT_DONE && stream->next == ')') {
           stream_advance(stream);
           break;
         } else if (e) {
+          array_delete(&child_capture_quantifiers);
           return e;
         }
 
+        capture_quantifiers__add_all(capture_quantifiers, &child_capture_quantifiers);
+        array_clear(&child_capture_quantifiers);
         child_is_immediate = false;
       }
+      array_delete(&child_capture_quantifiers);
     }
 
     // A dot/pound character indicates the start of a predicate.
@@ -1785,12 +2200,40 @@ static TSQueryError ts_query__parse_pattern(
       uint16_t last_child_step_index = 0;
       uint16_t negated_field_count = 0;
       TSFieldId negated_field_ids[MAX_NEGATED_FIELD_COUNT];
+      uint16_t negated_child_count = 0;
+      uint16_t negated_child_step_indices[MAX_NEGATED_CHILD_COUNT];
+      CaptureQuantifiers child_capture_quantifiers = array_new();
       for (;;) {
-        // Parse a negated field assertion
         if (stream->next == '!') {
           stream_advance(stream);
+
+          // Parse a negated child pattern. Unlike a negated field, it must
+          // immediately follow the negation operator.
+          if (stream->next == '(' || stream->next == '"') {
+            if (negated_child_count == MAX_NEGATED_CHILD_COUNT) {
+              array_delete(&child_capture_quantifiers);
+              return TSQueryErrorSyntax;
+            }
+            TSQueryError e = ts_query__parse_negated_child_pattern(
+              self,
+              stream,
+              depth + 1,
+              &negated_child_step_indices[negated_child_count]
+            );
+            if (e) {
+              array_delete(&child_capture_quantifiers);
+              return e;
+            }
+            negated_child_count++;
+            continue;
+          }
+
+          // Parse a negated field assertion
           stream_skip_whitespace(stream);
-          if (!stream_is_ident_start(stream)) return TSQueryErrorSyntax;
+          if (!stream_is_ident_start(stream)) {
+            array_delete(&child_capture_quantifiers);
+            return TSQueryErrorSyntax;
+          }
           const char *field_name = stream->input;
           stream_scan_identifier(stream);
           uint32_t length = stream->input - field_name;
@@ -1803,6 +2246,7 @@ static TSQueryError ts_query__parse_pattern(
           );
           if (!field_id) {
             stream->input = field_name;
+            array_delete(&child_capture_quantifiers);
             return TSQueryErrorField;
           }
 
@@ -1827,14 +2271,16 @@ static TSQueryError ts_query__parse_pattern(
           self,
           stream,
           depth + 1,
-          child_is_immediate
+          child_is_immediate,
+          &child_capture_quantifiers
         );
         if (e == PARENT_DONE && stream->next == ')') {
           if (child_is_immediate) {
             if (last_child_step_index == 0) {
+              array_delete(&child_capture_quantifiers);
               return TSQueryErrorSyntax;
             }
-            self->steps.contents[last_child_step_index].is_last_child = true;
+            ts_query__mark_last_child_steps(self, last_child_step_index, depth + 1);
           }
 
           if (negated_field_count) {
@@ -1846,15 +2292,30 @@ static TSQueryError ts_query__parse_pattern(
             );
           }
 
+          if (negated_child_count) {
+            QueryStep *step = &self->steps.contents[starting_step_index];
+            step->negated_child_list_id = self->negated_child_patterns.size;
+            array_extend(
+              &self->negated_child_patterns,
+              negated_child_count,
+              negated_child_step_indices
+            );
+            array_push(&self->negated_child_patterns, NONE);
+          }
+
           stream_advance(stream);
           break;
         } else if (e) {
+          array_delete(&child_capture_quantifiers);
           return e;
         }
 
+        capture_quantifiers__add_all(capture_quantifiers, &child_capture_quantifiers);
+        array_clear(&child_capture_quantifiers);
         last_child_step_index = step_index;
         child_is_immediate = false;
       }
+      array_delete(&child_capture_quantifiers);
     }
   }
 
@@ -1913,7 +2374,8 @@ static TSQueryError ts_query__parse_pattern(
       self,
       stream,
       depth,
-      is_immediate
+      is_immediate,
+      capture_quantifiers
     );
     if (e == PARENT_DONE) return TSQueryErrorSyntax;
     if (e) return e;
@@ -1953,9 +2415,12 @@ static TSQueryError ts_query__parse_pattern(
   stream_skip_whitespace(stream);
 
   // Parse suffixes modifiers for this pattern
+  TSQuantifier quantifier = TSQuantifierOne;
   for (;;) {
     // Parse the one-or-more operator.
     if (stream->next == '+') {
+      quantifier = quantifier_join(TSQuantifierOneOrMore, quantifier);
+
       stream_advance(stream);
       stream_skip_whitespace(stream);
 
@@ -1968,6 +2433,8 @@ static TSQueryError ts_query__parse_pattern(
 
     // Parse the zero-or-more repetition operator.
     else if (stream->next == '*') {
+      quantifier = quantifier_join(TSQuantifierZeroOrMore, quantifier);
+
       stream_advance(stream);
       stream_skip_whitespace(stream);
 
@@ -1986,6 +2453,8 @@ static TSQueryError ts_query__parse_pattern(
 
     // Parse the optional operator.
     else if (stream->next == '?') {
+      quantifier = quantifier_join(TSQuantifierZeroOrOne, quantifier);
+
       stream_advance(stream);
       stream_skip_whitespace(stream);
 
@@ -2012,6 +2481,9 @@ static TSQueryError ts_query__parse_pattern(
         length
       );
 
+      // Add the capture quantifier
+      capture_quantifiers__add_for_id(capture_quantifiers, capture_id, TSQuantifierOne);
+
       uint32_t step_index = starting_step_index;
       for (;;) {
         QueryStep *step = &self->steps.contents[step_index];
@@ -2035,15 +2507,18 @@ static TSQueryError ts_query__parse_pattern(
     }
   }
 
+  capture_quantifiers__mul(capture_quantifiers, quantifier);
+
   return 0;
 }
 
-TSQuery *ts_query_new(
+static TSQuery *ts_query__new(
   const TSLanguage *language,
   const char *source,
   uint32_t source_len,
   uint32_t *error_offset,
-  TSQueryError *error_type
+  TSQueryError *error_type,
+  bool allow_impossible_patterns
 ) {
   TSQuery *self = ts_malloc(sizeof(TSQuery));
   *self = (TSQuery) {
@@ -2053,14 +2528,18 @@ TSQuery *ts_query_new(
     .predicate_values = symbol_table_new(),
     .predicate_steps = array_new(),
     .patterns = array_new(),
+    .capture_quantifiers = array_new(),
     .step_offsets = array_new(),
     .string_buffer = array_new(),
     .negated_fields = array_new(),
+    .negated_child_steps = array_new(),
+    .negated_child_patterns = array_new(),
     .wildcard_root_pattern_count = 0,
     .language = language,
   };
 
   array_push(&self->negated_fields, 0);
+  array_push(&self->negated_child_patterns, NONE);
 
   // Parse all of the S-expressions in the given string.
   Stream stream = stream_new(source, source_len);
@@ -2069,18 +2548,33 @@ TSQuery *ts_query_new(
     uint32_t pattern_index = self->patterns.size;
     uint32_t start_step_index = self->steps.size;
     uint32_t start_predicate_step_index = self->predicate_steps.size;
+    uint32_t start_step_offset_index = self->step_offsets.size;
     array_push(&self->patterns, ((QueryPattern) {
       .steps = (Slice) {.offset = start_step_index},
       .predicate_steps = (Slice) {.offset = start_predicate_step_index},
+      .step_offsets = (Slice) {.offset = start_step_offset_index},
       .start_byte = stream_offset(&stream),
+      .is_rooted = true,
     }));
-    *error_type = ts_query__parse_pattern(self, &stream, 0, false);
+    CaptureQuantifiers capture_quantifiers = array_new();
+    *error_type = ts_query__parse_pattern(self, &stream, 0, false, &capture_quantifiers);
     array_push(&self->steps, query_step__new(0, PATTERN_DONE_MARKER, false));
+    array_push(&self->capture_quantifiers, capture_quantifiers);
 
     QueryPattern *pattern = array_back(&self->patterns);
     pattern->steps.length = self->steps.size - start_step_index;
     pattern->predicate_steps.length = self->predicate_steps.size - start_predicate_step_index;
 
+    // Predicates do not have steps of their own, so omit any step offsets that
+    // refer to the step that marks the end of the pattern.
+    pattern->step_offsets.length = self->step_offsets.size - start_step_offset_index;
+    while (
+      pattern->step_offsets.length > 0 &&
+      self->step_offsets.contents[
+        pattern->step_offsets.offset + pattern->step_offsets.length - 1
+      ].step_index >= self->steps.size - 1
+    ) pattern->step_offsets.length--;
+
     // If any pattern could not be parsed, then report the error information
     // and terminate.
     if (*error_type) {
@@ -2095,13 +2589,29 @@ TSQuery *ts_query_new(
     for (;;) {
       QueryStep *step = &self->steps.contents[start_step_index];
 
+      // Determine whether the pattern has a single root node. If any branch of
+      // the pattern has another step at the root depth, then the pattern matches
+      // a sequence of sibling nodes.
+      for (uint32_t i = start_step_index + 1; i < self->steps.size; i++) {
+        QueryStep *child_step = &self->steps.contents[i];
+        if (child_step->is_dead_end || child_step->depth == PATTERN_DONE_MARKER) break;
+        if (child_step->depth == 0) {
+          pattern->is_rooted = false;
+          break;
+        }
+      }
+
       // If a pattern has a wildcard at its root, but it has a non-wildcard child,
       // then optimize the matching process by skipping matching the wildcard.
       // Later, during the matching process, the query cursor will check that
       // there is a parent node, and capture it if necessary.
       if (step->symbol == WILDCARD_SYMBOL && step->depth == 0) {
         QueryStep *second_step = &self->steps.contents[start_step_index + 1];
-        if (second_step->symbol != WILDCARD_SYMBOL && second_step->depth == 1) {
+        if (
+          second_step->symbol != WILDCARD_SYMBOL &&
+          second_step->depth == 1 &&
+          !step->negated_child_list_id
+        ) {
           wildcard_root_alternative_index = step->alternative_index;
           start_step_index += 1;
           step = second_step;
@@ -2127,10 +2637,19 @@ TSQuery *ts_query_new(
     }
   }
 
+  // If any patterns are impossible, then either report the first one, or
+  // disable all of them.
   if (!ts_query__analyze_patterns(self, error_offset)) {
-    *error_type = TSQueryErrorStructure;
-    ts_query_delete(self);
-    return NULL;
+    if (!allow_impossible_patterns) {
+      *error_type = TSQueryErrorStructure;
+      ts_query_delete(self);
+      return NULL;
+    }
+    for (unsigned i = 0; i < self->patterns.size; i++) {
+      if (self->patterns.contents[i].is_impossible) {
+        ts_query_disable_pattern(self, i);
+      }
+    }
   }
 
   ts_query__finalize_steps(self);
@@ -2138,15 +2657,41 @@ TSQuery *ts_query_new(
   return self;
 }
 
+TSQuery *ts_query_new(
+  const TSLanguage *language,
+  const char *source,
+  uint32_t source_len,
+  uint32_t *error_offset,
+  TSQueryError *error_type
+) {
+  return ts_query__new(language, source, source_len, error_offset, error_type, false);
+}
+
+TSQuery *ts_query_new_lenient(
+  const TSLanguage *language,
+  const char *source,
+  uint32_t source_len,
+  uint32_t *error_offset,
+  TSQueryError *error_type
+) {
+  return ts_query__new(language, source, source_len, error_offset, error_type, true);
+}
+
 void ts_query_delete(TSQuery *self) {
   if (self) {
     array_delete(&self->steps);
     array_delete(&self->pattern_map);
     array_delete(&self->predicate_steps);
     array_delete(&self->patterns);
+    for (unsigned i = 0; i < self->capture_quantifiers.size; i++) {
+      array_delete(&self->capture_quantifiers.contents[i]);
+    }
+    array_delete(&self->capture_quantifiers);
     array_delete(&self->step_offsets);
     array_delete(&self->string_buffer);
     array_delete(&self->negated_fields);
+    array_delete(&self->negated_child_steps);
+    array_delete(&self->negated_child_patterns);
     symbol_table_delete(&self->captures);
     symbol_table_delete(&self->predicate_values);
     ts_free(self);
@@ -2173,6 +2718,18 @@ const char *ts_query_capture_name_for_id(
   return symbol_table_name_for_id(&self->captures, index, length);
 }
 
+TSQuantifier ts_query_capture_quantifier_for_id(
+  const TSQuery *self,
+  uint32_t pattern_index,
+  uint32_t capture_index
+) {
+  if (pattern_index >= self->capture_quantifiers.size) return TSQuantifierZero;
+  return capture_quantifiers__get(
+    &self->capture_quantifiers.contents[pattern_index],
+    capture_index
+  );
+}
+
 const char *ts_query_string_value_for_id(
   const TSQuery *self,
   uint32_t index,
@@ -2201,6 +2758,44 @@ uint32_t ts_query_start_byte_for_pattern(
   return self->patterns.contents[pattern_index].start_byte;
 }
 
+bool ts_query_is_pattern_rooted(
+  const TSQuery *self,
+  uint32_t pattern_index
+) {
+  return self->patterns.contents[pattern_index].is_rooted;
+}
+
+bool ts_query_is_pattern_non_local(
+  const TSQuery *self,
+  uint32_t pattern_index
+) {
+  return self->patterns.contents[pattern_index].is_non_local;
+}
+
+bool ts_query_is_pattern_impossible(
+  const TSQuery *self,
+  uint32_t pattern_index
+) {
+  return self->patterns.contents[pattern_index].is_impossible;
+}
+
+uint32_t ts_query_step_count_for_pattern(
+  const TSQuery *self,
+  uint32_t pattern_index
+) {
+  return self->patterns.contents[pattern_index].step_offsets.length;
+}
+
+uint32_t ts_query_step_offset_for_pattern(
+  const TSQuery *self,
+  uint32_t pattern_index,
+  uint32_t step_index
+) {
+  Slice slice = self->patterns.contents[pattern_index].step_offsets;
+  if (step_index >= slice.length) return 0;
+  return self->step_offsets.contents[slice.offset + step_index].byte_offset;
+}
+
 bool ts_query_step_is_definite(
   const TSQuery *self,
   uint32_t byte_offset
@@ -2244,6 +2839,7 @@ void ts_query_disable_pattern(
   for (unsigned i = 0; i < self->pattern_map.size; i++) {
     PatternEntry *pattern = &self->pattern_map.contents[i];
     if (pattern->pattern_index == pattern_index) {
+      if (i < self->wildcard_root_pattern_count) self->wildcard_root_pattern_count--;
       array_erase(&self->pattern_map, i);
       i--;
     }
@@ -2267,6 +2863,10 @@ TSQueryCursor *ts_query_cursor_new(void) {
     .end_byte = UINT32_MAX,
     .start_point = {0, 0},
     .end_point = POINT_MAX,
+    .max_start_depth = UINT32_MAX,
+    .max_root_step_depth = 0,
+    .nodes_visited = 0,
+    .pattern_stats = array_new(),
   };
   array_reserve(&self->states, 8);
   array_reserve(&self->finished_states, 8);
@@ -2276,7 +2876,9 @@ TSQueryCursor *ts_query_cursor_new(void) {
 void ts_query_cursor_delete(TSQueryCursor *self) {
   array_delete(&self->states);
   array_delete(&self->finished_states);
+  array_delete(&self->pattern_stats);
   ts_tree_cursor_delete(&self->cursor);
+  ts_tree_cursor_delete(&self->negation_cursor);
   capture_list_pool_delete(&self->capture_list_pool);
   ts_free(self);
 }
@@ -2285,6 +2887,37 @@ bool ts_query_cursor_did_exceed_match_limit(const TSQueryCursor *self) {
   return self->did_exceed_match_limit;
 }
 
+uint32_t ts_query_cursor_match_limit(const TSQueryCursor *self) {
+  return self->capture_list_pool.max_capture_list_count;
+}
+
+void ts_query_cursor_set_match_limit(TSQueryCursor *self, uint32_t limit) {
+  // Capture list ids are stored in 16 bits, and the largest id is reserved.
+  if (limit > NONE) limit = NONE;
+  if (limit == 0) limit = 1;
+  self->capture_list_pool.max_capture_list_count = limit;
+}
+
+uint32_t ts_query_cursor_max_start_depth(const TSQueryCursor *self) {
+  return self->max_start_depth;
+}
+
+void ts_query_cursor_set_max_start_depth(TSQueryCursor *self, uint32_t max_start_depth) {
+  self->max_start_depth = max_start_depth;
+}
+
+uint32_t ts_query_cursor_nodes_visited(const TSQueryCursor *self) {
+  return self->nodes_visited;
+}
+
+const TSQueryPatternStats *ts_query_cursor_pattern_stats(
+  const TSQueryCursor *self,
+  uint32_t *length
+) {
+  *length = self->pattern_stats.size;
+  return self->pattern_stats.contents;
+}
+
 void ts_query_cursor_exec(
   TSQueryCursor *self,
   const TSQuery *query,
@@ -2300,6 +2933,19 @@ void ts_query_cursor_exec(
   self->halted = false;
   self->query = query;
   self->did_exceed_match_limit = false;
+  self->nodes_visited = 0;
+  array_clear(&self->pattern_stats);
+  array_grow_by(&self->pattern_stats, query->patterns.size);
+
+  // Find the deepest step at which a pattern can be started, in order to
+  // know when to stop descending because of the maximum start depth.
+  self->max_root_step_depth = 0;
+  for (unsigned i = 0; i < query->pattern_map.size; i++) {
+    const QueryStep *step = &query->steps.contents[query->pattern_map.contents[i].step_index];
+    if (step->depth > self->max_root_step_depth) {
+      self->max_root_step_depth = step->depth;
+    }
+  }
 }
 
 void ts_query_cursor_set_byte_range(
@@ -2449,6 +3095,7 @@ static void ts_query_cursor__add_state(
 ) {
   QueryStep *step = &self->query->steps.contents[pattern->step_index];
   uint32_t start_depth = self->depth - step->depth;
+  if (start_depth > self->max_start_depth) return;
 
   // Keep the states array in ascending order of start_depth and pattern_index,
   // so that it can be processed more efficiently elsewhere. Usually, there is
@@ -2489,6 +3136,7 @@ static void ts_query_cursor__add_state(
     pattern->pattern_index,
     pattern->step_index
   );
+  self->pattern_stats.contents[pattern->pattern_index].states_created++;
   array_insert(&self->states, index, ((QueryState) {
     .capture_list_id = NONE,
     .step_index = pattern->step_index,
@@ -2534,6 +3182,7 @@ static CaptureList *ts_query_cursor__prepare_to_capture(
           state_index, pattern_index, byte_offset
         );
         QueryState *other_state = &self->states.contents[state_index];
+        self->pattern_stats.contents[other_state->pattern_index].matches_dropped++;
         state->capture_list_id = other_state->capture_list_id;
         other_state->capture_list_id = NONE;
         other_state->dead = true;
@@ -2561,6 +3210,7 @@ static void ts_query_cursor__capture(
   if (state->dead) return;
   CaptureList *capture_list = ts_query_cursor__prepare_to_capture(self, state, UINT32_MAX);
   if (!capture_list) {
+    self->pattern_stats.contents[state->pattern_index].matches_dropped++;
     state->dead = true;
     return;
   }
@@ -2594,7 +3244,10 @@ static QueryState *ts_query_cursor__copy_state(
   // If the state has captures, copy its capture list.
   if (state->capture_list_id != NONE) {
     CaptureList *new_captures = ts_query_cursor__prepare_to_capture(self, &copy, state_index);
-    if (!new_captures) return NULL;
+    if (!new_captures) {
+      self->pattern_stats.contents[state->pattern_index].matches_dropped++;
+      return NULL;
+    }
     const CaptureList *old_captures = capture_list_pool_get(
       &self->capture_list_pool,
       state->capture_list_id
@@ -2602,11 +3255,125 @@ static QueryState *ts_query_cursor__copy_state(
     array_push_all(new_captures, old_captures);
   }
 
+  self->pattern_stats.contents[copy.pattern_index].states_created++;
   array_insert(&self->states, state_index + 1, copy);
   *state_ref = &self->states.contents[state_index];
   return &self->states.contents[state_index + 1];
 }
 
+static bool ts_query__has_negated_child(
+  const TSQuery *self,
+  TSTreeCursor *cursor,
+  uint16_t negated_child_list_id
+);
+
+// Determine whether the cursor's current node matches the negated child step
+// at the given index, along with all of that step's descendant steps. Child
+// steps must match the node's children in order, but not necessarily adjacently,
+// so each one can be matched against the first child that satisfies it.
+static bool ts_query__negated_child_step_matches(
+  const TSQuery *self,
+  TSTreeCursor *cursor,
+  uint32_t step_index
+) {
+  const QueryStep *step = &self->negated_child_steps.contents[step_index];
+  TSNode node = ts_tree_cursor_current_node(cursor);
+  TSFieldId field_id = 0;
+  bool has_later_siblings;
+  bool has_later_named_siblings;
+  bool can_have_later_siblings_with_this_field;
+  TSSymbol supertypes[8] = {0};
+  unsigned supertype_count = 8;
+  ts_tree_cursor_current_status(
+    cursor,
+    &field_id,
+    &has_later_siblings,
+    &has_later_named_siblings,
+    &can_have_later_siblings_with_this_field,
+    supertypes,
+    &supertype_count
+  );
+
+  if (step->symbol == NAMED_WILDCARD_SYMBOL) {
+    if (!ts_node_is_named(node)) return false;
+  } else if (step->symbol != WILDCARD_SYMBOL && step->symbol != ts_node_symbol(node)) {
+    return false;
+  }
+  if (step->field && step->field != field_id) return false;
+  if (step->supertype_symbol) {
+    bool has_supertype = false;
+    for (unsigned i = 0; i < supertype_count; i++) {
+      if (supertypes[i] == step->supertype_symbol) {
+        has_supertype = true;
+        break;
+      }
+    }
+    if (!has_supertype) return false;
+  }
+  if (step->negated_field_list_id) {
+    const TSFieldId *negated_field_id = &self->negated_fields.contents[step->negated_field_list_id];
+    for (; *negated_field_id; negated_field_id++) {
+      if (ts_node_child_by_field_id(node, *negated_field_id).id) return false;
+    }
+  }
+  if (
+    step->negated_child_list_id &&
+    ts_query__has_negated_child(self, cursor, step->negated_child_list_id)
+  ) return false;
+
+  uint16_t child_depth = step->depth + 1;
+  uint32_t child_step_index = step_index + 1;
+  if (self->negated_child_steps.contents[child_step_index].depth != child_depth) return true;
+  if (!ts_tree_cursor_goto_first_child(cursor)) return false;
+
+  bool result = false;
+  do {
+    if (ts_query__negated_child_step_matches(self, cursor, child_step_index)) {
+      const QueryStep *child_step;
+      do {
+        child_step = &self->negated_child_steps.contents[++child_step_index];
+      } while (child_step->depth != PATTERN_DONE_MARKER && child_step->depth > child_depth);
+      if (child_step->depth != child_depth) {
+        result = true;
+        break;
+      }
+    }
+  } while (ts_tree_cursor_goto_next_sibling(cursor));
+  ts_tree_cursor_goto_parent(cursor);
+  return result;
+}
+
+// Determine whether any child of the cursor's current node matches one of the
+// negated child patterns in the given list. The cursor is left at the same node.
+static bool ts_query__has_negated_child(
+  const TSQuery *self,
+  TSTreeCursor *cursor,
+  uint16_t negated_child_list_id
+) {
+  if (!ts_tree_cursor_goto_first_child(cursor)) return false;
+  bool result = false;
+  do {
+    const uint16_t *step_index = &self->negated_child_patterns.contents[negated_child_list_id];
+    for (; *step_index != NONE; step_index++) {
+      if (ts_query__negated_child_step_matches(self, cursor, *step_index)) {
+        result = true;
+        break;
+      }
+    }
+  } while (!result && ts_tree_cursor_goto_next_sibling(cursor));
+  ts_tree_cursor_goto_parent(cursor);
+  return result;
+}
+
+static bool ts_query_cursor__has_negated_child(
+  TSQueryCursor *self,
+  TSNode node,
+  uint16_t negated_child_list_id
+) {
+  ts_tree_cursor_reset(&self->negation_cursor, node);
+  return ts_query__has_negated_child(self->query, &self->negation_cursor, negated_child_list_id);
+}
+
 // Walk the tree, processing patterns until at least one pattern finishes,
 // If one or more patterns finish, return `true` and store their states in the
 // `finished_states` array. Multiple patterns can finish on the same node. If
@@ -2654,6 +3421,7 @@ static inline bool ts_query_cursor__advance(
         if (step->depth == PATTERN_DONE_MARKER) {
           if (state->start_depth > self->depth || self->halted) {
             LOG("  finish pattern %u\n", state->pattern_index);
+            self->pattern_stats.contents[state->pattern_index].matches_produced++;
             state->id = self->next_state_id++;
             array_push(&self->finished_states, *state);
             did_match = true;
@@ -2709,6 +3477,8 @@ static inline bool ts_query_cursor__advance(
         continue;
       }
 
+      self->nodes_visited++;
+
       // Get the properties of the current node.
       TSSymbol symbol = ts_node_symbol(node);
       bool is_named = ts_node_is_named(node);
@@ -2829,6 +3599,14 @@ static inline bool ts_query_cursor__advance(
           }
         }
 
+        if (
+          node_does_match &&
+          step->negated_child_list_id &&
+          ts_query_cursor__has_negated_child(self, node, step->negated_child_list_id)
+        ) {
+          node_does_match = false;
+        }
+
         // Remove states immediately if it is ever clear that they cannot match.
         if (!node_does_match) {
           if (!later_sibling_can_match) {
@@ -3041,6 +3819,7 @@ static inline bool ts_query_cursor__advance(
               LOG("  defer finishing pattern %u\n", state->pattern_index);
             } else {
               LOG("  finish pattern %u\n", state->pattern_index);
+              self->pattern_stats.contents[state->pattern_index].matches_produced++;
               state->id = self->next_state_id++;
               array_push(&self->finished_states, *state);
               array_erase(&self->states, state - self->states.contents);
@@ -3051,8 +3830,14 @@ static inline bool ts_query_cursor__advance(
         }
       }
 
-      // Continue descending if possible.
-      if (ts_tree_cursor_goto_first_child(&self->cursor)) {
+      // Continue descending if possible. If there are no in-progress states,
+      // then the node's children only need to be visited if new patterns can
+      // still be started beneath it without exceeding the maximum start depth.
+      bool should_descend =
+        self->states.size > 0 ||
+        (uint64_t)self->depth + 1 <=
+          (uint64_t)self->max_start_depth + self->max_root_step_depth;
+      if (should_descend && ts_tree_cursor_goto_first_child(&self->cursor)) {
         self->depth++;
       } else {
         self->ascending = true;
@@ -3193,6 +3978,9 @@ bool ts_query_cursor_next_capture(
         first_unfinished_pattern_index,
         first_unfinished_capture_byte
       );
+      if (first_unfinished_state_index < self->states.size) {
+        self->pattern_stats.contents[first_unfinished_pattern_index].matches_dropped++;
+      }
       capture_list_pool_release(
         &self->capture_list_pool,
         self->states.contents[first_unfinished_state_index].capture_list_id
@@ -376,6 +376,7 @@ void ts_subtree_summarize_children(
 
   self.ptr->named_child_count = 0;
   self.ptr->visible_child_count = 0;
+  self.ptr->visible_descendant_count = 0;
   self.ptr->error_cost = 0;
   self.ptr->repeat_depth = 0;
   self.ptr->node_count = 1;
@@ -428,13 +429,16 @@ void ts_subtree_summarize_children(
 
     self.ptr->dynamic_precedence += ts_subtree_dynamic_precedence(child);
     self.ptr->node_count += ts_subtree_node_count(child);
+    self.ptr->visible_descendant_count += ts_subtree_visible_descendant_count(child);
 
     if (alias_sequence && alias_sequence[structural_index] != 0 && !ts_subtree_extra(child)) {
+      self.ptr->visible_descendant_count++;
       self.ptr->visible_child_count++;
       if (ts_language_symbol_metadata(language, alias_sequence[structural_index]).named) {
         self.ptr->named_child_count++;
       }
     } else if (ts_subtree_visible(child)) {
+      self.ptr->visible_descendant_count++;
       self.ptr->visible_child_count++;
       if (ts_subtree_named(child)) self.ptr->named_child_count++;
     } else if (grandchild_count > 0) {
@@ -1057,3 +1061,370 @@ bool ts_subtree_external_scanner_state_eq(Subtree self, Subtree other) {
   }
   return ts_external_scanner_state_eq(state1, state2);
 }
+
+// Serialization
+
+enum {
+  SubtreeEncodingInline,
+  SubtreeEncodingHeap,
+};
+
+static void ts_subtree__serialize_length(Length length, SerializationBuffer *buffer) {
+  ts_serialization_buffer_write_u32(buffer, length.bytes);
+  ts_serialization_buffer_write_u32(buffer, length.extent.row);
+  ts_serialization_buffer_write_u32(buffer, length.extent.column);
+}
+
+static bool ts_subtree__deserialize_length(SerializationReader *reader, Length *length) {
+  return
+    ts_serialization_reader_read_u32(reader, &length->bytes) &&
+    ts_serialization_reader_read_u32(reader, &length->extent.row) &&
+    ts_serialization_reader_read_u32(reader, &length->extent.column);
+}
+
+// Write the fields of a single subtree, but not its children.
+static void ts_subtree__serialize_node(Subtree self, SerializationBuffer *buffer) {
+  if (self.data.is_inline) {
+    ts_serialization_buffer_write_u8(buffer, SubtreeEncodingInline);
+    ts_serialization_buffer_write_u8(buffer,
+      self.data.visible << 0 |
+      self.data.named << 1 |
+      self.data.extra << 2 |
+      self.data.has_changes << 3 |
+      self.data.is_missing << 4 |
+      self.data.is_keyword << 5
+    );
+    ts_serialization_buffer_write_u8(buffer, self.data.symbol);
+    ts_serialization_buffer_write_u8(buffer, self.data.padding_bytes);
+    ts_serialization_buffer_write_u8(buffer, self.data.size_bytes);
+    ts_serialization_buffer_write_u8(buffer, self.data.padding_columns);
+    ts_serialization_buffer_write_u8(buffer, self.data.padding_rows);
+    ts_serialization_buffer_write_u8(buffer, self.data.lookahead_bytes);
+    ts_serialization_buffer_write_u16(buffer, self.data.parse_state);
+    return;
+  }
+
+  const SubtreeHeapData *data = self.ptr;
+  ts_serialization_buffer_write_u8(buffer, SubtreeEncodingHeap);
+  ts_serialization_buffer_write_u16(buffer,
+    data->visible << 0 |
+    data->named << 1 |
+    data->extra << 2 |
+    data->fragile_left << 3 |
+    data->fragile_right << 4 |
+    data->has_changes << 5 |
+    data->has_external_tokens << 6 |
+    data->depends_on_column << 7 |
+    data->is_missing << 8 |
+    data->is_keyword << 9
+  );
+  ts_serialization_buffer_write_u16(buffer, data->symbol);
+  ts_serialization_buffer_write_u16(buffer, data->parse_state);
+  ts_subtree__serialize_length(data->padding, buffer);
+  ts_subtree__serialize_length(data->size, buffer);
+  ts_serialization_buffer_write_u32(buffer, data->lookahead_bytes);
+  ts_serialization_buffer_write_u32(buffer, data->error_cost);
+  ts_serialization_buffer_write_u32(buffer, data->child_count);
+
+  if (data->child_count > 0) {
+    ts_serialization_buffer_write_u32(buffer, data->visible_child_count);
+    ts_serialization_buffer_write_u32(buffer, data->named_child_count);
+    ts_serialization_buffer_write_u32(buffer, data->visible_descendant_count);
+    ts_serialization_buffer_write_u32(buffer, data->node_count);
+    ts_serialization_buffer_write_u32(buffer, data->repeat_depth);
+    ts_serialization_buffer_write_u32(buffer, (uint32_t)data->dynamic_precedence);
+    ts_serialization_buffer_write_u16(buffer, data->production_id);
+    ts_serialization_buffer_write_u16(buffer, data->first_leaf.symbol);
+    ts_serialization_buffer_write_u16(buffer, data->first_leaf.parse_state);
+  } else if (data->has_external_tokens) {
+    const ExternalScannerState *state = &data->external_scanner_state;
+    ts_serialization_buffer_write_u32(buffer, state->length);
+    array_extend(buffer, state->length, ts_external_scanner_state_data(state));
+  } else if (data->symbol == ts_builtin_sym_error) {
+    ts_serialization_buffer_write_u32(buffer, (uint32_t)data->lookahead_char);
+  }
+}
+
+// Write a subtree and all of its descendants in pre-order.
+void ts_subtree_serialize(Subtree self, SerializationBuffer *buffer) {
+  SubtreeArray stack = array_new();
+  array_push(&stack, self);
+  while (stack.size > 0) {
+    Subtree tree = array_pop(&stack);
+    ts_subtree__serialize_node(tree, buffer);
+    uint32_t child_count = ts_subtree_child_count(tree);
+    if (child_count > 0) {
+      Subtree *children = ts_subtree_children(tree);
+      for (uint32_t i = child_count; i > 0; i--) {
+        array_push(&stack, children[i - 1]);
+      }
+    }
+  }
+  array_delete(&stack);
+}
+
+static inline bool ts_subtree__is_valid_symbol(const TSLanguage *language, TSSymbol symbol) {
+  return
+    symbol < language->symbol_count ||
+    symbol == ts_builtin_sym_error ||
+    symbol == ts_builtin_sym_error_repeat;
+}
+
+static inline bool ts_subtree__is_valid_parse_state(const TSLanguage *language, TSStateId state) {
+  return state < language->state_count || state == TS_TREE_STATE_NONE;
+}
+
+// Read the fields of a single subtree. If the subtree has children, then
+// space is allocated for them, but they are filled with empty placeholders
+// that must be replaced by the caller.
+static bool ts_subtree__deserialize_node(
+  SerializationReader *reader,
+  SubtreePool *pool,
+  const TSLanguage *language,
+  MutableSubtree *result
+) {
+  uint8_t encoding;
+  if (!ts_serialization_reader_read_u8(reader, &encoding)) return false;
+
+  if (encoding == SubtreeEncodingInline) {
+    uint8_t flags, symbol, padding_bytes, size_bytes, padding_columns, padding_rows, lookahead_bytes;
+    uint16_t parse_state;
+    if (
+      !ts_serialization_reader_read_u8(reader, &flags) ||
+      !ts_serialization_reader_read_u8(reader, &symbol) ||
+      !ts_serialization_reader_read_u8(reader, &padding_bytes) ||
+      !ts_serialization_reader_read_u8(reader, &size_bytes) ||
+      !ts_serialization_reader_read_u8(reader, &padding_columns) ||
+      !ts_serialization_reader_read_u8(reader, &padding_rows) ||
+      !ts_serialization_reader_read_u8(reader, &lookahead_bytes) ||
+      !ts_serialization_reader_read_u16(reader, &parse_state)
+    ) return false;
+    if (
+      padding_rows >= 16 ||
+      lookahead_bytes >= 16 ||
+      !ts_subtree__is_valid_symbol(language, symbol) ||
+      !ts_subtree__is_valid_parse_state(language, parse_state)
+    ) return false;
+
+    *result = (MutableSubtree) {{
+      .parse_state = parse_state,
+      .symbol = symbol,
+      .padding_bytes = padding_bytes,
+      .padding_rows = padding_rows,
+      .padding_columns = padding_columns,
+      .size_bytes = size_bytes,
+      .lookahead_bytes = lookahead_bytes,
+      .visible = flags & (1 << 0),
+      .named = flags & (1 << 1),
+      .extra = flags & (1 << 2),
+      .has_changes = flags & (1 << 3),
+      .is_missing = flags & (1 << 4),
+      .is_keyword = flags & (1 << 5),
+      .is_inline = true,
+    }};
+    return true;
+  }
+
+  if (encoding != SubtreeEncodingHeap) return false;
+
+  uint16_t flags, symbol, parse_state;
+  Length padding, size;
+  uint32_t lookahead_bytes, error_cost, child_count;
+  if (
+    !ts_serialization_reader_read_u16(reader, &flags) ||
+    !ts_serialization_reader_read_u16(reader, &symbol) ||
+    !ts_serialization_reader_read_u16(reader, &parse_state) ||
+    !ts_subtree__deserialize_length(reader, &padding) ||
+    !ts_subtree__deserialize_length(reader, &size) ||
+    !ts_serialization_reader_read_u32(reader, &lookahead_bytes) ||
+    !ts_serialization_reader_read_u32(reader, &error_cost) ||
+    !ts_serialization_reader_read_u32(reader, &child_count)
+  ) return false;
+  if (
+    !ts_subtree__is_valid_symbol(language, symbol) ||
+    !ts_subtree__is_valid_parse_state(language, parse_state)
+  ) return false;
+
+  SubtreeHeapData header = {
+    .ref_count = 1,
+    .padding = padding,
+    .size = size,
+    .lookahead_bytes = lookahead_bytes,
+    .error_cost = error_cost,
+    .child_count = child_count,
+    .symbol = symbol,
+    .parse_state = parse_state,
+    .visible = flags & (1 << 0),
+    .named = flags & (1 << 1),
+    .extra = flags & (1 << 2),
+    .fragile_left = flags & (1 << 3),
+    .fragile_right = flags & (1 << 4),
+    .has_changes = flags & (1 << 5),
+    .has_external_tokens = flags & (1 << 6),
+    .depends_on_column = flags & (1 << 7),
+    .is_missing = flags & (1 << 8),
+    .is_keyword = flags & (1 << 9),
+    {{.first_leaf = {.symbol = 0, .parse_state = 0}}}
+  };
+
+  if (child_count > 0) {
+    // Every encoded child occupies at least one byte, so this bounds the
+    // allocation below by the size of the input.
+    if (child_count > (uint32_t)(reader->end - reader->data)) return false;
+
+    uint32_t visible_child_count, named_child_count, visible_descendant_count;
+    uint32_t node_count, repeat_depth, dynamic_precedence;
+    uint16_t production_id, first_leaf_symbol, first_leaf_parse_state;
+    if (
+      !ts_serialization_reader_read_u32(reader, &visible_child_count) ||
+      !ts_serialization_reader_read_u32(reader, &named_child_count) ||
+      !ts_serialization_reader_read_u32(reader, &visible_descendant_count) ||
+      !ts_serialization_reader_read_u32(reader, &node_count) ||
+      !ts_serialization_reader_read_u32(reader, &repeat_depth) ||
+      !ts_serialization_reader_read_u32(reader, &dynamic_precedence) ||
+      !ts_serialization_reader_read_u16(reader, &production_id) ||
+      !ts_serialization_reader_read_u16(reader, &first_leaf_symbol) ||
+      !ts_serialization_reader_read_u16(reader, &first_leaf_parse_state)
+    ) return false;
+    if (
+      (production_id > 0 && production_id >= language->production_id_count) ||
+      !ts_subtree__is_valid_symbol(language, first_leaf_symbol) ||
+      !ts_subtree__is_valid_parse_state(language, first_leaf_parse_state)
+    ) return false;
+
+    header.visible_child_count = visible_child_count;
+    header.named_child_count = named_child_count;
+    header.visible_descendant_count = visible_descendant_count;
+    header.node_count = node_count;
+    header.repeat_depth = repeat_depth;
+    header.dynamic_precedence = (int32_t)dynamic_precedence;
+    header.production_id = production_id;
+    header.first_leaf.symbol = first_leaf_symbol;
+    header.first_leaf.parse_state = first_leaf_parse_state;
+
+    // Allocate the node's data at the end of the array of children, and
+    // fill the children with inline placeholders so that the node can be
+    // released safely if a later child fails to load.
+    Subtree *children = ts_malloc(ts_subtree_alloc_size(child_count));
+    for (uint32_t i = 0; i < child_count; i++) {
+      children[i] = (Subtree) {.data = {.is_inline = true}};
+    }
+    SubtreeHeapData *data = (SubtreeHeapData *)&children[child_count];
+    *data = header;
+    result->ptr = data;
+    return true;
+  }
+
+  if (header.has_external_tokens) {
+    uint32_t length;
+    if (!ts_serialization_reader_read_u32(reader, &length)) return false;
+    if (length > (uint32_t)(reader->end - reader->data)) return false;
+    ts_external_scanner_state_init(&header.external_scanner_state, reader->data, length);
+    reader->data += length;
+  } else if (symbol == ts_builtin_sym_error) {
+    uint32_t lookahead_char;
+    if (!ts_serialization_reader_read_u32(reader, &lookahead_char)) return false;
+    header.lookahead_char = (int32_t)lookahead_char;
+  }
+
+  SubtreeHeapData *data = ts_subtree_pool_allocate(pool);
+  *data = header;
+  result->ptr = data;
+  return true;
+}
+
+typedef struct {
+  MutableSubtree tree;
+  uint32_t child_index;
+} DeserializationFrame;
+
+// Check that the fields that were read for a node agree with the children
+// that were read for it. The node and cursor code index into the tree using
+// the node's child counts and alias sequence, so these are recomputed from
+// the children and compared with the stored values.
+static bool ts_subtree__has_consistent_children(
+  MutableSubtree self,
+  const TSLanguage *language
+) {
+  const Subtree *children = ts_subtree_children(self);
+  if (self.ptr->production_id > 0) {
+    uint32_t structural_child_count = 0;
+    for (uint32_t i = 0; i < self.ptr->child_count; i++) {
+      if (!ts_subtree_extra(children[i])) structural_child_count++;
+    }
+    if (structural_child_count > language->max_alias_sequence_length) return false;
+  }
+
+  // Summarizing the children also recomputes some fields that the parser
+  // adjusts after creating a node, such as its dynamic precedence, so the
+  // stored fields are restored afterward.
+  SubtreeHeapData stored = *self.ptr;
+  ts_subtree_summarize_children(self, language);
+  bool result =
+    self.ptr->visible_child_count == stored.visible_child_count &&
+    self.ptr->named_child_count == stored.named_child_count &&
+    self.ptr->visible_descendant_count == stored.visible_descendant_count &&
+    self.ptr->node_count == stored.node_count &&
+    self.ptr->repeat_depth == stored.repeat_depth &&
+    self.ptr->has_external_tokens == stored.has_external_tokens &&
+    self.ptr->depends_on_column == stored.depends_on_column &&
+    self.ptr->padding.bytes == stored.padding.bytes &&
+    self.ptr->size.bytes == stored.size.bytes;
+  *self.ptr = stored;
+  return result;
+}
+
+// Read a subtree and all of its descendants, in the order that they were
+// written by `ts_subtree_serialize`.
+bool ts_subtree_deserialize(
+  SerializationReader *reader,
+  SubtreePool *pool,
+  const TSLanguage *language,
+  Subtree *result
+) {
+  Array(DeserializationFrame) stack = array_new();
+  bool success = false;
+
+  for (;;) {
+    MutableSubtree tree;
+    if (!ts_subtree__deserialize_node(reader, pool, language, &tree)) break;
+
+    if (!tree.data.is_inline && tree.ptr->child_count > 0) {
+      array_push(&stack, ((DeserializationFrame) {tree, 0}));
+      continue;
+    }
+
+    // Attach each completed subtree to its parent, completing any parents
+    // whose last child has now been read.
+    Subtree completed = ts_subtree_from_mut(tree);
+    while (stack.size > 0) {
+      DeserializationFrame *frame = array_back(&stack);
+      MutableSubtree mutable_parent = frame->tree;
+      Subtree parent = ts_subtree_from_mut(mutable_parent);
+      ts_subtree_children(parent)[frame->child_index++] = completed;
+      if (frame->child_index < parent.ptr->child_count) break;
+      stack.size--;
+      if (!ts_subtree__has_consistent_children(mutable_parent, language)) {
+        completed = NULL_SUBTREE;
+        ts_subtree_release(pool, parent);
+        break;
+      }
+      completed = parent;
+    }
+
+    if (completed.ptr == NULL) break;
+    if (stack.size == 0) {
+      *result = completed;
+      success = true;
+      break;
+    }
+  }
+
+  // On failure, release any partially-constructed nodes. Each one owns
+  // the children that have been attached to it so far.
+  for (uint32_t i = 0; i < stack.size; i++) {
+    ts_subtree_release(pool, ts_subtree_from_mut(stack.contents[i].tree));
+  }
+  array_delete(&stack);
+  return success;
+}
@@ -87,6 +87,7 @@ typedef struct {
     struct {
       uint32_t visible_child_count;
       uint32_t named_child_count;
+      uint32_t visible_descendant_count;
       uint32_t node_count;
       uint32_t repeat_depth;
       int32_t dynamic_precedence;
@@ -125,6 +126,59 @@ typedef struct {
   MutableSubtreeArray tree_stack;
 } SubtreePool;
 
+// A growable byte buffer used when writing a syntax tree's binary encoding.
+typedef Array(char) SerializationBuffer;
+
+// A read position within a syntax tree's binary encoding.
+typedef struct {
+  const char *data;
+  const char *end;
+} SerializationReader;
+
+// Multi-byte integers are always written in little-endian order, so that
+// serialized trees can be shared between machines.
+static inline void ts_serialization_buffer_write_u8(SerializationBuffer *self, uint8_t value) {
+  array_push(self, (char)value);
+}
+
+static inline void ts_serialization_buffer_write_u16(SerializationBuffer *self, uint16_t value) {
+  array_push(self, (char)(value & 0xFF));
+  array_push(self, (char)(value >> 8));
+}
+
+static inline void ts_serialization_buffer_write_u32(SerializationBuffer *self, uint32_t value) {
+  for (unsigned i = 0; i < 4; i++) {
+    array_push(self, (char)((value >> (8 * i)) & 0xFF));
+  }
+}
+
+static inline bool ts_serialization_reader_read_u8(SerializationReader *self, uint8_t *value) {
+  if (self->end - self->data < 1) return false;
+  *value = (uint8_t)self->data[0];
+  self->data += 1;
+  return true;
+}
+
+static inline bool ts_serialization_reader_read_u16(SerializationReader *self, uint16_t *value) {
+  if (self->end - self->data < 2) return false;
+  const uint8_t *bytes = (const uint8_t *)self->data;
+  *value = (uint16_t)(bytes[0] | (bytes[1] << 8));
+  self->data += 2;
+  return true;
+}
+
+static inline bool ts_serialization_reader_read_u32(SerializationReader *self, uint32_t *value) {
+  if (self->end - self->data < 4) return false;
+  const uint8_t *bytes = (const uint8_t *)self->data;
+  *value =
+    (uint32_t)bytes[0] |
+    ((uint32_t)bytes[1] << 8) |
+    ((uint32_t)bytes[2] << 16) |
+    ((uint32_t)bytes[3] << 24);
+  self->data += 4;
+  return true;
+}
+
 void ts_external_scanner_state_init(ExternalScannerState *, const char *, unsigned);
 const char *ts_external_scanner_state_data(const ExternalScannerState *);
 
@@ -161,6 +215,8 @@ char *ts_subtree_string(Subtree, const TSLanguage *, bool include_all);
 void ts_subtree_print_dot_graph(Subtree, const TSLanguage *, FILE *);
 Subtree ts_subtree_last_external_token(Subtree);
 bool ts_subtree_external_scanner_state_eq(Subtree, Subtree);
+void ts_subtree_serialize(Subtree, SerializationBuffer *);
+bool ts_subtree_deserialize(SerializationReader *, SubtreePool *, const TSLanguage *, Subtree *);
 
 #define SUBTREE_GET(self, name) (self.data.is_inline ? self.data.name : self.ptr->name)
 
@@ -253,6 +309,12 @@ static inline uint32_t ts_subtree_visible_child_count(Subtree self) {
   }
 }
 
+static inline uint32_t ts_subtree_visible_descendant_count(Subtree self) {
+  return (self.data.is_inline || self.ptr->child_count == 0)
+    ? 0
+    : self.ptr->visible_descendant_count;
+}
+
 static inline uint32_t ts_subtree_error_cost(Subtree self) {
   if (ts_subtree_missing(self)) {
     return ERROR_COST_PER_MISSING_TREE + ERROR_COST_PER_RECOVERY;
@@ -1,5 +1,6 @@
 #include "tree_sitter/api.h"
 #include "./array.h"
+#include "./atomic.h"
 #include "./get_changed_ranges.h"
 #include "./subtree.h"
 #include "./tree_cursor.h"
@@ -14,9 +15,7 @@ TSTree *ts_tree_new(
   TSTree *result = ts_malloc(sizeof(TSTree));
   result->root = root;
   result->language = language;
-  result->parent_cache = NULL;
-  result->parent_cache_start = 0;
-  result->parent_cache_size = 0;
+  result->parent_cache = ts_calloc(PARENT_CACHE_CAPACITY, sizeof(ParentCacheEntry));
   result->included_ranges = ts_calloc(included_range_count, sizeof(TSRange));
   memcpy(result->included_ranges, included_ranges, included_range_count * sizeof(TSRange));
   result->included_range_count = included_range_count;
@@ -35,7 +34,7 @@ void ts_tree_delete(TSTree *self) {
   ts_subtree_release(&pool, self->root);
   ts_subtree_pool_delete(&pool);
   ts_free(self->included_ranges);
-  if (self->parent_cache) ts_free(self->parent_cache);
+  ts_free(self->parent_cache);
   ts_free(self);
 }
 
@@ -78,8 +77,7 @@ void ts_tree_edit(TSTree *self, const TSInputEdit *edit) {
 
   SubtreePool pool = ts_subtree_pool_new(0);
   self->root = ts_subtree_edit(self->root, edit, &pool);
-  self->parent_cache_start = 0;
-  self->parent_cache_size = 0;
+  memset(self->parent_cache, 0, PARENT_CACHE_CAPACITY * sizeof(ParentCacheEntry));
   ts_subtree_pool_delete(&pool);
 }
 
@@ -112,37 +110,190 @@ void ts_tree_print_dot_graph(const TSTree *self, FILE *file) {
   ts_subtree_print_dot_graph(self->root, self->language, file);
 }
 
-TSNode ts_tree_get_cached_parent(const TSTree *self, const TSNode *node) {
-  for (uint32_t i = 0; i < self->parent_cache_size; i++) {
-    uint32_t index = (self->parent_cache_start + i) % PARENT_CACHE_CAPACITY;
-    ParentCacheEntry *entry = &self->parent_cache[index];
-    if (entry->child == node->id) {
-      return ts_node_new(self, entry->parent, entry->position, entry->alias_symbol);
+// The binary encoding of a tree starts with a fixed header, followed by
+// the tree's included ranges, followed by its subtrees in pre-order.
+static const char SERIALIZATION_MAGIC[4] = {'T', 'S', 'T', 'R'};
+static const uint32_t SERIALIZATION_FORMAT_VERSION = 1;
+
+// The header identifies the language using two FNV-1a hashes: one of the
+// grammar's symbols and fields, which differs between grammars, and one of
+// its parse table, which differs between versions of the same grammar.
+static const uint32_t FNV_OFFSET_BASIS = 2166136261u;
+static const uint32_t FNV_PRIME = 16777619u;
+
+static inline uint32_t ts_tree__hash_value(uint32_t hash, uint32_t value) {
+  return (hash ^ value) * FNV_PRIME;
+}
+
+static uint32_t ts_tree__hash_string(uint32_t hash, const char *string) {
+  if (string) {
+    for (const char *c = string; *c; c++) hash = ts_tree__hash_value(hash, (uint8_t)*c);
+  }
+  return ts_tree__hash_value(hash, 0);
+}
+
+static uint32_t ts_tree__language_symbols_hash(const TSLanguage *language) {
+  uint32_t hash = FNV_OFFSET_BASIS;
+  for (uint32_t i = 0; i < language->symbol_count + language->alias_count; i++) {
+    TSSymbolMetadata metadata = language->symbol_metadata[i];
+    hash = ts_tree__hash_string(hash, language->symbol_names[i]);
+    hash = ts_tree__hash_value(hash, metadata.visible | metadata.named << 1);
+  }
+  for (uint32_t i = 1; i <= language->field_count; i++) {
+    hash = ts_tree__hash_string(hash, language->field_names[i]);
+  }
+  return hash;
+}
+
+static uint32_t ts_tree__language_parse_table_hash(const TSLanguage *language) {
+  uint32_t hash = FNV_OFFSET_BASIS;
+  uint32_t large_table_size = language->large_state_count * language->symbol_count;
+  for (uint32_t i = 0; i < large_table_size; i++) {
+    hash = ts_tree__hash_value(hash, language->parse_table[i]);
+  }
+  for (uint32_t state = language->large_state_count; state < language->state_count; state++) {
+    uint32_t index = language->small_parse_table_map[state - language->large_state_count];
+    const uint16_t *data = &language->small_parse_table[index];
+    uint16_t group_count = *(data++);
+    hash = ts_tree__hash_value(hash, group_count);
+    for (unsigned i = 0; i < group_count; i++) {
+      uint16_t section_value = *(data++);
+      uint16_t symbol_count = *(data++);
+      hash = ts_tree__hash_value(hash, section_value);
+      hash = ts_tree__hash_value(hash, symbol_count);
+      for (unsigned j = 0; j < symbol_count; j++) {
+        hash = ts_tree__hash_value(hash, *(data++));
+      }
     }
   }
-  return ts_node_new(NULL, NULL, length_zero(), 0);
+  return hash;
+}
+
+char *ts_tree_serialize(const TSTree *self, uint32_t *length) {
+  SerializationBuffer buffer = array_new();
+  array_extend(&buffer, sizeof(SERIALIZATION_MAGIC), SERIALIZATION_MAGIC);
+  ts_serialization_buffer_write_u32(&buffer, SERIALIZATION_FORMAT_VERSION);
+  ts_serialization_buffer_write_u32(&buffer, self->language->version);
+  ts_serialization_buffer_write_u32(&buffer, ts_tree__language_symbols_hash(self->language));
+  ts_serialization_buffer_write_u32(&buffer, ts_tree__language_parse_table_hash(self->language));
+
+  ts_serialization_buffer_write_u32(&buffer, self->included_range_count);
+  for (unsigned i = 0; i < self->included_range_count; i++) {
+    const TSRange *range = &self->included_ranges[i];
+    ts_serialization_buffer_write_u32(&buffer, range->start_point.row);
+    ts_serialization_buffer_write_u32(&buffer, range->start_point.column);
+    ts_serialization_buffer_write_u32(&buffer, range->end_point.row);
+    ts_serialization_buffer_write_u32(&buffer, range->end_point.column);
+    ts_serialization_buffer_write_u32(&buffer, range->start_byte);
+    ts_serialization_buffer_write_u32(&buffer, range->end_byte);
+  }
+
+  ts_subtree_serialize(self->root, &buffer);
+  *length = buffer.size;
+  return buffer.contents;
+}
+
+TSTree *ts_tree_deserialize(
+  const TSLanguage *language,
+  const char *data,
+  uint32_t length,
+  TSTreeDeserializationError *error
+) {
+  SerializationReader reader = {data, data + length};
+  *error = TSTreeDeserializationErrorInvalidData;
+
+  uint32_t format_version, language_version, symbols_hash, parse_table_hash;
+  if (
+    length < sizeof(SERIALIZATION_MAGIC) ||
+    memcmp(data, SERIALIZATION_MAGIC, sizeof(SERIALIZATION_MAGIC)) != 0
+  ) return NULL;
+  reader.data += sizeof(SERIALIZATION_MAGIC);
+
+  if (!ts_serialization_reader_read_u32(&reader, &format_version)) return NULL;
+  if (format_version != SERIALIZATION_FORMAT_VERSION) {
+    *error = TSTreeDeserializationErrorFormatVersion;
+    return NULL;
+  }
+
+  if (
+    !ts_serialization_reader_read_u32(&reader, &language_version) ||
+    !ts_serialization_reader_read_u32(&reader, &symbols_hash) ||
+    !ts_serialization_reader_read_u32(&reader, &parse_table_hash)
+  ) return NULL;
+  if (symbols_hash != ts_tree__language_symbols_hash(language)) {
+    *error = TSTreeDeserializationErrorLanguage;
+    return NULL;
+  }
+  if (
+    language_version != language->version ||
+    parse_table_hash != ts_tree__language_parse_table_hash(language)
+  ) {
+    *error = TSTreeDeserializationErrorLanguageVersion;
+    return NULL;
+  }
+
+  uint32_t included_range_count;
+  if (!ts_serialization_reader_read_u32(&reader, &included_range_count)) return NULL;
+  if (included_range_count > (uint32_t)(reader.end - reader.data) / (6 * sizeof(uint32_t))) return NULL;
+  TSRange *included_ranges = ts_calloc(included_range_count, sizeof(TSRange));
+  for (unsigned i = 0; i < included_range_count; i++) {
+    TSRange *range = &included_ranges[i];
+    ts_serialization_reader_read_u32(&reader, &range->start_point.row);
+    ts_serialization_reader_read_u32(&reader, &range->start_point.column);
+    ts_serialization_reader_read_u32(&reader, &range->end_point.row);
+    ts_serialization_reader_read_u32(&reader, &range->end_point.column);
+    ts_serialization_reader_read_u32(&reader, &range->start_byte);
+    ts_serialization_reader_read_u32(&reader, &range->end_byte);
+  }
+
+  Subtree root;
+  SubtreePool pool = ts_subtree_pool_new(0);
+  bool success = ts_subtree_deserialize(&reader, &pool, language, &root);
+  if (success && reader.data != reader.end) {
+    ts_subtree_release(&pool, root);
+    success = false;
+  }
+  ts_subtree_pool_delete(&pool);
+
+  TSTree *result = NULL;
+  if (success) {
+    result = ts_tree_new(root, language, included_ranges, included_range_count);
+    *error = TSTreeDeserializationErrorNone;
+  }
+  ts_free(included_ranges);
+  return result;
+}
+
+// The parent cache can be read and written by several threads that are using the same
+// tree. Each entry is guarded by its own lock. Rather than waiting for a lock that is
+// held by another thread, a lookup is treated as a miss, and an update is skipped.
+static inline ParentCacheEntry *ts_tree__parent_cache_entry(const TSTree *self, const TSNode *node) {
+  uintptr_t index = (uintptr_t)node->id / sizeof(Subtree);
+  return &self->parent_cache[index % PARENT_CACHE_CAPACITY];
 }
 
-void ts_tree_set_cached_parent(const TSTree *_self, const TSNode *node, const TSNode *parent) {
-  TSTree *self = (TSTree *)_self;
-  if (!self->parent_cache) {
-    self->parent_cache = ts_calloc(PARENT_CACHE_CAPACITY, sizeof(ParentCacheEntry));
+TSNode ts_tree_get_cached_parent(const TSTree *self, const TSNode *node) {
+  ParentCacheEntry *entry = ts_tree__parent_cache_entry(self, node);
+  TSNode result = ts_node_new(NULL, NULL, length_zero(), 0);
+  if (atomic_try_lock(&entry->lock)) {
+    if (entry->child == node->id) {
+      result = ts_node_new(self, entry->parent, entry->position, entry->alias_symbol);
+    }
+    atomic_unlock(&entry->lock);
   }
+  return result;
+}
 
-  uint32_t index = (self->parent_cache_start + self->parent_cache_size) % PARENT_CACHE_CAPACITY;
-  self->parent_cache[index] = (ParentCacheEntry) {
-    .child = node->id,
-    .parent = (const Subtree *)parent->id,
-    .position = {
+void ts_tree_set_cached_parent(const TSTree *self, const TSNode *node, const TSNode *parent) {
+  ParentCacheEntry *entry = ts_tree__parent_cache_entry(self, node);
+  if (atomic_try_lock(&entry->lock)) {
+    entry->child = node->id;
+    entry->parent = (const Subtree *)parent->id;
+    entry->position = (Length) {
       parent->context[0],
       {parent->context[1], parent->context[2]}
-    },
-    .alias_symbol = parent->context[3],
-  };
-
-  if (self->parent_cache_size == PARENT_CACHE_CAPACITY) {
-    self->parent_cache_start++;
-  } else {
-    self->parent_cache_size++;
+    };
+    entry->alias_symbol = parent->context[3];
+    atomic_unlock(&entry->lock);
   }
 }
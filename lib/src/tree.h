@@ -6,6 +6,7 @@ extern "C" {
 #endif
 
 typedef struct {
+  volatile uint32_t lock;
   const Subtree *child;
   const Subtree *parent;
   Length position;
@@ -16,8 +17,6 @@ struct TSTree {
   Subtree root;
   const TSLanguage *language;
   ParentCacheEntry *parent_cache;
-  uint32_t parent_cache_start;
-  uint32_t parent_cache_size;
   TSRange *included_ranges;
   unsigned included_range_count;
 };
@@ -3,6 +3,7 @@
 #include "./tree_cursor.h"
 #include "./language.h"
 #include "./tree.h"
+#include "./point.h"
 
 typedef struct {
   Subtree parent;
@@ -10,26 +11,52 @@ typedef struct {
   Length position;
   uint32_t child_index;
   uint32_t structural_child_index;
+  uint32_t descendant_index;
   const TSSymbol *alias_sequence;
 } CursorChildIterator;
 
 // CursorChildIterator
 
+static inline bool ts_tree_cursor_is_entry_visible(const TreeCursor *self, uint32_t index) {
+  TreeCursorEntry *entry = &self->stack.contents[index];
+  if (index == 0 || ts_subtree_visible(*entry->subtree)) {
+    return true;
+  } else if (!ts_subtree_extra(*entry->subtree)) {
+    TreeCursorEntry *parent_entry = &self->stack.contents[index - 1];
+    return ts_language_alias_at(
+      self->tree->language,
+      parent_entry->subtree->ptr->production_id,
+      entry->structural_child_index
+    );
+  } else {
+    return false;
+  }
+}
+
 static inline CursorChildIterator ts_tree_cursor_iterate_children(const TreeCursor *self) {
   TreeCursorEntry *last_entry = array_back(&self->stack);
   if (ts_subtree_child_count(*last_entry->subtree) == 0) {
-    return (CursorChildIterator) {NULL_SUBTREE, self->tree, length_zero(), 0, 0, NULL};
+    return (CursorChildIterator) {NULL_SUBTREE, self->tree, length_zero(), 0, 0, 0, NULL};
   }
   const TSSymbol *alias_sequence = ts_language_alias_sequence(
     self->tree->language,
     last_entry->subtree->ptr->production_id
   );
+
+  // The descendant index of the first child follows that of the parent,
+  // unless the parent is hidden.
+  uint32_t descendant_index = last_entry->descendant_index;
+  if (ts_tree_cursor_is_entry_visible(self, self->stack.size - 1)) {
+    descendant_index++;
+  }
+
   return (CursorChildIterator) {
     .tree = self->tree,
     .parent = *last_entry->subtree,
     .position = last_entry->position,
     .child_index = 0,
     .structural_child_index = 0,
+    .descendant_index = descendant_index,
     .alias_sequence = alias_sequence,
   };
 }
@@ -44,6 +71,7 @@ static inline bool ts_tree_cursor_child_iterator_next(CursorChildIterator *self,
     .position = self->position,
     .child_index = self->child_index,
     .structural_child_index = self->structural_child_index,
+    .descendant_index = self->descendant_index,
   };
   *visible = ts_subtree_visible(*child);
   bool extra = ts_subtree_extra(*child);
@@ -52,6 +80,9 @@ static inline bool ts_tree_cursor_child_iterator_next(CursorChildIterator *self,
     self->structural_child_index++;
   }
 
+  self->descendant_index += ts_subtree_visible_descendant_count(*child);
+  if (*visible) self->descendant_index++;
+
   self->position = length_add(self->position, ts_subtree_size(*child));
   self->child_index++;
 
@@ -86,6 +117,7 @@ void ts_tree_cursor_init(TreeCursor *self, TSNode node) {
     },
     .child_index = 0,
     .structural_child_index = 0,
+    .descendant_index = 0,
   }));
 }
 
@@ -123,7 +155,40 @@ bool ts_tree_cursor_goto_first_child(TSTreeCursor *_self) {
   return false;
 }
 
-int64_t ts_tree_cursor_goto_first_child_for_byte(TSTreeCursor *_self, uint32_t goal_byte) {
+bool ts_tree_cursor_goto_last_child(TSTreeCursor *_self) {
+  TreeCursor *self = (TreeCursor *)_self;
+
+  bool did_descend;
+  do {
+    did_descend = false;
+
+    bool visible;
+    bool last_entry_is_visible = false;
+    TreeCursorEntry entry;
+    TreeCursorEntry last_entry = {NULL, length_zero(), 0, 0, 0};
+    CursorChildIterator iterator = ts_tree_cursor_iterate_children(self);
+    while (ts_tree_cursor_child_iterator_next(&iterator, &entry, &visible)) {
+      if (visible || ts_subtree_visible_child_count(*entry.subtree) > 0) {
+        last_entry = entry;
+        last_entry_is_visible = visible;
+      }
+    }
+
+    if (last_entry.subtree) {
+      array_push(&self->stack, last_entry);
+      if (last_entry_is_visible) return true;
+      did_descend = true;
+    }
+  } while (did_descend);
+
+  return false;
+}
+
+static inline int64_t ts_tree_cursor_goto_first_child_for_byte_and_point(
+  TSTreeCursor *_self,
+  uint32_t goal_byte,
+  TSPoint goal_point
+) {
   TreeCursor *self = (TreeCursor *)_self;
   uint32_t initial_size = self->stack.size;
   uint32_t visible_child_index = 0;
@@ -136,8 +201,8 @@ int64_t ts_tree_cursor_goto_first_child_for_byte(TSTreeCursor *_self, uint32_t g
     TreeCursorEntry entry;
     CursorChildIterator iterator = ts_tree_cursor_iterate_children(self);
     while (ts_tree_cursor_child_iterator_next(&iterator, &entry, &visible)) {
-      uint32_t end_byte = entry.position.bytes + ts_subtree_size(*entry.subtree).bytes;
-      bool at_goal = end_byte > goal_byte;
+      Length entry_end = length_add(entry.position, ts_subtree_size(*entry.subtree));
+      bool at_goal = entry_end.bytes > goal_byte && point_lt(goal_point, entry_end.extent);
       uint32_t visible_child_count = ts_subtree_visible_child_count(*entry.subtree);
 
       if (at_goal) {
@@ -168,6 +233,14 @@ int64_t ts_tree_cursor_goto_first_child_for_byte(TSTreeCursor *_self, uint32_t g
   return -1;
 }
 
+int64_t ts_tree_cursor_goto_first_child_for_byte(TSTreeCursor *self, uint32_t goal_byte) {
+  return ts_tree_cursor_goto_first_child_for_byte_and_point(self, goal_byte, POINT_ZERO);
+}
+
+int64_t ts_tree_cursor_goto_first_child_for_point(TSTreeCursor *self, TSPoint goal_point) {
+  return ts_tree_cursor_goto_first_child_for_byte_and_point(self, 0, goal_point);
+}
+
 bool ts_tree_cursor_goto_next_sibling(TSTreeCursor *_self) {
   TreeCursor *self = (TreeCursor *)_self;
   uint32_t initial_size = self->stack.size;
@@ -178,6 +251,7 @@ bool ts_tree_cursor_goto_next_sibling(TSTreeCursor *_self) {
     iterator.child_index = entry.child_index;
     iterator.structural_child_index = entry.structural_child_index;
     iterator.position = entry.position;
+    iterator.descendant_index = entry.descendant_index;
 
     bool visible = false;
     ts_tree_cursor_child_iterator_next(&iterator, &entry, &visible);
@@ -201,6 +275,110 @@ bool ts_tree_cursor_goto_next_sibling(TSTreeCursor *_self) {
   return false;
 }
 
+bool ts_tree_cursor_goto_previous_sibling(TSTreeCursor *_self) {
+  TreeCursor *self = (TreeCursor *)_self;
+  uint32_t initial_size = self->stack.size;
+
+  while (self->stack.size > 1) {
+    bool entry_is_visible = ts_tree_cursor_is_entry_visible(self, self->stack.size - 1);
+    TreeCursorEntry entry = array_pop(&self->stack);
+    if (entry_is_visible && self->stack.size + 1 < initial_size) break;
+
+    // Scan forward through the preceding siblings, because the positions of
+    // the children can only be computed from the start of the parent.
+    bool visible;
+    bool previous_entry_is_visible = false;
+    TreeCursorEntry sibling_entry;
+    TreeCursorEntry previous_entry = {NULL, length_zero(), 0, 0, 0};
+    CursorChildIterator iterator = ts_tree_cursor_iterate_children(self);
+    while (
+      ts_tree_cursor_child_iterator_next(&iterator, &sibling_entry, &visible) &&
+      sibling_entry.child_index < entry.child_index
+    ) {
+      if (visible || ts_subtree_visible_child_count(*sibling_entry.subtree) > 0) {
+        previous_entry = sibling_entry;
+        previous_entry_is_visible = visible;
+      }
+    }
+
+    if (previous_entry.subtree) {
+      array_push(&self->stack, previous_entry);
+      if (!previous_entry_is_visible) ts_tree_cursor_goto_last_child(_self);
+      return true;
+    }
+  }
+
+  self->stack.size = initial_size;
+  return false;
+}
+
+bool ts_tree_cursor_goto_descendant(TSTreeCursor *_self, uint32_t goal_descendant_index) {
+  TreeCursor *self = (TreeCursor *)_self;
+
+  // Leave the cursor where it is if the original node doesn't have that many descendants.
+  TreeCursorEntry *root = &self->stack.contents[0];
+  uint32_t descendant_count =
+    root->descendant_index +
+    (ts_tree_cursor_is_entry_visible(self, 0) ? 1 : 0) +
+    ts_subtree_visible_descendant_count(*root->subtree);
+  if (goal_descendant_index >= descendant_count) return false;
+
+  // Ascend to the lowest ancestor that contains the goal node.
+  for (;;) {
+    uint32_t i = self->stack.size - 1;
+    TreeCursorEntry *entry = &self->stack.contents[i];
+    uint32_t next_descendant_index =
+      entry->descendant_index +
+      (ts_tree_cursor_is_entry_visible(self, i) ? 1 : 0) +
+      ts_subtree_visible_descendant_count(*entry->subtree);
+    if (
+      entry->descendant_index <= goal_descendant_index &&
+      next_descendant_index > goal_descendant_index
+    ) {
+      break;
+    } else if (self->stack.size <= 1) {
+      return false;
+    } else {
+      self->stack.size--;
+    }
+  }
+
+  // Descend to the goal node.
+  bool did_descend;
+  do {
+    did_descend = false;
+
+    bool visible;
+    TreeCursorEntry entry;
+    CursorChildIterator iterator = ts_tree_cursor_iterate_children(self);
+    if (iterator.descendant_index > goal_descendant_index) return true;
+
+    while (ts_tree_cursor_child_iterator_next(&iterator, &entry, &visible)) {
+      if (iterator.descendant_index > goal_descendant_index) {
+        array_push(&self->stack, entry);
+        if (visible && entry.descendant_index == goal_descendant_index) return true;
+        did_descend = true;
+        break;
+      }
+    }
+  } while (did_descend);
+  return true;
+}
+
+uint32_t ts_tree_cursor_current_descendant_index(const TSTreeCursor *_self) {
+  const TreeCursor *self = (const TreeCursor *)_self;
+  return array_back(&self->stack)->descendant_index;
+}
+
+uint32_t ts_tree_cursor_current_depth(const TSTreeCursor *_self) {
+  const TreeCursor *self = (const TreeCursor *)_self;
+  uint32_t depth = 0;
+  for (unsigned i = 1; i < self->stack.size; i++) {
+    if (ts_tree_cursor_is_entry_visible(self, i)) depth++;
+  }
+  return depth;
+}
+
 bool ts_tree_cursor_goto_parent(TSTreeCursor *_self) {
   TreeCursor *self = (TreeCursor *)_self;
   for (unsigned i = self->stack.size - 2; i + 1 > 0; i--) {
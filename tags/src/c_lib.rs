@@ -99,7 +99,7 @@ pub extern "C" fn ts_tagger_add_language(
             tagger.languages.insert(scope_name.to_string(), c);
             TSTagsError::Ok
         }
-        Err(Error::Query(_)) => TSTagsError::InvalidQuery,
+        Err(Error::Query(..)) => TSTagsError::InvalidQuery,
         Err(Error::Regex(_)) => TSTagsError::InvalidRegex,
         Err(Error::Cancelled) => TSTagsError::Timeout,
         Err(Error::InvalidLanguage) => TSTagsError::InvalidLanguage,
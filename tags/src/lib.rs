@@ -60,9 +60,16 @@ pub struct Tag {
     pub syntax_type_id: u32,
 }
 
+/// Identifies one of the queries that make up a `TagsConfiguration`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum QuerySection {
+    Locals,
+    Tags,
+}
+
 #[derive(Debug, PartialEq)]
 pub enum Error {
-    Query(QueryError),
+    Query(QuerySection, QueryError),
     Regex(regex::Error),
     Cancelled,
     InvalidLanguage,
@@ -134,10 +141,20 @@ impl<'c> CancellationVariant<'c> {
 }
 
 impl TagsConfiguration {
+    /// Creates a `TagsConfiguration` for a given `Language` and set of queries.
+    ///
+    /// If one of the queries is invalid, the returned `Error::Query` identifies that query,
+    /// and the location of its `QueryError` is relative to the start of it.
     pub fn new(language: Language, tags_query: &str, locals_query: &str) -> Result<Self, Error> {
-        let query = Query::new(language, &format!("{}{}", locals_query, tags_query))?;
-
+        let query_source = format!("{}{}", locals_query, tags_query);
         let tags_query_offset = locals_query.len();
+        let query = Query::new(language, &query_source).map_err(|error| {
+            let (section_index, error) =
+                error.relative_to_section(&query_source, &[0, tags_query_offset]);
+            let section = [QuerySection::Locals, QuerySection::Tags][section_index];
+            Error::Query(section, error)
+        })?;
+
         let mut tags_pattern_index = 0;
         for i in 0..(query.pattern_count()) {
             let pattern_offset = query.start_byte_for_pattern(i);
@@ -638,12 +655,6 @@ impl From<regex::Error> for Error {
     }
 }
 
-impl From<QueryError> for Error {
-    fn from(error: QueryError) -> Self {
-        Error::Query(error)
-    }
-}
-
 fn line_range(
     text: &[u8],
     start_byte: usize,